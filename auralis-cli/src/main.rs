@@ -1,14 +1,157 @@
-use clap::Parser;
-use anyhow::Result;
-use auralis_core::PipeWireClient;
-use std::thread;
-use std::time::Duration;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use anyhow::{Context, Result};
+use auralis_core::persistence::StateStore;
+use auralis_core::topology::Topology;
+use auralis_core::{bundle, control, AuralisConfig, ExportFormat, Orb, OrbEvent, PipeWireClient, Profiles, UiCommand};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::{Duration, Instant};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    /// Print the outputs, clusters and apps once discovery is complete, one per line
     #[arg(short, long)]
     list: bool,
+
+    /// Print pactl/pw-link commands instead of executing them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// PipeWire remote to connect to, e.g. pipewire-0-manager (default: $PIPEWIRE_REMOTE or pipewire-0)
+    #[arg(long, global = true, value_parser = clap::builder::NonEmptyStringValueParser::new())]
+    remote: Option<String>,
+
+    /// pipewire-pulse server of --remote for pactl, e.g. unix:/run/pipewire/pulse/native
+    /// (default: $PULSE_SERVER). Without one nothing is changed on the remote.
+    #[arg(long, global = true, value_parser = clap::builder::NonEmptyStringValueParser::new())]
+    pulse_server: Option<String>,
+
+    /// Kept so older scripts still parse: the CLI never unloads modules left behind by an
+    /// earlier run, they may belong to a running Auralis
+    #[arg(long, global = true, hide = true)]
+    no_cleanup: bool,
+
+    /// Start even while another Auralis is running; its modules are left alone
+    #[arg(long, global = true)]
+    allow_multiple: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+impl Args {
+    /// For a client of our own, used when no Auralis is running (see `running_instance`)
+    fn config(&self) -> AuralisConfig {
+        AuralisConfig {
+            dry_run: self.dry_run,
+            pipewire_remote: self.remote.clone(),
+            pulse_server: self.pulse_server.clone(),
+            safe_mode: true,
+            allow_multiple: self.allow_multiple,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Activate a cluster profile from profiles.toml
+    Profile {
+        name: String,
+    },
+    /// Print the current orbs, clusters and modules, for bug reports
+    Dump {
+        #[arg(long, value_enum, default_value_t = DumpFormat::Json)]
+        format: DumpFormat,
+    },
+    /// Write the layout (clusters, settings, profiles) to a file to share or move it
+    Export {
+        file: PathBuf,
+    },
+    /// Bring in a layout written by `export`, through the running Auralis if there is one
+    Import {
+        file: PathBuf,
+        /// Replace the current layout instead of adding to it
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Make clusters, default output and role outputs what a topology file says, then exit.
+    /// Whatever Auralis manages that the file doesn't list is undone.
+    Apply {
+        file: PathBuf,
+        /// Print what it would change without changing it
+        #[arg(long)]
+        check: bool,
+    },
+    /// Print a shell completion script for bash, zsh, fish, ...
+    #[command(hide = true)]
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DumpFormat {
+    Dot,
+    Json,
+}
+
+impl From<DumpFormat> for ExportFormat {
+    fn from(format: DumpFormat) -> Self {
+        match format {
+            DumpFormat::Dot => ExportFormat::Dot,
+            DumpFormat::Json => ExportFormat::Json,
+        }
+    }
+}
+
+/// Block until PipeWire is connected and has announced all existing devices
+/// (or discovery has gone quiet)
+fn wait_for_discovery(rx: &Receiver<OrbEvent>, remote: Option<&str>) -> Result<()> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut connected = false;
+    loop {
+        match rx.recv_timeout(Duration::from_millis(500)) {
+            Ok(OrbEvent::BackendState { connected: true }) => connected = true,
+            Ok(OrbEvent::DiscoveryComplete) => return Ok(()),
+            Ok(_) => {}
+            Err(_) if connected => return Ok(()),
+            Err(_) if Instant::now() > deadline => match remote {
+                Some(remote) => anyhow::bail!("Could not connect to PipeWire remote '{}'", remote),
+                None => anyhow::bail!("Could not connect to PipeWire"),
+            },
+            Err(_) => {}
+        }
+    }
+}
+
+/// The control socket of an Auralis already running on the default remote, if there is one.
+/// Commands go there rather than to a client of our own, which would fight it over the modules.
+fn running_instance(args: &Args) -> Option<PathBuf> {
+    let path = control::default_path()?;
+    (args.remote.is_none() && !args.dry_run && std::os::unix::net::UnixStream::connect(&path).is_ok()).then_some(path)
+}
+
+/// A client of our own, once discovery has settled. It leaves stale modules loaded.
+struct Embedded {
+    client: PipeWireClient,
+    events: Receiver<OrbEvent>,
+    _commands: Sender<UiCommand>,
+}
+
+impl Embedded {
+    fn start(args: &Args, config: AuralisConfig) -> Result<Self> {
+        let (tx, events) = std::sync::mpsc::channel();
+        let (commands, cmd_rx) = std::sync::mpsc::channel();
+        let client = PipeWireClient::with_config(tx, cmd_rx, config)?;
+        // Everything that existed at startup has been announced once this returns
+        wait_for_discovery(&events, args.remote.as_deref())?;
+        Ok(Self { client, events, _commands: commands })
+    }
 }
 
 #[tokio::main]
@@ -16,20 +159,116 @@ async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     let args = Args::parse();
 
+    // Needs no audio server, so it's answered before anything connects
+    if let Some(Command::Completions { shell }) = args.command {
+        let mut command = Args::command();
+        let bin_name = command.get_name().to_string();
+        clap_complete::generate(shell, &mut command, bin_name, &mut std::io::stdout());
+        return Ok(());
+    }
+
+    // Layouts are plain files, so these don't connect either
+    if let Some(Command::Export { file }) = &args.command {
+        let path = StateStore::default_path().ok_or_else(|| anyhow::anyhow!("No config directory to read the layout from"))?;
+        bundle::export(&StateStore::load(path), Profiles::default_path().as_deref(), file)?;
+        println!("Layout exported to {}", file.display());
+        return Ok(());
+    }
+
+    if let Some(Command::Import { file, replace }) = &args.command {
+        // A running app would save over the file and keep its clusters as they were, so it
+        // imports the layout itself
+        if let Some(socket) = running_instance(&args) {
+            let file = std::fs::canonicalize(file).with_context(|| format!("Failed to read {}", file.display()))?;
+            control::call(&socket, "command", json!({ "ImportLayout": { "path": file, "replace": replace } }))?;
+            println!("Layout sent to the running Auralis");
+            return Ok(());
+        }
+        let path = StateStore::default_path().ok_or_else(|| anyhow::anyhow!("No config directory to import the layout into"))?;
+        let mut store = StateStore::load(path);
+        let summary = bundle::import(&mut store, Profiles::default_path().as_deref(), file, *replace)?;
+        println!("Imported {} cluster setup(s) and {} profile(s)", summary.clusters, summary.profiles);
+        return Ok(());
+    }
+
+    if let Some(Command::Profile { name }) = &args.command {
+        // The running app owns the clusters, so it's asked to switch instead
+        if let Some(socket) = running_instance(&args) {
+            control::call(&socket, "command", json!({ "ApplyProfile": { "name": name } }))?;
+            println!("Profile '{}' sent to the running Auralis", name);
+            return Ok(());
+        }
+        let embedded = Embedded::start(&args, args.config())?;
+        embedded.client.apply_profile(name)?;
+        for event in embedded.events.try_iter() {
+            if let OrbEvent::Error { message } = event {
+                eprintln!("⚠ {}", message);
+            }
+        }
+        println!("Profile '{}' applied", name);
+        return Ok(());
+    }
+
+    if let Some(Command::Apply { file, check }) = &args.command {
+        let topology = Topology::load(file)?;
+        let steps: Vec<String> = if let Some(socket) = running_instance(&args) {
+            let method = if *check { "plan" } else { "apply" };
+            let planned = control::call(&socket, method, serde_json::to_value(&topology)?)?;
+            planned.as_array().into_iter().flatten().filter_map(Value::as_str).map(str::to_string).collect()
+        } else {
+            let embedded = Embedded::start(&args, AuralisConfig { allow_multiple: *check, ..args.config() })?;
+            let steps = if *check {
+                embedded.client.plan_topology(&topology)?
+            } else {
+                embedded.client.apply_topology(&topology)?
+            };
+            for event in embedded.events.try_iter() {
+                if let OrbEvent::Error { message } = event {
+                    eprintln!("⚠ {}", message);
+                }
+            }
+            steps.iter().map(ToString::to_string).collect()
+        };
+        if steps.is_empty() {
+            println!("Nothing to change");
+        }
+        for step in steps {
+            println!("{}", step);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Dump { format }) = args.command {
+        let format = ExportFormat::from(format);
+        if let Some(socket) = running_instance(&args) {
+            match control::call(&socket, "snapshot", json!({ "format": format }))? {
+                Value::String(dot) => print!("{}", dot),
+                snapshot => println!("{}", serde_json::to_string_pretty(&snapshot)?),
+            }
+            return Ok(());
+        }
+        // Only looks, so it may run next to the app
+        let embedded = Embedded::start(&args, AuralisConfig { allow_multiple: true, ..args.config() })?;
+        print!("{}", embedded.client.export_graph(format));
+        return Ok(());
+    }
+
     if args.list {
-        println!("Initializing PipeWire Client...");
-        let (tx, rx) = std::sync::mpsc::channel();
-        let (_cmd_tx, cmd_rx) = std::sync::mpsc::channel();
-        let _client = PipeWireClient::new(tx, cmd_rx)?;
-        
-        println!("Listening for events (Ctrl+C to stop)...");
-        // Keep the main thread alive to let the background thread run
-        loop {
-            if let Ok(event) = rx.try_recv() {
-                println!("Discovered Event: {:?}", event);
+        if let Some(socket) = running_instance(&args) {
+            for orb in control::call(&socket, "list", Value::Null)?.as_array().into_iter().flatten() {
+                let field = |key: &str| orb[key].as_str().unwrap_or_default().to_string();
+                println!("{}\t{}\t{}", field("kind"), field("name"), field("node_name"));
             }
-            thread::sleep(Duration::from_millis(100));
+            return Ok(());
+        }
+        let embedded = Embedded::start(&args, AuralisConfig { allow_multiple: true, ..args.config() })?;
+        let graph = embedded.client.snapshot();
+        let mut orbs: Vec<&Orb> = graph.orbs.values().collect();
+        orbs.sort_by(|a, b| (control::kind_label(a), &a.name).cmp(&(control::kind_label(b), &b.name)));
+        for orb in orbs {
+            println!("{}\t{}\t{}", control::kind_label(orb), orb.name, orb.node_name);
         }
+        return Ok(());
     }
 
     Ok(())
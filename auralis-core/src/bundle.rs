@@ -0,0 +1,134 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::persistence::{PersistedState, StateStore};
+use crate::profiles::{Profile, Profiles};
+
+// A layout bundle is everything Auralis remembers (state.json) plus profiles.toml in one
+// JSON file, to move a setup to another machine or share it. The state inside keeps its own
+// version and is upgraded like a state file; the bundle version covers the wrapper.
+
+/// Layout of the bundle wrapper, see `migrate`
+pub const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bundle {
+    pub version: u32,
+    pub state: PersistedState,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// What an import brought in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub clusters: usize,
+    pub profiles: usize,
+}
+
+impl Bundle {
+    /// Bundle of the given state and profiles. The TURN password is left out: bundles get
+    /// shared, and the relay's password shouldn't travel with them.
+    pub fn new(state: &PersistedState, profiles: &Profiles) -> Self {
+        let mut state = state.clone();
+        if let Some(turn) = &mut state.settings.beam_turn {
+            turn.credential.clear();
+        }
+        Self { version: BUNDLE_VERSION, state, profiles: profiles.profiles.clone() }
+    }
+
+    /// Parse a bundle of any version up to `BUNDLE_VERSION`, upgrading older ones
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut value: serde_json::Value = serde_json::from_str(contents)?;
+        let Some(version) = value.get("version") else {
+            bail!("not an Auralis layout bundle");
+        };
+        let version = version.as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v > 0)
+            .ok_or_else(|| anyhow!("invalid version {}", version))?;
+        if version > BUNDLE_VERSION {
+            bail!("written by a newer Auralis (version {}, this one reads up to {})", version, BUNDLE_VERSION);
+        }
+
+        for from in version..BUNDLE_VERSION {
+            migrate(from, &mut value);
+            value["version"] = (from + 1).into();
+        }
+        let state = PersistedState::from_value(value["state"].take()).context("invalid state")?;
+        let profiles = serde_json::from_value(value["profiles"].take()).unwrap_or_default();
+        Ok(Self { version: BUNDLE_VERSION, state, profiles })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Bring the bundle into the local state and profiles. Replacing takes it as is (keeping the
+    /// local TURN password for the same relay); merging adds its clusters, profiles, media role
+    /// outputs, canvas positions and device lists to what's there and keeps the local settings.
+    pub fn apply(self, state: &mut PersistedState, profiles: &mut Profiles, replace: bool) -> ImportSummary {
+        let summary = ImportSummary { clusters: self.state.clusters.len(), profiles: self.profiles.len() };
+        let mut imported = self.state;
+
+        if replace {
+            if let (Some(turn), Some(local)) = (&mut imported.settings.beam_turn, &state.settings.beam_turn) {
+                if turn.credential.is_empty() && turn.url == local.url && turn.username == local.username {
+                    turn.credential = local.credential.clone();
+                }
+            }
+            *state = imported;
+            profiles.profiles = self.profiles;
+            return summary;
+        }
+
+        state.clusters.extend(imported.clusters);
+        let (settings, theirs) = (&mut state.settings, imported.settings);
+        settings.role_targets.extend(theirs.role_targets);
+        settings.canvas_positions.extend(theirs.canvas_positions);
+        for device in theirs.ignored_devices {
+            if !settings.ignored_devices.contains(&device) {
+                settings.ignored_devices.push(device);
+            }
+        }
+        for output in theirs.suspended_outputs {
+            if !settings.suspended_outputs.contains(&output) {
+                settings.suspended_outputs.push(output);
+            }
+        }
+        profiles.profiles.extend(self.profiles);
+        summary
+    }
+}
+
+/// Upgrade the bundle wrapper from version `from` to `from + 1`
+fn migrate(from: u32, _value: &mut serde_json::Value) {
+    unreachable!("no migration from bundle version {}", from)
+}
+
+/// Write the bundle of `store` and the profiles at `profiles_path` to `path`
+pub fn export(store: &StateStore, profiles_path: Option<&Path>, path: &Path) -> Result<()> {
+    let profiles = Profiles::load_or_default(profiles_path)?;
+    let contents = Bundle::new(&store.data, &profiles).to_json()?;
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Read the bundle at `path` into `store` and the profiles at `profiles_path`, saving both.
+/// Nothing is changed if the bundle can't be read.
+pub fn import(store: &mut StateStore, profiles_path: Option<&Path>, path: &Path, replace: bool) -> Result<ImportSummary> {
+    let contents = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let bundle = Bundle::parse(&contents).with_context(|| format!("Can't import {}", path.display()))?;
+    let mut profiles = Profiles::load_or_default(profiles_path)?;
+    let before = profiles.clone();
+
+    let mut summary = bundle.apply(&mut store.data, &mut profiles, replace);
+    store.try_save()?;
+    match profiles_path {
+        Some(profiles_path) if profiles != before => profiles.save(profiles_path)?,
+        Some(_) => {}
+        // Profiles are turned off, there's nowhere to put them
+        None => summary.profiles = 0,
+    }
+    Ok(summary)
+}
@@ -0,0 +1,67 @@
+use anyhow::{bail, Result};
+
+// Channel layouts a cluster's combine-sink can be created with, in PulseAudio channel-map
+// notation. Anything else has no agreed-on speaker positions, so it's refused rather than
+// guessed at.
+
+/// Channel count, name shown to the user, channel map
+pub const LAYOUTS: [(u32, &str, &str); 5] = [
+    (1, "Mono", "mono"),
+    (2, "Stereo", "front-left,front-right"),
+    (4, "Quad", "front-left,front-right,rear-left,rear-right"),
+    (6, "5.1", "front-left,front-right,front-center,lfe,rear-left,rear-right"),
+    (8, "7.1", "front-left,front-right,front-center,lfe,rear-left,rear-right,side-left,side-right"),
+];
+
+/// What a member that doesn't report `audio.channels` is assumed to have
+const ASSUMED_MEMBER_CHANNELS: u32 = 2;
+
+/// Whether a sink's `audio.channels` marks it as a placeholder without a usable format: zero or
+/// garbage. Not reporting it at all is fine, `ASSUMED_MEMBER_CHANNELS` is taken then.
+pub fn is_placeholder(audio_channels: Option<&str>) -> bool {
+    audio_channels.is_some_and(|c| !c.trim().parse::<u32>().is_ok_and(|n| n > 0))
+}
+
+/// Channel map for a layout, `None` if there's no standard one
+pub fn channel_map(channels: u32) -> Option<&'static str> {
+    LAYOUTS.iter().find(|(n, _, _)| *n == channels).map(|(_, _, map)| *map)
+}
+
+/// "5.1", "Stereo", ... or just the count for a non-standard one
+pub fn label(channels: u32) -> String {
+    match LAYOUTS.iter().find(|(n, _, _)| *n == channels) {
+        Some((_, label, _)) => label.to_string(),
+        None => format!("{} channels", channels),
+    }
+}
+
+/// Widest standard layout every member can play, as channel count and map: two 5.1 receivers
+/// get 5.1, a 5.1 receiver and a stereo speaker get stereo, mono speakers get mono
+pub fn common_layout(member_channels: &[Option<u32>]) -> (u32, &'static str) {
+    let narrowest = member_channels.iter()
+        .map(|c| c.unwrap_or(ASSUMED_MEMBER_CHANNELS))
+        .min()
+        .unwrap_or(ASSUMED_MEMBER_CHANNELS);
+    LAYOUTS.iter()
+        .rev()
+        .find(|(n, _, _)| *n <= narrowest)
+        .map(|(n, _, map)| (*n, *map))
+        .unwrap_or((LAYOUTS[0].0, LAYOUTS[0].2))
+}
+
+/// Channel map for a cluster with `channels`, refused if there's no standard layout or no member
+/// has that many channels (the extra ones would only be mixed away)
+pub fn validate(channels: u32, member_channels: &[Option<u32>]) -> Result<&'static str> {
+    let Some(map) = channel_map(channels) else {
+        let supported: Vec<String> = LAYOUTS.iter().map(|(n, _, _)| n.to_string()).collect();
+        bail!("{} channels has no standard speaker layout (supported: {})", channels, supported.join(", "));
+    };
+    let widest = member_channels.iter()
+        .map(|c| c.unwrap_or(ASSUMED_MEMBER_CHANNELS))
+        .max()
+        .unwrap_or(ASSUMED_MEMBER_CHANNELS);
+    if channels > widest {
+        bail!("{} needs a device with {} channels, the widest member has {}", label(channels), channels, widest);
+    }
+    Ok(map)
+}
@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+use crate::instance_lock::InstanceLock;
+use crate::persistence::StateStore;
+use crate::profiles::Profiles;
+
+/// Runtime configuration for the Auralis core
+#[derive(Debug, Clone)]
+pub struct AuralisConfig {
+    /// Log external commands (pactl, pw-link) instead of executing them
+    pub dry_run: bool,
+    /// Where cluster names, zones etc. are persisted. `None` keeps them in memory only.
+    pub state_path: Option<PathBuf>,
+    /// `profiles.toml` with named cluster setups. `None` disables profiles.
+    pub profiles_path: Option<PathBuf>,
+    /// Watch PipeWire for devices and streams. Off for tests that feed the core
+    /// their own orbs with `PipeWireClient::inject_orb`.
+    pub discovery: bool,
+    /// PipeWire remote (socket name, e.g. "pipewire-0-manager") to connect to.
+    /// `None` uses libpipewire's default, which honours `PIPEWIRE_REMOTE`.
+    pub pipewire_remote: Option<String>,
+    /// pipewire-pulse server of that remote (`unix:/path/to/pulse/native`) for pactl, else
+    /// `PULSE_SERVER`. Without either, pactl can't reach the remote and nothing is changed on it.
+    pub pulse_server: Option<String>,
+    /// Leave stale Auralis modules loaded at startup instead of unloading them
+    pub safe_mode: bool,
+    /// Lock file held while this instance runs, see `InstanceLock`. `None` doesn't check
+    /// for other instances.
+    pub lock_path: Option<PathBuf>,
+    /// Start even if another instance holds the lock (its modules are then never swept)
+    pub allow_multiple: bool,
+    /// Unix socket to take commands and queries on, see `control`. `None` doesn't listen;
+    /// neither does an instance started next to another one (`allow_multiple`).
+    pub control_socket: Option<PathBuf>,
+}
+
+impl Default for AuralisConfig {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            state_path: StateStore::default_path(),
+            profiles_path: Profiles::default_path(),
+            discovery: true,
+            pipewire_remote: None,
+            pulse_server: None,
+            safe_mode: false,
+            lock_path: InstanceLock::default_path(),
+            allow_multiple: false,
+            control_socket: None,
+        }
+    }
+}
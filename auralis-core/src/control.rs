@@ -0,0 +1,191 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::thread;
+use tracing::{info, warn};
+use crate::export::ExportFormat;
+use crate::graph::{Orb, OrbKind, UiCommand};
+use crate::pipewire_client::{PipeWireClient, SharedState};
+use crate::topology::Topology;
+
+// Local control socket for scripts and other tools (window managers, home automation,
+// auralis-cli): newline-delimited JSON-RPC 2.0, one request per line, one response line each.
+//
+//   {"jsonrpc": "2.0", "id": 1, "method": "list"}
+//   {"jsonrpc": "2.0", "id": 1, "result": [{"kind": "output", "name": "Speakers", ...}]}
+//
+// Methods:
+// - `list`: every orb as {id, kind, name, node_name, cluster}, `kind` as in `kind_label`
+// - `snapshot`: the whole graph, the same JSON as `auralis-cli dump`; with `{"format": "dot"}`
+//   the Graphviz text instead, as a string
+// - `metrics`: `MetricsSnapshot`
+// - `command`: `params` is a `UiCommand` as serde writes it, `{"Connect": {"source": "<uuid>",
+//   "target": "<uuid>"}}` or just `"ResetAll"`. It's queued like one from the UI; the result is
+//   `null`, what it did shows in the next `list`.
+// - `plan`: `params` is a `Topology`; answers with what it takes to get there, one line per
+//   step (empty if nothing)
+// - `apply`: the same, and queues the steps like `command` would
+//
+// Errors come back as {"error": {"code", "message"}} with the JSON-RPC codes.
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// `auralis.sock` in the runtime directory; `None` without `XDG_RUNTIME_DIR`
+pub fn default_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR").map(|dir| PathBuf::from(dir).join("auralis.sock"))
+}
+
+/// Listen on `path`, answering queries from `state` and passing commands to `commands`.
+/// A socket file nobody answers on is left over from a crash and replaced.
+pub fn serve(path: &Path, state: SharedState, commands: Sender<UiCommand>) -> Result<()> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            bail!("{} is in use by another process", path.display());
+        }
+        std::fs::remove_file(path).with_context(|| format!("Failed to remove stale {}", path.display()))?;
+    }
+    let listener = UnixListener::bind(path).with_context(|| format!("Failed to listen on {}", path.display()))?;
+    info!("🔌 [CONTROL] Listening on {}", path.display());
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let (state, commands) = (state.clone(), commands.clone());
+                    thread::spawn(move || handle_connection(stream, &state, &commands));
+                }
+                Err(e) => warn!("Control socket connection failed: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle_connection(stream: UnixStream, state: &SharedState, commands: &Sender<UiCommand>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            warn!("Control socket connection failed: {}", e);
+            return;
+        }
+    };
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { return };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = respond(state, commands, &line);
+        if writeln!(writer, "{}", response).is_err() {
+            return;
+        }
+    }
+}
+
+/// The response line to one request line
+pub fn respond(state: &SharedState, commands: &Sender<UiCommand>, line: &str) -> Value {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return error(Value::Null, PARSE_ERROR, format!("Invalid JSON: {}", e)),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return error(id, INVALID_REQUEST, "Missing method".to_string());
+    };
+
+    let result = match method {
+        "list" => {
+            let graph = state.graph();
+            let mut orbs: Vec<&Orb> = graph.orbs.values().collect();
+            orbs.sort_by(|a, b| (kind_label(a), &a.name).cmp(&(kind_label(b), &b.name)));
+            Value::Array(orbs.into_iter().map(|orb| json!({
+                "id": orb.id.to_string(),
+                "kind": kind_label(orb),
+                "name": orb.name,
+                "node_name": orb.node_name,
+                "cluster": orb.membership().map(|id| id.to_string()),
+            })).collect())
+        }
+        "snapshot" => {
+            let format = match request.get("params").and_then(|params| params.get("format")) {
+                Some(format) => match serde_json::from_value(format.clone()) {
+                    Ok(format) => format,
+                    Err(e) => return error(id, INVALID_PARAMS, format!("Not a format: {}", e)),
+                },
+                None => ExportFormat::Json,
+            };
+            let export = PipeWireClient::export_of(state, format);
+            match format {
+                ExportFormat::Json => serde_json::from_str(&export).unwrap_or_default(),
+                ExportFormat::Dot => Value::String(export),
+            }
+        }
+        "metrics" => json!(PipeWireClient::metrics_of(state)),
+        "command" => {
+            let params = request.get("params").cloned().unwrap_or(Value::Null);
+            let command: UiCommand = match serde_json::from_value(params) {
+                Ok(command) => command,
+                Err(e) => return error(id, INVALID_PARAMS, format!("Not a command: {}", e)),
+            };
+            info!("🔌 [CONTROL] Command {:?}", command);
+            if commands.send(command).is_err() {
+                return error(id, INVALID_REQUEST, "Auralis is shutting down".to_string());
+            }
+            Value::Null
+        }
+        "plan" | "apply" => {
+            let params = request.get("params").cloned().unwrap_or(Value::Null);
+            let topology: Topology = match serde_json::from_value(params) {
+                Ok(topology) => topology,
+                Err(e) => return error(id, INVALID_PARAMS, format!("Not a layout: {}", e)),
+            };
+            let steps = match PipeWireClient::plan_topology_of(state, &topology) {
+                Ok(steps) => steps,
+                Err(e) => return error(id, INVALID_PARAMS, format!("{:#}", e)),
+            };
+            let queue = method == "apply" && !steps.is_empty();
+            if queue && commands.send(UiCommand::ApplyTopology { topology }).is_err() {
+                return error(id, INVALID_REQUEST, "Auralis is shutting down".to_string());
+            }
+            json!(steps.iter().map(ToString::to_string).collect::<Vec<_>>())
+        }
+        other => return error(id, METHOD_NOT_FOUND, format!("Unknown method '{}'", other)),
+    };
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// What an orb is, for listings: output, member (of a cluster), cluster, app or beam
+pub fn kind_label(orb: &Orb) -> &'static str {
+    match orb.kind {
+        OrbKind::PhysicalSink { .. } if orb.membership().is_some() => "member",
+        OrbKind::PhysicalSink { .. } => "output",
+        OrbKind::Cluster { .. } => "cluster",
+        OrbKind::ApplicationSource { .. } => "app",
+        OrbKind::BeamOutput { .. } => "beam",
+    }
+}
+
+/// Ask the Auralis listening on `path` something: `method` with `params` (`Value::Null` for
+/// none). Returns the result, or the error it answered with. Fails to connect if none runs.
+pub fn call(path: &Path, method: &str, params: Value) -> Result<Value> {
+    let mut stream = UnixStream::connect(path).with_context(|| format!("Failed to connect to {}", path.display()))?;
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    writeln!(stream, "{}", request)?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let mut response: Value = serde_json::from_str(&line).context("Invalid response")?;
+    if let Some(message) = response["error"]["message"].as_str() {
+        bail!("{}", message);
+    }
+    response.get_mut("result").map(Value::take).ok_or_else(|| anyhow!("Response without a result"))
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use pipewire as pw;
+use crate::channels;
+use crate::graph::DeviceBus;
+use crate::media_roles;
+use crate::sink_names;
+
+/// Read access to a global's properties, so the filtering rules can run without PipeWire
+pub trait PropsView {
+    fn get(&self, key: &str) -> Option<&str>;
+}
+
+impl PropsView for pw::spa::utils::dict::DictRef {
+    fn get(&self, key: &str) -> Option<&str> {
+        pw::spa::utils::dict::DictRef::get(self, key)
+    }
+}
+
+impl PropsView for HashMap<String, String> {
+    fn get(&self, key: &str) -> Option<&str> {
+        HashMap::get(self, key).map(String::as_str)
+    }
+}
+
+/// What to do with a newly announced audio node
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiscoveryDecision {
+    /// Filtered out: system sounds, dummy outputs
+    Ignore,
+    /// One of Auralis' own sinks; only its node ID is worth remembering
+    OwnSink(String),
+    /// A hidden cluster member came (back); keep it out of the UI
+    Park(String),
+    AddSink {
+        node_name: String,
+        description: String,
+        bus: DeviceBus,
+        channels: Option<u32>,
+        /// The card it belongs to, see `device_key`
+        device: Option<String>,
+    },
+    AddStream {
+        node_name: String,
+        /// `application.name`, empty if the stream doesn't say
+        app_name: String,
+        /// Name to show, see `display_name`
+        description: String,
+        channels: Option<u32>,
+        /// `media.role` as one of `media_roles::ROLES`, `None` if it has none we route
+        media_role: Option<String>,
+    },
+}
+
+/// Properties a sink's display name is taken from, best first: "Built-in Audio" rather than
+/// "alsa_output.pci-0000_00_1f.3.analog-stereo"
+const SINK_NAME_KEYS: [&str; 4] = ["node.description", "node.nick", "device.description", "node.name"];
+/// Same for a playback stream: the app, otherwise what it's playing
+const STREAM_NAME_KEYS: [&str; 5] = ["application.name", "media.name", "node.description", "node.nick", "node.name"];
+
+/// Friendliest name a sink or stream announces, skipping empty properties
+pub fn display_name(props: &dyn PropsView, is_sink: bool) -> String {
+    let keys: &[&str] = if is_sink { &SINK_NAME_KEYS } else { &STREAM_NAME_KEYS };
+    keys.iter()
+        .filter_map(|key| props.get(key))
+        .map(str::trim)
+        .find(|value| !value.is_empty())
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+/// Card a sink node belongs to: `device.name`, or the device object's ID if the node doesn't
+/// carry the name. Switching the card's profile replaces its nodes but keeps this.
+pub fn device_key(props: &dyn PropsView) -> Option<String> {
+    let named = props.get("device.name").map(str::trim).filter(|name| !name.is_empty());
+    match named {
+        Some(name) => Some(name.to_string()),
+        None => props.get("device.id").map(|id| format!("device:{}", id.trim())),
+    }
+}
+
+/// Classify a registry global. `None` for anything that isn't an audio sink or playback
+/// stream. `is_cluster_member` tells whether a sink node name is hidden inside a cluster.
+pub fn classify_global(props: &dyn PropsView, is_cluster_member: impl Fn(&str) -> bool) -> Option<DiscoveryDecision> {
+    let media_class = props.get("media.class")?;
+    let is_sink = media_class == "Audio/Sink";
+    if !is_sink && media_class != "Stream/Output/Audio" {
+        return None;
+    }
+
+    let name = props.get("node.name").unwrap_or("Unknown");
+    let description = display_name(props, is_sink);
+    let app_name = props.get("application.name").unwrap_or("");
+
+    if is_sink && is_cluster_member(name) {
+        return Some(DiscoveryDecision::Park(name.to_string()));
+    }
+
+    // Mutter (system sounds/compositor) and dummy devices aren't worth an orb
+    if app_name == "Mutter" || name.contains("Mutter") || name.to_lowercase().contains("dummy") {
+        return Some(DiscoveryDecision::Ignore);
+    }
+
+    let channels = props.get("audio.channels").and_then(|c| c.parse().ok());
+    if !is_sink {
+        return Some(DiscoveryDecision::AddStream {
+            node_name: name.to_string(),
+            app_name: app_name.to_string(),
+            description,
+            channels,
+            media_role: props.get("media.role").and_then(media_roles::canonical).map(str::to_string),
+        });
+    }
+
+    if sink_names::is_own(name) {
+        return Some(DiscoveryDecision::OwnSink(name.to_string()));
+    }
+
+    // Virtual/placeholder nodes briefly show up without a usable format; as orbs they
+    // couldn't be clustered and would just flicker in and out of the list
+    if channels::is_placeholder(props.get("audio.channels")) {
+        return Some(DiscoveryDecision::Ignore);
+    }
+
+    Some(DiscoveryDecision::AddSink {
+        node_name: name.to_string(),
+        description,
+        bus: DeviceBus::detect(props.get("device.api"), props.get("device.bus"), props.get("factory.name"), name),
+        channels,
+        device: device_key(props),
+    })
+}
@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::warn;
+use crate::graph::OrbEvent;
+
+// Events waiting for a UI that can't keep up (a long redraw during a device storm). Instead of
+// piling up, later events fold into earlier ones where the UI would end up in the same state,
+// and past a limit the oldest are dropped.
+
+/// How many events a queue coalesced and dropped, for diagnostics. Shared, so it can be read
+/// while the queue is in use on another thread.
+#[derive(Debug, Default)]
+pub struct QueueStats {
+    coalesced: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl QueueStats {
+    /// Events made unnecessary by a later one
+    pub fn coalesced(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+
+    /// Events thrown away because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Events in order, coalesced as they are pushed:
+/// - an `Update` of an orb with a waiting `Add` or `Update` replaces that one's orb
+/// - a `Remove` or `Clear` discards the waiting `Add`s and `Update`s it makes pointless
+/// - a newer `Settings`, `Routes`, `CommandStatus`, `DefaultSink` or `Duplicates` replaces the
+///   waiting one (they're complete snapshots), moving to the back
+pub struct EventQueue {
+    events: VecDeque<OrbEvent>,
+    limit: usize,
+    stats: Arc<QueueStats>,
+}
+
+impl EventQueue {
+    /// Queue holding at most `limit` events
+    pub fn new(limit: usize, stats: Arc<QueueStats>) -> Self {
+        Self { events: VecDeque::new(), limit, stats }
+    }
+
+    pub fn push(&mut self, event: OrbEvent) {
+        let before = self.events.len();
+        match &event {
+            OrbEvent::Update(orb) => {
+                let waiting = self.events.iter_mut().find_map(|e| match e {
+                    OrbEvent::Add(o) | OrbEvent::Update(o) if o.id == orb.id => Some(o),
+                    _ => None,
+                });
+                if let Some(waiting) = waiting {
+                    *waiting = orb.clone();
+                    self.stats.coalesced.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+            }
+            OrbEvent::Remove(id) => {
+                self.events.retain(|e| !matches!(e, OrbEvent::Add(o) | OrbEvent::Update(o) if o.id == *id));
+            }
+            OrbEvent::Clear => {
+                self.events.retain(|e| !matches!(e, OrbEvent::Add(_) | OrbEvent::Update(_) | OrbEvent::Remove(_)));
+            }
+            OrbEvent::Settings(_) => self.events.retain(|e| !matches!(e, OrbEvent::Settings(_))),
+            OrbEvent::Routes { .. } => self.events.retain(|e| !matches!(e, OrbEvent::Routes { .. })),
+            OrbEvent::CommandStatus { .. } => self.events.retain(|e| !matches!(e, OrbEvent::CommandStatus { .. })),
+            OrbEvent::DefaultSink { .. } => self.events.retain(|e| !matches!(e, OrbEvent::DefaultSink { .. })),
+            OrbEvent::Duplicates { .. } => self.events.retain(|e| !matches!(e, OrbEvent::Duplicates { .. })),
+            _ => {}
+        }
+        self.stats.coalesced.fetch_add((before - self.events.len()) as u64, Ordering::Relaxed);
+
+        self.events.push_back(event);
+        if self.events.len() > self.limit {
+            let oldest = self.events.pop_front();
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            warn!("📪 [QUEUE] {} events waiting for the UI, dropped {:?}", self.limit, oldest);
+        }
+    }
+
+    /// Put an event taken with `pop` back in front, as is
+    pub fn push_front(&mut self, event: OrbEvent) {
+        self.events.push_front(event);
+    }
+
+    pub fn pop(&mut self) -> Option<OrbEvent> {
+        self.events.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::str::FromStr;
+use crate::graph::{AudioGraph, Orb, OrbKind};
+
+/// Output format of a graph dump
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Graphviz, for `dot -Tsvg`
+    Dot,
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dot" => Ok(Self::Dot),
+            "json" => Ok(Self::Json),
+            other => bail!("Unknown export format '{}' (expected dot or json)", other),
+        }
+    }
+}
+
+/// Render a snapshot of the graph for bug reports.
+/// Orbs and clusters are sorted so two dumps of the same state diff cleanly.
+pub fn export_graph(graph: &AudioGraph, default_sink: Option<&str>, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&to_json(graph, default_sink)).unwrap_or_default(),
+        ExportFormat::Dot => to_dot(graph, default_sink),
+    }
+}
+
+fn sorted_orbs(graph: &AudioGraph) -> Vec<&Orb> {
+    let mut orbs: Vec<&Orb> = graph.orbs.values().collect();
+    orbs.sort_by(|a, b| (&a.node_name, a.id).cmp(&(&b.node_name, b.id)));
+    orbs
+}
+
+fn kind_name(kind: &OrbKind) -> &'static str {
+    match kind {
+        OrbKind::PhysicalSink { .. } => "sink",
+        OrbKind::ApplicationSource { .. } => "app",
+        OrbKind::BeamOutput { .. } => "beam",
+        OrbKind::Cluster { .. } => "cluster",
+    }
+}
+
+fn to_json(graph: &AudioGraph, default_sink: Option<&str>) -> Value {
+    let orbs: Vec<Value> = sorted_orbs(graph).into_iter()
+        .map(|orb| json!({
+            "id": orb.id.to_string(),
+            "pw_id": orb.pw_id,
+            "node_name": orb.node_name,
+            "name": orb.name,
+            "kind": kind_name(&orb.kind),
+            "parent": orb.membership().map(|id| id.to_string()),
+            "zone": orb.zone,
+            "latency_ms": orb.latency_ms,
+            "bus": format!("{:?}", orb.bus),
+        }))
+        .collect();
+
+    let mut clusters: Vec<_> = graph.clusters.values().collect();
+    clusters.sort_by(|a, b| a.sink_name.cmp(&b.sink_name));
+    let clusters: Vec<Value> = clusters.into_iter()
+        .map(|cluster| json!({
+            "id": cluster.id.to_string(),
+            "sink_name": cluster.sink_name,
+            "module_id": cluster.module_id,
+            "member_modules": cluster.member_modules,
+            "external": cluster.external,
+            "latency_ms": cluster.latency_ms,
+            "saved_default_sink": cluster.saved_default_sink,
+            "members": cluster.members().iter()
+                .map(|id| json!({
+                    "id": id.to_string(),
+                    "node_name": graph.orbs.get(id).map(|o| o.node_name.as_str()),
+                }))
+                .collect::<Vec<_>>(),
+        }))
+        .collect();
+
+    let mut parked: Vec<(&String, &u32)> = graph.parked.iter().collect();
+    parked.sort();
+
+    json!({
+        "default_sink": default_sink,
+        "orbs": orbs,
+        "clusters": clusters,
+        "parked": parked.into_iter().map(|(name, pw_id)| json!({ "node_name": name, "pw_id": pw_id })).collect::<Vec<_>>(),
+    })
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn to_dot(graph: &AudioGraph, default_sink: Option<&str>) -> String {
+    let mut out = String::from("digraph auralis {\n    rankdir=LR;\n    node [fontname=\"sans\"];\n");
+
+    for orb in sorted_orbs(graph) {
+        let mut label = format!("{}\\n{} (pw {})", dot_escape(&orb.name), dot_escape(&orb.node_name), orb.pw_id);
+        let shape = match &orb.kind {
+            OrbKind::Cluster { .. } => {
+                if let Some(cluster) = graph.clusters.get(&orb.id) {
+                    let _ = write!(label, "\\nmodule {}", cluster.module_id);
+                    if cluster.external {
+                        label.push_str(" (external)");
+                    }
+                }
+                "box3d"
+            }
+            OrbKind::ApplicationSource { .. } => "ellipse",
+            _ => "box",
+        };
+        // Hidden cluster members are dashed, the default sink is bold
+        let mut style = Vec::new();
+        if orb.membership().is_some() {
+            style.push("dashed");
+        }
+        if default_sink == Some(orb.node_name.as_str()) {
+            style.push("bold");
+        }
+        let _ = writeln!(out, "    \"{}\" [label=\"{}\" shape={} style=\"{}\"];", orb.id, label, shape, style.join(","));
+    }
+
+    let mut clusters: Vec<_> = graph.clusters.values().collect();
+    clusters.sort_by(|a, b| a.sink_name.cmp(&b.sink_name));
+    for cluster in clusters {
+        for member in cluster.members() {
+            let _ = writeln!(out, "    \"{}\" -> \"{}\";", cluster.id, member);
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
@@ -0,0 +1,246 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+// What a device can play, read from its node's `EnumFormat` params (and what it plays right
+// now from `Format`) as `pw-dump` prints them. Shown in the Properties panel, and the sample
+// rate of a cluster's combine-sink is negotiated from them. The cluster's channel layout goes
+// by `audio.channels` (see `channels`), not by these.
+
+/// A rate range reaching this means "any rate": adapter nodes resample, and say so with 1..i32::MAX
+const ANY_RATE: u32 = i32::MAX as u32;
+
+/// Rate of a cluster whose members don't agree on one (or don't say): PipeWire's default clock rate
+pub const DEFAULT_RATE: u32 = 48000;
+
+/// Rates a cluster may run at, a device's range is only checked against these
+const STANDARD_RATES: [u32; 11] = [8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000, 176400, 192000];
+
+/// Sample formats from best to worst; ones not listed rank below all of these
+const SAMPLE_FORMAT_RANK: [&str; 8] = ["F64LE", "F32LE", "S32LE", "S24_32LE", "S24LE", "S16LE", "S8", "U8"];
+
+/// One `EnumFormat` entry of a node, or its current `Format`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AudioFormat {
+    /// Sample formats as PipeWire names them ("S16LE", "F32LE", ...)
+    pub sample_formats: Vec<String>,
+    /// Sample rates in Hz, when listed one by one
+    pub rates: Vec<u32>,
+    /// Any rate from the first to the second (Hz), when given as a range
+    pub rate_range: Option<(u32, u32)>,
+    /// Most channels the format carries, `None` if it doesn't say
+    pub max_channels: Option<u32>,
+}
+
+/// Formats of a node: everything it offers and, while it's configured, the one in use
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeFormats {
+    pub supported: Vec<AudioFormat>,
+    pub current: Option<AudioFormat>,
+}
+
+impl AudioFormat {
+    /// Everything a device offers as one format: all sample formats and rates, the widest
+    /// rate range and the most channels
+    pub fn combined(formats: &[AudioFormat]) -> AudioFormat {
+        let mut combined = AudioFormat { max_channels: max_channels(formats), ..Default::default() };
+        for format in formats {
+            for sample_format in &format.sample_formats {
+                if !combined.sample_formats.contains(sample_format) {
+                    combined.sample_formats.push(sample_format.clone());
+                }
+            }
+            combined.rates.extend(&format.rates);
+            combined.rate_range = match (combined.rate_range, format.rate_range) {
+                (Some((a, b)), Some((c, d))) => Some((a.min(c), b.max(d))),
+                (range, None) | (None, range) => range,
+            };
+        }
+        combined.rates.sort_unstable();
+        combined.rates.dedup();
+        combined
+    }
+
+    /// The first listed rate, the one a negotiated format runs at
+    pub fn rate(&self) -> Option<u32> {
+        self.rates.first().copied()
+    }
+
+    /// Whether the format plays `rate`, `None` if it doesn't constrain rates (says nothing
+    /// about them, or resamples anything)
+    fn plays_rate(&self, rate: u32) -> Option<bool> {
+        let any = self.rate_range.is_some_and(|(min, max)| min <= 1 && max >= ANY_RATE);
+        if any || (self.rates.is_empty() && self.rate_range.is_none()) {
+            return None;
+        }
+        let in_range = self.rate_range.is_some_and(|(min, max)| (min..=max).contains(&rate));
+        Some(self.rates.contains(&rate) || in_range)
+    }
+
+    /// e.g. "S32LE · 48 kHz · 2 ch", leaving out what isn't known
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(format) = self.sample_formats.first() {
+            parts.push(format.clone());
+        }
+        if let Some(rates) = rates_label(std::slice::from_ref(self)) {
+            parts.push(rates);
+        }
+        if let Some(channels) = self.max_channels {
+            parts.push(format!("{} ch", channels));
+        }
+        match parts.is_empty() {
+            true => "Unknown".to_string(),
+            false => parts.join(" · "),
+        }
+    }
+}
+
+/// Format a cluster's combine-sink runs at, from what each member can play (one entry per
+/// member, see `AudioFormat::combined`). Members that don't report something don't constrain it.
+/// - rate: the highest standard rate every member plays natively, so none of them resamples;
+///   if they share none (a 44.1 kHz-only and a 48 kHz-only device), `DEFAULT_RATE` and the
+///   others resample
+/// - channels: the fewest any member has; a mono member makes it 1 (it is downmixed, see
+///   `channels::common_layout`)
+/// - sample format: the best one every member lists, none if they share none
+pub fn negotiate_cluster_format(members: &[AudioFormat]) -> AudioFormat {
+    // No member refusing it and at least one actually playing it
+    let rate = STANDARD_RATES.iter()
+        .rev()
+        .copied()
+        .find(|&rate| {
+            members.iter().all(|m| m.plays_rate(rate) != Some(false))
+                && members.iter().any(|m| m.plays_rate(rate) == Some(true))
+        })
+        .unwrap_or(DEFAULT_RATE);
+
+    let max_channels = members.iter().filter_map(|m| m.max_channels).min();
+
+    let rank = |format: &String| SAMPLE_FORMAT_RANK.iter().position(|f| f == format).unwrap_or(SAMPLE_FORMAT_RANK.len());
+    let listing: Vec<&AudioFormat> = members.iter().filter(|m| !m.sample_formats.is_empty()).collect();
+    let sample_format = listing.first()
+        .and_then(|first| first.sample_formats.iter()
+            .filter(|f| listing.iter().all(|m| m.sample_formats.contains(f)))
+            .min_by_key(|f| rank(f)));
+
+    AudioFormat {
+        sample_formats: sample_format.cloned().into_iter().collect(),
+        rates: vec![rate],
+        rate_range: None,
+        max_channels,
+    }
+}
+
+/// Most channels any of the formats carries
+pub fn max_channels(formats: &[AudioFormat]) -> Option<u32> {
+    formats.iter().filter_map(|f| f.max_channels).max()
+}
+
+/// Sample rates across all formats, e.g. "44.1, 48, 96 kHz" or "8–192 kHz"; `None` if none say
+pub fn rates_label(formats: &[AudioFormat]) -> Option<String> {
+    let mut rates: Vec<u32> = formats.iter().flat_map(|f| f.rates.iter().copied()).collect();
+    rates.sort_unstable();
+    rates.dedup();
+    let mut parts: Vec<String> = Vec::new();
+    if !rates.is_empty() {
+        let listed: Vec<String> = rates.iter().map(|&r| khz(r)).collect();
+        parts.push(format!("{} kHz", listed.join(", ")));
+    }
+
+    let ranges = formats.iter().filter_map(|f| f.rate_range);
+    if let Some((min, max)) = ranges.reduce(|(a, b), (c, d)| (a.min(c), b.max(d))) {
+        if min <= 1 && max >= ANY_RATE {
+            parts.push("any (resampled)".to_string());
+        } else {
+            parts.push(format!("{}–{} kHz", khz(min), khz(max)));
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// 44100 -> "44.1", 48000 -> "48"
+fn khz(rate: u32) -> String {
+    (rate as f64 / 1000.0).to_string()
+}
+
+/// Formats of node `node_id` in `pw-dump` output. A node without params gives empty formats.
+pub fn parse_pw_dump(stdout: &str, node_id: u32) -> Result<NodeFormats> {
+    let objects: Vec<Value> = serde_json::from_str(stdout)?;
+    let node = objects.iter()
+        .find(|o| o["id"].as_u64() == Some(node_id.into()))
+        .ok_or_else(|| anyhow!("pw-dump has no object {}", node_id))?;
+
+    let params = &node["info"]["params"];
+    let parse_all = |key: &str| -> Vec<AudioFormat> {
+        params[key].as_array()
+            .map(|entries| entries.iter().filter_map(parse_format).collect())
+            .unwrap_or_default()
+    };
+    Ok(NodeFormats {
+        supported: parse_all("EnumFormat"),
+        current: parse_all("Format").into_iter().next(),
+    })
+}
+
+/// One raw audio format object; other media types are skipped
+fn parse_format(value: &Value) -> Option<AudioFormat> {
+    if value["mediaType"].as_str() != Some("audio") || value["mediaSubtype"].as_str() != Some("raw") {
+        return None;
+    }
+
+    let sample_formats = match choice(&value["format"]) {
+        Some(Choice::List(values)) => values.into_iter().filter_map(Value::as_str).map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+    let (rates, rate_range) = match choice(&value["rate"]) {
+        Some(Choice::List(values)) => (values.into_iter().filter_map(as_u32).collect(), None),
+        Some(Choice::Range(min, max)) => (Vec::new(), as_u32(min).zip(as_u32(max))),
+        None => (Vec::new(), None),
+    };
+    let max_channels = match choice(&value["channels"]) {
+        Some(Choice::List(values)) => values.into_iter().filter_map(as_u32).max(),
+        Some(Choice::Range(_, max)) => as_u32(max),
+        None => None,
+    };
+    Some(AudioFormat { sample_formats, rates, rate_range, max_channels })
+}
+
+/// A property's possible values
+enum Choice<'a> {
+    List(Vec<&'a Value>),
+    Range(&'a Value, &'a Value),
+}
+
+/// pw-dump prints a fixed property as the value itself, a choice as
+/// `{"default": .., "alt1": .., ...}` (enum) or `{"default": .., "min": .., "max": ..}` (range)
+fn choice(value: &Value) -> Option<Choice<'_>> {
+    match value {
+        Value::Null => None,
+        Value::Object(fields) => {
+            if let (Some(min), Some(max)) = (fields.get("min"), fields.get("max")) {
+                return Some(Choice::Range(min, max));
+            }
+            // Default first, then the alternatives in order (keys don't keep theirs).
+            // The default is usually repeated as the first alternative.
+            let mut indexed: Vec<(usize, &Value)> = fields.iter()
+                .filter_map(|(key, v)| match key.as_str() {
+                    "default" => Some((0, v)),
+                    key => key.strip_prefix("alt")?.parse().ok().map(|i| (i, v)),
+                })
+                .collect();
+            indexed.sort_by_key(|(i, _)| *i);
+            let mut values: Vec<&Value> = Vec::new();
+            for (_, v) in indexed {
+                if !values.contains(&v) {
+                    values.push(v);
+                }
+            }
+            (!values.is_empty()).then_some(Choice::List(values))
+        }
+        value => Some(Choice::List(vec![value])),
+    }
+}
+
+fn as_u32(value: &Value) -> Option<u32> {
+    value.as_u64().and_then(|v| u32::try_from(v).ok())
+}
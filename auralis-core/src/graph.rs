@@ -1,12 +1,55 @@
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::time::SystemTime;
+use crate::channels;
+use crate::export::ExportFormat;
+use crate::formats::{self, AudioFormat, NodeFormats};
+use crate::persistence::{self, Appearance, CanvasLayout, ClusterPrefs, Settings, TurnServer};
+use crate::sink_names;
+use crate::system_audio::{combine_sink_args, loopback_args, null_sink_args, pactl_command_line, remap_sink_args};
+use crate::topology::Topology;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum OrbKind {
     PhysicalSink { description: String },   // e.g. "Sony Headphones"
     ApplicationSource { app_name: String }, // e.g. "Firefox"
-    BeamOutput { session_id: String },      // e.g. "Phone Beam"
-    Cluster { devices: Vec<String> },       // Merged devices
+    /// Audio streamed to a remote peer, e.g. "Phone Beam". The orb's `node_name` is the beamed
+    /// device's or cluster's sink, its `status` the connection state (see `UiCommand::BeamStatus`).
+    BeamOutput { session_id: String, peer_url: String, codec: String, bitrate_kbps: u32, started_at: SystemTime },
+    /// Merged devices: descriptions for display, and the member orbs in the same order.
+    /// `mono` lists the members that get a mono downmix, `volumes` the members whose
+    /// device volume (percent) was set from the cluster.
+    Cluster { devices: Vec<String>, members: Vec<Uuid>, mono: Vec<Uuid>, volumes: HashMap<Uuid, u32> },
+}
+
+/// What connecting (dropping) one orb onto another does, see `UiCommand::Connect`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionKind {
+    /// Two devices become a new cluster
+    NewCluster,
+    /// A device joins a cluster, whichever of the two was dropped
+    AddToCluster,
+    /// Two clusters are unloaded and rebuilt as one
+    MergeClusters,
+    /// An app plays to a device or cluster
+    Link,
+    /// Nothing happens
+    Invalid,
+}
+
+pub fn classify_connection(source: &OrbKind, target: &OrbKind) -> ConnectionKind {
+    match (source, target) {
+        (OrbKind::PhysicalSink { .. }, OrbKind::PhysicalSink { .. }) => ConnectionKind::NewCluster,
+        (OrbKind::PhysicalSink { .. }, OrbKind::Cluster { .. }) |
+        (OrbKind::Cluster { .. }, OrbKind::PhysicalSink { .. }) => ConnectionKind::AddToCluster,
+        (OrbKind::Cluster { .. }, OrbKind::Cluster { .. }) => ConnectionKind::MergeClusters,
+        (OrbKind::ApplicationSource { .. }, OrbKind::PhysicalSink { .. }) |
+        (OrbKind::ApplicationSource { .. }, OrbKind::Cluster { .. }) => ConnectionKind::Link,
+        _ => ConnectionKind::Invalid,
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -15,38 +58,1102 @@ pub enum OrbState {
     Orbiting { parent_id: Uuid },
 }
 
+/// Shortest dropout grace of an HDMI/DisplayPort cluster member, see `DeviceBus::dropout_grace_ms`
+pub const HDMI_DROPOUT_GRACE_MS: u32 = 5 * 60 * 1000;
+
+/// How a device is attached to the machine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeviceBus {
+    Bluetooth,
+    Usb,
+    Internal, // PCI / on-board sound
+    Hdmi,
+    #[default]
+    Unknown,
+}
+
+impl DeviceBus {
+    /// Classify from PipeWire node properties (`device.api`, `device.bus`, `factory.name`, `node.name`)
+    pub fn detect(device_api: Option<&str>, device_bus: Option<&str>, factory_name: Option<&str>, node_name: &str) -> Self {
+        let node_name = node_name.to_lowercase();
+
+        if device_api == Some("bluez5")
+            || device_bus == Some("bluetooth")
+            || factory_name.is_some_and(|f| f.contains("bluez"))
+            || node_name.starts_with("bluez_")
+        {
+            return DeviceBus::Bluetooth;
+        }
+        // HDMI/DisplayPort outputs sit on the PCI sound card, so check them first
+        if node_name.contains("hdmi") || node_name.contains("displayport") {
+            return DeviceBus::Hdmi;
+        }
+        match device_bus {
+            Some("usb") => return DeviceBus::Usb,
+            Some("pci") | Some("isa") => return DeviceBus::Internal,
+            _ => {}
+        }
+        if node_name.contains("usb") {
+            return DeviceBus::Usb;
+        }
+        if node_name.contains("pci") {
+            return DeviceBus::Internal;
+        }
+        DeviceBus::Unknown
+    }
+
+    /// Whether the device comes and goes with a monitor (asleep, switched off, other input)
+    pub fn follows_monitor(self) -> bool {
+        self == DeviceBus::Hdmi
+    }
+
+    /// How long a cluster member on this bus may be gone before its cluster is dissolved,
+    /// given `Settings::dropout_grace_ms`. Monitors sleep for minutes, so HDMI/DisplayPort
+    /// members get at least `HDMI_DROPOUT_GRACE_MS`; 0 (dissolve right away) stays 0.
+    pub fn dropout_grace_ms(self, setting_ms: u32) -> u32 {
+        match self {
+            DeviceBus::Hdmi if setting_ms > 0 => setting_ms.max(HDMI_DROPOUT_GRACE_MS),
+            _ => setting_ms,
+        }
+    }
+
+    /// Delay for the wired members of a cluster on `buses` so they wait for the Bluetooth ones,
+    /// given `Settings::bluetooth_offset_ms`. `None` unless the cluster mixes the two (and the
+    /// setting isn't 0): Bluetooth members play late by their codec's buffering, the others don't.
+    pub fn bluetooth_offset_ms(buses: &[DeviceBus], setting_ms: u32) -> Option<u32> {
+        let bluetooth = buses.iter().filter(|&&bus| bus == DeviceBus::Bluetooth).count();
+        (setting_ms > 0 && bluetooth > 0 && bluetooth < buses.len()).then_some(setting_ms)
+    }
+
+    /// Symbolic icon for the bus, `None` when unknown
+    pub fn icon_name(self) -> Option<&'static str> {
+        match self {
+            DeviceBus::Bluetooth => Some("bluetooth-symbolic"),
+            DeviceBus::Usb => Some("media-removable-symbolic"),
+            DeviceBus::Internal => Some("audio-card-symbolic"),
+            DeviceBus::Hdmi => Some("video-display-symbolic"),
+            DeviceBus::Unknown => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Orb {
     pub id: Uuid,
     pub pw_id: u32,             // PipeWire Node ID
+    pub node_name: String,      // PipeWire node.name (combine-sink name for clusters)
     pub kind: OrbKind,
     pub name: String,
     pub icon_name: String,
     pub status: String,
     pub state: OrbState,
+    pub zone: Option<String>,   // Room/zone a cluster belongs to, e.g. "Living Room"
+    pub latency_ms: Option<u32>, // Measured latency of a cluster's combine-sink
+    pub bus: DeviceBus,
+    pub channels: Option<u32>,  // audio.channels of a sink, if it reports one
+    /// Formats a device can play (its EnumFormat), empty until read or if it doesn't say
+    pub formats: Vec<AudioFormat>,
+    /// Format a device currently plays in, while it's configured for one
+    pub format: Option<AudioFormat>,
     // Physics state (mirrored from UI)
     pub position: (f64, f64),
     pub velocity: (f64, f64),
 }
 
+impl Orb {
+    /// Cluster this device is currently a member of
+    pub fn membership(&self) -> Option<Uuid> {
+        match self.state {
+            OrbState::Orbiting { parent_id } => Some(parent_id),
+            OrbState::Floating => None,
+        }
+    }
+
+    /// A cluster card holding a single device, waiting for a second one (see `UiCommand::StageCluster`)
+    pub fn is_staged(&self) -> bool {
+        matches!(self.kind, OrbKind::Cluster { .. }) && self.status == STAGED_STATUS
+    }
+}
+
+/// Status of a staged cluster's card
+pub const STAGED_STATUS: &str = "Staged";
+
 #[derive(Debug, Clone)]
 pub struct Cluster {
     pub id: Uuid,
     pub master_sink_id: Uuid,
     pub satellites: Vec<Uuid>,
     pub latency_ms: u32,
+    pub module_id: u32,                     // combine-sink module backing this cluster
+    pub sink_name: String,                  // combine-sink node name
+    pub saved_default_sink: Option<String>, // Default sink to restore when the cluster goes away
+    pub member_modules: Vec<u32>,           // delay and downmix modules routing individual members
+    pub external: bool,                     // combine-sink loaded by someone else, adopted at discovery
+}
+
+impl Cluster {
+    /// Master sink followed by the satellites
+    pub fn members(&self) -> Vec<Uuid> {
+        let mut members = vec![self.master_sink_id];
+        members.extend(self.satellites.iter().copied());
+        members
+    }
+
+    /// Every module backing this cluster, combine-sink first
+    pub fn modules(&self) -> Vec<u32> {
+        let mut modules = vec![self.module_id];
+        modules.extend(self.member_modules.iter().copied());
+        modules
+    }
+
+    /// Shell script with the `pactl` commands that build this cluster without Auralis: member
+    /// downmix and delay sinks, the combine-sink, member volumes, then making it the default and
+    /// moving what plays onto it. Member delays come from `prefs`, the rest from `graph`.
+    /// Sinks are named after the cluster; with Auralis' prefix they'd be swept up as leftovers.
+    pub fn to_shell_script(&self, graph: &AudioGraph, prefs: &ClusterPrefs) -> String {
+        let cluster_orb = graph.orbs.get(&self.id);
+        let name = cluster_orb.map_or("Cluster", |o| o.name.as_str());
+        let (mono, volumes) = match cluster_orb.map(|o| &o.kind) {
+            Some(OrbKind::Cluster { mono, volumes, .. }) => (mono.clone(), volumes.clone()),
+            _ => (Vec::new(), HashMap::new()),
+        };
+        let members: Vec<&Orb> = self.members().iter().filter_map(|id| graph.orbs.get(id)).collect();
+        let member_channels: Vec<Option<u32>> = members.iter().map(|o| o.channels).collect();
+        let (channel_count, channel_map) = cluster_orb
+            .and_then(|o| o.channels)
+            .and_then(|c| Some((c, channels::channel_map(c)?)))
+            .unwrap_or_else(|| channels::common_layout(&member_channels));
+        let member_formats: Vec<AudioFormat> = members.iter().map(|o| AudioFormat::combined(&o.formats)).collect();
+        let rate = formats::negotiate_cluster_format(&member_formats).rate().unwrap_or(formats::DEFAULT_RATE);
+        let base = script_sink_name(name);
+
+        let node_names: Vec<&str> = members.iter().map(|o| o.node_name.as_str()).collect();
+        let title: String = name.chars().filter(|c| !c.is_control()).collect();
+        let mut lines = vec![
+            "#!/bin/sh".to_string(),
+            format!("# Auralis cluster \"{}\" ({})", title, node_names.join(", ")),
+            "set -e".to_string(),
+            String::new(),
+        ];
+
+        // Same chain as `PipeWireClient::create_cluster`: combine -> delay -> mono -> device
+        let mut slaves = Vec::with_capacity(members.len());
+        for (i, member) in members.iter().enumerate() {
+            let mut target = member.node_name.clone();
+            if mono.contains(&member.id) {
+                let mono_sink = format!("{}_mono_{}", base, i + 1);
+                lines.push(pactl_command_line(&remap_sink_args(&mono_sink, &target, "mono")));
+                target = mono_sink;
+            }
+            match prefs.delays.get(&member.node_name) {
+                Some(&delay_ms) if delay_ms > 0 => {
+                    let delay_sink = format!("{}_delay_{}", base, i + 1);
+                    let description = format!("Auralis delay ({})", member.node_name);
+                    lines.push(pactl_command_line(&null_sink_args(&delay_sink, &description)));
+                    lines.push(pactl_command_line(&loopback_args(&format!("{}.monitor", delay_sink), &target, delay_ms)));
+                    slaves.push(delay_sink);
+                }
+                _ => slaves.push(target),
+            }
+        }
+        lines.push(pactl_command_line(&combine_sink_args(&base, &slaves, rate, channel_count, channel_map)));
+
+        for member in &members {
+            if let Some(percent) = volumes.get(&member.id) {
+                lines.push(pactl_command_line(&["set-sink-volume".to_string(), member.node_name.clone(), format!("{}%", percent)]));
+            }
+        }
+        lines.push(pactl_command_line(&["set-default-sink".to_string(), base.clone()]));
+        lines.push(format!(
+            "for input in $(pactl list short sink-inputs | cut -f1); do pactl move-sink-input \"$input\" {}; done",
+            base
+        ));
+        lines.join("\n") + "\n"
+    }
+}
+
+/// Sink name for a cluster built by a script: its name in lowercase, `_` for anything else
+fn script_sink_name(cluster_name: &str) -> String {
+    let mut name = String::new();
+    for c in cluster_name.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    let name = name.trim_end_matches('_');
+    match name {
+        "" => "cluster".to_string(),
+        name if sink_names::is_own(name) => format!("cluster_{}", name),
+        name => name.to_string(),
+    }
 }
 
-#[derive(Debug, Default)]
+/// A route linked with pw-link: the nodes it joins and the links PipeWire announced between
+/// them (one per channel), so unlinking removes exactly those
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OwnLink {
+    /// Output node, input node
+    pub nodes: (u32, u32),
+    pub link_ids: Vec<u32>,
+}
+
+/// Authoritative model of everything the core knows about.
+/// Mutations return the `OrbEvent`s the UI needs to mirror the change.
+#[derive(Debug, Clone, Default)]
 pub struct AudioGraph {
     pub orbs: HashMap<Uuid, Orb>,
     pub clusters: HashMap<Uuid, Cluster>,
+    /// Cluster members re-announced by PipeWire while hidden (node.name -> new PW_ID)
+    pub parked: HashMap<String, u32>,
+    /// Members whose node vanished, kept around for a grace period (orb -> dropout token)
+    pub pending_removal: HashMap<Uuid, u64>,
+    /// Card of each device's node (`discovery::device_key`). A profile switch replaces the
+    /// node, with a new PipeWire ID and node name, but not the card; see `replace_node`.
+    pub sink_devices: HashMap<Uuid, String>,
+    next_dropout_token: u64,
+    /// PipeWire links (link ID -> output node, input node), for working out what plays where
+    pub links: HashMap<u32, (u32, u32)>,
+    /// Last routes sent to the UI, see `refresh_routes`
+    pub routes: Vec<(Uuid, Uuid)>,
+    /// Routes Auralis linked itself (app, output), see `track_own_link`
+    pub own_links: HashMap<(Uuid, Uuid), OwnLink>,
+    /// Devices also playing another device's audio (from, also) -> loopback module ID,
+    /// see `UiCommand::Duplicate`
+    pub duplicates: HashMap<(Uuid, Uuid), u32>,
+    /// Clusters of one device, with no combine-sink yet (card orb -> device),
+    /// see `stage_cluster`. Not in `clusters`, there's nothing loaded to manage.
+    pub staged: HashMap<Uuid, Uuid>,
+    /// App orbs that announced a `media.role` we route (one of `media_roles::ROLES`)
+    pub media_roles: HashMap<Uuid, String>,
+    /// The server's default sink as last seen, see `observe_default_sink`
+    pub default_sink: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+impl AudioGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_orb(&mut self, orb: Orb) -> Vec<OrbEvent> {
+        self.orbs.insert(orb.id, orb.clone());
+        vec![OrbEvent::Add(orb)]
+    }
+
+    /// Remove an orb. Cluster membership is left alone, see `dissolve_cluster`; a staged
+    /// cluster's card goes with its device, there's nothing left to stage.
+    pub fn remove_orb(&mut self, id: Uuid) -> Vec<OrbEvent> {
+        self.media_roles.remove(&id);
+        self.sink_devices.remove(&id);
+        self.own_links.retain(|&(app, output), _| app != id && output != id);
+        let mut events = match self.orbs.remove(&id) {
+            Some(_) => vec![OrbEvent::Remove(id)],
+            None => Vec::new(),
+        };
+        let cards: Vec<Uuid> = self.staged.iter()
+            .filter(|&(&card, &device)| card == id || device == id)
+            .map(|(&card, _)| card)
+            .collect();
+        for card in cards {
+            self.staged.remove(&card);
+            if card != id && self.orbs.remove(&card).is_some() {
+                events.push(OrbEvent::Remove(card));
+            }
+        }
+        events
+    }
+
+    /// Orb backing a PipeWire global. Cluster orbs are skipped since their pw_id is a placeholder.
+    pub fn orb_id_by_pw_id(&self, pw_id: u32) -> Option<Uuid> {
+        self.orbs.values()
+            .find(|o| o.pw_id == pw_id && !matches!(o.kind, OrbKind::Cluster { .. }))
+            .map(|o| o.id)
+    }
+
+    /// Status of the device or stream with this PipeWire ID, as reported by its node.
+    /// Clusters keep their own status.
+    pub fn set_node_status(&mut self, pw_id: u32, status: &str) -> Vec<OrbEvent> {
+        let orb = self.orbs.values_mut()
+            .find(|o| o.pw_id == pw_id && matches!(o.kind, OrbKind::PhysicalSink { .. } | OrbKind::ApplicationSource { .. }));
+        match orb {
+            Some(orb) if orb.status != status => {
+                orb.status = status.to_string();
+                vec![OrbEvent::Update(orb.clone())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn beam_id(&self, session_id: &str) -> Option<Uuid> {
+        self.orbs.values()
+            .find(|o| matches!(&o.kind, OrbKind::BeamOutput { session_id: s, .. } if s == session_id))
+            .map(|o| o.id)
+    }
+
+    /// Formats read from a device's node, reported only if they changed
+    pub fn set_formats(&mut self, orb_id: Uuid, formats: NodeFormats) -> Vec<OrbEvent> {
+        match self.orbs.get_mut(&orb_id) {
+            Some(orb) if orb.formats != formats.supported || orb.format != formats.current => {
+                orb.formats = formats.supported;
+                orb.format = formats.current;
+                vec![OrbEvent::Update(orb.clone())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Connection state of a beam session changed
+    pub fn set_beam_status(&mut self, session_id: &str, status: &str) -> Vec<OrbEvent> {
+        let Some(orb) = self.beam_id(session_id).and_then(|id| self.orbs.get_mut(&id)) else {
+            return Vec::new();
+        };
+        if orb.status == status {
+            return Vec::new();
+        }
+        orb.status = status.to_string();
+        vec![OrbEvent::Update(orb.clone())]
+    }
+
+    /// Beam orb carrying the audio of the sink called `node_name`
+    pub fn find_beam(&self, node_name: &str) -> Option<&Orb> {
+        self.orbs.values()
+            .find(|o| matches!(o.kind, OrbKind::BeamOutput { .. }) && o.node_name == node_name)
+    }
+
+    /// Remove the orb of a beam session, if there is one
+    pub fn remove_beam(&mut self, session_id: &str) -> Vec<OrbEvent> {
+        let beam = self.beam_id(session_id);
+        match beam {
+            Some(id) => self.remove_orb(id),
+            None => Vec::new(),
+        }
+    }
+
+    /// Physical sink with the given node.name, clustered or not.
+    /// Descriptions are for display only: identical devices can share one.
+    pub fn find_sink(&self, node_name: &str) -> Option<&Orb> {
+        self.orbs.values().find(|o| {
+            matches!(o.kind, OrbKind::PhysicalSink { .. }) && o.node_name == node_name
+        })
+    }
+
+    /// Unclustered physical sink with the given description (first match wins)
+    pub fn find_floating_sink_by_description(&self, description: &str) -> Option<&Orb> {
+        self.orbs.values().find(|o| {
+            o.state == OrbState::Floating
+                && matches!(&o.kind, OrbKind::PhysicalSink { description: d } if d == description)
+        })
+    }
+
+    pub fn is_cluster_member(&self, node_name: &str) -> bool {
+        self.find_sink(node_name)
+            .map(|o| matches!(o.state, OrbState::Orbiting { .. }))
+            .unwrap_or(false)
+    }
+
+    /// Cluster the given orb is currently part of
+    pub fn cluster_of(&self, orb_id: Uuid) -> Option<Uuid> {
+        match self.orbs.get(&orb_id)?.state {
+            OrbState::Orbiting { parent_id } => Some(parent_id),
+            OrbState::Floating => None,
+        }
+    }
+
+    /// Remember a re-announced cluster member so it can be restored with its new ID
+    pub fn park(&mut self, node_name: &str, pw_id: u32) {
+        self.parked.insert(node_name.to_string(), pw_id);
+    }
+
+    pub fn forget_parked(&mut self, pw_id: u32) {
+        self.parked.retain(|_, v| *v != pw_id);
+    }
+
+    /// A cluster member's node went away. Instead of dissolving right away the member is
+    /// kept as is; returns the token `expire_dropout` needs, `None` if it's not a member.
+    pub fn begin_dropout(&mut self, orb_id: Uuid) -> Option<u64> {
+        self.cluster_of(orb_id)?;
+        Some(self.pend_removal(orb_id))
+    }
+
+    /// A device's node went away, maybe for a profile switch that brings up another node of
+    /// its card. Like `begin_dropout`, for any device whose card is known, clustered or not.
+    pub fn begin_replacement(&mut self, orb_id: Uuid) -> Option<u64> {
+        self.sink_devices.get(&orb_id)?;
+        Some(self.pend_removal(orb_id))
+    }
+
+    fn pend_removal(&mut self, orb_id: Uuid) -> u64 {
+        self.next_dropout_token += 1;
+        self.pending_removal.insert(orb_id, self.next_dropout_token);
+        self.next_dropout_token
+    }
+
+    /// A node came back while its orb was pending removal: take over the new PipeWire ID.
+    /// Returns `None` if nothing was pending for this node.
+    pub fn finish_dropout(&mut self, node_name: &str, pw_id: u32) -> Option<Vec<OrbEvent>> {
+        let orb_id = self.find_sink(node_name)?.id;
+        self.pending_removal.remove(&orb_id)?;
+
+        let orb = self.orbs.get_mut(&orb_id)?;
+        orb.pw_id = pw_id;
+        // The cluster may have been separated meanwhile, then the orb is visible
+        Some(match orb.state {
+            OrbState::Floating => vec![OrbEvent::Update(orb.clone())],
+            OrbState::Orbiting { .. } => Vec::new(),
+        })
+    }
+
+    /// A node of `device` (a card) appeared while a device of that card was pending removal:
+    /// it's the same device in another profile. The orb keeps its ID, cluster and place and
+    /// takes over the new node, shown as `name`. Returns the orb and its node name before,
+    /// `None` if no device of the card was pending.
+    pub fn replace_node(&mut self, device: &str, pw_id: u32, node_name: &str, description: &str, name: &str) -> Option<(Uuid, String, Vec<OrbEvent>)> {
+        let orb_id = *self.pending_removal.keys()
+            .find(|id| self.sink_devices.get(id).map(String::as_str) == Some(device))?;
+        self.pending_removal.remove(&orb_id);
+
+        let orb = self.orbs.get_mut(&orb_id)?;
+        let old_node_name = std::mem::replace(&mut orb.node_name, node_name.to_string());
+        orb.pw_id = pw_id;
+        orb.kind = OrbKind::PhysicalSink { description: description.to_string() };
+        // Formats are the old node's; the new node's are read once it's announced
+        orb.formats = Vec::new();
+        orb.format = None;
+        // Also updates the name on its cluster's card
+        let events = self.rename_device(orb_id, name.to_string());
+        Some((orb_id, old_node_name, events))
+    }
+
+    /// Grace period is over. True if the orb is still gone and should be removed for real,
+    /// false if it came back (or dropped out again, with a newer token) in the meantime.
+    pub fn expire_dropout(&mut self, orb_id: Uuid, token: u64) -> bool {
+        if self.pending_removal.get(&orb_id) != Some(&token) {
+            return false;
+        }
+        self.pending_removal.remove(&orb_id);
+        true
+    }
+
+    pub fn add_link(&mut self, link_id: u32, output_node: u32, input_node: u32) {
+        self.links.insert(link_id, (output_node, input_node));
+        for own in self.own_links.values_mut() {
+            if own.nodes == (output_node, input_node) && !own.link_ids.contains(&link_id) {
+                own.link_ids.push(link_id);
+            }
+        }
+    }
+
+    pub fn remove_link(&mut self, link_id: u32) -> bool {
+        for own in self.own_links.values_mut() {
+            own.link_ids.retain(|&id| id != link_id);
+        }
+        self.links.remove(&link_id).is_some()
+    }
+
+    /// Remember that Auralis linked `app` to `output` by joining `nodes`. PipeWire may have
+    /// announced the links already, those are picked up; later ones are by `add_link`.
+    pub fn track_own_link(&mut self, app: Uuid, output: Uuid, nodes: (u32, u32)) {
+        let mut link_ids: Vec<u32> = self.links.iter()
+            .filter(|(_, &link)| link == nodes)
+            .map(|(&link_id, _)| link_id)
+            .collect();
+        link_ids.sort();
+        self.own_links.insert((app, output), OwnLink { nodes, link_ids });
+    }
+
+    /// Stop tracking the route from `app` to `output` (every route of `app` for `None`),
+    /// returning the IDs of the links Auralis made for it
+    pub fn forget_own_links(&mut self, app: Uuid, output: Option<Uuid>) -> Vec<u32> {
+        let keys: Vec<(Uuid, Uuid)> = self.own_links.keys()
+            .filter(|&&(a, o)| a == app && output.is_none_or(|output| o == output))
+            .copied()
+            .collect();
+        let mut link_ids: Vec<u32> = keys.iter()
+            .filter_map(|key| self.own_links.remove(key))
+            .flat_map(|own| own.link_ids)
+            .collect();
+        link_ids.sort();
+        link_ids
+    }
+
+    /// Which app orb plays to which device or cluster orb, sorted and without duplicates
+    /// (a stereo stream has a link per channel). `sink_name_of` names the PipeWire nodes
+    /// of Auralis' own combine-sinks, whose cluster orbs don't carry the real ID.
+    pub fn playback_routes(&self, sink_name_of: impl Fn(u32) -> Option<String>) -> Vec<(Uuid, Uuid)> {
+        let mut routes: Vec<(Uuid, Uuid)> = self.links.values()
+            .filter_map(|&(output_node, input_node)| self.route_of_link(output_node, input_node, &sink_name_of))
+            .collect();
+        routes.sort();
+        routes.dedup();
+        routes
+    }
+
+    /// IDs of the PipeWire links that make up the route from `app` to `output`
+    pub fn route_links(&self, app: Uuid, output: Uuid, sink_name_of: impl Fn(u32) -> Option<String>) -> Vec<u32> {
+        let mut links: Vec<u32> = self.links.iter()
+            .filter(|(_, &(output_node, input_node))| {
+                self.route_of_link(output_node, input_node, &sink_name_of) == Some((app, output))
+            })
+            .map(|(&link_id, _)| link_id)
+            .collect();
+        links.sort();
+        links
+    }
+
+    /// IDs of every link going out of a PipeWire node
+    pub fn links_from(&self, node: u32) -> Vec<u32> {
+        let mut links: Vec<u32> = self.links.iter()
+            .filter(|(_, &(output_node, _))| output_node == node)
+            .map(|(&link_id, _)| link_id)
+            .collect();
+        links.sort();
+        links
+    }
+
+    fn route_of_link(&self, output_node: u32, input_node: u32, sink_name_of: impl Fn(u32) -> Option<String>) -> Option<(Uuid, Uuid)> {
+        let app = self.orbs.values()
+            .find(|o| o.pw_id == output_node && matches!(o.kind, OrbKind::ApplicationSource { .. }))?;
+        Some((app.id, self.output_orb_for_node(input_node, sink_name_of)?))
+    }
+
+    fn output_orb_for_node(&self, node: u32, sink_name_of: impl Fn(u32) -> Option<String>) -> Option<Uuid> {
+        let sink = self.orbs.values()
+            .find(|o| o.pw_id == node && matches!(o.kind, OrbKind::PhysicalSink { .. }));
+        if let Some(sink) = sink {
+            // Playing straight to a hidden member still counts for its cluster
+            return Some(self.cluster_of(sink.id).unwrap_or(sink.id));
+        }
+        if let Some(cluster) = self.external_cluster_by_pw_id(node) {
+            return Some(cluster);
+        }
+        let name = sink_name_of(node)?;
+        self.clusters.values().find(|c| c.sink_name == name).map(|c| c.id)
+    }
+
+    /// Recompute the routes, emitting `OrbEvent::Routes` only when they changed
+    pub fn refresh_routes(&mut self, sink_name_of: impl Fn(u32) -> Option<String>) -> Vec<OrbEvent> {
+        let routes = self.playback_routes(sink_name_of);
+        if routes == self.routes {
+            return Vec::new();
+        }
+        self.routes = routes.clone();
+        vec![OrbEvent::Routes { routes }]
+    }
+
+    /// Register a combine-sink as a cluster of `members`.
+    /// Members start orbiting the new cluster orb and are hidden from the UI.
+    pub fn create_cluster(
+        &mut self,
+        members: &[Uuid],
+        module_id: u32,
+        sink_name: String,
+        saved_default_sink: Option<String>,
+    ) -> (Uuid, Vec<OrbEvent>) {
+        let cluster_id = Uuid::new_v4();
+
+        let (devices, device_ids): (Vec<String>, Vec<Uuid>) = members.iter()
+            .filter_map(|id| {
+                let orb = self.orbs.get(id)?;
+                // The name as shown, which may be the user's alias for the device
+                matches!(orb.kind, OrbKind::PhysicalSink { .. }).then(|| (orb.name.clone(), *id))
+            })
+            .unzip();
+
+        let cluster_orb = Orb {
+            id: cluster_id,
+            pw_id: 999, // Placeholder
+            node_name: sink_name.clone(),
+            kind: OrbKind::Cluster { devices: devices.clone(), members: device_ids, mono: Vec::new(), volumes: HashMap::new() },
+            name: format!("Cluster ({})", devices.len()), // Simple name
+            icon_name: "audio-card".to_string(),
+            status: "Active".to_string(),
+            state: OrbState::Floating,
+            zone: None,
+            latency_ms: None,
+            bus: DeviceBus::Unknown,
+            channels: None,
+            formats: Vec::new(),
+            format: None,
+            position: (0.0, 0.0),
+            velocity: (0.0, 0.0),
+        };
+
+        let mut events = self.add_orb(cluster_orb);
+
+        for member in members {
+            if let Some(orb) = self.orbs.get_mut(member) {
+                orb.state = OrbState::Orbiting { parent_id: cluster_id };
+                // Kept so it can be restored later; the UI hides members or lists them as such
+                events.push(OrbEvent::Update(orb.clone()));
+            }
+        }
+
+        self.clusters.insert(cluster_id, Cluster {
+            id: cluster_id,
+            master_sink_id: members.first().copied().unwrap_or(cluster_id),
+            satellites: members.iter().skip(1).copied().collect(),
+            latency_ms: 0,
+            module_id,
+            sink_name,
+            saved_default_sink,
+            member_modules: Vec::new(),
+            external: false,
+        });
+
+        (cluster_id, events)
+    }
+
+    /// Put a lone device on a cluster card of its own, named after it, without loading
+    /// anything: the combine-sink is only built once a second device joins. The device
+    /// orbits the card like a cluster member. `None` if it isn't a free device.
+    pub fn stage_cluster(&mut self, device: Uuid) -> Option<(Uuid, Vec<OrbEvent>)> {
+        let orb = self.orbs.get(&device)?;
+        if !matches!(orb.kind, OrbKind::PhysicalSink { .. }) || orb.membership().is_some() {
+            return None;
+        }
+
+        let card_id = Uuid::new_v4();
+        let card = Orb {
+            id: card_id,
+            pw_id: 999, // Placeholder, like a cluster's
+            node_name: String::new(),
+            kind: OrbKind::Cluster { devices: vec![orb.name.clone()], members: vec![device], mono: Vec::new(), volumes: HashMap::new() },
+            name: orb.name.clone(),
+            icon_name: "audio-card".to_string(),
+            status: STAGED_STATUS.to_string(),
+            state: OrbState::Floating,
+            zone: None,
+            latency_ms: None,
+            bus: DeviceBus::Unknown,
+            channels: None,
+            formats: Vec::new(),
+            format: None,
+            position: (0.0, 0.0),
+            velocity: (0.0, 0.0),
+        };
+        let mut events = self.add_orb(card);
+        if let Some(orb) = self.orbs.get_mut(&device) {
+            orb.state = OrbState::Orbiting { parent_id: card_id };
+            events.push(OrbEvent::Update(orb.clone()));
+        }
+        self.staged.insert(card_id, device);
+        Some((card_id, events))
+    }
+
+    /// Take a staged cluster's card away and free its device, shown again with `restore`
+    /// (see `dissolve_cluster`). Returns the device.
+    pub fn unstage_cluster(&mut self, card: Uuid, restore: bool) -> Option<(Uuid, Vec<OrbEvent>)> {
+        let device = self.staged.remove(&card)?;
+        let mut events = self.remove_orb(card);
+        if let Some(orb) = self.orbs.get_mut(&device) {
+            orb.state = OrbState::Floating;
+            if restore {
+                if let Some(pw_id) = self.parked.remove(&orb.node_name) {
+                    orb.pw_id = pw_id;
+                }
+                events.push(OrbEvent::Add(orb.clone()));
+            }
+        }
+        Some((device, events))
+    }
+
+    /// Take over a combine-sink that Auralis didn't create: the sink's own orb is
+    /// replaced by a cluster orb (keeping its PipeWire ID) and the slaves are hidden.
+    pub fn adopt_cluster(&mut self, sink: Uuid, members: &[Uuid], module_id: u32) -> Option<(Uuid, Vec<OrbEvent>)> {
+        let sink_orb = self.orbs.get(&sink)?.clone();
+        let mut events = self.remove_orb(sink);
+
+        let (cluster_id, cluster_events) = self.create_cluster(members, module_id, sink_orb.node_name, None);
+        events.extend(cluster_events);
+        if let Some(cluster) = self.clusters.get_mut(&cluster_id) {
+            cluster.external = true;
+        }
+        events.extend(self.update_cluster_orb(cluster_id, |orb| {
+            orb.pw_id = sink_orb.pw_id;
+            orb.name = sink_orb.name;
+            orb.status = "External".to_string();
+        }));
+
+        Some((cluster_id, events))
+    }
+
+    /// Adopted cluster backed by the given PipeWire node
+    pub fn external_cluster_by_pw_id(&self, pw_id: u32) -> Option<Uuid> {
+        self.clusters.values()
+            .filter(|c| c.external)
+            .find(|c| self.orbs.get(&c.id).map(|o| o.pw_id) == Some(pw_id))
+            .map(|c| c.id)
+    }
+
+    /// Remove a cluster and release its members.
+    /// With `restore_members` the surviving members are shown in the UI again;
+    /// without it they stay hidden because they're about to join another cluster.
+    pub fn dissolve_cluster(&mut self, cluster_id: Uuid, restore_members: bool) -> Option<(Cluster, Vec<OrbEvent>)> {
+        let cluster = self.clusters.remove(&cluster_id)?;
+        let mut events = self.remove_orb(cluster_id);
+
+        for member in cluster.members() {
+            let Some(orb) = self.orbs.get_mut(&member) else { continue };
+            orb.state = OrbState::Floating;
+
+            if restore_members {
+                // Pick up the new global if the device was re-announced while hidden
+                if let Some(pw_id) = self.parked.remove(&orb.node_name) {
+                    orb.pw_id = pw_id;
+                }
+                events.push(OrbEvent::Add(orb.clone()));
+            }
+        }
+
+        Some((cluster, events))
+    }
+
+    /// Forget all clusters and show every physical device as a floating orb again.
+    /// Last resort when cluster bookkeeping and reality have drifted apart.
+    /// Adopted (external) clusters are left alone, they aren't ours to reset.
+    pub fn float_all_sinks(&mut self) -> Vec<OrbEvent> {
+        self.clusters.retain(|_, c| c.external);
+
+        let mut events = Vec::new();
+        for card in std::mem::take(&mut self.staged).into_keys() {
+            events.extend(self.remove_orb(card));
+        }
+        for orb in self.orbs.values_mut() {
+            if !matches!(orb.kind, OrbKind::PhysicalSink { .. }) {
+                continue;
+            }
+            if matches!(orb.state, OrbState::Orbiting { parent_id } if self.clusters.contains_key(&parent_id)) {
+                continue;
+            }
+            orb.state = OrbState::Floating;
+            if let Some(pw_id) = self.parked.remove(&orb.node_name) {
+                orb.pw_id = pw_id;
+            }
+            events.push(OrbEvent::Add(orb.clone()));
+        }
+        self.parked.clear();
+        events
+    }
+
+    /// Stable key for persisting per-cluster preferences, derived from member node names
+    pub fn cluster_key(&self, cluster_id: Uuid) -> Option<String> {
+        let cluster = self.clusters.get(&cluster_id)?;
+        let node_names: Vec<String> = cluster.members().iter()
+            .filter_map(|id| self.orbs.get(id).map(|o| o.node_name.clone()))
+            .collect();
+        Some(persistence::cluster_key(&node_names))
+    }
+
+    /// Show a device as `name`, also on the card of the cluster it's in (see `Settings::device_aliases`)
+    pub fn rename_device(&mut self, device: Uuid, name: String) -> Vec<OrbEvent> {
+        let Some(orb) = self.orbs.get_mut(&device) else { return Vec::new() };
+        orb.name = name.clone();
+        let mut events = vec![OrbEvent::Update(orb.clone())];
+        for orb in self.orbs.values_mut() {
+            let OrbKind::Cluster { devices, members, .. } = &mut orb.kind else { continue };
+            let Some(i) = members.iter().position(|m| *m == device) else { continue };
+            if let Some(shown) = devices.get_mut(i) {
+                *shown = name.clone();
+                events.push(OrbEvent::Update(orb.clone()));
+            }
+        }
+        events
+    }
+
+    pub fn rename_cluster(&mut self, cluster_id: Uuid, name: String) -> Vec<OrbEvent> {
+        self.update_cluster_orb(cluster_id, |orb| orb.name = name)
+    }
+
+    pub fn set_cluster_zone(&mut self, cluster_id: Uuid, zone: Option<String>) -> Vec<OrbEvent> {
+        self.update_cluster_orb(cluster_id, |orb| orb.zone = zone)
+    }
+
+    /// Record a latency measurement. Only emits an update when the value changed.
+    pub fn set_cluster_latency(&mut self, cluster_id: Uuid, latency_ms: u32) -> Vec<OrbEvent> {
+        let Some(cluster) = self.clusters.get_mut(&cluster_id) else {
+            return Vec::new();
+        };
+        cluster.latency_ms = latency_ms;
+
+        if self.orbs.get(&cluster_id).and_then(|o| o.latency_ms) == Some(latency_ms) {
+            return Vec::new();
+        }
+        self.update_cluster_orb(cluster_id, |orb| orb.latency_ms = Some(latency_ms))
+    }
+
+    /// Record which members are downmixed to mono
+    pub fn set_cluster_mono(&mut self, cluster_id: Uuid, downmixed: Vec<Uuid>) -> Vec<OrbEvent> {
+        self.update_cluster_orb(cluster_id, |orb| {
+            if let OrbKind::Cluster { mono, .. } = &mut orb.kind {
+                *mono = downmixed;
+            }
+        })
+    }
+
+    /// Channel count the cluster's combine-sink was loaded with
+    pub fn set_cluster_channels(&mut self, cluster_id: Uuid, channels: u32) -> Vec<OrbEvent> {
+        self.update_cluster_orb(cluster_id, |orb| orb.channels = Some(channels))
+    }
+
+    /// Volumes the cluster's members were set to, replacing the previous ones
+    pub fn set_cluster_volumes(&mut self, cluster_id: Uuid, member_volumes: HashMap<Uuid, u32>) -> Vec<OrbEvent> {
+        self.update_cluster_orb(cluster_id, |orb| {
+            if let OrbKind::Cluster { volumes, .. } = &mut orb.kind {
+                *volumes = member_volumes;
+            }
+        })
+    }
+
+    /// One member's volume changed
+    pub fn set_member_volume(&mut self, cluster_id: Uuid, device: Uuid, percent: u32) -> Vec<OrbEvent> {
+        self.update_cluster_orb(cluster_id, |orb| {
+            if let OrbKind::Cluster { volumes, .. } = &mut orb.kind {
+                volumes.insert(device, percent);
+            }
+        })
+    }
+
+    /// Record the server's default sink, reporting it if it changed. When a device (not a
+    /// cluster) became the default, whoever made it so wants audio there: separating any
+    /// cluster goes back to it instead of to what was the default when the cluster was made.
+    pub fn observe_default_sink(&mut self, sink_name: Option<String>) -> Vec<OrbEvent> {
+        if self.default_sink == sink_name {
+            return Vec::new();
+        }
+        if let Some(name) = &sink_name {
+            let is_cluster = self.clusters.values().any(|c| &c.sink_name == name);
+            if !is_cluster && !sink_names::is_own(name) {
+                for cluster in self.clusters.values_mut().filter(|c| !c.external) {
+                    cluster.saved_default_sink = Some(name.clone());
+                }
+            }
+        }
+        self.default_sink = sink_name.clone();
+        vec![OrbEvent::DefaultSink { sink_name }]
+    }
+
+    /// Where notification sounds go while the default output is a cluster of ours: the device
+    /// that was the default before it (`Cluster::saved_default_sink`), if it's still around
+    pub fn notification_device(&self) -> Option<&Orb> {
+        let default_sink = self.default_sink.as_deref()?;
+        let cluster = self.clusters.values().find(|c| c.sink_name == default_sink && !c.external)?;
+        let saved = cluster.saved_default_sink.as_deref()?;
+        self.orbs.values().find(|o| o.node_name == saved && matches!(o.kind, OrbKind::PhysicalSink { .. }))
+    }
+
+    fn update_cluster_orb(&mut self, cluster_id: Uuid, apply: impl FnOnce(&mut Orb)) -> Vec<OrbEvent> {
+        if !self.clusters.contains_key(&cluster_id) {
+            return Vec::new();
+        }
+        match self.orbs.get_mut(&cluster_id) {
+            Some(orb) => {
+                apply(orb);
+                vec![OrbEvent::Update(orb.clone())]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Which device also plays on which, sorted
+    pub fn duplicate_pairs(&self) -> Vec<(Uuid, Uuid)> {
+        let mut pairs: Vec<(Uuid, Uuid)> = self.duplicates.keys().copied().collect();
+        pairs.sort();
+        pairs
+    }
+
+    /// Whether `to` plays what `from` plays, directly or through other duplicates
+    pub fn plays_on(&self, from: Uuid, to: Uuid) -> bool {
+        let mut seen = vec![from];
+        let mut next = vec![from];
+        while let Some(device) = next.pop() {
+            for &(_, also) in self.duplicates.keys().filter(|&&(f, _)| f == device) {
+                if also == to {
+                    return true;
+                }
+                if !seen.contains(&also) {
+                    seen.push(also);
+                    next.push(also);
+                }
+            }
+        }
+        false
+    }
+
+    /// Stop tracking the duplicates from or to `orb`, returning their loopback modules
+    pub fn take_duplicates_of(&mut self, orb: Uuid) -> Vec<u32> {
+        let pairs: Vec<(Uuid, Uuid)> = self.duplicates.keys()
+            .filter(|&&(from, also)| from == orb || also == orb)
+            .copied()
+            .collect();
+        pairs.iter().filter_map(|pair| self.duplicates.remove(pair)).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.orbs.clear();
+        self.clusters.clear();
+        self.parked.clear();
+        self.pending_removal.clear();
+        self.sink_devices.clear();
+        self.links.clear();
+        self.routes.clear();
+        self.own_links.clear();
+        self.duplicates.clear();
+        self.staged.clear();
+        self.media_roles.clear();
+        self.default_sink = None;
+    }
+}
+
+/// Read-only copy of the core's graph for other threads, such as the UI's main thread, so they
+/// can look at the current state without going through the event channel. The core publishes
+/// a new copy as soon as it releases a lock it changed the graph under (see `GraphGuard`), so
+/// it's never behind the events; reading never waits for a command in progress.
+#[derive(Debug, Clone, Default)]
+pub struct GraphView(Arc<RwLock<AudioGraph>>);
+
+impl GraphView {
+    /// The graph as of the last change. Don't hold on to the guard, the core waits for it
+    /// before it can publish the next change.
+    pub fn read(&self) -> RwLockReadGuard<'_, AudioGraph> {
+        self.0.read().unwrap()
+    }
+
+    pub(crate) fn publish(&self, graph: &AudioGraph) {
+        let copy = graph.clone();
+        // The old copy is dropped after the lock is released
+        let _old = std::mem::replace(&mut *self.0.write().unwrap(), copy);
+    }
+}
+
+/// Something the UI (or the control socket, see `control`) asks the core to do
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UiCommand {
     Connect { source: Uuid, target: Uuid },
+    /// Put a device dropped on empty canvas on a cluster card of its own; dropping a second
+    /// device on the card builds the cluster (see `AudioGraph::stage_cluster`)
+    StageCluster { device: Uuid },
+    /// Cluster these devices in one go (the device list's "Cluster Selected"), taking them
+    /// out of the clusters they're in
+    CreateCluster { devices: Vec<Uuid> },
+    /// Show a device under another name everywhere, `None` goes back to its description.
+    /// Remembered by node name; routing keeps using the node name.
+    RenameDevice { device: Uuid, name: Option<String> },
     Disconnect { source: Uuid, target: Uuid },
+    RenameCluster { cluster: Uuid, name: String },
+    SetClusterZone { cluster: Uuid, zone: Option<String> },
+    /// Turn "Auto-switch to new devices" on or off
+    SetAutoSwitch { enabled: bool },
+    /// Pause Auralis routing: give the default output back to the device it was taken from,
+    /// keeping every cluster loaded. Stays paused across restarts until `ResumeRouting`.
+    SuspendRouting,
+    /// Make the cluster that was the default before `SuspendRouting` the default again
+    ResumeRouting,
+    /// How long a vanished cluster member may be gone before the cluster is dissolved
+    SetDropoutGrace { ms: u32 },
+    /// How long wired cluster members wait for Bluetooth ones; rebuilds the clusters it affects
+    SetBluetoothOffset { ms: u32 },
+    /// Recolor the canvas (`#rrggbb`), `None` goes back to the default
+    SetAccentColor { color: Option<String> },
+    /// Light, dark or follow the system
+    SetAppearance { appearance: Appearance },
+    /// How floating orbs are arranged on the canvas
+    SetCanvasLayout { layout: CanvasLayout },
+    /// Stop the canvas animations (see `Settings::reduce_motion`)
+    SetReduceMotion { enabled: bool },
+    /// Confirmation sounds on or off (see `Settings::show_notifications`)
+    SetShowNotifications { enabled: bool },
+    /// Beam a device's or cluster's audio: adds a beam orb that is "Connecting" until the
+    /// network side reports on the session (`BeamStatus`, `BeamClosed`)
+    StartBeam { source: Uuid },
+    /// A beam session ended (peer left or connection failed); `reason` is shown to the user
+    BeamClosed { session_id: String, reason: Option<String> },
+    /// A beam session's connection state changed (`BeamState::label` of auralis-net)
+    BeamStatus { session_id: String, status: String },
+    /// Replace the current clusters with the named profile's
+    ApplyProfile { name: String },
+    /// Bring clusters, default output and role outputs in line with `topology`, see
+    /// `Topology::plan`. Nothing is changed if it can't be planned.
+    ApplyTopology { topology: Topology },
+    /// Keep cluster members listed (as members) in the device list
+    SetShowClusterMembers { enabled: bool },
+    /// Play streams with a media role (one of `media_roles::ROLES`) on `sink`, a device or
+    /// cluster, instead of the default output. `None` sends them back to the default.
+    SetRoleTarget { role: String, sink: Option<Uuid> },
+    /// Keep notification sounds off a default cluster (see `Settings::notifications_off_clusters`)
+    SetNotificationsOffClusters { enabled: bool },
+    /// Remember where the orb called `name` was left on the canvas, `None` when it was taken off
+    SetCanvasPosition { name: String, position: Option<(f64, f64)> },
+    /// Show the device list as compact tiles (or full rows)
+    SetCompactDeviceList { enabled: bool },
+    /// Ask before merging two clusters dropped onto each other
+    SetConfirmMerges { enabled: bool },
+    /// Cluster devices dropped onto each other right away (see `Settings::auto_cluster_on_drop`)
+    SetAutoClusterOnDrop { enabled: bool },
+    /// Build the clusters again after PipeWire restarts, instead of leaving their devices apart
+    SetRebuildClusters { enabled: bool },
+    /// Show the onboarding tour (again), or mark it as seen
+    SetFirstRun { first_run: bool },
+    /// TURN relay for beams, `None` to connect directly only. Rejected if the URL is invalid.
+    SetBeamTurn { turn: Option<TurnServer> },
+    /// Stop an app from playing to a device or cluster (see `OrbEvent::Routes`)
+    Unlink { source: Uuid, target: Uuid },
+    /// Move the streams playing on `from` (device or cluster) over to `to`
+    MoveAllStreams { from: Uuid, to: Uuid },
+    /// Play what device `from` plays on device `also` too, through a loopback from `from`'s
+    /// monitor. Unlike a cluster, neither becomes a slave and the default output stays as it is.
+    Duplicate { from: Uuid, also: Uuid },
+    /// Stop playing `from`'s audio on `also`
+    StopDuplicate { from: Uuid, also: Uuid },
+    /// Delay one cluster member by `delta_ms` more (or less) to line up speakers
+    NudgeMemberDelay { cluster: Uuid, device: Uuid, delta_ms: i32 },
+    /// Downmix one cluster member to mono (or stop doing so), overriding the channel-count default
+    SetMemberDownmix { cluster: Uuid, device: Uuid, enabled: bool },
+    /// Set the volume of a cluster member's device, to balance it against the others
+    SetMemberVolume { cluster: Uuid, device: Uuid, percent: u32 },
+    /// Set every member of a cluster to the same volume, the average of their current ones;
+    /// remembered like `SetMemberVolume`
+    BalanceClusterVolumes { cluster: Uuid },
+    /// Move a cluster member one place up (or down) among the combine-sink's slaves, which sets
+    /// what channels it gets and which device is the main one; remembered for the cluster
+    MoveClusterMember { cluster: Uuid, device: Uuid, up: bool },
+    /// Balance the volumes of each new cluster (see `Settings::balance_new_clusters`)
+    SetBalanceNewClusters { enabled: bool },
+    /// Rebuild a cluster's combine-sink with another channel count (see `channels::LAYOUTS`)
+    SetClusterChannels { cluster: Uuid, channels: u32 },
+    /// Play a short tone on each member of a cluster in turn, to check speaker placement
+    ClusterChannelCheck { cluster: Uuid },
+    /// Play a short tone on one device only, a cluster member too, to tell which speaker it is
+    PlayTestTone { device: Uuid },
+    /// Make a device (or cluster) the default output
+    SetDefaultSink { device: Uuid },
+    /// Mute or unmute a device
+    ToggleMute { device: Uuid },
+    /// Set the volume (percent) of a device or cluster; a cluster member's is remembered like
+    /// `SetMemberVolume`. Answered with `OrbEvent::Volume`.
+    SetVolume { device: Uuid, percent: u32 },
+    /// Read the volume of a device or cluster, answered with `OrbEvent::Volume`
+    ReadVolume { device: Uuid },
+    /// Suspend a device, or a cluster with its members, so idle speakers power down (or wake
+    /// it up). Remembered, and applied again whenever the device or cluster comes back.
+    SuspendSink { orb: Uuid, suspend: bool },
+    /// Unload Auralis modules that no cluster or duplicate uses anymore, leaving clusters as
+    /// they are; answered with `OrbEvent::OrphansCleaned`
+    CleanUpOrphans,
+    /// Separate every cluster, sweep leftover Auralis modules and show all devices again
+    ResetAll,
+    /// Write the layout bundle (state and profiles, see `bundle`) to `path`,
+    /// answered with `OrbEvent::LayoutExported`
+    ExportLayout { path: PathBuf },
+    /// Merge the layout bundle at `path` into the current state, or replace it,
+    /// answered with `OrbEvent::LayoutImported`
+    ImportLayout { path: PathBuf, replace: bool },
+    /// Read a device's formats again (the one in use changes as it starts and stops playing)
+    ReadFormats { device: Uuid },
+    /// The `pactl` commands that build a cluster, answered with `OrbEvent::ClusterScript`
+    CopyClusterScript { cluster: Uuid },
+    /// Dump the current graph for a bug report, answered with `OrbEvent::GraphExport`
+    ExportGraph { format: ExportFormat },
     Shutdown,
 }
 
@@ -54,4 +1161,42 @@ pub enum UiCommand {
 pub enum OrbEvent {
     Add(Orb),
     Remove(Uuid),
+    /// Properties of a known orb changed; UI-side layout (position) should be kept
+    Update(Orb),
+    /// Drop every known orb (e.g. after losing the PipeWire connection)
+    Clear,
+    /// PipeWire connection came up or went away
+    BackendState { connected: bool },
+    /// Every object that existed when the connection came up has been announced.
+    /// Until then an empty graph means "still scanning", not "no devices".
+    DiscoveryComplete,
+    /// Current settings, sent on startup and whenever they change
+    Settings(Settings),
+    /// Something the user asked for could not be (fully) done
+    Error { message: String },
+    /// Channel check progress: the member being played, `None` once finished
+    ChannelCheck { cluster: Uuid, playing: Option<String> },
+    /// Reply to `UiCommand::CopyClusterScript`, see `Cluster::to_shell_script`
+    ClusterScript { script: String },
+    /// Reply to `UiCommand::ExportGraph`
+    GraphExport { contents: String },
+    /// `UiCommand::ExportLayout` wrote the bundle
+    LayoutExported { path: PathBuf },
+    /// `UiCommand::ImportLayout` brought in this many cluster setups and profiles
+    LayoutImported { clusters: usize, profiles: usize },
+    /// Names of the profiles in `profiles.toml`, sent again after an import changed them
+    Profiles { names: Vec<String> },
+    /// Which app orb currently plays to which device or cluster orb; replaces the previous set
+    Routes { routes: Vec<(Uuid, Uuid)> },
+    /// Commands being run and waiting for a free worker; sent whenever either changes
+    CommandStatus { in_flight: usize, queued: usize },
+    /// Node name of the server's default sink, whenever it changes (`None`: there's none)
+    DefaultSink { sink_name: Option<String> },
+    /// Which device also plays another's audio (from, also); replaces the previous set
+    Duplicates { pairs: Vec<(Uuid, Uuid)> },
+    /// Volume (percent) of a device or cluster as the server has it, see `UiCommand::ReadVolume`
+    Volume { device: Uuid, percent: u32 },
+    /// This many Auralis modules nothing used were unloaded, by `UiCommand::CleanUpOrphans`
+    /// or the watchdog (which only says so when it found some)
+    OrphansCleaned { count: usize },
 }
@@ -0,0 +1,75 @@
+use std::fmt;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+// Which Auralis process owns the modules on the audio server. Cleanup and the default sink are
+// global, so two instances tear down each other's clusters. The first one holds an advisory
+// lock (flock) on a file in the runtime directory; the kernel drops it when the process exits,
+// crashed or not.
+
+/// The lock, held for as long as this is alive
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+}
+
+/// Another process holds the lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlreadyRunning {
+    /// As that process wrote it into the file
+    pub pid: Option<u32>,
+}
+
+impl fmt::Display for AlreadyRunning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pid {
+            Some(pid) => write!(f, "Auralis is already running (PID {})", pid),
+            None => write!(f, "Auralis is already running"),
+        }
+    }
+}
+
+impl std::error::Error for AlreadyRunning {}
+
+impl InstanceLock {
+    /// `auralis.lock` in the runtime directory; `None` without `XDG_RUNTIME_DIR`
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("XDG_RUNTIME_DIR").map(|dir| PathBuf::from(dir).join("auralis.lock"))
+    }
+
+    /// Take the lock at `path`. A file that can't be opened at all is only warned about:
+    /// a read-only runtime dir shouldn't keep Auralis from starting.
+    pub fn acquire(path: &Path) -> Result<Option<Self>, AlreadyRunning> {
+        let mut file = match OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path) {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("Failed to open {}, not checking for other instances: {}", path.display(), e);
+                return Ok(None);
+            }
+        };
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut contents = String::new();
+                let _ = file.read_to_string(&mut contents);
+                return Err(AlreadyRunning { pid: contents.trim().parse().ok() });
+            }
+            Err(TryLockError::Error(e)) => {
+                warn!("Failed to lock {}, not checking for other instances: {}", path.display(), e);
+                return Ok(None);
+            }
+        }
+
+        // Only for the message the next instance shows
+        let written = file.set_len(0)
+            .and_then(|_| file.rewind())
+            .and_then(|_| write!(file, "{}", std::process::id()));
+        if let Err(e) = written {
+            warn!("Failed to write {}: {}", path.display(), e);
+        }
+        Ok(Some(Self { _file: file }))
+    }
+}
@@ -1,8 +1,30 @@
+pub mod bundle;
+pub mod channels;
+pub mod config;
+pub mod control;
+pub mod discovery;
+pub mod event_queue;
+pub mod export;
+pub mod formats;
 pub mod graph;
+pub mod instance_lock;
+pub mod media_roles;
+pub mod metrics;
+pub mod node_proxies;
+pub mod persistence;
 pub mod pipewire_client;
+pub mod profiles;
+pub mod sink_names;
+pub mod system_audio;
+pub mod topology;
 
-pub use graph::{Orb, OrbKind, OrbState, Cluster, AudioGraph, UiCommand, OrbEvent};
+pub use graph::{Orb, OrbKind, OrbState, DeviceBus, Cluster, AudioGraph, GraphView, UiCommand, OrbEvent, ConnectionKind, classify_connection};
+pub use config::AuralisConfig;
+pub use export::ExportFormat;
 pub use pipewire_client::PipeWireClient;
+pub use profiles::{Profile, Profiles};
+pub use persistence::{Appearance, CanvasLayout, Settings, TurnServer};
+pub use system_audio::{SystemAudio, PactlBackend, MockBackend, MissingTool};
 
 
 pub fn init() {
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::graph::{Orb, OrbKind};
+use crate::persistence;
+
+// Streams say what they play through `media.role`. WirePlumber's role names are used here;
+// the PulseAudio ones that older apps still send are mapped onto them.
+
+/// Role of notification sounds, see `Settings::notifications_off_clusters`
+pub const NOTIFICATION: &str = "Notification";
+
+/// Role, name shown to the user
+pub const ROLES: [(&str, &str); 5] = [
+    (NOTIFICATION, "Notifications"),
+    ("Music", "Music"),
+    ("Communication", "Calls"),
+    ("Movie", "Video"),
+    ("Game", "Games"),
+];
+
+/// PulseAudio role, the role it's routed as
+const ALIASES: [(&str, &str); 3] = [
+    ("event", "Notification"),
+    ("phone", "Communication"),
+    ("video", "Movie"),
+];
+
+/// The role of `ROLES` a stream's `media.role` stands for, `None` if it isn't one that can be routed
+pub fn canonical(media_role: &str) -> Option<&'static str> {
+    let media_role = media_role.trim();
+    ROLES.iter()
+        .map(|(role, _)| (*role, *role))
+        .chain(ALIASES)
+        .find(|(name, _)| name.eq_ignore_ascii_case(media_role))
+        .map(|(_, role)| role)
+}
+
+/// How a role's output is remembered: a device by node name, a cluster by its members
+/// (see `persistence::cluster_key`) since its combine-sink is named anew every time
+pub fn target_key(orbs: &HashMap<Uuid, Orb>, output: &Orb) -> Option<String> {
+    match &output.kind {
+        OrbKind::PhysicalSink { .. } => Some(output.node_name.clone()),
+        OrbKind::Cluster { members, .. } => {
+            let node_names: Vec<String> = members.iter()
+                .filter_map(|id| orbs.get(id).map(|o| o.node_name.clone()))
+                .collect();
+            Some(persistence::cluster_key(&node_names))
+        }
+        _ => None,
+    }
+}
+
+/// The device or cluster a remembered output stands for, if it's around (and not clustered)
+pub fn find_target<'a>(orbs: &'a HashMap<Uuid, Orb>, key: &str) -> Option<&'a Orb> {
+    orbs.values()
+        .filter(|o| o.membership().is_none())
+        .find(|o| target_key(orbs, o).as_deref() == Some(key))
+}
@@ -0,0 +1,121 @@
+use serde::Serialize;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+
+// Counters for watching a long-running core from outside: bumped where the core logs what it
+// does, read with `PipeWireClient::metrics`. Relaxed atomics, they're only ever summed up.
+
+/// Running totals since the core started
+#[derive(Debug)]
+pub struct Metrics {
+    started: Instant,
+    connected: AtomicBool,
+    commands: AtomicU64,
+    errors: AtomicU64,
+    clusters_created: AtomicU64,
+    clusters_dissolved: AtomicU64,
+    discovery_events: AtomicU64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            connected: AtomicBool::new(false),
+            commands: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            clusters_created: AtomicU64::new(0),
+            clusters_dissolved: AtomicU64::new(0),
+            discovery_events: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    pub fn command_processed(&self) {
+        self.commands.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn error_reported(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn cluster_created(&self) {
+        self.clusters_created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn cluster_dissolved(&self) {
+        self.clusters_dissolved.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A registry object appeared or went away
+    pub fn discovery_event(&self) {
+        self.discovery_events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The totals so far, with the graph's current orb and cluster counts
+    pub fn snapshot(&self, orbs: usize, clusters: usize) -> MetricsSnapshot {
+        MetricsSnapshot {
+            uptime_secs: self.started.elapsed().as_secs(),
+            connected: self.connected.load(Ordering::Relaxed),
+            commands_processed: self.commands.load(Ordering::Relaxed),
+            errors_reported: self.errors.load(Ordering::Relaxed),
+            clusters_created: self.clusters_created.load(Ordering::Relaxed),
+            clusters_dissolved: self.clusters_dissolved.load(Ordering::Relaxed),
+            discovery_events: self.discovery_events.load(Ordering::Relaxed),
+            orbs,
+            clusters,
+        }
+    }
+}
+
+/// Metrics at one point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MetricsSnapshot {
+    pub uptime_secs: u64,
+    /// Whether PipeWire is connected right now
+    pub connected: bool,
+    /// UI commands handled
+    pub commands_processed: u64,
+    /// Errors shown to the user (failed commands, missing profile devices, ...)
+    pub errors_reported: u64,
+    pub clusters_created: u64,
+    /// Clusters separated, merged into another or lost with a member
+    pub clusters_dissolved: u64,
+    pub discovery_events: u64,
+    pub orbs: usize,
+    pub clusters: usize,
+}
+
+impl MetricsSnapshot {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Prometheus text exposition format, every metric prefixed with `auralis_`
+    pub fn to_prometheus(&self) -> String {
+        let metrics: [(&str, &str, &str, u64); 9] = [
+            ("uptime_seconds", "gauge", "Seconds since the core started", self.uptime_secs),
+            ("connected", "gauge", "1 while PipeWire is connected", self.connected as u64),
+            ("commands_processed_total", "counter", "UI commands handled", self.commands_processed),
+            ("errors_reported_total", "counter", "Errors shown to the user", self.errors_reported),
+            ("clusters_created_total", "counter", "Clusters created", self.clusters_created),
+            ("clusters_dissolved_total", "counter", "Clusters dissolved", self.clusters_dissolved),
+            ("discovery_events_total", "counter", "Registry objects added or removed", self.discovery_events),
+            ("orbs", "gauge", "Orbs in the graph", self.orbs as u64),
+            ("clusters", "gauge", "Clusters in the graph", self.clusters as u64),
+        ];
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(out, "# HELP auralis_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE auralis_{} {}", name, kind);
+            let _ = writeln!(out, "auralis_{} {}", name, value);
+        }
+        out
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+/// Proxies bound to PipeWire nodes (with whatever listeners hang off them), keyed by node ID.
+/// Dropping an entry destroys the proxy, so releasing nodes as they go keeps hotplug
+/// churn from piling up dead proxies.
+pub struct NodeProxies<T> {
+    proxies: HashMap<u32, T>,
+}
+
+impl<T> Default for NodeProxies<T> {
+    fn default() -> Self {
+        Self { proxies: HashMap::new() }
+    }
+}
+
+impl<T> NodeProxies<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep `proxy` alive until the node goes away. IDs get reused, so a proxy
+    /// still held for the same ID is dropped.
+    pub fn track(&mut self, pw_id: u32, proxy: T) {
+        self.proxies.insert(pw_id, proxy);
+    }
+
+    /// Drop the proxy of a removed node; false if none was bound
+    pub fn release(&mut self, pw_id: u32) -> bool {
+        self.proxies.remove(&pw_id).is_some()
+    }
+
+    pub fn contains(&self, pw_id: u32) -> bool {
+        self.proxies.contains_key(&pw_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.proxies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proxies.is_empty()
+    }
+}
@@ -0,0 +1,399 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// User preferences for a cluster, keyed by its member set (see `cluster_key`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClusterPrefs {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    /// Extra delay per member node name, used to line up speakers
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub delays: HashMap<String, u32>,
+    /// Per member node name: downmix to mono or not. Unset members follow their channel count.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub downmix: HashMap<String, bool>,
+    /// Volume (percent) per member node name, to balance speakers of different loudness
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub volumes: HashMap<String, u32>,
+    /// Channel count of the combine-sink, `None` for what all members can play
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channels: Option<u32>,
+    /// Member node names in the order of the combine-sink's slaves, the first being the main
+    /// device. Members not in it go last, in the order they were added.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+}
+
+impl ClusterPrefs {
+    /// The same preferences for a member that's now the node `to` instead of `from` (a
+    /// profile switch). Its delay, volume and place carry over; whether to downmix it depends on
+    /// the new node's channels, so that's left to them.
+    pub fn with_member_renamed(&self, from: &str, to: &str) -> Self {
+        let mut prefs = self.clone();
+        for per_member in [&mut prefs.delays, &mut prefs.volumes] {
+            if let Some(value) = per_member.remove(from) {
+                per_member.insert(to.to_string(), value);
+            }
+        }
+        prefs.downmix.remove(from);
+        for name in prefs.order.iter_mut().filter(|name| *name == from) {
+            *name = to.to_string();
+        }
+        prefs
+    }
+}
+
+/// Light or dark UI
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Appearance {
+    /// Follow the desktop's preference
+    System,
+    Light,
+    #[default]
+    Dark,
+}
+
+/// How the canvas arranges floating orbs (devices and apps); cluster cards stay where they're put
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CanvasLayout {
+    /// Orbs stay where they're dropped
+    #[default]
+    Free,
+    /// Rows of orbs sorted by name, easy to aim at when clustering
+    Grid,
+    /// Evenly spaced around the middle of the canvas
+    Ring,
+    /// Orbs push apart when they overlap and drift to rest
+    Physics,
+}
+
+/// TURN relay for beams between peers that can't reach each other directly (symmetric NAT)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TurnServer {
+    /// `turn:host[:port][?transport=udp|tcp]`, or `turns:` for TLS
+    pub url: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub credential: String,
+}
+
+/// Check a TURN URL (RFC 7065). Credentials go in their own fields, not in the URL.
+pub fn validate_turn_url(url: &str) -> Result<(), String> {
+    let (scheme, rest) = url.split_once(':').ok_or("must start with turn: or turns:")?;
+    if scheme != "turn" && scheme != "turns" {
+        return Err(format!("unsupported scheme '{}', expected turn or turns", scheme));
+    }
+    // Tolerate the `turn://host` spelling GStreamer uses
+    let rest = rest.strip_prefix("//").unwrap_or(rest);
+    let (address, query) = match rest.split_once('?') {
+        Some((address, query)) => (address, Some(query)),
+        None => (rest, None),
+    };
+
+    if address.contains('@') {
+        return Err("put the username and password in their own fields".to_string());
+    }
+    let (host, port) = match address.rsplit_once(':') {
+        // Bracketed IPv6 literals contain colons of their own
+        Some((host, port)) if !port.contains(']') => (host, Some(port)),
+        _ => (address, None),
+    };
+    if host.is_empty() || host.contains('/') || host.chars().any(char::is_whitespace) {
+        return Err("missing or invalid host".to_string());
+    }
+    if let Some(port) = port {
+        if !matches!(port.parse::<u16>(), Ok(p) if p > 0) {
+            return Err(format!("invalid port '{}'", port));
+        }
+    }
+    match query {
+        None | Some("transport=udp") | Some("transport=tcp") => Ok(()),
+        Some(query) => Err(format!("unsupported option '{}', only transport=udp or transport=tcp", query)),
+    }
+}
+
+/// User-facing settings, persisted by the core and mirrored to the UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// Make newly plugged-in devices the default sink and move streams to them
+    #[serde(default)]
+    pub auto_switch_new_devices: bool,
+    /// Auralis routing is paused: clusters stay loaded but aren't the default output,
+    /// and nothing is switched or moved automatically
+    #[serde(default)]
+    pub routing_suspended: bool,
+    /// Node names the user doesn't want to be switched to automatically
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignored_devices: Vec<String>,
+    /// Devices (node name) and clusters (`cluster_key`) the user suspended to save power,
+    /// suspended again whenever they show up. See `media_roles::target_key`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suspended_outputs: Vec<String>,
+    /// How long a vanished cluster member may stay away (Bluetooth hiccups)
+    /// before its cluster is dissolved. 0 dissolves immediately, unless the member's card
+    /// is switching profiles.
+    #[serde(default = "default_dropout_grace_ms")]
+    pub dropout_grace_ms: u32,
+    /// How long wired members of a cluster with Bluetooth members wait for them, on top of
+    /// their own delay. 0 leaves them as they are. See `DeviceBus::bluetooth_offset_ms`.
+    #[serde(default = "default_bluetooth_offset_ms")]
+    pub bluetooth_offset_ms: u32,
+    /// Accent color of the canvas as `#rrggbb`, `None` for the default blue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accent_color: Option<String>,
+    #[serde(default)]
+    pub appearance: Appearance,
+    #[serde(default)]
+    pub canvas_layout: CanvasLayout,
+    /// No pulsing orbs, and the canvas layout puts them in place without gliding
+    #[serde(default)]
+    pub reduce_motion: bool,
+    /// Play a soft sound when a cluster the user asked for is ready
+    #[serde(default = "default_true")]
+    pub show_notifications: bool,
+    /// List cluster members in the device list (marked as such) instead of hiding them
+    #[serde(default)]
+    pub show_cluster_members: bool,
+    /// Output per media role (`media_roles::ROLES`): a device's node name or a cluster's
+    /// `cluster_key`, see `media_roles::target_key`. Roles not listed play on the default output.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub role_targets: BTreeMap<String, String>,
+    /// Names the user gave devices, by node name, shown instead of their description
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub device_aliases: BTreeMap<String, String>,
+    /// Where orbs were left on the canvas, by name (device description, app or cluster name)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub canvas_positions: BTreeMap<String, (f64, f64)>,
+    /// Device list as a grid of icon + name tiles instead of full rows
+    #[serde(default)]
+    pub compact_device_list: bool,
+    /// Bring the members of a new cluster to the same volume, see `UiCommand::BalanceClusterVolumes`.
+    /// Clusters with volumes remembered from before get those back instead.
+    #[serde(default)]
+    pub balance_new_clusters: bool,
+    /// Ask before merging two clusters dropped onto each other
+    #[serde(default = "default_true")]
+    pub confirm_merges: bool,
+    /// While a cluster is the default output, play notification sounds on the device that was
+    /// the default before it rather than on every speaker. Picking an output for the
+    /// notification role (`role_targets`) overrides this.
+    #[serde(default)]
+    pub notifications_off_clusters: bool,
+    /// Cluster a device dropped onto another one right away. Without, dropped devices are
+    /// gathered on the canvas until the user clusters them.
+    #[serde(default = "default_true")]
+    pub auto_cluster_on_drop: bool,
+    /// Build the clusters again once PipeWire is back after a restart (or dissolve them)
+    #[serde(default = "default_true")]
+    pub rebuild_clusters: bool,
+    /// Relay for beams across NATs; without one beams only connect on the same network
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beam_turn: Option<TurnServer>,
+    /// Show the onboarding tour; cleared once it's dismissed
+    #[serde(default = "default_true")]
+    pub first_run: bool,
+}
+
+pub const DEFAULT_DROPOUT_GRACE_MS: u32 = 3000;
+
+fn default_dropout_grace_ms() -> u32 {
+    DEFAULT_DROPOUT_GRACE_MS
+}
+
+/// Roughly what SBC/AAC speakers and headphones buffer
+pub const DEFAULT_BLUETOOTH_OFFSET_MS: u32 = 200;
+
+fn default_bluetooth_offset_ms() -> u32 {
+    DEFAULT_BLUETOOTH_OFFSET_MS
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            auto_switch_new_devices: false,
+            routing_suspended: false,
+            ignored_devices: Vec::new(),
+            suspended_outputs: Vec::new(),
+            dropout_grace_ms: DEFAULT_DROPOUT_GRACE_MS,
+            bluetooth_offset_ms: DEFAULT_BLUETOOTH_OFFSET_MS,
+            accent_color: None,
+            appearance: Appearance::default(),
+            canvas_layout: CanvasLayout::default(),
+            reduce_motion: false,
+            show_notifications: true,
+            show_cluster_members: false,
+            role_targets: BTreeMap::new(),
+            device_aliases: BTreeMap::new(),
+            canvas_positions: BTreeMap::new(),
+            compact_device_list: false,
+            balance_new_clusters: false,
+            confirm_merges: true,
+            auto_cluster_on_drop: true,
+            notifications_off_clusters: false,
+            rebuild_clusters: true,
+            beam_turn: None,
+            first_run: true,
+        }
+    }
+}
+
+/// Layout version of `state.json`. Bump it when the layout changes and add a step to `migrate`.
+pub const STATE_VERSION: u32 = 2;
+
+/// Everything Auralis remembers between runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersistedState {
+    /// Layout the file was written with, see `STATE_VERSION`
+    pub version: u32,
+    #[serde(default)]
+    pub clusters: HashMap<String, ClusterPrefs>,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+impl Default for PersistedState {
+    fn default() -> Self {
+        Self {
+            version: STATE_VERSION,
+            clusters: HashMap::new(),
+            settings: Settings::default(),
+        }
+    }
+}
+
+impl PersistedState {
+    /// Parse a state file of any version up to `STATE_VERSION`, upgrading older layouts.
+    /// A file without a version is version 1.
+    pub fn parse(contents: &str) -> Result<Self> {
+        Self::from_value(serde_json::from_str(contents)?)
+    }
+
+    /// Same as `parse`, for a state embedded in another document (see `bundle`)
+    pub fn from_value(mut value: serde_json::Value) -> Result<Self> {
+        if !value.is_object() {
+            bail!("not a JSON object");
+        }
+        let version = match value.get("version") {
+            None => 1,
+            Some(v) => v.as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .filter(|v| *v > 0)
+                .ok_or_else(|| anyhow!("invalid version {}", v))?,
+        };
+        if version > STATE_VERSION {
+            bail!("written by a newer Auralis (version {}, this one reads up to {})", version, STATE_VERSION);
+        }
+
+        for from in version..STATE_VERSION {
+            migrate(from, &mut value);
+            value["version"] = (from + 1).into();
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// Upgrade the state file layout from version `from` to `from + 1`
+fn migrate(from: u32, _value: &mut serde_json::Value) {
+    match from {
+        // Version 1 files predate the version field; the layout is otherwise the same
+        1 => {}
+        _ => unreachable!("no migration from state version {}", from),
+    }
+}
+
+/// Where a state file that can't be used is copied to before starting fresh
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Persisted state plus the file it lives in.
+/// An in-memory store (no path) never touches the filesystem.
+#[derive(Debug, Default)]
+pub struct StateStore {
+    path: Option<PathBuf>,
+    pub data: PersistedState,
+}
+
+impl StateStore {
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Load from `path`, migrating older versions. A missing file starts from an empty state;
+    /// an unreadable or newer one is backed up (see `backup_path`) and also starts empty.
+    pub fn load(path: PathBuf) -> Self {
+        let data = match std::fs::read_to_string(&path) {
+            Ok(contents) => match PersistedState::parse(&contents) {
+                Ok(data) => data,
+                Err(e) => {
+                    let backup = backup_path(&path);
+                    match std::fs::copy(&path, &backup) {
+                        Ok(_) => warn!("Can't use state file {} ({}), starting fresh; kept a copy at {}",
+                            path.display(), e, backup.display()),
+                        Err(copy_err) => warn!("Can't use state file {} ({}), starting fresh; backing it up failed: {}",
+                            path.display(), e, copy_err),
+                    }
+                    PersistedState::default()
+                }
+            },
+            Err(_) => PersistedState::default(),
+        };
+
+        Self { path: Some(path), data }
+    }
+
+    /// `$XDG_CONFIG_HOME/auralis/state.json`, falling back to `~/.config`
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("state.json"))
+    }
+
+    /// Save, logging rather than returning failures
+    pub fn save(&self) {
+        if let Err(e) = self.try_save() {
+            warn!("{:#}", e);
+        }
+    }
+
+    pub fn try_save(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        let json = serde_json::to_string_pretty(&self.data).context("Failed to serialize state")?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+        info!("💾 Saved state to {}", path.display());
+        Ok(())
+    }
+}
+
+/// `$XDG_CONFIG_HOME/auralis`, falling back to `~/.config/auralis`
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("auralis"))
+}
+
+/// Stable identity for a cluster across restarts: its sorted member node names
+pub fn cluster_key(node_names: &[String]) -> String {
+    let mut names: Vec<&str> = node_names.iter().map(|s| s.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+    names.join(",")
+}
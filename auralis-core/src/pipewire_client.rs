@@ -1,128 +1,407 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
+use auralis_net::webrtc::BeamState;
 use pipewire as pw;
 use std::thread;
-use std::sync::mpsc::{Sender, Receiver};
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 use tracing::{info, error, warn};
-use crate::graph::{Orb, OrbKind, OrbState, UiCommand, OrbEvent};
+use crate::bundle;
+use crate::channels;
+use crate::config::AuralisConfig;
+use crate::control;
+use crate::discovery::{self, DiscoveryDecision};
+use crate::export::{self, ExportFormat};
+use crate::formats::{self, AudioFormat};
+use crate::instance_lock::InstanceLock;
+use crate::metrics::{Metrics, MetricsSnapshot};
+use crate::node_proxies::NodeProxies;
+use crate::graph::{classify_connection, AudioGraph, Cluster, ConnectionKind, DeviceBus, GraphView, Orb, OrbKind, OrbState, UiCommand, OrbEvent};
+use crate::media_roles;
+use crate::persistence::{self, ClusterPrefs, Settings, StateStore, TurnServer};
+use crate::profiles::{Profile, Profiles};
+use crate::sink_names;
+use crate::system_audio::{MissingTool, ModuleInfo, PactlBackend, SystemAudio};
+use crate::topology::{Step, Topology};
 use uuid::Uuid;
 
+/// Delay before the first reconnect attempt after losing PipeWire
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_millis(500);
+/// Upper bound for the exponential reconnect backoff
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(10);
+/// How often cluster latency is re-read from the server
+const LATENCY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the watchdog looks for Auralis modules that nothing tracks anymore
+const ORPHAN_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+/// "Clean Up Orphaned Sinks" waits this long for clusters being built to be registered
+const ORPHAN_CLEANUP_WAIT: Duration = Duration::from_secs(10);
+/// Sinks announced this soon after connecting are existing devices, not newly plugged ones
+const AUTO_SWITCH_GRACE: Duration = Duration::from_secs(3);
+/// Give a new device a moment to settle before sending audio to it
+const AUTO_SWITCH_SETTLE: Duration = Duration::from_millis(500);
+/// Give a new stream a moment to show up as a sink-input before routing it by its media role
+const ROLE_ROUTE_SETTLE: Duration = Duration::from_millis(200);
+/// How often the command loop checks whether the UI went away while idle
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Wait for a burst of sink announcements to finish before looking for foreign combine-sinks
+const ADOPT_SCAN_SETTLE: Duration = Duration::from_secs(1);
+/// How long each cluster member plays during a channel check
+const CHANNEL_CHECK_TONE_MS: u32 = 1500;
+/// How long a freshly loaded combine-sink gets to show up before giving up on moving streams
+const SINK_READY_TIMEOUT: Duration = Duration::from_secs(3);
+/// How often `pactl` is asked in between registry announcements
+const SINK_READY_POLL: Duration = Duration::from_millis(100);
+/// Wait before retrying a default-sink switch or stream move that failed, usually because
+/// the sink was only just loaded
+const SWITCH_RETRY_DELAY: Duration = Duration::from_millis(300);
+/// A card switching profiles takes its old nodes down before announcing the new ones; a
+/// device stays this long for a node of its card to replace it
+const PROFILE_SWITCH_GRACE: Duration = Duration::from_millis(1500);
+/// Upper bound for a per-member alignment delay
+const MAX_MEMBER_DELAY_MS: u32 = 500;
+/// Upper bound for a device's or member's volume; above 100% PipeWire amplifies in software
+const MAX_MEMBER_VOLUME: u32 = 150;
+/// Buffer of a duplicate's loopback: the second device plays this far behind the first
+const DUPLICATE_LATENCY_MS: u32 = 50;
+/// What a new beam announces until the session reports its own (auralis-net encodes Opus)
+const BEAM_CODEC: &str = "Opus";
+const BEAM_BITRATE_KBPS: u32 = 64;
+
 /// Shared state for tracking Orbs and PipeWire nodes
 #[derive(Clone)]
-struct SharedState {
-    orb_to_pw_id: Arc<Mutex<HashMap<Uuid, u32>>>,
-    pw_id_to_orb: Arc<Mutex<HashMap<u32, Uuid>>>,
-    orb_names: Arc<Mutex<HashMap<Uuid, String>>>,
-    orb_kinds: Arc<Mutex<HashMap<Uuid, OrbKind>>>,
-    combine_modules: Arc<Mutex<HashMap<Uuid, u32>>>, // Track combine-sink module IDs for cleanup (ClusterID -> ModuleID)
-    active_cluster_members: Arc<Mutex<HashMap<String, String>>>, // Description -> NodeName mapping
-    hidden_cluster_members: Arc<Mutex<HashMap<String, u32>>>, // Name -> PW_ID of ignored devices
+pub struct SharedState {
+    graph: Arc<Mutex<AudioGraph>>,
+    /// Copy of `graph` for readers outside the core, see `GraphGuard`
+    view: GraphView,
     mock_modules: Arc<Mutex<Vec<u32>>>, // Track mock device module IDs
-    saved_default_sink: Arc<Mutex<HashMap<Uuid, String>>>, // ClusterID -> Original Default Sink
+    backend: Arc<dyn SystemAudio>,
+    store: Arc<Mutex<StateStore>>,
+    profiles_path: Option<PathBuf>,
+    ui_gone: Arc<AtomicBool>, // Set once an event send fails: nobody is listening anymore
+    adopt_scan_pending: Arc<AtomicBool>,
+    own_sinks: Arc<OwnSinks>,
+    /// Cluster sink to make the default again when routing is resumed
+    resume_sink: Arc<Mutex<Option<String>>>,
+    /// Member node names of the clusters lost with the PipeWire connection, rebuilt once
+    /// discovery completes after reconnecting
+    lost_clusters: Arc<Mutex<Vec<Vec<String>>>>,
+    /// Every sink name this instance handed out (`fresh_sink_name`). Only these are swept as
+    /// orphans; another instance's (`allow_multiple`) are left alone.
+    named_sinks: Arc<Mutex<HashSet<String>>>,
+    /// Clusters and duplicates being built, see `Building`
+    builds: Arc<AtomicUsize>,
+    metrics: Arc<Metrics>,
+}
+
+/// Held while modules are loaded that nothing tracks yet, so they aren't taken for orphans
+struct Building(Arc<AtomicUsize>);
+
+impl Drop for Building {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Lock on the authoritative graph. If the graph was borrowed mutably, the `GraphView` gets a
+/// fresh copy when the lock is released (still under it, so copies are published in order).
+pub struct GraphGuard<'a> {
+    graph: MutexGuard<'a, AudioGraph>,
+    view: &'a GraphView,
+    changed: bool,
+}
+
+impl Deref for GraphGuard<'_> {
+    type Target = AudioGraph;
+
+    fn deref(&self) -> &AudioGraph {
+        &self.graph
+    }
+}
+
+impl DerefMut for GraphGuard<'_> {
+    fn deref_mut(&mut self) -> &mut AudioGraph {
+        self.changed = true;
+        &mut self.graph
+    }
+}
+
+impl Drop for GraphGuard<'_> {
+    fn drop(&mut self) {
+        if self.changed {
+            self.view.publish(&self.graph);
+        }
+    }
+}
+
+/// Commands handed to the pool, reported to the UI as `OrbEvent::CommandStatus`
+#[derive(Default)]
+struct CommandLoad {
+    /// (in flight, queued)
+    counts: Mutex<(usize, usize)>,
+}
+
+impl CommandLoad {
+    /// Apply a change and report the new counts. Sent under the lock, so the UI
+    /// never sees an older count after a newer one.
+    fn update(&self, state: &SharedState, sender: &Sender<OrbEvent>, change: impl FnOnce(&mut usize, &mut usize)) {
+        let mut counts = self.counts.lock().unwrap();
+        let (in_flight, queued) = &mut *counts;
+        change(in_flight, queued);
+        state.send(sender, OrbEvent::CommandStatus { in_flight: *in_flight, queued: *queued });
+    }
+}
+
+/// Counts a command as finished when dropped, even if its handler panicked
+struct InFlight {
+    load: Arc<CommandLoad>,
+    state: SharedState,
+    sender: Sender<OrbEvent>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.load.update(&self.state, &self.sender, |in_flight, _| *in_flight -= 1);
+    }
+}
+
+/// A bound node proxy and its info listener; dropping it stops the monitoring
+type NodeMonitor = (pw::node::Node, pw::node::NodeListener);
+
+/// Sinks Auralis loaded, as announced by the registry (PW_ID -> node.name),
+/// so a command can wait for the server to actually have one
+#[derive(Default)]
+struct OwnSinks {
+    names: Mutex<HashMap<u32, String>>,
+    announced: Condvar,
 }
 
 impl SharedState {
-    fn new() -> Self {
+    /// State with an in-memory store, nothing is persisted
+    pub fn new(backend: Arc<dyn SystemAudio>) -> Self {
+        Self::with_store(backend, StateStore::in_memory())
+    }
+
+    pub fn with_store(backend: Arc<dyn SystemAudio>, store: StateStore) -> Self {
         Self {
-            orb_to_pw_id: Arc::new(Mutex::new(HashMap::new())),
-            pw_id_to_orb: Arc::new(Mutex::new(HashMap::new())),
-            orb_names: Arc::new(Mutex::new(HashMap::new())),
-            orb_kinds: Arc::new(Mutex::new(HashMap::new())),
-            combine_modules: Arc::new(Mutex::new(HashMap::new())),
-            active_cluster_members: Arc::new(Mutex::new(HashMap::new())),
-            hidden_cluster_members: Arc::new(Mutex::new(HashMap::new())),
+            graph: Arc::new(Mutex::new(AudioGraph::new())),
+            view: GraphView::default(),
             mock_modules: Arc::new(Mutex::new(Vec::new())),
-            saved_default_sink: Arc::new(Mutex::new(HashMap::new())),
+            backend,
+            store: Arc::new(Mutex::new(store)),
+            profiles_path: None,
+            ui_gone: Arc::new(AtomicBool::new(false)),
+            adopt_scan_pending: Arc::new(AtomicBool::new(false)),
+            own_sinks: Arc::new(OwnSinks::default()),
+            resume_sink: Arc::new(Mutex::new(None)),
+            lost_clusters: Arc::new(Mutex::new(Vec::new())),
+            named_sinks: Arc::new(Mutex::new(HashSet::new())),
+            builds: Arc::new(AtomicUsize::new(0)),
+            metrics: Arc::new(Metrics::default()),
+        }
+    }
+
+    /// Lock the authoritative graph
+    pub fn graph(&self) -> GraphGuard<'_> {
+        GraphGuard { graph: self.graph.lock().unwrap(), view: &self.view, changed: false }
+    }
+
+    /// Read-only handle on the graph that stays current, for the UI thread
+    pub fn view(&self) -> GraphView {
+        self.view.clone()
+    }
+
+    /// Copy of the graph, so it can be inspected without holding the lock
+    pub fn snapshot(&self) -> AudioGraph {
+        self.graph().clone()
+    }
+
+    /// Lock the persisted preferences
+    pub fn store(&self) -> MutexGuard<'_, StateStore> {
+        self.store.lock().unwrap()
+    }
+
+    /// Remember which devices made up our clusters, so they can be rebuilt after reconnecting.
+    /// Kept until then: failed reconnects (with nothing in the graph) don't overwrite it.
+    fn remember_clusters(&self) {
+        let graph = self.graph();
+        let lost: Vec<Vec<String>> = graph.clusters.values()
+            .filter(|c| !c.external)
+            .map(|c| c.members().iter()
+                .filter_map(|id| graph.orbs.get(id))
+                .map(|o| o.node_name.clone())
+                .collect())
+            .collect();
+        if !lost.is_empty() {
+            *self.lost_clusters.lock().unwrap() = lost;
+        }
+    }
+
+    /// Forget everything tied to the current PipeWire connection
+    fn reset(&self) {
+        // Module IDs are the old daemon's, so they're dropped without unloading: the new one may
+        // have given them to modules that aren't ours. Leftovers are swept by name at startup.
+        self.graph().clear();
+        self.mock_modules.lock().unwrap().clear();
+        self.own_sinks.names.lock().unwrap().clear();
+    }
+
+    fn own_sink_announced(&self, pw_id: u32, name: &str) {
+        self.own_sinks.names.lock().unwrap().insert(pw_id, name.to_string());
+        self.own_sinks.announced.notify_all();
+    }
+
+    fn own_sink_removed(&self, pw_id: u32) {
+        self.own_sinks.names.lock().unwrap().remove(&pw_id);
+    }
+
+    /// Name for a new sink of the given kind (`sink_names::CLUSTER`, ...) that no existing sink has
+    fn fresh_sink_name(&self, kind: &str) -> String {
+        let listed = self.backend.list_sinks().unwrap_or_else(|e| {
+            warn!("Failed to list sinks, can't rule out name collisions: {}", e);
+            Vec::new()
+        });
+        let announced = self.own_sinks.names.lock().unwrap();
+        let name = sink_names::fresh(kind, |name| {
+            listed.iter().any(|s| s.name == name) || announced.values().any(|n| n == name)
+        });
+        self.named_sinks.lock().unwrap().insert(name.clone());
+        name
+    }
+
+    /// Note that modules are being loaded until the returned guard is dropped
+    fn building(&self) -> Building {
+        self.builds.fetch_add(1, Ordering::SeqCst);
+        Building(self.builds.clone())
+    }
+
+    /// Recompute what plays where. Call with the graph locked.
+    fn refresh_routes(&self, graph: &mut AudioGraph) -> Vec<OrbEvent> {
+        let own_sinks = self.own_sinks.names.lock().unwrap();
+        graph.refresh_routes(|pw_id| own_sinks.get(&pw_id).cloned())
+    }
+
+    /// Whether the event receiver has been dropped (UI closed)
+    pub fn ui_gone(&self) -> bool {
+        self.ui_gone.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, sender: &Sender<OrbEvent>, event: OrbEvent) {
+        match &event {
+            OrbEvent::Error { .. } => self.metrics.error_reported(),
+            OrbEvent::BackendState { connected } => self.metrics.set_connected(*connected),
+            _ => {}
+        }
+        if sender.send(event).is_err() && !self.ui_gone.swap(true, Ordering::Relaxed) {
+            warn!("📪 [CORE] Event receiver dropped, treating it as shutdown");
         }
     }
 
-    fn register_orb(&self, orb_id: Uuid, pw_id: u32, name: String, kind: OrbKind) {
-        self.orb_to_pw_id.lock().unwrap().insert(orb_id, pw_id);
-        self.pw_id_to_orb.lock().unwrap().insert(pw_id, orb_id);
-        self.orb_names.lock().unwrap().insert(orb_id, name);
-        self.orb_kinds.lock().unwrap().insert(orb_id, kind);
+    /// Tell the user a backend call failed: `message`, unless a tool it needed isn't installed,
+    /// then which one and what to install
+    fn report_failure(&self, sender: &Sender<OrbEvent>, message: String, error: &anyhow::Error) {
+        let message = match MissingTool::find(error) {
+            Some(missing) => missing.to_string(),
+            None => message,
+        };
+        self.send(sender, OrbEvent::Error { message });
+    }
+
+    fn send_events(&self, sender: &Sender<OrbEvent>, events: Vec<OrbEvent>) {
+        for event in events {
+            self.send(sender, event);
+        }
     }
     
+    /// Unload the combine-sink and any delay modules of a cluster
+    fn unload_cluster_modules(&self, cluster: &Cluster) {
+        self.unload_modules(&cluster.modules());
+    }
+
+    /// Unload modules that nothing refers to anymore, failures are only logged
+    fn unload_modules(&self, modules: &[u32]) {
+        for &module_id in modules {
+            match self.backend.unload_module(module_id) {
+                Ok(()) => info!("✓ Unloaded module {}", module_id),
+                Err(e) => warn!("Failed to unload module {}: {}", module_id, e),
+            }
+        }
+    }
+
+    /// Unload every module Auralis loaded. Safe to call more than once.
+    /// Adopted combine-sinks belong to someone else and stay loaded.
     fn cleanup_combine_sinks(&self) {
-        let modules = self.combine_modules.lock().unwrap();
-        info!("Cleaning up {} combine-sinks", modules.len());
-        for module_id in modules.values() {
-            let _ = std::process::Command::new("pactl")
-                .args(&["unload-module", &module_id.to_string()])
-                .output();
+        let clusters: Vec<Cluster> = self.graph().clusters.drain()
+            .map(|(_, c)| c)
+            .filter(|c| !c.external)
+            .collect();
+        info!("Cleaning up {} combine-sinks", clusters.len());
+        for cluster in &clusters {
+            self.unload_cluster_modules(cluster);
+        }
+
+        let duplicates: Vec<u32> = self.graph().duplicates.drain().map(|(_, module_id)| module_id).collect();
+        if !duplicates.is_empty() {
+            info!("Cleaning up {} duplicates", duplicates.len());
+            self.unload_modules(&duplicates);
         }
         
         // Also cleanup mocks
-        let mocks = self.mock_modules.lock().unwrap();
+        let mocks: Vec<u32> = self.mock_modules.lock().unwrap().drain(..).collect();
         if !mocks.is_empty() {
             info!("Cleaning up {} mock devices", mocks.len());
-            for module_id in mocks.iter() {
-                let _ = std::process::Command::new("pactl")
-                    .args(&["unload-module", &module_id.to_string()])
-                    .output();
+            for module_id in mocks {
+                let _ = self.backend.unload_module(module_id);
             }
         }
     }
-    
-    fn is_cluster_member(&self, name: &str) -> bool {
-        self.active_cluster_members.lock().unwrap().contains_key(name)
-    }
-    
-    fn add_cluster_members(&self, members: Vec<(String, String)>) {
-        let mut map = self.active_cluster_members.lock().unwrap();
-        for (desc, node_name) in members {
-            map.insert(desc, node_name);
-        }
-    }
-    
-    fn remove_cluster_members(&self, names: &Vec<String>) {
-        let mut map = self.active_cluster_members.lock().unwrap();
-        for name in names {
-            map.remove(name);
-        }
-    }
 }
 
 pub struct PipeWireClient {
-    _thread: thread::JoinHandle<()>,
+    state: SharedState,
+    sender: Sender<OrbEvent>,
+    _thread: Option<thread::JoinHandle<()>>,
     _cmd_thread: thread::JoinHandle<()>,
+    _latency_thread: thread::JoinHandle<()>,
     _command_pool: threadpool::ThreadPool,
+    /// Held while this client runs, see `AuralisConfig::lock_path`
+    _lock: Option<InstanceLock>,
 }
 
 
 impl PipeWireClient {
-    fn cleanup_stale_modules() {
+    /// Whether a module is one Auralis loads: a sink of ours (see `sink_names::is_own`), a
+    /// mock device, or a delay loopback reading from one of our sinks
+    fn is_own_module(module: &ModuleInfo) -> bool {
+        let mock = module.arg("sink_name")
+            .and_then(|name| name.strip_prefix("Mock"))
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        Self::module_sink(module).is_some_and(sink_names::is_own) || (module.name == "module-null-sink" && mock)
+    }
+
+    /// The sink a module is there for: the one it creates, or for a loopback the one whose
+    /// monitor it reads (delay loopbacks read from an Auralis null-sink)
+    fn module_sink(module: &ModuleInfo) -> Option<&str> {
+        match module.name.as_str() {
+            "module-combine-sink" | "module-remap-sink" | "module-null-sink" => module.arg("sink_name"),
+            "module-loopback" => module.arg("source").and_then(|source| source.strip_suffix(".monitor")),
+            _ => None,
+        }
+    }
+
+    fn cleanup_stale_modules(state: &SharedState) {
         info!("🧹 [STARTUP] Checking for stale Auralis modules...");
-        let output = std::process::Command::new("pactl")
-            .args(&["list", "modules", "short"])
-            .output();
+        let output = state.backend.list_modules();
 
-        if let Ok(out) = output {
-            let stdout = String::from_utf8_lossy(&out.stdout);
-            let mut count = 0;
-            
-            for line in stdout.lines() {
-                // Line format: "536870932 module-combine-sink ..."
-                if (line.contains("module-combine-sink") && (line.contains("sink_name=auralis_combined_") || line.contains("sink_name=auralis_cluster_"))) ||
-                   (line.contains("module-null-sink") && line.contains("sink_name=Mock")) {
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if let Some(id_str) = parts.first() {
-                        info!("Found stale module: {}", line);
-                        let unload = std::process::Command::new("pactl")
-                            .args(&["unload-module", id_str])
-                            .output();
-                            
-                        match unload {
-                            Ok(_) => {
-                                info!("✓ Unloaded stale module {}", id_str);
-                                count += 1;
-                            }
-                            Err(e) => error!("Failed to unload module {}: {}", id_str, e),
-                        }
-                    }
-                }
-            }
+        if let Ok(modules) = output {
+            let stale: Vec<ModuleInfo> = modules.into_iter().filter(Self::is_own_module).collect();
+            let count = Self::unload_stale(state, &stale);
             if count > 0 {
                 info!("✓ [STARTUP] Cleaned up {} stale modules", count);
             } else {
@@ -133,6 +412,102 @@ impl PipeWireClient {
         }
     }
 
+    /// Unload `modules`, returning how many went
+    fn unload_stale(state: &SharedState, modules: &[ModuleInfo]) -> usize {
+        let mut count = 0;
+        for module in modules {
+            info!("Found stale module: {} {} {}", module.id, module.name, module.args);
+            match state.backend.unload_module(module.id) {
+                Ok(_) => {
+                    info!("✓ Unloaded stale module {}", module.id);
+                    count += 1;
+                }
+                Err(e) => error!("Failed to unload module {}: {}", module.id, e),
+            }
+        }
+        count
+    }
+
+    /// Modules this instance loaded that no cluster, duplicate or mock device accounts for.
+    /// They're left behind when a module loaded but its id couldn't be read, or a command died
+    /// halfway. `None` while something is being built: its modules would look the same.
+    fn orphaned_modules(state: &SharedState) -> Result<Option<Vec<ModuleInfo>>> {
+        if state.builds.load(Ordering::SeqCst) > 0 {
+            return Ok(None);
+        }
+        let modules = state.backend.list_modules()?;
+        // Started and finished in between is fine, by now its modules are tracked
+        if state.builds.load(Ordering::SeqCst) > 0 {
+            return Ok(None);
+        }
+        let mut tracked: HashSet<u32> = {
+            let graph = state.graph();
+            graph.clusters.values()
+                .flat_map(|cluster| cluster.modules())
+                .chain(graph.duplicates.values().copied())
+                .collect()
+        };
+        tracked.extend(state.mock_modules.lock().unwrap().iter().copied());
+        let named = state.named_sinks.lock().unwrap();
+        Ok(Some(modules.into_iter()
+            .filter(|module| Self::is_own_module(module) && !tracked.contains(&module.id))
+            .filter(|module| Self::module_sink(module).is_some_and(|sink| named.contains(sink)))
+            .collect()))
+    }
+
+    /// "Clean Up Orphaned Sinks": unload them now and say how many there were. Waits for
+    /// clusters being built to be registered first (see `orphaned_modules`).
+    fn clean_up_orphans(state: &SharedState, sender: &Sender<OrbEvent>) {
+        let deadline = Instant::now() + ORPHAN_CLEANUP_WAIT;
+        loop {
+            match Self::orphaned_modules(state) {
+                Ok(Some(orphans)) => {
+                    let count = Self::unload_stale(state, &orphans);
+                    info!("🧹 [CLEANUP] Unloaded {} orphaned modules", count);
+                    state.send(sender, OrbEvent::OrphansCleaned { count });
+                    return;
+                }
+                Ok(None) if Instant::now() < deadline => thread::sleep(SINK_READY_POLL),
+                Ok(None) => {
+                    state.send(sender, OrbEvent::Error {
+                        message: "Still building a cluster, try cleaning up again in a moment".to_string(),
+                    });
+                    return;
+                }
+                Err(e) => {
+                    error!("Failed to list modules for cleanup: {}", e);
+                    state.report_failure(sender, format!("Couldn't look for orphaned sinks: {}", e), &e);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// One watchdog pass. Unloads the orphans that `suspects`, the previous pass's, already
+    /// had: a cluster being built right now has its modules loaded before it's tracked.
+    /// Returns this pass's orphans, for the next one.
+    pub fn sweep_orphans(state: &SharedState, sender: &Sender<OrbEvent>, suspects: &HashSet<u32>) -> HashSet<u32> {
+        let orphans = match Self::orphaned_modules(state) {
+            Ok(Some(orphans)) => orphans,
+            // Try again next time, nothing learned
+            Ok(None) => return suspects.clone(),
+            Err(e) => {
+                warn!("🧹 [WATCHDOG] Failed to list modules: {}", e);
+                return HashSet::new();
+            }
+        };
+        let (leaked, fresh): (Vec<ModuleInfo>, Vec<ModuleInfo>) = orphans.into_iter()
+            .partition(|module| suspects.contains(&module.id));
+        if !leaked.is_empty() {
+            warn!("🧹 [WATCHDOG] {} Auralis modules are loaded that nothing tracks", leaked.len());
+            let count = Self::unload_stale(state, &leaked);
+            if count > 0 {
+                state.send(sender, OrbEvent::OrphansCleaned { count });
+            }
+        }
+        fresh.into_iter().map(|module| module.id).collect()
+    }
+
     fn spawn_mock_devices(state: &SharedState) {
         let mocks: Vec<(&str, &str)> = vec![
             // ("Mock1", "Living_Room"),
@@ -145,24 +520,10 @@ impl PipeWireClient {
         info!("🛠️ [MOCK] Spawning {} mock devices...", mocks.len());
 
         for (name, desc) in mocks {
-            let output = std::process::Command::new("pactl")
-                .args(&[
-                    "load-module",
-                    "module-null-sink",
-                    &format!("sink_name={}", name),
-                    &format!("sink_properties=device.description={}", desc),
-                ])
-                .output();
-            
-            match output {
-                Ok(out) => {
-                    let id_str = String::from_utf8_lossy(&out.stdout).trim().to_string();
-                    if let Ok(id) = id_str.parse::<u32>() {
-                        state.mock_modules.lock().unwrap().push(id);
-                        info!("  ✓ Created mock: {} (ID: {})", desc, id);
-                    } else {
-                        warn!("  ⚠ Failed to parse ID for mock {}", desc);
-                    }
+            match state.backend.load_null_sink(name, desc) {
+                Ok(id) => {
+                    state.mock_modules.lock().unwrap().push(id);
+                    info!("  ✓ Created mock: {} (ID: {})", desc, id);
                 }
                 Err(e) => error!("  ❌ Failed to create mock {}: {}", desc, e),
             }
@@ -170,278 +531,153 @@ impl PipeWireClient {
     }
 
     pub fn new(sender: Sender<OrbEvent>, receiver: Receiver<UiCommand>) -> Result<Self> {
-        // Cleanup before anything else
-        Self::cleanup_stale_modules();
+        Self::with_config(sender, receiver, AuralisConfig::default())
+    }
 
-        pw::init();
+    pub fn with_config(sender: Sender<OrbEvent>, receiver: Receiver<UiCommand>, config: AuralisConfig) -> Result<Self> {
+        if config.dry_run {
+            info!("🧪 [STARTUP] Dry-run mode: pactl/pw-link commands will be logged, not executed");
+        }
+
+        let pulse_server = config.pulse_server.clone().or_else(|| std::env::var("PULSE_SERVER").ok());
+        let backend = Arc::new(PactlBackend::new(config.dry_run)
+            .with_remote(config.pipewire_remote.clone())
+            .with_pulse_server(pulse_server));
+        Self::with_backend(sender, receiver, config, backend)
+    }
+
+    pub fn with_backend(
+        sender: Sender<OrbEvent>,
+        receiver: Receiver<UiCommand>,
+        config: AuralisConfig,
+        backend: Arc<dyn SystemAudio>,
+    ) -> Result<Self> {
+        // One instance manages the modules, see `InstanceLock`
+        let (lock, other) = match config.lock_path.as_deref().map(InstanceLock::acquire) {
+            Some(Ok(lock)) => (lock, None),
+            Some(Err(running)) if config.allow_multiple => (None, Some(running)),
+            Some(Err(running)) => bail!("{}. Close it first, or pass --allow-multiple to start another one anyway", running),
+            None => (None, None),
+        };
 
         // Shared state
-        let state = SharedState::new();
+        let store = match config.state_path {
+            Some(path) => StateStore::load(path),
+            None => StateStore::in_memory(),
+        };
+        let mut state = SharedState::with_store(backend, store);
+        state.profiles_path = config.profiles_path;
+
+        // Once up front, rather than as each action fails
+        let missing = state.backend.missing_tools();
+        if !missing.is_empty() {
+            let messages: Vec<String> = missing.iter().map(MissingTool::to_string).collect();
+            warn!("🧰 [STARTUP] {}", messages.join(", "));
+            state.send(&sender, OrbEvent::Error { message: format!("Some actions won't work: {}", messages.join(", ")) });
+        }
+
+        // Cleanup before anything else, unless the modules may belong to another instance.
+        // The watchdog only sweeps modules this instance loaded, so just `safe_mode` stops it.
+        let watchdog = !config.safe_mode;
+        match (other, config.safe_mode) {
+            (Some(running), _) => {
+                warn!("🔒 [STARTUP] {}, leaving its modules alone", running);
+                let message = format!("{}; its clusters and this one's may get in each other's way", running);
+                state.send(&sender, OrbEvent::Error { message });
+            }
+            (None, true) => info!("🛟 [STARTUP] Safe mode: not unloading stale modules"),
+            (None, false) => Self::cleanup_stale_modules(&state),
+        }
+
+        let settings = state.store().data.settings.clone();
+        state.send(&sender, OrbEvent::Settings(settings));
+
+        // Socket commands join the UI's on one channel; the UI closing its end no longer
+        // stops command handling then, `ui_gone` still does
+        let receiver = match config.control_socket.filter(|_| lock.is_some()) {
+            Some(path) => {
+                let (merged_tx, merged_rx) = channel();
+                match control::serve(&path, state.clone(), merged_tx.clone()) {
+                    Ok(()) => {
+                        thread::spawn(move || {
+                            for cmd in receiver {
+                                if merged_tx.send(cmd).is_err() {
+                                    break;
+                                }
+                            }
+                        });
+                        merged_rx
+                    }
+                    Err(e) => {
+                        warn!("🔌 [STARTUP] No control socket: {:#}", e);
+                        receiver
+                    }
+                }
+            }
+            None => receiver,
+        };
+
+        pw::init();
         
         // Spawn mocks
         Self::spawn_mock_devices(&state);
         
         let state_discovery = state.clone();
         let state_commands = state.clone();
+        let state_latency = state.clone();
         
         // Clone sender for command thread
         let sender_commands = sender.clone();
+        let sender_latency = sender.clone();
+        let sender_client = sender.clone();
 
-        let thread = thread::spawn(move || {
-            let mainloop = match pw::main_loop::MainLoop::new(None) {
-                Ok(ml) => ml,
-                Err(e) => {
-                    error!("Failed to create MainLoop: {}", e);
-                    return;
-                }
-            };
-            
-            let context = match pw::context::Context::new(&mainloop) {
-                Ok(c) => c,
-                Err(e) => {
-                    error!("Failed to create Context: {}", e);
-                    return;
-                }
-            };
-            
-            let core = match context.connect(None) {
-                Ok(c) => c,
-                Err(e) => {
-                    error!("Failed to connect to Core: {}", e);
-                    return;
-                }
-            };
-            
-            let registry = match core.get_registry() {
-                Ok(r) => r,
-                Err(e) => {
-                    error!("Failed to get Registry: {}", e);
-                    return;
-                }
-            };
-
-            let state_remove = state_discovery.clone();
-            let sender_remove = sender.clone();
-            let _listener = registry
-                .add_listener_local()
-                .global(move |global| {
-                    if let Some(props) = global.props {
-                        // Filter for Audio Sinks and Sink Inputs (Streams)
-                        let is_sink = props.get("media.class").map(|s| s == "Audio/Sink").unwrap_or(false);
-                        let is_stream = props.get("media.class").map(|s| s == "Stream/Output/Audio").unwrap_or(false);
-                        
-                        if is_sink || is_stream {
-                            let name = props.get("node.name").unwrap_or("Unknown");
-                            let description = props.get("node.description").unwrap_or(name);
-                            let app_name = props.get("application.name").unwrap_or("");
-                            
-                            // Check if this device is part of an active cluster
-                            if is_sink && state_discovery.is_cluster_member(description) {
-                                info!("Parking hidden cluster member: {} [ID: {}]", description, global.id);
-                                state_discovery.hidden_cluster_members.lock().unwrap().insert(description.to_string(), global.id);
-                                return;
-                            }
-                            
-                            // Filter out Mutter (System Sounds/Compositor) and Dummy devices
-                            if app_name == "Mutter" || name.contains("Mutter") || name.to_lowercase().contains("dummy") {
-                                return;
-                            }
-
-                            info!("Found Orb: {} ({}) [ID: {}]", description, props.get("media.class").unwrap_or("?"), global.id);
-
-                            let kind = if is_sink {
-                                if name.starts_with("auralis_combined_") || name.starts_with("auralis_cluster_") {
-                                    return; 
-                                }
-                                OrbKind::PhysicalSink { description: description.to_string() }
-                            } else {
-                                OrbKind::ApplicationSource { app_name: app_name.to_string() }
-                            };
-
-                            let id = Uuid::new_v4();
-                            
-                            // Register in shared state
-                            state_discovery.register_orb(id, global.id, name.to_string(), kind.clone());
-
-                            let orb = Orb {
-                                id,
-                                pw_id: global.id,
-                                kind,
-                                name: if !app_name.is_empty() { app_name.to_string() } else { description.to_string() },
-                                icon_name: if is_sink { "audio-card".to_string() } else { "audio-x-generic".to_string() },
-                                status: "Active".to_string(), // Default to Active for now
-                                state: OrbState::Floating,
-                                position: (0.0, 0.0),
-                                velocity: (0.0, 0.0),
-                            };
-
-                            let _ = sender.send(OrbEvent::Add(orb));
-                        }
-                    }
-                })
-                .register();
-                
-            let _remove_listener = registry
-                .add_listener_local()
-                .global_remove(move |id| {
-                    info!("Global removed: {}", id);
-                    // Find Orb ID by PipeWire ID
-                    let orb_id = {
-                        let map = state_remove.pw_id_to_orb.lock().unwrap();
-                        map.get(&id).cloned()
-                    };
-
-                    if let Some(uuid) = orb_id {
-                        info!("✓ Found Orb for PW_ID {}: {}", id, uuid);
-                        
-                        // Remove from hidden members if present
-                        state_remove.hidden_cluster_members.lock().unwrap().retain(|_, &mut v| v != id);
-
-                        // Check if this was a cluster member
-                        // We must use OrbKind to get the friendly description, as that's what we track in active_cluster_members
-                        let kind_opt = state_remove.orb_kinds.lock().unwrap().get(&uuid).cloned();
-                        
-                        if let Some(OrbKind::PhysicalSink { description: name }) = kind_opt {
-                            if state_remove.is_cluster_member(&name) {
-                                info!("💔 Cluster member disconnected: {}", name);
-                                
-                                // Find the cluster this device belonged to
-                                let cluster_info = {
-                                    let kinds = state_remove.orb_kinds.lock().unwrap();
-                                    kinds.iter().find_map(|(cid, kind)| {
-                                        if let OrbKind::Cluster { devices } = kind {
-                                            if devices.contains(&name) {
-                                                return Some((*cid, devices.clone()));
-                                            }
-                                        }
-                                        None
-                                    })
-                                };
-
-                                if let Some((cluster_id, devices)) = cluster_info {
-                                    info!("💥 Dissolving cluster {} due to member loss", cluster_id);
-                                    
-                                    // 1. Unload combine-sinks (Clean up system state)
-                                    state_remove.cleanup_combine_sinks();
-                                    
-                                    // 2. Remove Cluster Orb from UI
-                                    let _ = sender_remove.send(OrbEvent::Remove(cluster_id));
-                                    
-                                    // 3. Remove Cluster Orb from State
-                                    {
-                                        let mut kinds = state_remove.orb_kinds.lock().unwrap();
-                                        kinds.remove(&cluster_id);
-                                        state_remove.orb_names.lock().unwrap().remove(&cluster_id);
-                                    }
-
-                                    // 4. Update active members list
-                                    state_remove.remove_cluster_members(&devices);
-                                    
-                                    // 5. Restore OTHER devices
-                                    for dev_name in devices {
-                                        if dev_name == name { continue; } // Don't restore the dying device
-                                        
-                                        info!("♻️ Restoring survivor: {}", dev_name);
-                                        
-                                        // Check if we have a "parked" new instance of this device
-                                        let parked_id = state_remove.hidden_cluster_members.lock().unwrap().get(&dev_name).cloned();
-                                        
-                                        if let Some(new_pw_id) = parked_id {
-                                            info!("✓ Found parked survivor {} with new ID {}", dev_name, new_pw_id);
-                                            // Create NEW Orb for this survivor
-                                            let new_uuid = Uuid::new_v4();
-                                            
-                                            // Register in shared state
-                                            state_remove.register_orb(new_uuid, new_pw_id, dev_name.clone(), OrbKind::PhysicalSink { description: dev_name.clone() });
-                                            
-                                            let orb = Orb {
-                                                id: new_uuid,
-                                                pw_id: new_pw_id,
-                                                kind: OrbKind::PhysicalSink { description: dev_name.clone() },
-                                                name: dev_name.clone(),
-                                                icon_name: "audio-card".to_string(),
-                                                status: "Active".to_string(),
-                                                state: OrbState::Floating,
-                                                position: (0.0, 0.0),
-                                                velocity: (0.0, 0.0),
-                                            };
-                                            let _ = sender_remove.send(OrbEvent::Add(orb));
-                                            
-                                            // Remove from hidden members as it's now active
-                                            state_remove.hidden_cluster_members.lock().unwrap().remove(&dev_name);
-                                            
-                                        } else {
-                                            // Fallback: Try to restore old one (if it wasn't removed)
-                                            // Find UUID/Details of the survivor
-                                            // CRITICAL FIX: Search orb_kinds for PhysicalSink, NOT just orb_names
-                                            // This prevents matching the combine-sink output stream which might share the same name
-                                            let survivor_uuid = {
-                                                let kinds = state_remove.orb_kinds.lock().unwrap();
-                                                kinds.iter().find_map(|(u, k)| {
-                                                    if let OrbKind::PhysicalSink { description } = k {
-                                                        if description == &dev_name {
-                                                            return Some(*u);
-                                                        }
-                                                    }
-                                                    None
-                                                })
-                                            };
-                                            
-                                            if let Some(suid) = survivor_uuid {
-                                                // Only restore if it still exists in the maps (wasn't removed)
-                                                let (spw_id, skind) = {
-                                                    let pw_map = state_remove.orb_to_pw_id.lock().unwrap();
-                                                    let kind_map = state_remove.orb_kinds.lock().unwrap();
-                                                    (pw_map.get(&suid).cloned(), kind_map.get(&suid).cloned())
-                                                };
-                                                
-                                                if let (Some(spw_id), Some(skind)) = (spw_id, skind) {
-                                                    info!("✓ Restoring existing survivor {} (ID: {})", dev_name, spw_id);
-                                                    let orb = Orb {
-                                                        id: suid,
-                                                        pw_id: spw_id,
-                                                        kind: skind,
-                                                        name: dev_name.clone(),
-                                                        icon_name: "audio-card".to_string(),
-                                                        status: "Active".to_string(),
-                                                        state: OrbState::Floating,
-                                                        position: (0.0, 0.0),
-                                                        velocity: (0.0, 0.0),
-                                                    };
-                                                    let _ = sender_remove.send(OrbEvent::Add(orb));
-                                                } else {
-                                                    info!("⚠ Survivor {} not found in state (likely removed)", dev_name);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
+        let remote = config.pipewire_remote.filter(|r| !r.trim().is_empty());
+        let thread = config.discovery.then(|| thread::spawn(move || {
+            let mut backoff = RECONNECT_BACKOFF_MIN;
+            let mut unreachable_reported = false;
+            loop {
+                let was_connected = Self::run_discovery(&state_discovery, &sender, remote.as_deref());
+                Self::connection_lost(&state_discovery, &sender);
 
-                        // Remove from shared state
-                        {
-                            state_remove.orb_to_pw_id.lock().unwrap().remove(&uuid);
-                            state_remove.pw_id_to_orb.lock().unwrap().remove(&id);
-                            state_remove.orb_names.lock().unwrap().remove(&uuid);
-                            state_remove.orb_kinds.lock().unwrap().remove(&uuid);
-                        }
-                        
-                        // Notify UI
-                        let _ = sender_remove.send(OrbEvent::Remove(uuid));
-                    } else {
-                        // info!("Ignored removal of unknown global: {}", id);
-                    }
-                })
-                .register();
+                // A mistyped remote never connects: say so once, not on every retry
+                if was_connected {
+                    unreachable_reported = false;
+                } else if let (Some(remote), false) = (&remote, unreachable_reported) {
+                    let message = format!("Could not reach PipeWire remote '{}', still trying", remote);
+                    state_discovery.send(&sender, OrbEvent::Error { message });
+                    unreachable_reported = true;
+                }
 
-            info!("Starting PipeWire main loop");
-            mainloop.run();
+                backoff = if was_connected {
+                    RECONNECT_BACKOFF_MIN
+                } else {
+                    (backoff * 2).min(RECONNECT_BACKOFF_MAX)
+                };
+                warn!("🔌 [CORE] PipeWire connection lost, reconnecting in {:?}", backoff);
+                thread::sleep(backoff);
+            }
+        }));
+        
+        // Passive monitoring of latency and the default sink: only reads what the server reports.
+        // Every so often the watchdog also unloads Auralis modules nothing tracks anymore.
+        let latency_thread = thread::spawn(move || {
+            let mut last_sweep = Instant::now();
+            let mut suspects = HashSet::new();
+            loop {
+                thread::sleep(LATENCY_POLL_INTERVAL);
+                Self::measure_latency(&state_latency, &sender_latency);
+                Self::reconcile_default_sink(&state_latency, &sender_latency);
+                if watchdog && last_sweep.elapsed() >= ORPHAN_SWEEP_INTERVAL {
+                    suspects = Self::sweep_orphans(&state_latency, &sender_latency, &suspects);
+                    last_sweep = Instant::now();
+                }
+            }
         });
         
         // Create thread pool for command handlers (max 10 concurrent)
         let pool = threadpool::ThreadPool::new(10);
         let pool_for_thread = pool.clone();
+        let load = Arc::new(CommandLoad::default());
         
         // Command handling thread
         let state_for_thread = state_commands.clone();
@@ -456,7 +692,16 @@ impl PipeWireClient {
                     info!("💓 [CORE-HEALTH] Receiver thread alive, processed {} commands so far", cmd_count);
                 }
                 
-                match receiver.recv() {
+                // Nobody listens to our events anymore: let running commands finish,
+                // then unload whatever they created and stop taking new ones
+                if state_for_thread.ui_gone() {
+                    warn!("🛑 [CORE-EXIT] UI is gone, shutting down command handling");
+                    pool_for_thread.join();
+                    state_for_thread.cleanup_combine_sinks();
+                    break;
+                }
+
+                match receiver.recv_timeout(COMMAND_POLL_INTERVAL) {
                     Ok(cmd) => {
                         cmd_count += 1;
                         info!("📨 [CORE-RECV] Command #{} received: {:?}", cmd_count, cmd);
@@ -464,28 +709,19 @@ impl PipeWireClient {
                         // Execute handler in thread pool (bounded to 10 workers)
                         let state_clone = state_for_thread.clone();
                         let sender_clone = sender_commands.clone();
+                        let load_clone = load.clone();
                         
+                        load.update(&state_for_thread, &sender_commands, |_, queued| *queued += 1);
                         pool_for_thread.execute(move || {
-                            match cmd {
-                                UiCommand::Connect { source, target } => {
-                                    info!("🔗 [CORE-EXEC] Executing Connect: {} -> {}", source, target);
-                                    Self::handle_connect(&state_clone, &sender_clone, source, target);
-                                    info!("✓ [CORE-DONE] Connect command completed");
-                                }
-                                UiCommand::Disconnect { source, target } => {
-                                    info!("🔴 [CORE-RECV] Disconnect command received: {} -> {}", source, target);
-                                    info!("🔧 [CORE-EXEC] Executing Disconnect handler");
-                                    Self::handle_disconnect(&state_clone, &sender_clone, source, target);
-                                    info!("✓ [CORE-DONE] Disconnect command completed");
-                                }
-                                UiCommand::Shutdown => {
-                                    info!("🛑 [CORE-RECV] Shutdown command received");
-                                    state_clone.cleanup_combine_sinks();
-                                    info!("✓ [CORE-DONE] Cleanup complete, exiting thread");
-                                }
-                            }
+                            load_clone.update(&state_clone, &sender_clone, |in_flight, queued| {
+                                *queued -= 1;
+                                *in_flight += 1;
+                            });
+                            let _in_flight = InFlight { load: load_clone, state: state_clone.clone(), sender: sender_clone.clone() };
+                            Self::handle_command(&state_clone, &sender_clone, cmd);
                         });
                     }
+                    Err(RecvTimeoutError::Timeout) => {}
                     Err(e) => {
                         error!("💀 [CORE-ERROR] Command receiver channel closed: {}", e);
                         error!("💀 [CORE-EXIT] Receiver thread terminating after {} commands", cmd_count);
@@ -496,475 +732,2703 @@ impl PipeWireClient {
         });
 
         Ok(Self {
+            state,
+            sender: sender_client,
             _thread: thread,
             _cmd_thread: cmd_thread,
+            _latency_thread: latency_thread,
             _command_pool: pool,
+            _lock: lock,
         })
     }
 
-    fn handle_connect(state: &SharedState, sender: &Sender<OrbEvent>, source: Uuid, target: Uuid) {
-        let src_kind;
-        let tgt_kind;
-        let src_name;
-        let tgt_name;
-        
-        {
-            let names = state.orb_names.lock().unwrap();
-            let kinds = state.orb_kinds.lock().unwrap();
-            
-            src_kind = kinds.get(&source).cloned();
-            tgt_kind = kinds.get(&target).cloned();
-            
-            src_name = names.get(&source).map(|s| s.to_string()).unwrap_or_else(|| "Unknown".to_string());
-            tgt_name = names.get(&target).map(|s| s.to_string()).unwrap_or_else(|| "Unknown".to_string());
-        } // Locks dropped here!
+    /// Bind a node and mirror its state ("Playing", "Idle", ...) into its orb's status.
+    /// The proxy and listener only live as long as the returned monitor.
+    fn monitor_node(
+        registry: &pw::registry::Registry,
+        global: &pw::registry::GlobalObject<&pw::spa::utils::dict::DictRef>,
+        state: &SharedState,
+        sender: &Sender<OrbEvent>,
+    ) -> Result<NodeMonitor> {
+        let node: pw::node::Node = registry.bind(global)?;
+        let (state, sender, pw_id) = (state.clone(), sender.clone(), global.id);
+        let listener = node.add_listener_local()
+            .info(move |info| {
+                let status = match info.state() {
+                    pw::node::NodeState::Running => "Playing",
+                    pw::node::NodeState::Idle => "Idle",
+                    pw::node::NodeState::Suspended => "Suspended",
+                    pw::node::NodeState::Error(_) => "Error",
+                    pw::node::NodeState::Creating => return,
+                };
+                let events = state.graph().set_node_status(pw_id, status);
+                state.send_events(&sender, events);
+            })
+            .register();
+        Ok((node, listener))
+    }
 
-        match (src_kind, tgt_kind) {
-            // Case 1: Sink + Sink = New Cluster
-            (Some(OrbKind::PhysicalSink { description: desc1 }), Some(OrbKind::PhysicalSink { description: desc2 })) => {
-                info!("Creating cluster for {} + {}", src_name, tgt_name);
-                Self::create_cluster(state, sender, vec![desc1, desc2]);
-            }
-            
-            // Case 2: Sink + Cluster = Add to Cluster
-            (Some(OrbKind::PhysicalSink { description }), Some(OrbKind::Cluster { devices })) => {
-                info!("Adding {} to cluster {:?}", description, devices);
-                
-                // 1. Unload old cluster
-                Self::unload_cluster(state, sender, target);
-                
-                // 2. Create new cluster
-                let mut new_devices = devices.clone();
-                new_devices.push(description);
-                Self::create_cluster(state, sender, new_devices);
-            }
-            
-            // Case 3: Cluster + Sink = Add to Cluster
-            (Some(OrbKind::Cluster { devices }), Some(OrbKind::PhysicalSink { description })) => {
-                info!("Adding {} to cluster {:?}", description, devices);
-                
-                // 1. Unload old cluster
-                Self::unload_cluster(state, sender, source);
-                
-                // 2. Create new cluster
-                let mut new_devices = devices.clone();
-                new_devices.push(description);
-                Self::create_cluster(state, sender, new_devices);
-            }
-            
-            // Case 4: Cluster + Cluster = Merge Clusters
-            (Some(OrbKind::Cluster { devices: d1 }), Some(OrbKind::Cluster { devices: d2 })) => {
-                info!("Merging clusters {:?} + {:?}", d1, d2);
-                
-                // 1. Unload both
-                Self::unload_cluster(state, sender, source);
-                Self::unload_cluster(state, sender, target);
-                
-                // 2. Create super-cluster
-                let mut new_devices = d1.clone();
-                new_devices.extend(d2);
-                Self::create_cluster(state, sender, new_devices);
-            }
+    /// Connect to PipeWire (`remote`, or the default one) and run the discovery main loop
+    /// until the connection drops. Returns whether a connection was established at all.
+    fn run_discovery(state: &SharedState, events: &Sender<OrbEvent>, remote: Option<&str>) -> bool {
+        let state_discovery = state.clone();
+        let sender = events.clone();
+        let connected_at = Instant::now();
 
-            // Case 5: Source -> Sink = Link
-            (Some(OrbKind::ApplicationSource { .. }), Some(OrbKind::PhysicalSink { .. })) |
-            (Some(OrbKind::ApplicationSource { .. }), Some(OrbKind::Cluster { .. })) => {
-                info!("Linking source {} -> sink {}", src_name, tgt_name);
-                Self::link_source_to_sink(state, source, target);
+        let mainloop = match pw::main_loop::MainLoop::new(None) {
+            Ok(ml) => ml,
+            Err(e) => {
+                error!("Failed to create MainLoop: {}", e);
+                return false;
             }
-            
-            _ => {
-                warn!("Invalid connection type");
+        };
+        
+        let context = match pw::context::Context::new(&mainloop) {
+            Ok(c) => c,
+            Err(e) => {
+                error!("Failed to create Context: {}", e);
+                return false;
             }
-        }
-    }
-    
-    fn unload_cluster(state: &SharedState, sender: &Sender<OrbEvent>, cluster_id: Uuid) {
-        // 1. Determine Target Sink for Streams
-        let target_sink = {
-            let mut saved = state.saved_default_sink.lock().unwrap();
-            saved.remove(&cluster_id)
         };
         
-        // If no saved sink, try to find the first device in the cluster
-        let fallback_sink = if target_sink.is_none() {
-            let kinds = state.orb_kinds.lock().unwrap();
-            if let Some(OrbKind::Cluster { devices }) = kinds.get(&cluster_id) {
-                devices.first().cloned()
-            } else {
-                None
+        let properties = remote.map(|name| pw::properties::properties! { *pw::keys::REMOTE_NAME => name });
+        let core = match context.connect(properties) {
+            Ok(c) => c,
+            Err(e) => {
+                error!("Failed to connect to Core ({}): {}", remote.unwrap_or("default remote"), e);
+                return false;
+            }
+        };
+        
+        let registry = match core.get_registry() {
+            Ok(r) => r,
+            Err(e) => {
+                error!("Failed to get Registry: {}", e);
+                return false;
             }
-        } else {
-            None
         };
 
-        let restore_to = target_sink.or(fallback_sink);
-
-        // 2. Move Streams & Restore Default Sink
-        if let Some(sink_name) = restore_to {
-            info!("Restoring streams to: {}", sink_name);
-            
-            // Move streams
-            // We need the ID of the cluster module to find its sink ID, but we can also just move ALL streams 
-            // that are currently on the cluster sink.
-            // Since we don't have the cluster sink name easily here (we constructed it dynamically),
-            // we'll use a broad approach: Move ALL sink-inputs to the target.
-            // This is safer than leaving them to fallback.
-            let _ = std::process::Command::new("bash")
-                .arg("-c")
-                .arg(format!(
-                    "pactl list sink-inputs short | cut -f1 | xargs -I{{}} pactl move-sink-input {{}} {} 2>/dev/null || true",
-                    sink_name
-                ))
-                .output();
-                
-            // Restore default sink
-            let _ = std::process::Command::new("pactl")
-                .args(&["set-default-sink", &sink_name])
-                .output();
-        }
+        // Node proxies are bound from inside the registry listener
+        let registry = Rc::new(registry);
+        let registry_bind = Rc::downgrade(&registry);
+        let node_proxies: Rc<RefCell<NodeProxies<NodeMonitor>>> = Rc::new(RefCell::new(NodeProxies::new()));
+        let node_proxies_remove = node_proxies.clone();
 
-        // 3. Unload Module
-        let module_id = {
-            let mut modules = state.combine_modules.lock().unwrap();
-            modules.remove(&cluster_id)
+        let state_remove = state_discovery.clone();
+        let sender_remove = sender.clone();
+        let _listener = registry
+            .add_listener_local()
+            .global(move |global| {
+                state_discovery.metrics.discovery_event();
+                if let Some(props) = global.props {
+                    // Links tell which stream plays to which sink
+                    if global.type_ == pw::types::ObjectType::Link {
+                        let node = |key| props.get(key).and_then(|id: &str| id.parse::<u32>().ok());
+                        if let (Some(output_node), Some(input_node)) = (node("link.output.node"), node("link.input.node")) {
+                            let mut graph = state_discovery.graph();
+                            graph.add_link(global.id, output_node, input_node);
+                            let events = state_discovery.refresh_routes(&mut graph);
+                            drop(graph);
+                            state_discovery.send_events(&sender, events);
+                        }
+                        return;
+                    }
+
+                    let mut graph = state_discovery.graph();
+                    let Some(decision) = discovery::classify_global(props, |name| graph.is_cluster_member(name)) else {
+                        return;
+                    };
+
+                    // Watch the state of every device and stream, hidden cluster members included
+                    if !matches!(decision, DiscoveryDecision::Ignore | DiscoveryDecision::OwnSink(_)) {
+                        if let Some(registry) = registry_bind.upgrade() {
+                            match Self::monitor_node(&registry, global, &state_discovery, &sender) {
+                                Ok(monitor) => node_proxies.borrow_mut().track(global.id, monitor),
+                                Err(e) => warn!("Failed to bind node {}: {}", global.id, e),
+                            }
+                        }
+                    }
+
+                    // Back within the dropout grace period: the combine-sink picks the
+                    // node up again by name, so only its new ID needs recording
+                    if let DiscoveryDecision::Park(name) | DiscoveryDecision::AddSink { node_name: name, .. } = &decision {
+                        if let Some(events) = graph.finish_dropout(name, global.id) {
+                            info!("🔗 Dropped-out device returned: {} [ID: {}]", name, global.id);
+                            drop(graph);
+                            state_discovery.send_events(&sender, events);
+                            return;
+                        }
+                    }
+
+                    // Another node of a card whose device just went away: a profile switch
+                    // (A2DP <-> headset, analog <-> HDMI), the device lives on with the new node
+                    let device = match &decision {
+                        DiscoveryDecision::AddSink { device, .. } => device.clone(),
+                        _ => None,
+                    };
+                    if let (Some(card), DiscoveryDecision::AddSink { node_name, description, .. }) = (&device, &decision) {
+                        let alias = state_discovery.store().data.settings.device_aliases.get(node_name).cloned();
+                        let shown = alias.unwrap_or_else(|| description.clone());
+                        if let Some((orb_id, old_node_name, events)) = graph.replace_node(card, global.id, node_name, description, &shown) {
+                            info!("🔁 Device switched profile: {} -> {} [ID: {}]", old_node_name, node_name, global.id);
+                            drop(graph);
+                            state_discovery.send_events(&sender, events);
+                            let (state_replaced, sender_replaced) = (state_discovery.clone(), sender.clone());
+                            // Off the PipeWire loop, rebuilding a cluster waits for its sink
+                            thread::spawn(move || Self::device_replaced(&state_replaced, &sender_replaced, orb_id, &old_node_name));
+                            return;
+                        }
+                    }
+
+                    let (node_name, kind, name, bus, channels, media_role) = match decision {
+                        DiscoveryDecision::Ignore => return,
+                        DiscoveryDecision::OwnSink(name) => {
+                            state_discovery.own_sink_announced(global.id, &name);
+                            return;
+                        }
+                        DiscoveryDecision::Park(name) => {
+                            info!("Parking hidden cluster member: {} [ID: {}]", name, global.id);
+                            graph.park(&name, global.id);
+                            return;
+                        }
+                        DiscoveryDecision::AddSink { node_name, description, bus, channels, .. } => {
+                            let kind = OrbKind::PhysicalSink { description: description.clone() };
+                            let alias = state_discovery.store().data.settings.device_aliases.get(&node_name).cloned();
+                            (node_name, kind, alias.unwrap_or(description), bus, channels, None)
+                        }
+                        DiscoveryDecision::AddStream { node_name, app_name, description, channels, media_role } => {
+                            let kind = OrbKind::ApplicationSource { app_name };
+                            (node_name, kind, description, DeviceBus::Unknown, channels, media_role)
+                        }
+                    };
+                    let is_sink = matches!(kind, OrbKind::PhysicalSink { .. });
+                    info!("Found Orb: {} ({}) [ID: {}]", name, node_name, global.id);
+
+                    let icon_name = match (is_sink, bus.icon_name()) {
+                        (true, Some(icon)) => icon,
+                        (true, None) => "audio-card",
+                        (false, _) => "audio-x-generic",
+                    };
+
+                    let orb_id = Uuid::new_v4();
+                    let orb = Orb {
+                        id: orb_id,
+                        pw_id: global.id,
+                        node_name: node_name.clone(),
+                        kind,
+                        name,
+                        icon_name: icon_name.to_string(),
+                        status: "Active".to_string(), // Default to Active for now
+                        state: OrbState::Floating,
+                        zone: None,
+                        latency_ms: None,
+                        bus,
+                        channels,
+                        formats: Vec::new(),
+                        format: None,
+                        position: (0.0, 0.0),
+                        velocity: (0.0, 0.0),
+                    };
+
+                    let mut events = graph.add_orb(orb);
+                    // Its links may have been announced first
+                    events.extend(state_discovery.refresh_routes(&mut graph));
+                    if let Some(role) = &media_role {
+                        graph.media_roles.insert(orb_id, role.clone());
+                    }
+                    if let Some(card) = device {
+                        graph.sink_devices.insert(orb_id, card);
+                    }
+                    drop(graph);
+                    state_discovery.send_events(&sender, events);
+
+                    let routed = media_role.is_some_and(|role| {
+                        let settings = &state_discovery.store().data.settings;
+                        settings.role_targets.contains_key(&role)
+                            || (role == media_roles::NOTIFICATION && settings.notifications_off_clusters)
+                    });
+                    if routed {
+                        let (state_route, sender_route) = (state_discovery.clone(), sender.clone());
+                        // Off the PipeWire loop, pactl round-trips take a while
+                        thread::spawn(move || {
+                            thread::sleep(ROLE_ROUTE_SETTLE);
+                            Self::route_by_role(&state_route, &sender_route, orb_id);
+                        });
+                    }
+
+                    if is_sink {
+                        Self::schedule_adopt_scan(&state_discovery, &sender);
+
+                        let (state_formats, sender_formats) = (state_discovery.clone(), sender.clone());
+                        // Off the PipeWire loop, pw-dump takes a while
+                        thread::spawn(move || Self::read_formats(&state_formats, &sender_formats, orb_id));
+                    }
+
+                    if is_sink && Self::is_suspended_output(&state_discovery, &node_name) {
+                        let (state_suspend, sender_suspend) = (state_discovery.clone(), sender.clone());
+                        let sink = node_name.clone();
+                        // Off the PipeWire loop, pactl round-trips take a while
+                        thread::spawn(move || {
+                            info!("💤 Suspending {} again", sink);
+                            let name = state_suspend.graph().orbs.get(&orb_id).map_or(sink.clone(), |o| o.name.clone());
+                            Self::apply_suspend(&state_suspend, &sender_suspend, &name, std::slice::from_ref(&sink), true);
+                        });
+                    }
+
+                    if is_sink && Self::wants_auto_switch(&state_discovery, &node_name, connected_at.elapsed()) {
+                        let state_switch = state_discovery.clone();
+                        let sender_switch = sender.clone();
+                        // Off the PipeWire loop, pactl round-trips take a while
+                        thread::spawn(move || {
+                            thread::sleep(AUTO_SWITCH_SETTLE);
+                            Self::auto_switch_to(&state_switch, &sender_switch, &node_name);
+                        });
+                    }
+                }
+            })
+            .register();
+            
+        let _remove_listener = registry
+            .add_listener_local()
+            .global_remove(move |id| {
+                info!("Global removed: {}", id);
+                state_remove.metrics.discovery_event();
+                state_remove.own_sink_removed(id);
+                node_proxies_remove.borrow_mut().release(id);
+
+                {
+                    let mut graph = state_remove.graph();
+                    if graph.remove_link(id) {
+                        let events = state_remove.refresh_routes(&mut graph);
+                        drop(graph);
+                        state_remove.send_events(&sender_remove, events);
+                        return;
+                    }
+                }
+
+                let grace_ms = state_remove.store().data.settings.dropout_grace_ms;
+                let mut graph = state_remove.graph();
+
+                // Remove from parked members if present
+                graph.forget_parked(id);
+
+                // An adopted combine-sink was unloaded behind our back: just show its members again
+                if let Some(cluster_id) = graph.external_cluster_by_pw_id(id) {
+                    info!("External combine-sink {} went away", id);
+                    let events = graph.dissolve_cluster(cluster_id, true)
+                        .map(|(_, events)| events)
+                        .unwrap_or_default();
+                    state_remove.metrics.cluster_dissolved();
+                    drop(graph);
+                    state_remove.send_events(&sender_remove, events);
+                    return;
+                }
+
+                // Find Orb ID by PipeWire ID
+                let Some(uuid) = graph.orb_id_by_pw_id(id) else {
+                    return;
+                };
+                info!("✓ Found Orb for PW_ID {}: {}", id, uuid);
+
+                // Bluetooth speakers drop out for a moment all the time, HDMI ones whenever
+                // their monitor sleeps: give a cluster member the chance to come back before
+                // breaking up its cluster. Any device of a known card also waits a moment
+                // for a profile switch to bring up its new node.
+                let bus = graph.orbs.get(&uuid).map_or(DeviceBus::Unknown, |o| o.bus);
+                let grace = Duration::from_millis(bus.dropout_grace_ms(grace_ms).into());
+                let dropout = match grace.is_zero() {
+                    true => None,
+                    false => graph.begin_dropout(uuid).map(|token| (token, grace.max(PROFILE_SWITCH_GRACE))),
+                };
+                let pending = dropout.or_else(|| graph.begin_replacement(uuid).map(|token| (token, PROFILE_SWITCH_GRACE)));
+                if let Some((token, wait)) = pending {
+                    drop(graph);
+                    info!("⏳ Device {} went away, waiting {:?} for it to return", uuid, wait);
+                    let state_grace = state_remove.clone();
+                    let sender_grace = sender_remove.clone();
+                    thread::spawn(move || {
+                        thread::sleep(wait);
+                        if state_grace.graph().expire_dropout(uuid, token) {
+                            info!("⌛ Device {} did not return", uuid);
+                            Self::remove_vanished_orb(&state_grace, &sender_grace, uuid);
+                        }
+                    });
+                    return;
+                }
+                drop(graph);
+
+                Self::remove_vanished_orb(&state_remove, &sender_remove, uuid);
+            })
+            .register();
+
+        // The daemon answers a sync only after it has sent every existing global
+        let initial_sync = match core.sync(0) {
+            Ok(seq) => Some(seq),
+            Err(e) => {
+                warn!("Failed to sync with PipeWire, discovery completion won't be reported: {}", e);
+                None
+            }
+        };
+        let state_done = state.clone();
+        let sender_done = events.clone();
+
+        // Quit the main loop when the daemon goes away so the caller can reconnect
+        let mainloop_weak = mainloop.downgrade();
+        let _core_listener = core
+            .add_listener_local()
+            .done(move |id, seq| {
+                if id == pw::core::PW_ID_CORE && Some(seq) == initial_sync {
+                    info!("✓ Initial discovery complete");
+                    state_done.send(&sender_done, OrbEvent::DiscoveryComplete);
+
+                    // Off the loop: building a cluster waits for its sink to be announced here
+                    let (state, sender) = (state_done.clone(), sender_done.clone());
+                    thread::spawn(move || Self::rebuild_lost_clusters(&state, &sender));
+                }
+            })
+            .error(move |id, _seq, res, message| {
+                if id == pw::core::PW_ID_CORE {
+                    error!("PipeWire core error ({}): {}", res, message);
+                    if let Some(mainloop) = mainloop_weak.upgrade() {
+                        mainloop.quit();
+                    }
+                }
+            })
+            .register();
+
+        state.send(events, OrbEvent::BackendState { connected: true });
+
+        info!("Starting PipeWire main loop");
+        mainloop.run();
+        true
+    }
+
+    /// Change a persisted setting and tell the UI, unless nothing actually changed
+    fn update_settings(state: &SharedState, sender: &Sender<OrbEvent>, apply: impl FnOnce(&mut Settings)) {
+        let settings = {
+            let mut store = state.store();
+            let before = store.data.settings.clone();
+            apply(&mut store.data.settings);
+            if store.data.settings == before {
+                return;
+            }
+            store.save();
+            store.data.settings.clone()
+        };
+        state.send(sender, OrbEvent::Settings(settings));
+    }
+
+    /// Give a device an alias (or take it away), remembered by node name. Its canvas spot
+    /// moves along, it's kept by name.
+    fn rename_device(state: &SharedState, sender: &Sender<OrbEvent>, device: Uuid, name: Option<String>) {
+        let Some(orb) = state.graph().orbs.get(&device).cloned() else {
+            warn!("No device found for {}", device);
+            return;
+        };
+        let OrbKind::PhysicalSink { description } = &orb.kind else {
+            warn!("{} isn't a device, not renaming it", orb.name);
+            return;
+        };
+        let alias = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty() && n != description);
+        let shown = alias.clone().unwrap_or_else(|| description.clone());
+
+        Self::update_settings(state, sender, |settings| {
+            match &alias {
+                Some(alias) => settings.device_aliases.insert(orb.node_name.clone(), alias.clone()),
+                None => settings.device_aliases.remove(&orb.node_name),
+            };
+            if let Some(position) = settings.canvas_positions.remove(&orb.name) {
+                settings.canvas_positions.insert(shown.clone(), position);
+            }
+        });
+        let events = state.graph().rename_device(device, shown);
+        state.send_events(sender, events);
+    }
+
+    /// Drop the orb of a node that's gone for good. A vanished cluster member takes its cluster down with it.
+    fn remove_vanished_orb(state: &SharedState, sender: &Sender<OrbEvent>, uuid: Uuid) {
+        let (events, dissolved, duplicates) = {
+            let mut graph = state.graph();
+            let cluster_id = graph.cluster_of(uuid);
+            let mut events = graph.remove_orb(uuid);
+
+            // A loopback to or from a device that's gone plays nothing
+            let duplicates = graph.take_duplicates_of(uuid);
+            if !duplicates.is_empty() {
+                events.push(OrbEvent::Duplicates { pairs: graph.duplicate_pairs() });
+            }
+
+            let dissolved = cluster_id.and_then(|cid| {
+                info!("💥 Dissolving cluster {} due to member loss", cid);
+                graph.dissolve_cluster(cid, true)
+            });
+            if let Some((_, restore_events)) = &dissolved {
+                events.extend(restore_events.iter().cloned());
+            }
+
+            (events, dissolved.map(|(cluster, _)| cluster), duplicates)
+        };
+
+        // Clean up system state outside the lock
+        if let Some(cluster) = dissolved {
+            state.metrics.cluster_dissolved();
+            state.unload_cluster_modules(&cluster);
+        }
+        state.unload_modules(&duplicates);
+
+        // Notify UI
+        state.send_events(sender, events);
+    }
+
+    /// Execute a single UI command against the shared state and backend
+    pub fn handle_command(state: &SharedState, sender: &Sender<OrbEvent>, cmd: UiCommand) {
+        if state.ui_gone() {
+            warn!("UI is gone, dropping command {:?}", cmd);
+            return;
+        }
+
+        state.metrics.command_processed();
+        Self::dispatch_command(state, sender, cmd);
+
+        // The UI went away while this was running: nobody will ever separate
+        // what we just built, so unload it now
+        if state.ui_gone() {
+            state.cleanup_combine_sinks();
+        }
+    }
+
+    fn dispatch_command(state: &SharedState, sender: &Sender<OrbEvent>, cmd: UiCommand) {
+        match cmd {
+            UiCommand::Connect { source, target } => {
+                info!("🔗 [CORE-EXEC] Executing Connect: {} -> {}", source, target);
+                if Self::handle_connect(state, sender, source, target).is_some() {
+                    Self::confirm_cluster(state);
+                }
+                info!("✓ [CORE-DONE] Connect command completed");
+            }
+            UiCommand::StageCluster { device } => {
+                info!("🫧 [CORE-EXEC] Staging a cluster for {}", device);
+                Self::detach_members(state, sender, &[device]);
+                let staged = state.graph().stage_cluster(device);
+                match staged {
+                    Some((_, events)) => state.send_events(sender, events),
+                    None => warn!("{} can't start a cluster", device),
+                }
+            }
+            UiCommand::Disconnect { source, target } => {
+                info!("🔴 [CORE-RECV] Disconnect command received: {} -> {}", source, target);
+                info!("🔧 [CORE-EXEC] Executing Disconnect handler");
+                Self::handle_disconnect(state, sender, source, target);
+                info!("✓ [CORE-DONE] Disconnect command completed");
+            }
+            UiCommand::CreateCluster { devices } => {
+                info!("🔗 [CORE-EXEC] Clustering {} devices", devices.len());
+                if Self::cluster_devices(state, sender, devices).is_some() {
+                    Self::confirm_cluster(state);
+                }
+            }
+            UiCommand::RenameDevice { device, name } => {
+                info!("✏️ [CORE-EXEC] Renaming device {} to {:?}", device, name);
+                Self::rename_device(state, sender, device, name);
+            }
+            UiCommand::RenameCluster { cluster, name } => {
+                info!("✏️ [CORE-EXEC] Renaming cluster {} to {:?}", cluster, name);
+                let name = name.trim().to_string();
+                if name.is_empty() {
+                    warn!("Ignoring empty cluster name");
+                    return;
+                }
+                Self::update_cluster_prefs(state, sender, cluster, |graph, prefs| {
+                    prefs.name = Some(name.clone());
+                    graph.rename_cluster(cluster, name)
+                });
+            }
+            UiCommand::SetClusterZone { cluster, zone } => {
+                info!("🏠 [CORE-EXEC] Moving cluster {} to zone {:?}", cluster, zone);
+                let zone = zone.map(|z| z.trim().to_string()).filter(|z| !z.is_empty());
+                Self::update_cluster_prefs(state, sender, cluster, |graph, prefs| {
+                    prefs.zone = zone.clone();
+                    graph.set_cluster_zone(cluster, zone)
+                });
+            }
+            UiCommand::SetAutoSwitch { enabled } => {
+                info!("⚙️ [CORE-EXEC] Auto-switch to new devices: {}", enabled);
+                Self::update_settings(state, sender, |settings| settings.auto_switch_new_devices = enabled);
+            }
+            UiCommand::SetDropoutGrace { ms } => {
+                info!("⚙️ [CORE-EXEC] Cluster member dropout grace: {}ms", ms);
+                Self::update_settings(state, sender, |settings| settings.dropout_grace_ms = ms);
+            }
+            UiCommand::SetBluetoothOffset { ms } => {
+                info!("⚙️ [CORE-EXEC] Bluetooth offset: {}ms", ms);
+                Self::set_bluetooth_offset(state, sender, ms);
+            }
+            UiCommand::SetAccentColor { color } => {
+                info!("⚙️ [CORE-EXEC] Accent color: {:?}", color);
+                Self::update_settings(state, sender, |settings| settings.accent_color = color);
+            }
+            UiCommand::SetAppearance { appearance } => {
+                info!("⚙️ [CORE-EXEC] Appearance: {:?}", appearance);
+                Self::update_settings(state, sender, |settings| settings.appearance = appearance);
+            }
+            UiCommand::SetCanvasLayout { layout } => {
+                info!("⚙️ [CORE-EXEC] Canvas layout: {:?}", layout);
+                Self::update_settings(state, sender, |settings| settings.canvas_layout = layout);
+            }
+            UiCommand::SetReduceMotion { enabled } => {
+                info!("⚙️ [CORE-EXEC] Reduce motion: {}", enabled);
+                Self::update_settings(state, sender, |settings| settings.reduce_motion = enabled);
+            }
+            UiCommand::SetShowNotifications { enabled } => {
+                info!("⚙️ [CORE-EXEC] Notifications: {}", enabled);
+                Self::update_settings(state, sender, |settings| settings.show_notifications = enabled);
+            }
+            UiCommand::StartBeam { source } => {
+                info!("📡 [CORE-EXEC] Starting a beam of {}", source);
+                match Self::start_beam(state, sender, source) {
+                    Ok(events) => state.send_events(sender, events),
+                    Err(e) => {
+                        warn!("Not beaming {}: {:#}", source, e);
+                        state.send(sender, OrbEvent::Error { message: format!("Couldn't start a beam: {:#}", e) });
+                    }
+                }
+            }
+            UiCommand::BeamClosed { session_id, reason } => {
+                info!("📡 [CORE-EXEC] Beam {} closed ({:?})", session_id, reason);
+                let events = state.graph().remove_beam(&session_id);
+                state.send_events(sender, events);
+                state.backend.stop_beam(&session_id);
+                if let Some(reason) = reason {
+                    state.send(sender, OrbEvent::Error { message: format!("Beam {}: {}", session_id, reason) });
+                }
+            }
+            UiCommand::BeamStatus { session_id, status } => {
+                info!("📡 [CORE-EXEC] Beam {} is {}", session_id, status);
+                let events = state.graph().set_beam_status(&session_id, &status);
+                state.send_events(sender, events);
+            }
+            UiCommand::ApplyProfile { name } => {
+                info!("🎬 [CORE-EXEC] Applying profile {:?}", name);
+                if let Err(e) = Self::apply_named_profile(state, sender, &name) {
+                    error!("Failed to apply profile {:?}: {:#}", name, e);
+                    state.send(sender, OrbEvent::Error { message: format!("Profile '{}': {:#}", name, e) });
+                }
+            }
+            UiCommand::ApplyTopology { topology } => {
+                info!("🗺️ [CORE-EXEC] Applying a topology of {} cluster(s)", topology.clusters.len());
+                if let Err(e) = Self::apply_topology_to(state, sender, &topology) {
+                    error!("Failed to apply topology: {:#}", e);
+                    state.send(sender, OrbEvent::Error { message: format!("Couldn't apply the layout: {:#}", e) });
+                }
+            }
+            UiCommand::SetShowClusterMembers { enabled } => {
+                info!("⚙️ [CORE-EXEC] Show cluster members in device list: {}", enabled);
+                Self::update_settings(state, sender, |settings| settings.show_cluster_members = enabled);
+            }
+            UiCommand::SetRoleTarget { role, sink } => {
+                info!("🎚️ [CORE-EXEC] Routing {} streams to {:?}", role, sink);
+                Self::set_role_target(state, sender, &role, sink);
+            }
+            UiCommand::SetNotificationsOffClusters { enabled } => {
+                info!("🔔 [CORE-EXEC] Notifications off clusters: {}", enabled);
+                Self::update_settings(state, sender, |settings| settings.notifications_off_clusters = enabled);
+                Self::follow_role(state, sender, media_roles::NOTIFICATION);
+            }
+            UiCommand::SetCanvasPosition { name, position } => {
+                info!("⚙️ [CORE-EXEC] Canvas position of {}: {:?}", name, position);
+                Self::update_settings(state, sender, |settings| {
+                    match position {
+                        Some(position) => settings.canvas_positions.insert(name, position),
+                        None => settings.canvas_positions.remove(&name),
+                    };
+                });
+            }
+            UiCommand::SetCompactDeviceList { enabled } => {
+                info!("⚙️ [CORE-EXEC] Compact device list: {}", enabled);
+                Self::update_settings(state, sender, |settings| settings.compact_device_list = enabled);
+            }
+            UiCommand::SuspendRouting => {
+                info!("⚙️ [CORE-EXEC] Suspending routing");
+                Self::suspend_routing(state, sender);
+            }
+            UiCommand::ResumeRouting => {
+                info!("⚙️ [CORE-EXEC] Resuming routing");
+                Self::resume_routing(state, sender);
+            }
+            UiCommand::SetConfirmMerges { enabled } => {
+                info!("⚙️ [CORE-EXEC] Confirm cluster merges: {}", enabled);
+                Self::update_settings(state, sender, |settings| settings.confirm_merges = enabled);
+            }
+            UiCommand::SetAutoClusterOnDrop { enabled } => {
+                info!("⚙️ [CORE-EXEC] Auto-cluster on drop: {}", enabled);
+                Self::update_settings(state, sender, |settings| settings.auto_cluster_on_drop = enabled);
+            }
+            UiCommand::SetBalanceNewClusters { enabled } => {
+                info!("⚙️ [CORE-EXEC] Balance volumes of new clusters: {}", enabled);
+                Self::update_settings(state, sender, |settings| settings.balance_new_clusters = enabled);
+            }
+            UiCommand::SetRebuildClusters { enabled } => {
+                info!("⚙️ [CORE-EXEC] Rebuild clusters after a PipeWire restart: {}", enabled);
+                Self::update_settings(state, sender, |settings| settings.rebuild_clusters = enabled);
+            }
+            UiCommand::SetFirstRun { first_run } => {
+                info!("⚙️ [CORE-EXEC] Onboarding tour pending: {}", first_run);
+                Self::update_settings(state, sender, |settings| settings.first_run = first_run);
+            }
+            UiCommand::SetBeamTurn { turn } => {
+                // An empty URL switches the relay off
+                let turn = turn
+                    .map(|t| TurnServer { url: t.url.trim().to_string(), ..t })
+                    .filter(|t| !t.url.is_empty());
+                info!("⚙️ [CORE-EXEC] Beam TURN server: {:?}", turn.as_ref().map(|t| &t.url));
+                if let Some(Err(e)) = turn.as_ref().map(|t| persistence::validate_turn_url(&t.url)) {
+                    state.send(sender, OrbEvent::Error { message: format!("TURN server: {}", e) });
+                    return;
+                }
+                Self::update_settings(state, sender, |settings| settings.beam_turn = turn);
+            }
+            UiCommand::Unlink { source, target } => {
+                info!("✂️ [CORE-EXEC] Unlinking {} -> {}", source, target);
+                Self::unlink_source(state, sender, source, Some(target));
+            }
+            UiCommand::MoveAllStreams { from, to } => {
+                info!("🔀 [CORE-EXEC] Moving streams {} -> {}", from, to);
+                Self::move_all_streams(state, sender, from, to);
+            }
+            UiCommand::Duplicate { from, also } => {
+                info!("👯 [CORE-EXEC] Playing {} on {} too", from, also);
+                match Self::duplicate_output(state, from, also) {
+                    Ok(pairs) => state.send(sender, OrbEvent::Duplicates { pairs }),
+                    Err(e) => {
+                        warn!("Not duplicating {} to {}: {:#}", from, also, e);
+                        state.report_failure(sender, format!("Couldn't play on both: {:#}", e), &e);
+                    }
+                }
+            }
+            UiCommand::StopDuplicate { from, also } => {
+                info!("👯 [CORE-EXEC] No longer playing {} on {}", from, also);
+                let (module_id, pairs) = {
+                    let mut graph = state.graph();
+                    (graph.duplicates.remove(&(from, also)), graph.duplicate_pairs())
+                };
+                match module_id {
+                    Some(module_id) => {
+                        state.unload_modules(&[module_id]);
+                        state.send(sender, OrbEvent::Duplicates { pairs });
+                    }
+                    None => warn!("{} isn't playing on {}", from, also),
+                }
+            }
+            UiCommand::NudgeMemberDelay { cluster, device, delta_ms } => {
+                info!("⏱️ [CORE-EXEC] Nudging delay of {} in cluster {} by {} ms", device, cluster, delta_ms);
+                Self::nudge_member_delay(state, sender, cluster, device, delta_ms);
+            }
+            UiCommand::SetMemberDownmix { cluster, device, enabled } => {
+                info!("🔉 [CORE-EXEC] Mono downmix of {} in cluster {}: {}", device, cluster, enabled);
+                Self::update_member_prefs(state, sender, cluster, device, |prefs, node_name| {
+                    prefs.downmix.insert(node_name.to_string(), enabled);
+                });
+            }
+            UiCommand::SetMemberVolume { cluster, device, percent } => {
+                info!("🔊 [CORE-EXEC] Volume of {} in cluster {}: {}%", device, cluster, percent);
+                Self::set_member_volume(state, sender, cluster, device, percent);
+            }
+            UiCommand::BalanceClusterVolumes { cluster } => {
+                info!("🔊 [CORE-EXEC] Balancing the volumes of cluster {}", cluster);
+                Self::balance_cluster_volumes(state, sender, cluster);
+            }
+            UiCommand::MoveClusterMember { cluster, device, up } => {
+                info!("↕️ [CORE-EXEC] Moving {} {} in cluster {}", device, if up { "up" } else { "down" }, cluster);
+                Self::move_cluster_member(state, sender, cluster, device, up);
+            }
+            UiCommand::SetClusterChannels { cluster, channels } => {
+                info!("🔈 [CORE-EXEC] Channels of cluster {}: {}", cluster, channels);
+                Self::set_cluster_channels(state, sender, cluster, channels);
+            }
+            UiCommand::ClusterChannelCheck { cluster } => {
+                info!("🔊 [CORE-EXEC] Channel check for cluster {}", cluster);
+                Self::channel_check(state, sender, cluster);
+            }
+            UiCommand::PlayTestTone { device } => {
+                info!("🔊 [CORE-EXEC] Test tone on {}", device);
+                Self::play_test_tone(state, sender, device);
+            }
+            UiCommand::CleanUpOrphans => {
+                info!("🧹 [CORE-EXEC] Cleaning up orphaned sinks");
+                Self::clean_up_orphans(state, sender);
+            }
+            UiCommand::ExportLayout { path } => {
+                info!("📦 [CORE-EXEC] Exporting layout to {}", path.display());
+                let exported = bundle::export(&state.store(), state.profiles_path.as_deref(), &path);
+                match exported {
+                    Ok(()) => state.send(sender, OrbEvent::LayoutExported { path }),
+                    Err(e) => {
+                        error!("Layout export failed: {:#}", e);
+                        state.send(sender, OrbEvent::Error { message: format!("Couldn't export the layout: {:#}", e) });
+                    }
+                }
+            }
+            UiCommand::ImportLayout { path, replace } => {
+                info!("📦 [CORE-EXEC] Importing layout from {} (replace: {})", path.display(), replace);
+                let before = state.store().data.clusters.clone();
+                let imported = bundle::import(&mut state.store(), state.profiles_path.as_deref(), &path, replace);
+                match imported {
+                    Ok(summary) => {
+                        let settings = state.store().data.settings.clone();
+                        state.send(sender, OrbEvent::Settings(settings));
+                        Self::apply_imported_prefs(state, sender, &before);
+                        if summary.profiles > 0 {
+                            match Profiles::load_or_default(state.profiles_path.as_deref()) {
+                                Ok(profiles) => state.send(sender, OrbEvent::Profiles { names: profiles.names() }),
+                                Err(e) => warn!("Failed to reload profiles: {:#}", e),
+                            }
+                        }
+                        state.send(sender, OrbEvent::LayoutImported { clusters: summary.clusters, profiles: summary.profiles });
+                    }
+                    Err(e) => {
+                        error!("Layout import failed: {:#}", e);
+                        state.send(sender, OrbEvent::Error { message: format!("Couldn't import the layout: {:#}", e) });
+                    }
+                }
+            }
+            UiCommand::ReadFormats { device } => {
+                Self::read_formats(state, sender, device);
+            }
+            UiCommand::CopyClusterScript { cluster } => {
+                match Self::cluster_script(state, cluster) {
+                    Some(script) => state.send(sender, OrbEvent::ClusterScript { script }),
+                    None => warn!("No cluster {} to write a script for", cluster),
+                }
+            }
+            UiCommand::ExportGraph { format } => {
+                let contents = Self::export_of(state, format);
+                state.send(sender, OrbEvent::GraphExport { contents });
+            }
+            UiCommand::ResetAll => {
+                warn!("🧯 [CORE-EXEC] Resetting all audio routing");
+                Self::reset_all(state, sender);
+            }
+            UiCommand::SetDefaultSink { device } => {
+                info!("🔈 [CORE-EXEC] Setting default sink to {}", device);
+                Self::with_output_node(state, sender, device, "set it as default", |name| {
+                    Self::retry_once(|| state.backend.set_default_sink(name))
+                });
+                Self::reconcile_default_sink(state, sender);
+            }
+            UiCommand::ToggleMute { device } => {
+                info!("🔇 [CORE-EXEC] Toggling mute on {}", device);
+                Self::with_output_node(state, sender, device, "mute it", |name| state.backend.toggle_sink_mute(name));
+            }
+            UiCommand::SetVolume { device, percent } => {
+                info!("🔊 [CORE-EXEC] Volume of {}: {}%", device, percent);
+                Self::set_volume(state, sender, device, percent);
+            }
+            UiCommand::ReadVolume { device } => {
+                Self::report_volume(state, sender, device);
+            }
+            UiCommand::SuspendSink { orb, suspend } => {
+                info!("💤 [CORE-EXEC] {} {}", if suspend { "Suspending" } else { "Waking" }, orb);
+                Self::suspend_output(state, sender, orb, suspend);
+            }
+            UiCommand::Shutdown => {
+                info!("🛑 [CORE-RECV] Shutdown command received");
+                state.cleanup_combine_sinks();
+                info!("✓ [CORE-DONE] Cleanup complete, exiting thread");
+            }
+        }
+    }
+
+    /// Returns the cluster built, if dropping `source` onto `target` built one
+    fn handle_connect(state: &SharedState, sender: &Sender<OrbEvent>, source: Uuid, target: Uuid) -> Option<Uuid> {
+        // An orb dropped onto itself would become a cluster of one device twice, or a cluster
+        // merged with itself; there's nothing to connect
+        if source == target {
+            return None;
+        }
+        let (src, tgt) = {
+            let graph = state.graph();
+            (graph.orbs.get(&source).cloned(), graph.orbs.get(&target).cloned())
+        }; // Lock dropped here!
+
+        let (Some(src), Some(tgt)) = (src, tgt) else {
+            warn!("Invalid connection type");
+            return None;
+        };
+
+        match classify_connection(&src.kind, &tgt.kind) {
+            ConnectionKind::NewCluster => {
+                if src.membership().is_some() && src.membership() == tgt.membership() {
+                    info!("{} and {} are already clustered together", src.name, tgt.name);
+                    return None;
+                }
+                info!("Creating cluster for {} + {}", src.name, tgt.name);
+                Self::warn_monitor_members(state, sender, &[&src, &tgt]);
+                Self::detach_members(state, sender, &[source, target]);
+                Self::create_cluster(state, sender, vec![source, target])
+            }
+
+            // Either may have been dropped onto the other
+            ConnectionKind::AddToCluster => {
+                let (cluster, device) = match src.kind {
+                    OrbKind::Cluster { .. } => (&src, &tgt),
+                    _ => (&tgt, &src),
+                };
+                if device.membership() == Some(cluster.id) {
+                    info!("{} is already in cluster {}", device.name, cluster.name);
+                    return None;
+                }
+                info!("Adding {} to cluster {}", device.name, cluster.name);
+                Self::warn_monitor_members(state, sender, &[device]);
+
+                // 0. Take it out of the cluster it's in, if any
+                Self::detach_members(state, sender, &[device.id]);
+
+                // 1. Unload old cluster
+                let mut members = Self::unload_cluster(state, sender, cluster.id);
+
+                // 2. Create new cluster
+                members.push(device.id);
+                Self::create_cluster(state, sender, members)
+            }
+
+            ConnectionKind::MergeClusters => {
+                info!("Merging clusters {} + {}", src.name, tgt.name);
+
+                // 1. Unload both
+                let mut members = Self::unload_cluster(state, sender, source);
+                members.extend(Self::unload_cluster(state, sender, target));
+
+                // 2. Create super-cluster
+                Self::create_cluster(state, sender, members)
+            }
+
+            ConnectionKind::Link if tgt.is_staged() => {
+                info!("Not linking {} to {}, it's no cluster yet", src.name, tgt.name);
+                state.send(sender, OrbEvent::Error {
+                    message: format!("Add another device to {} before playing to it", tgt.name),
+                });
+                None
+            }
+
+            ConnectionKind::Link => {
+                info!("Linking source {} -> sink {}", src.name, tgt.name);
+                Self::link_source_to_sink(state, sender, &src, &tgt);
+                None
+            }
+
+            ConnectionKind::Invalid => {
+                warn!("Invalid connection type");
+                None
+            }
+        }
+    }
+
+    /// Apply a preference change to a cluster orb and remember it for the same set of devices
+    fn update_cluster_prefs(
+        state: &SharedState,
+        sender: &Sender<OrbEvent>,
+        cluster_id: Uuid,
+        apply: impl FnOnce(&mut AudioGraph, &mut ClusterPrefs) -> Vec<OrbEvent>,
+    ) {
+        let events = {
+            let mut graph = state.graph();
+            let Some(key) = graph.cluster_key(cluster_id) else {
+                warn!("No cluster found for {}", cluster_id);
+                return;
+            };
+
+            let mut store = state.store();
+            let prefs = store.data.clusters.entry(key).or_default();
+            let events = apply(&mut graph, prefs);
+            store.save();
+            events
+        };
+
+        state.send_events(sender, events);
+    }
+
+    /// Whether a just-discovered sink should become the default ("Auto-switch to new devices").
+    /// Skips the initial burst of existing devices and anything Auralis created itself.
+    pub fn wants_auto_switch(state: &SharedState, node_name: &str, since_connect: Duration) -> bool {
+        if since_connect < AUTO_SWITCH_GRACE || sink_names::is_own(node_name) {
+            return false;
+        }
+
+        let store = state.store();
+        let settings = &store.data.settings;
+        settings.auto_switch_new_devices && !settings.routing_suspended && !settings.ignored_devices.iter().any(|d| d == node_name)
+    }
+
+    fn auto_switch_to(state: &SharedState, sender: &Sender<OrbEvent>, node_name: &str) {
+        // Gone again, or clustered in the meantime
+        let still_floating = state.graph().find_sink(node_name)
+            .map(|o| o.state == OrbState::Floating)
+            .unwrap_or(false);
+        if !still_floating {
+            return;
+        }
+
+        info!("🔌 [CORE] Auto-switching to new device {}", node_name);
+        if Self::set_default_sink_or_report(state, sender, node_name) {
+            Self::move_all_streams_or_report(state, sender, node_name);
+        }
+    }
+
+    /// Add an orb as if discovery had found it, for driving the core without PipeWire
+    /// (see `AuralisConfig::discovery`)
+    pub fn inject_orb(&self, orb: Orb) {
+        let events = self.state.graph().add_orb(orb);
+        self.state.send_events(&self.sender, events);
+    }
+
+    /// Copy of everything discovered so far
+    pub fn snapshot(&self) -> AudioGraph {
+        self.state.snapshot()
+    }
+
+    /// Read-only handle on the graph that the core keeps current, so the UI can look at it
+    /// directly instead of only mirroring events
+    pub fn graph_view(&self) -> GraphView {
+        self.state.view()
+    }
+
+    /// Counters since startup plus current orb and cluster counts, for monitoring
+    pub fn metrics(&self) -> MetricsSnapshot {
+        Self::metrics_of(&self.state)
+    }
+
+    /// `metrics` for a bare state, as used by `handle_command`
+    pub fn metrics_of(state: &SharedState) -> MetricsSnapshot {
+        let graph = state.graph();
+        state.metrics.snapshot(graph.orbs.len(), graph.clusters.len())
+    }
+
+    /// Orbs, clusters, module IDs and the default sink as DOT or JSON, for bug reports
+    pub fn export_graph(&self, format: ExportFormat) -> String {
+        Self::export_of(&self.state, format)
+    }
+
+    /// `export_graph` of a state, for holders of a `SharedState` (the control socket)
+    pub fn export_of(state: &SharedState, format: ExportFormat) -> String {
+        let graph = state.snapshot();
+        let default_sink = state.backend.get_default_sink().ok();
+        export::export_graph(&graph, default_sink.as_deref(), format)
+    }
+
+    /// Activate a profile from `profiles.toml` on this client's state.
+    /// Devices the profile names but that aren't present are reported as `OrbEvent::Error`.
+    pub fn apply_profile(&self, name: &str) -> Result<()> {
+        Self::apply_named_profile(&self.state, &self.sender, name)
+    }
+
+    fn apply_named_profile(state: &SharedState, sender: &Sender<OrbEvent>, name: &str) -> Result<()> {
+        let profiles = Profiles::load_or_default(state.profiles_path.as_deref())?;
+        let Some(profile) = profiles.profiles.get(name) else {
+            bail!("No such profile (known: {})", profiles.names().join(", "));
+        };
+        Self::apply_profile_to(state, sender, name, profile);
+        Ok(())
+    }
+
+    /// What `apply_topology` would do, changing nothing
+    pub fn plan_topology(&self, topology: &Topology) -> Result<Vec<Step>> {
+        Self::plan_topology_of(&self.state, topology)
+    }
+
+    pub fn plan_topology_of(state: &SharedState, topology: &Topology) -> Result<Vec<Step>> {
+        let role_targets = state.store().data.settings.role_targets.clone();
+        topology.plan(&state.graph(), &role_targets)
+    }
+
+    /// Bring the routing in line with `topology`, see `Topology::plan`. Returns the steps taken.
+    pub fn apply_topology(&self, topology: &Topology) -> Result<Vec<Step>> {
+        Self::apply_topology_to(&self.state, &self.sender, topology)
+    }
+
+    pub fn apply_topology_to(state: &SharedState, sender: &Sender<OrbEvent>, topology: &Topology) -> Result<Vec<Step>> {
+        let steps = Self::plan_topology_of(state, topology)?;
+        let report = |message: String| {
+            warn!("{}", message);
+            state.send(sender, OrbEvent::Error { message });
+        };
+        // By key, as the steps name them; clusters get new IDs as they're built
+        let cluster_by_key = |key: &str| -> Option<Uuid> {
+            let graph = state.graph();
+            graph.clusters.values()
+                .filter(|c| !c.external)
+                .find(|c| graph.cluster_key(c.id).as_deref() == Some(key))
+                .map(|c| c.id)
+        };
+        let output_by_key = |key: &str| -> Option<(Uuid, String)> {
+            let graph = state.graph();
+            media_roles::find_target(&graph.orbs, key).map(|o| (o.id, o.node_name.clone()))
+        };
+
+        for step in &steps {
+            info!("🗺️ [TOPOLOGY] {}", step);
+            match step {
+                Step::Separate { cluster, .. } => {
+                    if let Some(id) = cluster_by_key(cluster) {
+                        Self::handle_disconnect(state, sender, id, id);
+                    }
+                }
+                Step::Create { devices, name } => {
+                    let members: Vec<Uuid> = {
+                        let graph = state.graph();
+                        devices.iter().filter_map(|n| graph.find_sink(n).map(|o| o.id)).collect()
+                    };
+                    Self::cluster_devices(state, sender, members);
+                    match (cluster_by_key(&persistence::cluster_key(devices)), name) {
+                        (Some(cluster), Some(name)) => Self::handle_command(state, sender, UiCommand::RenameCluster { cluster, name: name.clone() }),
+                        (Some(_), None) => {}
+                        (None, _) => report(format!("Layout: couldn't cluster {}", devices.join(", "))),
+                    }
+                }
+                Step::Rename { cluster, to, .. } => {
+                    if let Some(cluster) = cluster_by_key(cluster) {
+                        Self::handle_command(state, sender, UiCommand::RenameCluster { cluster, name: to.clone() });
+                    }
+                }
+                Step::SetDefault { output, name } => match output_by_key(output) {
+                    Some((_, sink_name)) => {
+                        if Self::set_default_sink_or_report(state, sender, &sink_name) {
+                            Self::move_all_streams_or_report(state, sender, &sink_name);
+                        }
+                        Self::reconcile_default_sink(state, sender);
+                    }
+                    None => report(format!("Layout: '{}' isn't there to play on by default", name)),
+                },
+                Step::SetRole { role, output: None, .. } => Self::set_role_target(state, sender, role, None),
+                Step::SetRole { role, output: Some(output), name } => match output_by_key(output) {
+                    Some((id, _)) => Self::set_role_target(state, sender, role, Some(id)),
+                    None => report(format!("Layout: '{}' isn't there to play {} on", name.as_deref().unwrap_or(output), role)),
+                },
+            }
+        }
+        Ok(steps)
+    }
+
+    /// Read the formats a device offers and uses from its node. Devices that don't report any
+    /// keep what was read before (shown as unknown if nothing was).
+    fn read_formats(state: &SharedState, sender: &Sender<OrbEvent>, device: Uuid) {
+        let Some(pw_id) = state.graph().orbs.get(&device).map(|o| o.pw_id) else { return };
+        match state.backend.node_formats(pw_id) {
+            Ok(formats) => {
+                let events = state.graph().set_formats(device, formats);
+                state.send_events(sender, events);
+            }
+            Err(e) => warn!("Failed to read the formats of node {}: {}", pw_id, e),
+        }
+    }
+
+    /// Shell commands that build the cluster without Auralis, see `Cluster::to_shell_script`
+    pub fn cluster_script(state: &SharedState, cluster_id: Uuid) -> Option<String> {
+        let key = state.graph().cluster_key(cluster_id)?;
+        let members = state.graph().clusters.get(&cluster_id)?.members();
+        let prefs = Self::playing_prefs(state, &key, &members);
+        let graph = state.graph();
+        Some(graph.clusters.get(&cluster_id)?.to_shell_script(&graph, &prefs))
+    }
+
+    /// Either we never got a connection or the daemon went away (restart, crash). Everything we
+    /// know refers to dead PipeWire IDs, so start from scratch, noting the clusters to rebuild.
+    pub fn connection_lost(state: &SharedState, sender: &Sender<OrbEvent>) {
+        state.send(sender, OrbEvent::BackendState { connected: false });
+        state.remember_clusters();
+        state.reset();
+        state.send(sender, OrbEvent::Clear);
+    }
+
+    /// Build the clusters lost with the last connection again from their devices, once discovery
+    /// found them. Clusters that can't be rebuilt (setting off, devices gone) stay dissolved.
+    pub fn rebuild_lost_clusters(state: &SharedState, sender: &Sender<OrbEvent>) {
+        let lost = std::mem::take(&mut *state.lost_clusters.lock().unwrap());
+        if lost.is_empty() {
+            return;
+        }
+        if !state.store().data.settings.rebuild_clusters {
+            info!("Not rebuilding {} clusters after reconnecting (turned off)", lost.len());
+            return;
+        }
+
+        for node_names in lost {
+            let (members, missing): (Vec<Uuid>, Vec<String>) = {
+                let graph = state.graph();
+                let mut missing = Vec::new();
+                let members = node_names.iter()
+                    .filter_map(|name| {
+                        let found = graph.find_sink(name).filter(|o| o.membership().is_none()).map(|o| o.id);
+                        if found.is_none() {
+                            missing.push(name.clone());
+                        }
+                        found
+                    })
+                    .collect();
+                (members, missing)
+            };
+
+            if !missing.is_empty() {
+                let message = if members.len() < 2 {
+                    format!("Couldn't rebuild a cluster after PipeWire restarted: {} didn't come back", missing.join(", "))
+                } else {
+                    format!("Rebuilt a cluster after PipeWire restarted without {}, which didn't come back", missing.join(", "))
+                };
+                warn!("{}", message);
+                state.send(sender, OrbEvent::Error { message });
+            }
+            if members.len() < 2 {
+                continue;
+            }
+            info!("♻️ [CORE] Rebuilding cluster of {:?}", node_names);
+            let _ = Self::create_cluster(state, sender, members);
+        }
+    }
+
+    /// Dissolve every current cluster and build the profile's instead, as far as its devices exist
+    pub fn apply_profile_to(state: &SharedState, sender: &Sender<OrbEvent>, name: &str, profile: &Profile) {
+        let report = |message: String| {
+            warn!("{}", message);
+            state.send(sender, OrbEvent::Error { message });
+        };
+
+        // 1. Start from individual devices
+        let current: Vec<Uuid> = state.graph().clusters.keys().copied().collect();
+        for cluster_id in current {
+            Self::handle_disconnect(state, sender, cluster_id, cluster_id);
+        }
+
+        // 2. Build each cluster from whatever devices are present
+        let mut built: Vec<(Vec<String>, Uuid)> = Vec::new();
+        for devices in &profile.clusters {
+            let members: Vec<Uuid> = devices.iter()
+                .filter_map(|description| {
+                    let found = state.graph().find_floating_sink_by_description(description).map(|o| o.id);
+                    if found.is_none() {
+                        report(format!("Profile '{}': device '{}' not found", name, description));
+                    }
+                    found
+                })
+                .collect();
+
+            if members.len() < 2 {
+                report(format!("Profile '{}': not enough devices for cluster {:?}", name, devices));
+                continue;
+            }
+            if let Some(cluster_id) = Self::create_cluster(state, sender, members) {
+                built.push((devices.clone(), cluster_id));
+            }
+        }
+
+        // 3. Default sink: a cluster containing the device, or the device itself
+        if let Some(default) = &profile.default_sink {
+            let sink_name = built.iter()
+                .find(|(devices, _)| devices.contains(default))
+                .and_then(|(_, cluster_id)| state.graph().clusters.get(cluster_id).map(|c| c.sink_name.clone()))
+                .or_else(|| state.graph().find_floating_sink_by_description(default).map(|o| o.node_name.clone()));
+
+            match sink_name {
+                Some(sink_name) => {
+                    if Self::set_default_sink_or_report(state, sender, &sink_name) {
+                        info!("✓ Profile default sink: {}", sink_name);
+                    }
+                    Self::move_all_streams_or_report(state, sender, &sink_name);
+                }
+                None => report(format!("Profile '{}': default sink '{}' not found", name, default)),
+            }
+        }
+
+        info!("✓ Profile '{}' applied ({} of {} clusters)", name, built.len(), profile.clusters.len());
+    }
+
+    /// Device swap: move what's playing on one sink to another.
+    /// Clusters work too since their orb carries the combine-sink's node name.
+    fn move_all_streams(state: &SharedState, sender: &Sender<OrbEvent>, from: Uuid, to: Uuid) {
+        let (src, dst) = {
+            let graph = state.graph();
+            (graph.orbs.get(&from).cloned(), graph.orbs.get(&to).cloned())
+        };
+
+        let (Some(src), Some(dst)) = (src, dst) else {
+            warn!("Unknown device for stream move: {} -> {}", from, to);
+            return;
+        };
+        let is_output = |o: &Orb| matches!(o.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. });
+        if !is_output(&src) || !is_output(&dst) || from == to {
+            warn!("Can't move streams from {} to {}", src.name, dst.name);
+            return;
+        }
+
+        // Follow the streams with the default sink, but only if they were on the default
+        let was_default = state.backend.get_default_sink()
+            .map(|s| s == src.node_name)
+            .unwrap_or(false);
+
+        let moved = state.backend.move_sink_inputs_between(&src.node_name, &dst.node_name);
+        info!("✓ Moved {} streams from {} to {}", moved, src.node_name, dst.node_name);
+
+        if was_default && Self::set_default_sink_or_report(state, sender, &dst.node_name) {
+            info!("✓ {} is the new default sink", dst.node_name);
+        }
+    }
+
+    /// Look for foreign combine-sinks once the current wave of discovery has settled
+    fn schedule_adopt_scan(state: &SharedState, sender: &Sender<OrbEvent>) {
+        if state.adopt_scan_pending.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let (state, sender) = (state.clone(), sender.clone());
+        // Off the PipeWire loop, pactl round-trips take a while
+        thread::spawn(move || {
+            thread::sleep(ADOPT_SCAN_SETTLE);
+            state.adopt_scan_pending.store(false, Ordering::Relaxed);
+            Self::adopt_external_combine_sinks(&state, &sender);
+        });
+    }
+
+    /// Show combine-sinks created outside Auralis (by hand or another tool) as clusters.
+    /// Node properties don't reliably say what a sink combines, so the module list is
+    /// the source of truth; the sink and its slaves must already have been discovered.
+    pub fn adopt_external_combine_sinks(state: &SharedState, sender: &Sender<OrbEvent>) {
+        let modules = match state.backend.list_modules() {
+            Ok(modules) => modules,
+            Err(e) => {
+                warn!("Failed to list modules: {}", e);
+                return;
+            }
+        };
+
+        for module in modules.iter().filter(|m| m.name == "module-combine-sink") {
+            let Some(sink_name) = module.arg("sink_name") else { continue };
+            if sink_names::is_own(sink_name) {
+                continue;
+            }
+            // Without slaves= it follows every sink, which isn't a cluster we can show
+            let slaves: Vec<&str> = module.arg("slaves")
+                .map(|s| s.split(',').filter(|s| !s.is_empty()).collect())
+                .unwrap_or_default();
+
+            let adopted = {
+                let mut graph = state.graph();
+                if graph.clusters.values().any(|c| c.sink_name == sink_name) {
+                    continue;
+                }
+                let Some(sink) = graph.find_sink(sink_name).map(|o| o.id) else { continue };
+                let members: Vec<Uuid> = slaves.iter()
+                    .filter_map(|slave| graph.find_sink(slave))
+                    .filter(|o| o.state == OrbState::Floating)
+                    .map(|o| o.id)
+                    .collect();
+                if members.is_empty() {
+                    continue;
+                }
+                graph.adopt_cluster(sink, &members, module.id)
+            };
+
+            if let Some((cluster_id, events)) = adopted {
+                info!("✓ Adopted external combine-sink {} as cluster {}", sink_name, cluster_id);
+                state.send_events(sender, events);
+            }
+        }
+    }
+
+    /// Undo everything Auralis did to the audio server, without restarting.
+    /// Clusters are separated normally first; the module sweep then catches anything untracked.
+    fn reset_all(state: &SharedState, sender: &Sender<OrbEvent>) {
+        let clusters: Vec<Uuid> = state.graph().clusters.values()
+            .filter(|c| !c.external)
+            .map(|c| c.id)
+            .collect();
+        for cluster in clusters {
+            Self::handle_disconnect(state, sender, cluster, cluster);
+        }
+
+        let duplicates: Vec<u32> = state.graph().duplicates.drain().map(|(_, module_id)| module_id).collect();
+        state.unload_modules(&duplicates);
+        state.send(sender, OrbEvent::Duplicates { pairs: Vec::new() });
+
+        Self::cleanup_stale_modules(state);
+
+        let events = state.graph().float_all_sinks();
+        state.send_events(sender, events);
+
+        // The old default may have been one of the sinks we just unloaded
+        let sinks: Vec<String> = {
+            let graph = state.graph();
+            let mut sinks: Vec<(u32, String)> = graph.orbs.values()
+                .filter(|o| matches!(o.kind, OrbKind::PhysicalSink { .. }))
+                .map(|o| (o.pw_id, o.node_name.clone()))
+                .collect();
+            sinks.sort();
+            sinks.into_iter().map(|(_, name)| name).collect()
+        };
+        let current = state.backend.get_default_sink().unwrap_or_default();
+        if sinks.contains(&current) {
+            info!("✓ [RESET] Default sink {} is still valid", current);
+        } else if let Some(first) = sinks.first() {
+            match Self::retry_once(|| state.backend.set_default_sink(first)) {
+                Ok(()) => info!("✓ [RESET] Default sink set to {}", first),
+                Err(e) => {
+                    error!("Failed to set default sink: {}", e);
+                    state.send(sender, OrbEvent::Error { message: "Couldn't restore a default output".to_string() });
+                }
+            }
+        } else {
+            warn!("[RESET] No output devices to make the default");
+        }
+
+        info!("✓ [RESET] Audio routing reset");
+    }
+
+    /// Run a backend call on the node of a device or cluster, reporting failures to the UI
+    fn with_output_node(
+        state: &SharedState,
+        sender: &Sender<OrbEvent>,
+        device: Uuid,
+        what: &str,
+        call: impl FnOnce(&str) -> Result<()>,
+    ) {
+        let Some(orb) = state.graph().orbs.get(&device).cloned() else {
+            warn!("Unknown device {}", device);
+            return;
+        };
+        if !matches!(orb.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. }) {
+            warn!("{} is not an output", orb.name);
+            return;
+        }
+
+        if let Err(e) = call(&orb.node_name) {
+            warn!("Backend call on {} failed: {}", orb.node_name, e);
+            state.report_failure(sender, format!("Couldn't {} on {}", what, orb.name), &e);
+        }
+    }
+
+    /// Load a loopback from `from`'s monitor to `also`, returning the duplicates now running.
+    /// Only devices: a cluster's combine-sink is replaced whenever it changes.
+    fn duplicate_output(state: &SharedState, from: Uuid, also: Uuid) -> Result<Vec<(Uuid, Uuid)>> {
+        let _building = state.building();
+        let (from_orb, also_orb) = {
+            let graph = state.graph();
+            let device = |id| graph.orbs.get(&id)
+                .filter(|o| matches!(o.kind, OrbKind::PhysicalSink { .. }))
+                .cloned();
+            let (Some(from_orb), Some(also_orb)) = (device(from), device(also)) else {
+                bail!("only devices can play each other's audio");
+            };
+            if from == also {
+                bail!("{} already plays its own audio", from_orb.name);
+            }
+            if graph.duplicates.contains_key(&(from, also)) {
+                bail!("{} already plays on {}", from_orb.name, also_orb.name);
+            }
+            // Any way round back to `from` would feed the audio back into itself
+            if graph.plays_on(also, from) {
+                bail!("{} already plays on {}", also_orb.name, from_orb.name);
+            }
+            (from_orb, also_orb)
+        };
+
+        let monitor = format!("{}.monitor", from_orb.node_name);
+        let module_id = state.backend.load_loopback(&monitor, &also_orb.node_name, DUPLICATE_LATENCY_MS)?;
+        info!("✓ {} plays on {} too (module {})", from_orb.name, also_orb.name, module_id);
+
+        let mut graph = state.graph();
+        graph.duplicates.insert((from, also), module_id);
+        Ok(graph.duplicate_pairs())
+    }
+
+    /// Start a beam session of a device or cluster and add its orb. The session's connection
+    /// states come back as `BeamStatus`, and `BeamClosed` once it has ended.
+    fn start_beam(state: &SharedState, sender: &Sender<OrbEvent>, source: Uuid) -> Result<Vec<OrbEvent>> {
+        let orb = {
+            let graph = state.graph();
+            let Some(orb) = graph.orbs.get(&source) else {
+                bail!("unknown device {}", source);
+            };
+            if !matches!(orb.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. }) || orb.membership().is_some() {
+                bail!("only devices and clusters can be beamed, {} is not one", orb.name);
+            }
+            if graph.find_beam(&orb.node_name).is_some() {
+                bail!("{} is already being beamed", orb.name);
+            }
+            orb.clone()
+        };
+
+        let session_id = Uuid::new_v4().to_string();
+        let turn = state.store().data.settings.beam_turn.clone();
+        let (state_beam, sender_beam, id) = (state.clone(), sender.clone(), session_id.clone());
+        state.backend.start_beam(&session_id, &orb.node_name, turn.as_ref(), Box::new(move |beam_state| {
+            // A failed beam stays (red) until it's stopped
+            let command = match beam_state {
+                BeamState::Closed => UiCommand::BeamClosed { session_id: id.clone(), reason: None },
+                _ => UiCommand::BeamStatus { session_id: id.clone(), status: beam_state.label().to_string() },
+            };
+            Self::handle_command(&state_beam, &sender_beam, command);
+        }))?;
+
+        let beam = Orb {
+            id: Uuid::new_v4(),
+            pw_id: 0,
+            node_name: orb.node_name.clone(),
+            kind: OrbKind::BeamOutput {
+                session_id,
+                peer_url: String::new(),
+                codec: BEAM_CODEC.to_string(),
+                bitrate_kbps: BEAM_BITRATE_KBPS,
+                started_at: std::time::SystemTime::now(),
+            },
+            name: format!("{} Beam", orb.name),
+            icon_name: "network-wireless".to_string(),
+            status: "Connecting".to_string(),
+            state: OrbState::Floating,
+            zone: None,
+            latency_ms: None,
+            bus: DeviceBus::Unknown,
+            channels: orb.channels,
+            formats: Vec::new(),
+            format: None,
+            position: (0.0, 0.0),
+            velocity: (0.0, 0.0),
+        };
+        Ok(state.graph().add_orb(beam))
+    }
+
+    /// Suspend (or wake) a device, or a cluster's combine-sink and members, and remember it
+    fn suspend_output(state: &SharedState, sender: &Sender<OrbEvent>, orb_id: Uuid, suspend: bool) {
+        let (name, key, sinks) = {
+            let graph = state.graph();
+            let Some(orb) = graph.orbs.get(&orb_id) else {
+                warn!("Unknown device {}", orb_id);
+                return;
+            };
+            let Some(key) = media_roles::target_key(&graph.orbs, orb) else {
+                warn!("{} is not an output", orb.name);
+                return;
+            };
+            let mut sinks = vec![orb.node_name.clone()];
+            if let OrbKind::Cluster { members, .. } = &orb.kind {
+                sinks.extend(members.iter().filter_map(|id| graph.orbs.get(id)).map(|o| o.node_name.clone()));
+            }
+            (orb.name.clone(), key, sinks)
+        };
+
+        Self::update_settings(state, sender, |settings| {
+            settings.suspended_outputs.retain(|k| *k != key);
+            if suspend {
+                settings.suspended_outputs.push(key);
+            }
+        });
+        Self::apply_suspend(state, sender, &name, &sinks, suspend);
+    }
+
+    fn apply_suspend(state: &SharedState, sender: &Sender<OrbEvent>, name: &str, sinks: &[String], suspend: bool) {
+        let failed = sinks.iter()
+            .filter(|sink| match state.backend.suspend_sink(sink, suspend) {
+                Ok(()) => false,
+                Err(e) => {
+                    warn!("Failed to {} {}: {}", if suspend { "suspend" } else { "wake" }, sink, e);
+                    true
+                }
+            })
+            .count();
+        if failed > 0 {
+            let what = if suspend { "suspend" } else { "wake up" };
+            state.send(sender, OrbEvent::Error { message: format!("Couldn't {} {}", what, name) });
+        }
+    }
+
+    fn is_suspended_output(state: &SharedState, key: &str) -> bool {
+        state.store().data.settings.suspended_outputs.iter().any(|k| k == key)
+    }
+
+    /// Play a tone on each member of a cluster in turn, bypassing the combine-sink,
+    /// so the user can tell which speaker is which
+    fn channel_check(state: &SharedState, sender: &Sender<OrbEvent>, cluster_id: Uuid) {
+        let members: Vec<(String, String)> = {
+            let graph = state.graph();
+            let Some(cluster) = graph.clusters.get(&cluster_id) else {
+                warn!("Channel check for unknown cluster {}", cluster_id);
+                return;
+            };
+            cluster.members().iter()
+                .filter_map(|id| graph.orbs.get(id))
+                .map(|orb| (orb.node_name.clone(), orb.name.clone()))
+                .collect()
+        };
+
+        let total = members.len();
+        for (i, (node_name, name)) in members.into_iter().enumerate() {
+            let playing = match (total, i) {
+                (2, 0) => format!("{} left", name),
+                (2, _) => format!("{} right", name),
+                _ => format!("{} ({}/{})", name, i + 1, total),
+            };
+            state.send(sender, OrbEvent::ChannelCheck { cluster: cluster_id, playing: Some(playing) });
+
+            if let Err(e) = state.backend.play_test_tone(&node_name, CHANNEL_CHECK_TONE_MS) {
+                warn!("Test tone on {} failed: {}", node_name, e);
+                state.report_failure(sender, format!("Couldn't play a test tone on {}", name), &e);
+            }
+        }
+
+        state.send(sender, OrbEvent::ChannelCheck { cluster: cluster_id, playing: None });
+    }
+
+    /// Play a tone on one device, addressed by node name so a cluster member plays it alone
+    fn play_test_tone(state: &SharedState, sender: &Sender<OrbEvent>, device: Uuid) {
+        let target = state.graph().orbs.get(&device)
+            .filter(|orb| matches!(orb.kind, OrbKind::PhysicalSink { .. }))
+            .map(|orb| (orb.node_name.clone(), orb.name.clone()));
+        let Some((node_name, name)) = target else {
+            warn!("No device found for {}", device);
+            return;
+        };
+        if let Err(e) = state.backend.play_test_tone(&node_name, CHANNEL_CHECK_TONE_MS) {
+            warn!("Test tone on {} failed: {}", node_name, e);
+            state.report_failure(sender, format!("Couldn't play a test tone on {}", name), &e);
+        }
+    }
+
+    /// Re-read the latency of every cluster's combine-sink and report changes
+    pub fn measure_latency(state: &SharedState, sender: &Sender<OrbEvent>) {
+        let sinks: Vec<(Uuid, String)> = state.graph().clusters.values()
+            .map(|c| (c.id, c.sink_name.clone()))
+            .collect();
+
+        for (cluster_id, sink_name) in sinks {
+            // Query outside the lock, pactl can be slow
+            let latency_ms = match state.backend.sink_latency_ms(&sink_name) {
+                Ok(ms) => ms,
+                Err(e) => {
+                    warn!("Failed to read latency of {}: {}", sink_name, e);
+                    continue;
+                }
+            };
+
+            let events = state.graph().set_cluster_latency(cluster_id, latency_ms);
+            state.send_events(sender, events);
+        }
+    }
+
+    /// Re-read the default sink, which anything on the system can change, see
+    /// `AudioGraph::observe_default_sink`
+    pub fn reconcile_default_sink(state: &SharedState, sender: &Sender<OrbEvent>) {
+        let sink_name = match state.backend.get_default_sink() {
+            Ok(name) => Some(name).filter(|n| !n.is_empty()),
+            Err(e) => {
+                warn!("Failed to read the default sink: {}", e);
+                return;
+            }
+        };
+        let events = state.graph().observe_default_sink(sink_name);
+        state.send_events(sender, events);
+    }
+
+    /// Bring the live clusters in line with prefs an import changed (`before`: the prefs it
+    /// replaced). A new name or zone is set in place; delays, downmixes, volumes and channel
+    /// layouts are fixed when a cluster is loaded, so one whose changed is built again.
+    fn apply_imported_prefs(state: &SharedState, sender: &Sender<OrbEvent>, before: &HashMap<String, ClusterPrefs>) {
+        let changed: Vec<(Uuid, ClusterPrefs, bool)> = {
+            let graph = state.graph();
+            let store = state.store();
+            graph.clusters.values()
+                .filter(|cluster| !cluster.external)
+                .filter_map(|cluster| {
+                    let key = graph.cluster_key(cluster.id)?;
+                    let prefs = store.data.clusters.get(&key).cloned().unwrap_or_default();
+                    let old = before.get(&key).cloned().unwrap_or_default();
+                    if prefs == old {
+                        return None;
+                    }
+                    let rebuild = prefs.delays != old.delays
+                        || prefs.downmix != old.downmix
+                        || prefs.volumes != old.volumes
+                        || prefs.channels != old.channels
+                        || prefs.order != old.order;
+                    Some((cluster.id, prefs, rebuild))
+                })
+                .collect()
+        };
+
+        for (cluster_id, prefs, rebuild) in changed {
+            if rebuild {
+                info!("Rebuilding cluster {} with the imported setup", cluster_id);
+                let members = Self::unload_cluster(state, sender, cluster_id);
+                let _ = Self::create_cluster(state, sender, members);
+                continue;
+            }
+            let events = {
+                let mut graph = state.graph();
+                let mut events = graph.set_cluster_zone(cluster_id, prefs.zone);
+                if let Some(name) = prefs.name {
+                    events.extend(graph.rename_cluster(cluster_id, name));
+                }
+                events
+            };
+            state.send_events(sender, events);
+        }
+    }
+
+    /// Change the alignment delay of one member and rebuild the cluster with it
+    fn nudge_member_delay(state: &SharedState, sender: &Sender<OrbEvent>, cluster_id: Uuid, device: Uuid, delta_ms: i32) {
+        Self::update_member_prefs(state, sender, cluster_id, device, |prefs, node_name| {
+            let current = prefs.delays.get(node_name).copied().unwrap_or(0) as i64;
+            let delay = (current + delta_ms as i64).clamp(0, MAX_MEMBER_DELAY_MS as i64) as u32;
+            info!("Delay for {}: {} ms", node_name, delay);
+            if delay == 0 {
+                prefs.delays.remove(node_name);
+            } else {
+                prefs.delays.insert(node_name.to_string(), delay);
+            }
+        });
+    }
+
+    /// Saved prefs of the cluster `key` of `members`, wired members' delays raised by the
+    /// Bluetooth offset (see `DeviceBus::bluetooth_offset_ms`). Only for building it, never saved.
+    fn playing_prefs(state: &SharedState, key: &str, members: &[Uuid]) -> ClusterPrefs {
+        let mut prefs = state.store().data.clusters.get(key).cloned().unwrap_or_default();
+        let offset_ms = state.store().data.settings.bluetooth_offset_ms;
+        let graph = state.graph();
+        let orbs: Vec<&Orb> = members.iter().filter_map(|id| graph.orbs.get(id)).collect();
+        let buses: Vec<DeviceBus> = orbs.iter().map(|orb| orb.bus).collect();
+        if let Some(offset_ms) = DeviceBus::bluetooth_offset_ms(&buses, offset_ms) {
+            for orb in orbs.iter().filter(|orb| orb.bus != DeviceBus::Bluetooth) {
+                *prefs.delays.entry(orb.node_name.clone()).or_default() += offset_ms;
+            }
+        }
+        prefs
+    }
+
+    /// Change `Settings::bluetooth_offset_ms` and rebuild the clusters whose members it delays,
+    /// the delays are fixed when a cluster is loaded
+    fn set_bluetooth_offset(state: &SharedState, sender: &Sender<OrbEvent>, ms: u32) {
+        let before = state.store().data.settings.bluetooth_offset_ms;
+        Self::update_settings(state, sender, |settings| settings.bluetooth_offset_ms = ms);
+
+        let affected: Vec<Uuid> = {
+            let graph = state.graph();
+            graph.clusters.values()
+                .filter(|cluster| !cluster.external)
+                .filter(|cluster| {
+                    let buses: Vec<DeviceBus> = cluster.members().iter()
+                        .filter_map(|id| graph.orbs.get(id).map(|orb| orb.bus))
+                        .collect();
+                    DeviceBus::bluetooth_offset_ms(&buses, before) != DeviceBus::bluetooth_offset_ms(&buses, ms)
+                })
+                .map(|cluster| cluster.id)
+                .collect()
+        };
+        for cluster_id in affected {
+            info!("Rebuilding cluster {} for the new Bluetooth offset", cluster_id);
+            let members = Self::unload_cluster(state, sender, cluster_id);
+            let _ = Self::create_cluster(state, sender, members);
+        }
+    }
+
+    /// A device came back as another node of its card (see `AudioGraph::replace_node`). Its
+    /// formats are read again, and a cluster it's in is built again, since the combine-sink
+    /// played to the old node by name. What was remembered for the cluster (name, delays,
+    /// volumes, media roles) carries over to the new member set unless that has its own.
+    pub fn device_replaced(state: &SharedState, sender: &Sender<OrbEvent>, device: Uuid, old_node_name: &str) {
+        Self::read_formats(state, sender, device);
+
+        let (cluster_id, node_name, old_key, new_key) = {
+            let graph = state.graph();
+            let cluster = graph.cluster_of(device)
+                .and_then(|id| graph.clusters.get(&id))
+                .filter(|cluster| !cluster.external);
+            let (Some(cluster), Some(orb)) = (cluster, graph.orbs.get(&device)) else { return };
+            let members: Vec<&Orb> = cluster.members().iter().filter_map(|id| graph.orbs.get(id)).collect();
+            let new_names: Vec<String> = members.iter().map(|o| o.node_name.clone()).collect();
+            let old_names: Vec<String> = members.iter()
+                .map(|o| if o.id == device { old_node_name.to_string() } else { o.node_name.clone() })
+                .collect();
+            (cluster.id, orb.node_name.clone(), persistence::cluster_key(&old_names), persistence::cluster_key(&new_names))
+        };
+
+        let carried = {
+            let mut store = state.store();
+            let prefs = store.data.clusters.get(&old_key)
+                .filter(|_| !store.data.clusters.contains_key(&new_key))
+                .map(|prefs| prefs.with_member_renamed(old_node_name, &node_name));
+            if let Some(prefs) = &prefs {
+                store.data.clusters.insert(new_key.clone(), prefs.clone());
+                store.save();
+            }
+            prefs.is_some()
+        };
+        if carried {
+            Self::update_settings(state, sender, |settings| {
+                for target in settings.role_targets.values_mut().filter(|target| **target == old_key) {
+                    *target = new_key.clone();
+                }
+                for output in settings.suspended_outputs.iter_mut().filter(|output| **output == old_key) {
+                    *output = new_key.clone();
+                }
+            });
+        }
+
+        info!("Rebuilding cluster {} for {}'s new node {}", cluster_id, old_node_name, node_name);
+        let members = Self::unload_cluster(state, sender, cluster_id);
+        let _ = Self::create_cluster(state, sender, members);
+    }
+
+    /// Rebuild a cluster with another channel layout, if its members can play it
+    fn set_cluster_channels(state: &SharedState, sender: &Sender<OrbEvent>, cluster_id: Uuid, channels: u32) {
+        let (key, member_channels, current) = {
+            let graph = state.graph();
+            let cluster = graph.clusters.get(&cluster_id).filter(|c| !c.external);
+            let (Some(cluster), Some(key)) = (cluster, graph.cluster_key(cluster_id)) else {
+                warn!("No cluster of ours found for {}", cluster_id);
+                return;
+            };
+            let member_channels: Vec<Option<u32>> = cluster.members().iter()
+                .map(|id| graph.orbs.get(id).and_then(|o| o.channels))
+                .collect();
+            (key, member_channels, graph.orbs.get(&cluster_id).and_then(|o| o.channels))
+        };
+
+        if let Err(e) = channels::validate(channels, &member_channels) {
+            warn!("Refusing {} channels for cluster {}: {}", channels, cluster_id, e);
+            state.send(sender, OrbEvent::Error { message: format!("Can't change the cluster's channels: {}", e) });
+            return;
+        }
+        if current == Some(channels) {
+            return;
+        }
+
+        {
+            let mut store = state.store();
+            store.data.clusters.entry(key).or_default().channels = Some(channels);
+            store.save();
+        }
+
+        // The layout is fixed when the combine-sink is loaded
+        let members = Self::unload_cluster(state, sender, cluster_id);
+        let _ = Self::create_cluster(state, sender, members);
+    }
+
+    /// Prefs key of a cluster Auralis built and the node name of one of its members
+    fn member_of(state: &SharedState, cluster_id: Uuid, device: Uuid) -> Option<(String, String)> {
+        let graph = state.graph();
+        let is_member = graph.clusters.get(&cluster_id)
+            .map(|c| c.members().contains(&device) && !c.external)
+            .unwrap_or(false);
+        let (Some(key), Some(orb), true) = (graph.cluster_key(cluster_id), graph.orbs.get(&device), is_member) else {
+            warn!("{} is not a member of cluster {}", device, cluster_id);
+            return None;
+        };
+        Some((key, orb.node_name.clone()))
+    }
+
+    /// Set a member's device volume and remember it for the cluster. Unlike delays and
+    /// downmixes this goes straight to the device, the cluster stays as it is.
+    fn set_member_volume(state: &SharedState, sender: &Sender<OrbEvent>, cluster_id: Uuid, device: Uuid, percent: u32) {
+        let Some((key, node_name)) = Self::member_of(state, cluster_id, device) else { return };
+        let percent = percent.min(MAX_MEMBER_VOLUME);
+
+        if let Err(e) = state.backend.set_sink_volume(&node_name, percent) {
+            error!("Failed to set volume of {}: {}", node_name, e);
+            state.report_failure(sender, format!("Couldn't change the volume: {}", e), &e);
+            return;
+        }
+
+        {
+            let mut store = state.store();
+            store.data.clusters.entry(key).or_default().volumes.insert(node_name, percent);
+            store.save();
+        }
+
+        let events = state.graph().set_member_volume(cluster_id, device, percent);
+        state.send_events(sender, events);
+    }
+
+    /// Set a device's or cluster's volume and report what it is now. Members of a cluster of
+    /// ours go through `set_member_volume`, so their card and prefs keep up.
+    fn set_volume(state: &SharedState, sender: &Sender<OrbEvent>, device: Uuid, percent: u32) {
+        let cluster = {
+            let graph = state.graph();
+            graph.cluster_of(device).filter(|id| graph.clusters.get(id).is_some_and(|c| !c.external))
+        };
+        match cluster {
+            Some(cluster) => Self::set_member_volume(state, sender, cluster, device, percent),
+            None => {
+                let percent = percent.min(MAX_MEMBER_VOLUME);
+                Self::with_output_node(state, sender, device, "change the volume", |name| {
+                    state.backend.set_sink_volume(name, percent)
+                });
+            }
+        }
+        Self::report_volume(state, sender, device);
+    }
+
+    /// Read a device's or cluster's volume and tell the UI (`OrbEvent::Volume`)
+    fn report_volume(state: &SharedState, sender: &Sender<OrbEvent>, device: Uuid) {
+        let node_name = state.graph().orbs.get(&device)
+            .filter(|orb| matches!(orb.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. }) && !orb.is_staged())
+            .map(|orb| orb.node_name.clone());
+        let Some(node_name) = node_name else { return };
+        match state.backend.sink_volume(&node_name) {
+            Ok(percent) => state.send(sender, OrbEvent::Volume { device, percent }),
+            Err(e) => warn!("Failed to read the volume of {}: {}", node_name, e),
+        }
+    }
+
+    /// Set every member of a cluster to the average of their current volumes, remembered like
+    /// `set_member_volume`. Volume percent follows perceived loudness (it's on a cubic scale),
+    /// so similar speakers end up about as loud; one that is much louder by itself still needs
+    /// its own nudge. Members whose volume can't be read keep theirs.
+    fn balance_cluster_volumes(state: &SharedState, sender: &Sender<OrbEvent>, cluster_id: Uuid) {
+        let members: Vec<(Uuid, String)> = {
+            let graph = state.graph();
+            let Some(cluster) = graph.clusters.get(&cluster_id).filter(|c| !c.external) else {
+                warn!("No cluster of ours found for {}", cluster_id);
+                return;
+            };
+            cluster.members().iter()
+                .filter_map(|id| graph.orbs.get(id).map(|o| (*id, o.node_name.clone())))
+                .collect()
+        };
+        let volumes: Vec<(Uuid, u32)> = members.iter()
+            .filter_map(|(id, node_name)| match state.backend.sink_volume(node_name) {
+                Ok(percent) => Some((*id, percent)),
+                Err(e) => {
+                    warn!("Failed to read the volume of {}: {}", node_name, e);
+                    None
+                }
+            })
+            .collect();
+        if volumes.len() < 2 {
+            state.send(sender, OrbEvent::Error { message: "Couldn't balance the cluster: its volumes can't be read".to_string() });
+            return;
+        }
+
+        let sum: u32 = volumes.iter().map(|(_, percent)| percent).sum();
+        let target = ((sum as f64 / volumes.len() as f64).round() as u32).min(MAX_MEMBER_VOLUME);
+        info!("Balancing {} members at {}%", volumes.len(), target);
+        for (device, _) in volumes {
+            Self::set_member_volume(state, sender, cluster_id, device, target);
+        }
+    }
+
+    /// Swap a member with the one before (or after) it and rebuild the cluster with its slaves
+    /// in the new order
+    fn move_cluster_member(state: &SharedState, sender: &Sender<OrbEvent>, cluster_id: Uuid, device: Uuid, up: bool) {
+        let Some((key, _)) = Self::member_of(state, cluster_id, device) else { return };
+        let mut members: Vec<Uuid> = state.graph().clusters.get(&cluster_id)
+            .map(|cluster| cluster.members())
+            .unwrap_or_default();
+        let Some(index) = members.iter().position(|id| *id == device) else { return };
+        let other = if up { index.checked_sub(1) } else { Some(index + 1).filter(|i| *i < members.len()) };
+        let Some(other) = other else { return };
+        members.swap(index, other);
+
+        let order: Vec<String> = {
+            let graph = state.graph();
+            members.iter().filter_map(|id| graph.orbs.get(id).map(|o| o.node_name.clone())).collect()
+        };
+        {
+            let mut store = state.store();
+            store.data.clusters.entry(key).or_default().order = order;
+            store.save();
+        }
+
+        // The slaves are fixed when the combine-sink is loaded
+        let members = Self::unload_cluster(state, sender, cluster_id);
+        let _ = Self::create_cluster(state, sender, members);
+    }
+
+    /// `members` in the order saved for their cluster; ones it doesn't know keep theirs, after it
+    fn in_saved_order(state: &SharedState, mut members: Vec<Uuid>) -> Vec<Uuid> {
+        let graph = state.graph();
+        let node_names: Vec<String> = members.iter()
+            .filter_map(|id| graph.orbs.get(id).map(|o| o.node_name.clone()))
+            .collect();
+        let store = state.store();
+        let Some(prefs) = store.data.clusters.get(&persistence::cluster_key(&node_names)) else { return members };
+        members.sort_by_key(|id| {
+            let node_name = graph.orbs.get(id).map(|o| o.node_name.as_str());
+            prefs.order.iter().position(|name| Some(name.as_str()) == node_name).unwrap_or(usize::MAX)
+        });
+        members
+    }
+
+    /// Change a member's persisted preferences and rebuild the cluster with them
+    fn update_member_prefs(
+        state: &SharedState,
+        sender: &Sender<OrbEvent>,
+        cluster_id: Uuid,
+        device: Uuid,
+        apply: impl FnOnce(&mut ClusterPrefs, &str),
+    ) {
+        let Some((key, node_name)) = Self::member_of(state, cluster_id, device) else { return };
+
+        {
+            let mut store = state.store();
+            apply(store.data.clusters.entry(key).or_default(), &node_name);
+            store.save();
+        }
+
+        // Delays and downmixes are baked into the combine-sink's slaves, so rebuild it
+        let members = Self::unload_cluster(state, sender, cluster_id);
+        let _ = Self::create_cluster(state, sender, members);
+    }
+
+    /// Route a member through a null-sink + loopback that adds `delay_ms`.
+    /// Returns the sink to use as combine-sink slave and the modules to unload later.
+    fn load_member_delay(state: &SharedState, node_name: &str, delay_ms: u32) -> Result<(String, Vec<u32>)> {
+        let delay_sink = state.fresh_sink_name(sink_names::DELAY);
+        let null_module = state.backend.load_null_sink(&delay_sink, &format!("Auralis delay ({})", node_name))?;
+
+        let monitor = format!("{}.monitor", delay_sink);
+        match state.backend.load_loopback(&monitor, node_name, delay_ms) {
+            Ok(loopback_module) => Ok((delay_sink, vec![null_module, loopback_module])),
+            Err(e) => {
+                let _ = state.backend.unload_module(null_module);
+                Err(e)
+            }
+        }
+    }
+
+    /// Put a mono sink in front of a member so it gets a proper downmix instead of
+    /// whatever a stereo combine-sink makes of it. Returns the sink to play into and its module.
+    fn load_member_downmix(state: &SharedState, node_name: &str) -> Result<(String, u32)> {
+        let mono_sink = state.fresh_sink_name(sink_names::MONO);
+        let module_id = state.backend.load_remap_sink(&mono_sink, node_name, "mono")?;
+        Ok((mono_sink, module_id))
+    }
+
+    /// Move streams back to the sink that was default before the cluster,
+    /// or to the first member if we never saw one
+    fn restore_default_sink(state: &SharedState, sender: &Sender<OrbEvent>, cluster: &Cluster) {
+        let restore_to = cluster.saved_default_sink.clone().or_else(|| {
+            state.graph().orbs.get(&cluster.master_sink_id).map(|o| o.node_name.clone())
+        });
+
+        if let Some(sink_name) = restore_to {
+            info!("Restoring streams to: {}", sink_name);
+            Self::move_all_streams_or_report(state, sender, &sink_name);
+            Self::set_default_sink_or_report(state, sender, &sink_name);
+        }
+    }
+
+    fn routing_suspended(state: &SharedState) -> bool {
+        state.store().data.settings.routing_suspended
+    }
+
+    /// Hand the default output back to whatever it was before the cluster that has it now.
+    /// Clusters stay loaded; the one that was the default is remembered for `resume_routing`.
+    fn suspend_routing(state: &SharedState, sender: &Sender<OrbEvent>) {
+        if Self::routing_suspended(state) {
+            return;
+        }
+        Self::update_settings(state, sender, |settings| settings.routing_suspended = true);
+
+        let default_sink = state.backend.get_default_sink().ok();
+        let cluster = default_sink.and_then(|name| {
+            state.graph().clusters.values().find(|c| c.sink_name == name).cloned()
+        });
+        if let Some(cluster) = cluster {
+            *state.resume_sink.lock().unwrap() = Some(cluster.sink_name.clone());
+            Self::restore_default_sink(state, sender, &cluster);
+        }
+    }
+
+    /// Undo `suspend_routing`: the remembered cluster, if it's still around, gets the
+    /// default output and the streams back
+    fn resume_routing(state: &SharedState, sender: &Sender<OrbEvent>) {
+        if !Self::routing_suspended(state) {
+            return;
+        }
+        Self::update_settings(state, sender, |settings| settings.routing_suspended = false);
+
+        let Some(sink_name) = state.resume_sink.lock().unwrap().take() else { return };
+        if !state.graph().clusters.values().any(|c| c.sink_name == sink_name) {
+            info!("Cluster {} is gone, leaving the default output alone", sink_name);
+            return;
+        }
+        if Self::set_default_sink_or_report(state, sender, &sink_name) {
+            Self::move_all_streams_or_report(state, sender, &sink_name);
+        }
+    }
+
+    /// Remember where streams with `role` go and move the ones already playing there
+    fn set_role_target(state: &SharedState, sender: &Sender<OrbEvent>, role: &str, sink: Option<Uuid>) {
+        let Some(role) = media_roles::canonical(role) else {
+            warn!("Unknown media role {:?}", role);
+            return;
         };
-        
-        if let Some(mid) = module_id {
-            let _ = std::process::Command::new("pactl")
-                .args(&["unload-module", &mid.to_string()])
-                .output();
-            info!("✓ Unloaded cluster module {}", mid);
+        let key = match sink {
+            Some(sink) => {
+                let graph = state.graph();
+                let Some(key) = graph.orbs.get(&sink).and_then(|o| media_roles::target_key(&graph.orbs, o)) else {
+                    warn!("{} is not an output", sink);
+                    return;
+                };
+                Some(key)
+            }
+            None => None,
+        };
+        Self::update_settings(state, sender, |settings| {
+            match key {
+                Some(key) => settings.role_targets.insert(role.to_string(), key),
+                None => settings.role_targets.remove(role),
+            };
+        });
+
+        // Streams already playing follow right away
+        Self::follow_role(state, sender, role);
+    }
+
+    /// Move the streams already playing with `role` to its output (see `role_output`), or back
+    /// to the default output if it has none
+    fn follow_role(state: &SharedState, sender: &Sender<OrbEvent>, role: &str) {
+        let playing: Vec<(Uuid, u32)> = {
+            let graph = state.graph();
+            graph.media_roles.iter()
+                .filter(|(_, r)| r.as_str() == role)
+                .filter_map(|(id, _)| graph.orbs.get(id).map(|o| (*id, o.pw_id)))
+                .collect()
+        };
+        if playing.is_empty() {
+            return;
         }
+        if Self::role_output(state, role).is_some() {
+            for (app, _) in playing {
+                Self::route_by_role(state, sender, app);
+            }
+            return;
+        }
+        let default_sink = state.backend.get_default_sink().unwrap_or_default();
+        if default_sink.is_empty() {
+            return;
+        }
+        for (_, pw_id) in playing {
+            if let Err(e) = Self::retry_once(|| state.backend.move_sink_input(pw_id, &default_sink)) {
+                warn!("Failed to move stream {} back to {}: {}", pw_id, default_sink, e);
+            }
+        }
+    }
+
+    /// Where streams with `role` go, if it's around: the output picked for the role, else for
+    /// notifications the device a default cluster took over from (`notifications_off_clusters`)
+    fn role_output(state: &SharedState, role: &str) -> Option<Orb> {
+        let (key, off_clusters) = {
+            let store = state.store();
+            let settings = &store.data.settings;
+            (settings.role_targets.get(role).cloned(), settings.notifications_off_clusters)
+        };
+        let graph = state.graph();
+        match key {
+            Some(key) => media_roles::find_target(&graph.orbs, &key).cloned(),
+            None if role == media_roles::NOTIFICATION && off_clusters => graph.notification_device().cloned(),
+            None => None,
+        }
+    }
+
+    /// Move an app to the output its media role is routed to, if that output is around
+    fn route_by_role(state: &SharedState, sender: &Sender<OrbEvent>, app_id: Uuid) {
+        if Self::routing_suspended(state) {
+            return;
+        }
+        let (app, role) = {
+            let graph = state.graph();
+            let Some(app) = graph.orbs.get(&app_id).cloned() else { return };
+            let Some(role) = graph.media_roles.get(&app_id).cloned() else { return };
+            (app, role)
+        };
+        let Some(target) = Self::role_output(state, &role) else {
+            info!("No output for {} ({}) is here, leaving it on the default", app.name, role);
+            return;
+        };
+
+        match Self::retry_once(|| state.backend.move_sink_input(app.pw_id, &target.node_name)) {
+            Ok(()) => info!("✓ Routed {} to {} by its media role", app.name, target.name),
+            Err(e) => {
+                error!("Failed to route {} to {}: {}", app.name, target.node_name, e);
+                state.report_failure(sender, format!("Couldn't move {} to {}", app.name, target.name), &e);
+            }
+        }
+    }
+
+    /// Run a default-sink switch or stream move, once more after `SWITCH_RETRY_DELAY` if it fails
+    fn retry_once(call: impl Fn() -> Result<()>) -> Result<()> {
+        call().or_else(|e| {
+            warn!("{}, retrying in {:?}", e, SWITCH_RETRY_DELAY);
+            thread::sleep(SWITCH_RETRY_DELAY);
+            call()
+        })
+    }
+
+    /// Name of the device or cluster playing to `sink_name`, for messages
+    fn output_label(state: &SharedState, sink_name: &str) -> String {
+        state.graph().orbs.values()
+            .find(|o| o.node_name == sink_name && matches!(o.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. }))
+            .map(|o| o.name.clone())
+            .unwrap_or_else(|| sink_name.to_string())
+    }
+
+    /// Make `sink_name` the default output, with one retry. A failure is reported to the user,
+    /// since audio won't follow. Returns whether it worked.
+    fn set_default_sink_or_report(state: &SharedState, sender: &Sender<OrbEvent>, sink_name: &str) -> bool {
+        match Self::retry_once(|| state.backend.set_default_sink(sink_name)) {
+            Ok(()) => {
+                Self::reconcile_default_sink(state, sender);
+                true
+            }
+            Err(e) => {
+                error!("Failed to set default sink {}: {}", sink_name, e);
+                let label = Self::output_label(state, sink_name);
+                state.report_failure(sender, format!("Couldn't make {} the default output", label), &e);
+                false
+            }
+        }
+    }
+
+    /// Move every stream to `sink_name`. The ones that refuse get one retry; any still
+    /// around after that is reported to the user.
+    fn move_all_streams_or_report(state: &SharedState, sender: &Sender<OrbEvent>, sink_name: &str) {
+        let failed = state.backend.move_all_sink_inputs(sink_name);
+        if failed.is_empty() {
+            return;
+        }
+        thread::sleep(SWITCH_RETRY_DELAY);
+
+        // Streams that ended in the meantime don't count
+        let live = state.backend.list_sink_inputs().unwrap_or_default();
+        let stuck = failed.into_iter()
+            .filter(|id| live.contains(id))
+            .filter(|id| state.backend.move_sink_input(*id, sink_name).is_err())
+            .count();
+        if stuck > 0 {
+            error!("{} streams couldn't be moved to {}", stuck, sink_name);
+            let label = Self::output_label(state, sink_name);
+            let streams = if stuck == 1 { "1 stream".to_string() } else { format!("{} streams", stuck) };
+            state.send(sender, OrbEvent::Error { message: format!("Couldn't move {} to {}", streams, label) });
+        }
+    }
+    
+    /// Tear down a cluster whose members are about to join a new one.
+    /// Returns the member orbs, which stay hidden from the UI.
+    fn unload_cluster(state: &SharedState, sender: &Sender<OrbEvent>, cluster_id: Uuid) -> Vec<Uuid> {
+        // A staged cluster has nothing loaded, its device just leaves the card
+        let unstaged = state.graph().unstage_cluster(cluster_id, false);
+        if let Some((device, events)) = unstaged {
+            state.send_events(sender, events);
+            return vec![device];
+        }
+
+        let dissolved = state.graph().dissolve_cluster(cluster_id, false);
+        let Some((cluster, events)) = dissolved else {
+            warn!("No cluster found for {}", cluster_id);
+            return Vec::new();
+        };
+        state.metrics.cluster_dissolved();
+
+        // 1. Move Streams & Restore Default Sink
+        Self::restore_default_sink(state, sender, &cluster);
+
+        // 2. Unload Module
+        state.unload_cluster_modules(&cluster);
         
-        // Remove from UI
-        let _ = sender.send(OrbEvent::Remove(cluster_id));
-        
-        // Remove from State
-        state.orb_kinds.lock().unwrap().remove(&cluster_id);
-        state.orb_names.lock().unwrap().remove(&cluster_id);
-        state.orb_to_pw_id.lock().unwrap().remove(&cluster_id);
+        // 3. Remove from UI
+        state.send_events(sender, events);
         
         // Note: We do NOT restore devices here, because we are immediately creating a new cluster
         // that will "consume" them.
+        cluster.members()
     }
 
-    fn handle_disconnect(state: &SharedState, sender: &Sender<OrbEvent>, source: Uuid, _target: Uuid) {
-        // For Cluster orbs, unload ALL combine-sink modules containing their devices
-        let kind = {
-            let kinds = state.orb_kinds.lock().unwrap();
-            kinds.get(&source).cloned()
+    /// Tell the user that devices about to be clustered play through a monitor, so the cluster
+    /// goes quiet on them (but waits, see `DeviceBus::dropout_grace_ms`) while it sleeps
+    fn warn_monitor_members(state: &SharedState, sender: &Sender<OrbEvent>, devices: &[&Orb]) {
+        let names: Vec<&str> = devices.iter()
+            .filter(|o| o.bus.follows_monitor())
+            .map(|o| o.name.as_str())
+            .collect();
+        if names.is_empty() {
+            return;
+        }
+        let grace_ms = state.store().data.settings.dropout_grace_ms;
+        let (plays, it) = if names.len() == 1 { ("plays", "it") } else { ("play", "them") };
+        let wait = match DeviceBus::Hdmi.dropout_grace_ms(grace_ms) {
+            0 => "the cluster is separated".to_string(),
+            ms => format!("the cluster waits up to {} minutes for {}", ms.div_ceil(60_000), it),
         };
-        
-        if let Some(OrbKind::Cluster { devices }) = kind {
-            info!("Separating cluster: {:?}", devices);
-            
-            // 1. Determine Target for Restoration (Saved Default or First Device)
-            let target_sink = {
-                let mut saved = state.saved_default_sink.lock().unwrap();
-                saved.remove(&source)
-            };
-            
-            let restore_to = target_sink.or_else(|| devices.first().cloned());
-            
-            if let Some(sink_name) = restore_to {
-                info!("Restoring streams to: {}", sink_name);
-                let _ = std::process::Command::new("bash")
-                    .arg("-c")
-                    .arg(format!(
-                        "pactl list sink-inputs short | cut -f1 | xargs -I{{}} pactl move-sink-input {{}} {} 2>/dev/null || true",
-                        sink_name
-                    ))
-                    .output();
-                    
-                let _ = std::process::Command::new("pactl")
-                    .args(&["set-default-sink", &sink_name])
-                    .output();
-            }
-
-            // Get module ID to unload
-            let module_id = {
-                let mut modules = state.combine_modules.lock().unwrap();
-                modules.remove(&source)
-            };
-            
-            if let Some(mid) = module_id {
-                let result = std::process::Command::new("pactl")
-                    .args(&["unload-module", &mid.to_string()])
-                    .output();
-                
-                match result {
-                    Ok(out) if out.status.success() => {
-                        info!("✓ Unloaded combine-sink module {}", mid);
-                    }
-                    Ok(out) => {
-                        let stderr = String::from_utf8_lossy(&out.stderr);
-                        warn!("Failed to unload module {}: {}", mid, stderr);
-                    }
-                    Err(e) => {
-                        error!("Failed to execute pactl: {}", e);
-                    }
+        state.send(sender, OrbEvent::Error {
+            message: format!("{} {} through a monitor (HDMI/DisplayPort); while the monitor sleeps {}", names.join(", "), plays, wait),
+        });
+    }
+
+    /// Build one cluster from `devices`, ignoring unknown IDs, repeats and anything that isn't
+    /// a device. Needs two of them; devices already clustered together are left as they are.
+    /// Returns the cluster built, if one was.
+    fn cluster_devices(state: &SharedState, sender: &Sender<OrbEvent>, devices: Vec<Uuid>) -> Option<Uuid> {
+        let (members, unchanged) = {
+            let graph = state.graph();
+            let mut members: Vec<Uuid> = Vec::new();
+            for id in devices {
+                let is_device = graph.orbs.get(&id).is_some_and(|o| matches!(o.kind, OrbKind::PhysicalSink { .. }));
+                if is_device && !members.contains(&id) {
+                    members.push(id);
                 }
-            } else {
-                warn!("No module found for cluster {}", source);
             }
-            
-            // Clear tracked modules (already removed above)
-            // state.combine_modules.lock().unwrap().clear();
-            
-            // Remove cluster from state
-            state.orb_kinds.lock().unwrap().remove(&source);
-            state.orb_names.lock().unwrap().remove(&source);
-            state.orb_to_pw_id.lock().unwrap().remove(&source);
-            
-            // 1. Remove Cluster from UI
-            let _ = sender.send(OrbEvent::Remove(source));
+            // Exactly the members of an existing cluster
+            let unchanged = graph.cluster_of(members.first().copied().unwrap_or_default())
+                .and_then(|id| graph.clusters.get(&id))
+                .is_some_and(|c| c.members().len() == members.len() && c.members().iter().all(|m| members.contains(m)));
+            (members, unchanged)
+        };
+        if members.len() < 2 {
+            warn!("Not clustering {} device(s), it takes two", members.len());
+            state.send(sender, OrbEvent::Error { message: "Select at least two devices to cluster".to_string() });
+            return None;
+        }
+        if unchanged {
+            info!("Those devices are already clustered together");
+            return None;
+        }
+        let orbs: Vec<Orb> = {
+            let graph = state.graph();
+            members.iter().filter_map(|id| graph.orbs.get(id).cloned()).collect()
+        };
+        Self::warn_monitor_members(state, sender, &orbs.iter().collect::<Vec<_>>());
+        Self::detach_members(state, sender, &members);
+        Self::create_cluster(state, sender, members)
+    }
 
-            // 2. Restore Original Devices
-            info!("Restoring original devices from cluster: {:?}", devices);
-            
-            // Remove from active cluster members so they can be rediscovered/shown
-            state.remove_cluster_members(&devices);
-            
-            // Since we kept them in state, we can iterate and find them.
-            let kinds = state.orb_kinds.lock().unwrap();
-            let names = state.orb_names.lock().unwrap();
-            let pw_ids = state.orb_to_pw_id.lock().unwrap();
-            
-            let mut restored_count = 0;
-            
-            for (uuid, kind) in kinds.iter() {
-                if let OrbKind::PhysicalSink { description } = kind {
-                    if devices.contains(description) {
-                        // Found one of the original devices!
-                        if let Some(name) = names.get(uuid) {
-                            if let Some(pw_id) = pw_ids.get(uuid) {
-                                // Determine display name based on kind, similar to discovery logic
-                                let display_name = match &kind {
-                                    OrbKind::PhysicalSink { description } => description.clone(),
-                                    OrbKind::ApplicationSource { app_name } => app_name.clone(),
-                                    _ => name.clone(),
-                                };
-
-                                let orb = Orb {
-                                    id: *uuid,
-                                    pw_id: *pw_id,
-                                    kind: kind.clone(),
-                                    name: display_name.clone(), // Use friendly name
-                                    icon_name: "audio-card".to_string(), // Default, UI might override
-                                    status: "Active".to_string(),
-                                    state: OrbState::Floating,
-                                    position: (0.0, 0.0),
-                                    velocity: (0.0, 0.0),
-                                };
-                                info!("Restoring device to UI: {}", display_name);
-                                let _ = sender.send(OrbEvent::Add(orb));
-                                restored_count += 1;
-                            }
-                        }
-                    }
+    /// The desktop's soft "complete" sound for a cluster the user asked for, unless turned off
+    /// (`Settings::show_notifications`). Rebuilds and profiles don't play it.
+    fn confirm_cluster(state: &SharedState) {
+        if !state.store().data.settings.show_notifications {
+            return;
+        }
+        if let Err(e) = state.backend.play_sound("complete", "Cluster ready") {
+            warn!("No confirmation sound: {}", e);
+        }
+    }
+
+    /// Take devices out of the clusters they're in, so they can be clustered elsewhere without
+    /// ending up in two combine-sinks. Each such cluster is rebuilt from its other members, or
+    /// separated if fewer than two would be left.
+    fn detach_members(state: &SharedState, sender: &Sender<OrbEvent>, devices: &[Uuid]) {
+        let mut clusters: Vec<(Uuid, Vec<Uuid>)> = Vec::new();
+        {
+            let graph = state.graph();
+            for cluster_id in devices.iter().filter_map(|id| graph.orbs.get(id)?.membership()) {
+                if clusters.iter().any(|(id, _)| *id == cluster_id) {
+                    continue;
+                }
+                // Its only device is leaving, the card goes without showing the device again
+                if graph.staged.contains_key(&cluster_id) {
+                    clusters.push((cluster_id, Vec::new()));
+                    continue;
                 }
+                let Some(cluster) = graph.clusters.get(&cluster_id) else { continue };
+                let rest = cluster.members().into_iter().filter(|id| !devices.contains(id)).collect();
+                clusters.push((cluster_id, rest));
+            }
+        }
+
+        for (cluster_id, rest) in clusters {
+            info!("Moving devices out of cluster {} ({} left)", cluster_id, rest.len());
+            let staged = state.graph().staged.contains_key(&cluster_id);
+            if staged {
+                Self::unload_cluster(state, sender, cluster_id);
+            } else if rest.len() < 2 {
+                Self::handle_disconnect(state, sender, cluster_id, cluster_id);
+            } else {
+                Self::unload_cluster(state, sender, cluster_id);
+                let _ = Self::create_cluster(state, sender, rest);
             }
-            
-            info!("✓ Devices separated - restored {} devices to UI", restored_count);
-        } else {
-            info!("Disconnect requested for non-cluster Orb: {:?}", kind);
         }
     }
 
+    fn handle_disconnect(state: &SharedState, sender: &Sender<OrbEvent>, source: Uuid, _target: Uuid) {
+        // Apps: detach them from wherever they were linked or pinned
+        let is_app = state.graph().orbs.get(&source)
+            .map(|o| matches!(o.kind, OrbKind::ApplicationSource { .. }))
+            .unwrap_or(false);
+        if is_app {
+            info!("Detaching app {}", source);
+            Self::unlink_source(state, sender, source, None);
+            return;
+        }
+
+        let unstaged = state.graph().unstage_cluster(source, true);
+        if let Some((device, events)) = unstaged {
+            info!("Releasing staged device {}", device);
+            state.send_events(sender, events);
+            return;
+        }
 
-    fn create_cluster(state: &SharedState, sender: &Sender<OrbEvent>, devices: Vec<String>) {
-        // 1. Resolve Device Names to PipeWire Node Names
-        let mut node_names = Vec::new();
-        let mut member_pairs = Vec::new(); // (Description, NodeName)
+        let dissolved = state.graph().dissolve_cluster(source, true);
+
+        let Some((cluster, events)) = dissolved else {
+            info!("Disconnect requested for non-cluster Orb: {}", source);
+            return;
+        };
+        info!("Separating cluster {} ({} members)", source, cluster.members().len());
+        state.metrics.cluster_dissolved();
         
-        {
-            let kinds = state.orb_kinds.lock().unwrap();
-            let names = state.orb_names.lock().unwrap();
-            let active_members = state.active_cluster_members.lock().unwrap();
-            
-            for desc in &devices {
-                // Try to find in current Orbs (Floating)
-                let uuid = kinds.iter().find_map(|(u, k)| {
-                    if let OrbKind::PhysicalSink { description } = k {
-                        if description == desc {
-                            return Some(*u);
-                        }
+        // 1. Restore streams to the saved default (or first device)
+        Self::restore_default_sink(state, sender, &cluster);
+
+        // 2. Unload the combine-sink
+        state.unload_cluster_modules(&cluster);
+        
+        // 3. Remove cluster from UI and restore original devices
+        let restored_count = events.iter().filter(|e| matches!(e, OrbEvent::Add(_))).count();
+        state.send_events(sender, events);
+        
+        info!("✓ Devices separated - restored {} devices to UI", restored_count);
+    }
+
+
+    /// Build a combine-sink for `members` and make it the default. Returns the new cluster's ID.
+    fn create_cluster(state: &SharedState, sender: &Sender<OrbEvent>, members: Vec<Uuid>) -> Option<Uuid> {
+        let _building = state.building();
+        // Slaves go in the order the user put them in before, else in the order they were added
+        let members = Self::in_saved_order(state, members);
+
+        // 1. Resolve member orbs to PipeWire node names
+        let resolved: Vec<(Uuid, String, Option<u32>)> = {
+            let graph = state.graph();
+            members.iter()
+                .filter_map(|id| match graph.orbs.get(id) {
+                    Some(orb) => Some((*id, orb.node_name.clone(), orb.channels)),
+                    None => {
+                        warn!("Could not find Node Name for device: {}", id);
+                        None
                     }
-                    None
-                });
-                
-                if let Some(u) = uuid {
-                    if let Some(name) = names.get(&u) {
-                        node_names.push(name.clone());
-                        member_pairs.push((desc.clone(), name.clone()));
+                })
+                .collect()
+        };
+        let node_names: Vec<String> = resolved.iter().map(|(_, name, _)| name.clone()).collect();
+        
+        if node_names.is_empty() {
+            error!("No valid devices found for cluster");
+            return None;
+        }
+
+        // A member without audio channels would leave the combine-sink playing into nothing
+        let formatless: Vec<String> = {
+            let graph = state.graph();
+            resolved.iter()
+                .filter(|(_, _, channels)| *channels == Some(0))
+                .filter_map(|(id, _, _)| graph.orbs.get(id).map(|orb| orb.name.clone()))
+                .collect()
+        };
+        if !formatless.is_empty() {
+            error!("Refusing cluster, no usable audio format: {:?}", formatless);
+            state.send(sender, OrbEvent::Error {
+                message: format!("Couldn't create the cluster: {} has no usable audio format", formatless.join(", ")),
+            });
+            return None;
+        }
+
+        info!("Creating cluster with {} devices: {:?}", node_names.len(), node_names);
+
+        let key = persistence::cluster_key(&node_names);
+        let prefs = Self::playing_prefs(state, &key, &members);
+
+        // 2. Route members through their downmix and delay sinks: combine -> delay -> mono -> device
+        let mut slaves = Vec::with_capacity(node_names.len());
+        let mut member_modules = Vec::new();
+        let mut mono_members = Vec::new();
+        for (member, node_name, channels) in &resolved {
+            let mut target = node_name.clone();
+
+            // Single-channel devices are downmixed unless the user said otherwise
+            let downmix = prefs.downmix.get(node_name).copied().unwrap_or(*channels == Some(1));
+            if downmix {
+                match Self::load_member_downmix(state, node_name) {
+                    Ok((mono_sink, module_id)) => {
+                        info!("✓ Downmixing {} to mono", node_name);
+                        target = mono_sink;
+                        member_modules.push(module_id);
+                        mono_members.push(*member);
                     }
-                } else {
-                    // Try to find in existing cluster members
-                    if let Some(node_name) = active_members.get(desc) {
-                        node_names.push(node_name.clone());
-                        member_pairs.push((desc.clone(), node_name.clone()));
-                    } else {
-                        warn!("Could not find Node Name for device: {}", desc);
+                    Err(e) => warn!("Failed to downmix {}, using it directly: {}", node_name, e),
+                }
+            }
+
+            match prefs.delays.get(node_name) {
+                Some(&delay_ms) if delay_ms > 0 => match Self::load_member_delay(state, &target, delay_ms) {
+                    Ok((delay_sink, modules)) => {
+                        info!("✓ Delaying {} by {} ms", node_name, delay_ms);
+                        slaves.push(delay_sink);
+                        member_modules.extend(modules);
+                    }
+                    Err(e) => {
+                        warn!("Failed to delay {}, using it directly: {}", node_name, e);
+                        slaves.push(target);
                     }
+                },
+                _ => slaves.push(target),
+            }
+        }
+
+        // 3. Create combine-sink, as wide as the members allow unless the user picked a layout
+        let member_channels: Vec<Option<u32>> = resolved.iter().map(|(_, _, channels)| *channels).collect();
+        let (channel_count, channel_map) = match prefs.channels {
+            Some(c) => match channels::validate(c, &member_channels) {
+                Ok(map) => (c, map),
+                Err(e) => {
+                    warn!("Ignoring saved layout of {} channels: {}", c, e);
+                    channels::common_layout(&member_channels)
+                }
+            },
+            None => channels::common_layout(&member_channels),
+        };
+        let format = {
+            let graph = state.graph();
+            let member_formats: Vec<AudioFormat> = members.iter()
+                .filter_map(|id| graph.orbs.get(id))
+                .map(|orb| AudioFormat::combined(&orb.formats))
+                .collect();
+            formats::negotiate_cluster_format(&member_formats)
+        };
+        info!("Cluster format: {}", format.label());
+        let rate = format.rate().unwrap_or(formats::DEFAULT_RATE);
+        let combine_name = state.fresh_sink_name(sink_names::CLUSTER);
+
+        let module_id = match state.backend.load_combine_sink(&combine_name, &slaves, rate, channel_count, channel_map) {
+            Ok(id) => id,
+            Err(e) => {
+                error!("Failed to create combine-sink: {}", e);
+                state.report_failure(sender, format!("Couldn't create the cluster: {}", e), &e);
+                for module_id in member_modules {
+                    let _ = state.backend.unload_module(module_id);
+                }
+                return None;
+            }
+        };
+        info!("✓ Cluster created (module {})", module_id);
+        state.metrics.cluster_created();
+
+        // Rebuilt from the prefs key the members would come back sorted; remember their order
+        {
+            let mut store = state.store();
+            if store.data.clusters.get(&key).map(|prefs| &prefs.order) != Some(&node_names) {
+                store.data.clusters.entry(key.clone()).or_default().order = node_names.clone();
+                store.save();
+            }
+        }
+
+        // Member volumes live on the devices themselves; put back what the user balanced them to,
+        // or balance a cluster that has none yet if that's wanted
+        let balance = prefs.volumes.is_empty() && state.store().data.settings.balance_new_clusters;
+        let mut member_volumes = HashMap::new();
+        for (member, node_name, _) in &resolved {
+            let Some(&percent) = prefs.volumes.get(node_name) else { continue };
+            match state.backend.set_sink_volume(node_name, percent) {
+                Ok(()) => {
+                    member_volumes.insert(*member, percent);
                 }
+                Err(e) => warn!("Failed to restore volume of {}: {}", node_name, e),
             }
         }
         
-        if node_names.is_empty() {
-            error!("No valid devices found for cluster");
-            return;
+        // 4. Save current default so it can be restored when the cluster goes away
+        let saved_default = state.backend.get_default_sink()
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        if let Some(def) = &saved_default {
+            info!("Saved default sink: {}", def);
         }
 
-        info!("Creating cluster with {} devices: {:?}", node_names.len(), devices);
+        // 5. Register Cluster Orb (members become hidden from the UI)
+        let (cluster_id, events) = {
+            let mut graph = state.graph();
+            let (cluster_id, mut events) = graph.create_cluster(&members, module_id, combine_name.clone(), saved_default);
+            if let Some(cluster) = graph.clusters.get_mut(&cluster_id) {
+                cluster.member_modules = member_modules;
+            }
+            if !mono_members.is_empty() {
+                events.extend(graph.set_cluster_mono(cluster_id, mono_members));
+            }
+            if !member_volumes.is_empty() {
+                events.extend(graph.set_cluster_volumes(cluster_id, member_volumes));
+            }
+            events.extend(graph.set_cluster_channels(cluster_id, channel_count));
 
-        // 2. Create combine-sink using pactl
-        // Generate a deterministic name based on sorted device names to avoid duplicates?
-        // Or just random? Random is safer for now to avoid collisions with old modules.
-        let combine_name = format!("auralis_cluster_{}", Uuid::new_v4().simple());
-        let slaves = node_names.join(",");
+            // Same devices as a cluster the user named before? Bring its name and zone back.
+            if let Some(name) = prefs.name {
+                events.extend(graph.rename_cluster(cluster_id, name));
+            }
+            if prefs.zone.is_some() {
+                events.extend(graph.set_cluster_zone(cluster_id, prefs.zone));
+            }
+            (cluster_id, events)
+        };
         
-        let output = std::process::Command::new("pactl")
-            .args(&[
-                "load-module",
-                "module-combine-sink",
-                &format!("sink_name={}", combine_name),
-                &format!("slaves={}", slaves),
-                "latency_compensate=yes",  // Enable automatic latency compensation
-                "rate=48000",               // Standard sample rate
-                "channels=2",               // Stereo
-            ])
-            .output();
-
-        match output {
-            Ok(out) => {
-                if out.status.success() {
-                    let module_id_str = String::from_utf8_lossy(&out.stdout).trim().to_string();
-                    if let Ok(module_id) = module_id_str.parse::<u32>() {
-                        info!("✓ Cluster created (module {})", module_id);
-                        
-                        // 3. Create Cluster Orb
-                        let cluster_id = Uuid::new_v4();
-                        
-                        // Store module ID mapping
-                        state.combine_modules.lock().unwrap().insert(cluster_id, module_id);
-                        
-                        // Track active members
-                        state.add_cluster_members(member_pairs);
-                        
-                            // 4. Set as Default Sink
-                        // Save current default first
-                        let current_default = std::process::Command::new("pactl")
-                            .args(&["get-default-sink"])
-                            .output()
-                            .ok()
-                            .and_then(|out| String::from_utf8(out.stdout).ok())
-                            .map(|s| s.trim().to_string());
-
-                        if let Some(def) = current_default {
-                            info!("Saved default sink: {}", def);
-                            state.saved_default_sink.lock().unwrap().insert(cluster_id, def);
-                        }
+        // 6. Set as Default Sink. While routing is paused it only gets that once resumed,
+        // and streams stay where they are.
+        if Self::routing_suspended(state) {
+            info!("Routing is paused, not making the cluster the default");
+            *state.resume_sink.lock().unwrap() = Some(combine_name.clone());
+        } else {
+            if Self::set_default_sink_or_report(state, sender, &combine_name) {
+                info!("✓ Set cluster as default sink");
+            }
 
-                        let _ = std::process::Command::new("pactl")
-                            .args(&["set-default-sink", &combine_name])
-                            .output();
-                        info!("✓ Set cluster as default sink");
-
-                        // 5. Move active streams
-                        std::thread::sleep(std::time::Duration::from_millis(200));
-                        let _ = std::process::Command::new("bash")
-                            .arg("-c")
-                            .arg(format!(
-                                "pactl list sink-inputs short | cut -f1 | xargs -I{{}} pactl move-sink-input {{}} {} 2>/dev/null || true",
-                                combine_name
-                            ))
-                            .output();
-                        
-                        // 6. Register Cluster Orb
-                        let cluster_orb = Orb {
-                            id: cluster_id,
-                            pw_id: 999, // Placeholder
-                            kind: OrbKind::Cluster { devices: devices.clone() },
-                            name: format!("Cluster ({})", devices.len()), // Simple name
-                            icon_name: "audio-card".to_string(),
-                            status: "Active".to_string(),
-                            state: OrbState::Floating,
-                            position: (0.0, 0.0),
-                            velocity: (0.0, 0.0),
-                        };
-                        
-                        state.register_orb(
-                            cluster_id, 
-                            999, 
-                            combine_name,
-                            cluster_orb.kind.clone()
-                        );
-                        
-                        let _ = sender.send(OrbEvent::Add(cluster_orb));
-                        
-                        // 7. Remove original devices from UI
-                        // 7. Remove original devices from UI
-                        // We need to find the UUIDs of the devices we just clustered to remove them from the UI
-                        {
-                            let kinds = state.orb_kinds.lock().unwrap();
-                            for desc in &devices {
-                                // Find UUID for this description (Floating only)
-                                let uuid = kinds.iter().find_map(|(u, k)| {
-                                    if let OrbKind::PhysicalSink { description } = k {
-                                        if description == desc {
-                                            return Some(*u);
-                                        }
-                                    }
-                                    None
-                                });
-                                
-                                if let Some(u) = uuid {
-                                    let _ = sender.send(OrbEvent::Remove(u));
-                                    // We do NOT remove from state here. We keep them in state so we can restore them later.
-                                    // They are effectively "hidden" from the UI but tracked by the backend.
-                                    // The `active_cluster_members` set prevents them from being re-added by discovery.
-                                }
-                            }
-                        }
-                        
-                    } else {
-                        error!("Failed to parse module ID");
-                    }
-                } else {
-                    let stderr = String::from_utf8_lossy(&out.stderr);
-                    error!("Failed to create combine-sink: {}", stderr);
+            // 7. Move active streams, once the server actually has the sink.
+            // Moving them earlier silently fails and the audio stays where it was.
+            if Self::wait_for_sink(state, &combine_name) {
+                Self::move_all_streams_or_report(state, sender, &combine_name);
+                // Notification sounds went along; they stay on the device that was the default
+                if state.store().data.settings.notifications_off_clusters {
+                    Self::follow_role(state, sender, media_roles::NOTIFICATION);
                 }
+            } else {
+                state.send(sender, OrbEvent::Error {
+                    message: "Cluster created, but its output never showed up. Audio is still playing on the old device.".to_string(),
+                });
             }
-            Err(e) => {
-                error!("Failed to execute pactl: {}", e);
+        }
+        
+        // Suspended by the user before: keep saving power
+        if Self::is_suspended_output(state, &key) {
+            let mut sinks = vec![combine_name.clone()];
+            sinks.extend(node_names.iter().cloned());
+            let name = state.graph().orbs.get(&cluster_id).map_or(combine_name.clone(), |o| o.name.clone());
+            Self::apply_suspend(state, sender, &name, &sinks, true);
+        }
+
+        // 8. Show cluster, hide original devices
+        state.send_events(sender, events);
+        if balance {
+            Self::balance_cluster_volumes(state, sender, cluster_id);
+        }
+        Some(cluster_id)
+    }
+
+
+    /// Wait until the registry announces `sink_name`, checking `pactl` as well in case
+    /// discovery isn't running (or is lagging). Gives up after `SINK_READY_TIMEOUT`.
+    fn wait_for_sink(state: &SharedState, sink_name: &str) -> bool {
+        let deadline = Instant::now() + SINK_READY_TIMEOUT;
+        loop {
+            let names = state.own_sinks.names.lock().unwrap();
+            if names.values().any(|n| n == sink_name) {
+                return true;
+            }
+            drop(names);
+
+            let listed = state.backend.list_sinks()
+                .map(|sinks| sinks.iter().any(|s| s.name == sink_name))
+                .unwrap_or(false);
+            if listed {
+                return true;
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                warn!("{} did not show up within {:?}", sink_name, SINK_READY_TIMEOUT);
+                return false;
             }
+            // Woken early by the announcement, otherwise poll pactl again
+            let names = state.own_sinks.names.lock().unwrap();
+            let _ = state.own_sinks.announced.wait_timeout(names, SINK_READY_POLL.min(deadline - now));
         }
     }
 
+    /// Undo a source -> output route (`target`), or every route of the source (`None`).
+    /// Removes the links, then sends a stream that plays nowhere anymore back to the default
+    /// output rather than leaving it silent. Raw source nodes (monitors, mics linked with
+    /// pw-link) aren't sink-inputs, for those removing the links is all there is.
+    fn unlink_source(state: &SharedState, sender: &Sender<OrbEvent>, source: Uuid, target: Option<Uuid>) {
+        let (app, output, links, other_routes) = {
+            let mut graph = state.graph();
+            let Some(app) = graph.orbs.get(&source).cloned() else {
+                warn!("Unknown orb for unlink: {}", source);
+                return;
+            };
+            // Links Auralis made are removed exactly, others by what they connect
+            let own_links = graph.forget_own_links(source, target);
+            let own_sinks = state.own_sinks.names.lock().unwrap();
+            let links = match target {
+                Some(_) if !own_links.is_empty() => own_links,
+                Some(target) => graph.route_links(source, target, |pw_id| own_sinks.get(&pw_id).cloned()),
+                None => graph.links_from(app.pw_id),
+            };
+            let other_routes = target.is_some_and(|target| {
+                graph.routes.iter().any(|&(app, output)| app == source && output != target)
+            });
+            (app, target.and_then(|t| graph.orbs.get(&t).cloned()), links, other_routes)
+        };
+        if !matches!(app.kind, OrbKind::ApplicationSource { .. }) {
+            warn!("{} is not an app", app.name);
+            return;
+        }
+        let what = match &output {
+            Some(output) => format!("{} playing to {}", app.name, output.name),
+            None => app.name.clone(),
+        };
+        if links.is_empty() && target.is_some() {
+            warn!("Nothing to unlink for {}", what);
+            return;
+        }
 
-    fn link_source_to_sink(state: &SharedState, source: Uuid, sink: Uuid) {
-        let names = state.orb_names.lock().unwrap();
-        let src_name = names.get(&source).map(|s| s.as_str()).unwrap_or("source");
-        let sink_name = names.get(&sink).map(|s| s.as_str()).unwrap_or("sink");
+        let errors: Vec<anyhow::Error> = links.iter().filter_map(|&link_id| state.backend.unlink(link_id).err()).collect();
+        let failed = errors.len();
+        if failed > 0 {
+            warn!("Failed to remove {} of {} links of {}", failed, links.len(), app.node_name);
+        } else if other_routes {
+            info!("✓ Unlinked {}", what);
+            return;
+        }
 
-        // Use pw-cli to link
-        let output = std::process::Command::new("pw-link")
-            .args(&[src_name, sink_name])
-            .output();
+        // pipewire-pulse uses the node ID as the sink-input index
+        let is_sink_input = state.backend.list_sink_inputs()
+            .map(|inputs| inputs.contains(&app.pw_id))
+            .unwrap_or(false);
+        let default_sink = state.backend.get_default_sink().unwrap_or_default();
+        let already_default = output.as_ref().is_some_and(|o| o.node_name == default_sink);
 
-        match output {
-            Ok(out) => {
-                if out.status.success() {
-                    info!("Linked {} -> {}", src_name, sink_name);
-                } else {
-                    let stderr = String::from_utf8_lossy(&out.stderr);
-                    error!("Failed to link: {}", stderr);
-                }
+        if !is_sink_input || default_sink.is_empty() || already_default {
+            if let Some(e) = errors.first() {
+                state.report_failure(sender, format!("Couldn't stop {}", what), e);
             }
+            return;
+        }
+        // Links made by the session manager may refuse to go, moving the stream always works
+        match Self::retry_once(|| state.backend.move_sink_input(app.pw_id, &default_sink)) {
+            Ok(()) => info!("✓ Moved {} back to {}", app.node_name, default_sink),
             Err(e) => {
-                error!("Failed to execute pw-link: {}", e);
+                error!("Failed to move {} to {}: {}", app.node_name, default_sink, e);
+                state.report_failure(sender, format!("Couldn't stop {}", what), &e);
             }
         }
     }
+
+    /// Link with pw-link and track the links it makes, so `unlink_source` removes exactly those
+    fn link_source_to_sink(state: &SharedState, sender: &Sender<OrbEvent>, source: &Orb, sink: &Orb) {
+        if let Err(e) = state.backend.link(&source.node_name, &sink.node_name) {
+            error!("Failed to link: {}", e);
+            state.report_failure(sender, format!("Couldn't play {} on {}", source.name, sink.name), &e);
+            return;
+        }
+        info!("Linked {} -> {}", source.node_name, sink.node_name);
+
+        let mut graph = state.graph();
+        match Self::output_node(state, &graph, sink) {
+            Some(node) => graph.track_own_link(source.id, sink.id, (source.pw_id, node)),
+            None => warn!("No node for {} yet, its links to {} aren't tracked", sink.name, source.name),
+        }
+    }
+
+    /// PipeWire node an app plays to for an output orb: the device's, or the cluster's
+    /// combine-sink (the cluster orb only carries a placeholder ID)
+    fn output_node(state: &SharedState, graph: &AudioGraph, output: &Orb) -> Option<u32> {
+        let Some(cluster) = graph.clusters.get(&output.id) else {
+            return Some(output.pw_id);
+        };
+        if cluster.external {
+            return Some(output.pw_id);
+        }
+        let own_sinks = state.own_sinks.names.lock().unwrap();
+        own_sinks.iter().find(|(_, name)| **name == cluster.sink_name).map(|(&pw_id, _)| pw_id)
+    }
 }
@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::persistence;
+
+/// A named set of clusters that can be activated in one go.
+/// Devices are referred to by their description, as shown in the UI.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Each entry is one cluster, listed by device description
+    #[serde(default)]
+    pub clusters: Vec<Vec<String>>,
+    /// Device (or a member of one of the clusters) to make the default sink
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_sink: Option<String>,
+}
+
+/// Contents of `profiles.toml`:
+///
+/// ```toml
+/// [profiles."Movie Night"]
+/// clusters = [["Living Room Speakers", "Subwoofer"]]
+/// default_sink = "Living Room Speakers"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profiles {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Profiles {
+    pub fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Load if the file exists, otherwise no profiles
+    pub fn load_or_default(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) if path.exists() => Self::load(path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let contents = toml::to_string_pretty(self)?;
+        std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// `$XDG_CONFIG_HOME/auralis/profiles.toml`, falling back to `~/.config`
+    pub fn default_path() -> Option<PathBuf> {
+        persistence::config_dir().map(|dir| dir.join("profiles.toml"))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+}
@@ -0,0 +1,35 @@
+use uuid::Uuid;
+
+// Every sink Auralis loads is named `PREFIX` + kind + a random suffix. Discovery hides
+// them and stale-module cleanup sweeps them by name, so a new kind of sink needs a constant
+// here and an entry in `is_own`.
+
+/// Common prefix of every sink Auralis creates (older releases used `auralis_combined_`)
+pub const PREFIX: &str = "auralis_";
+/// Combine-sink behind a cluster
+pub const CLUSTER: &str = "auralis_cluster_";
+/// Null-sink that a member's delay loopback reads from
+pub const DELAY: &str = "auralis_delay_";
+/// Remap-sink downmixing a member to mono
+pub const MONO: &str = "auralis_mono_";
+
+/// Combine-sinks of releases before the kinds above
+const LEGACY_CLUSTER: &str = "auralis_combined_";
+
+/// Whether a sink was created by Auralis (this or an older version): one of the kinds above
+/// followed by a plain suffix, so a user's own "auralis_something" sink isn't taken for ours
+pub fn is_own(sink_name: &str) -> bool {
+    [CLUSTER, DELAY, MONO, LEGACY_CLUSTER].iter()
+        .filter_map(|kind| sink_name.strip_prefix(kind))
+        .any(|suffix| !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// A name starting with `kind` that `taken` doesn't know yet
+pub fn fresh(kind: &str, taken: impl Fn(&str) -> bool) -> String {
+    loop {
+        let name = format!("{}{}", kind, Uuid::new_v4().simple());
+        if !taken(&name) {
+            return name;
+        }
+    }
+}
@@ -0,0 +1,978 @@
+use anyhow::{anyhow, bail, Result};
+use auralis_net::webrtc::{BeamConfig, BeamSession, BeamState};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::runtime::Runtime;
+use tracing::{error, info, warn};
+use crate::formats::{parse_pw_dump, NodeFormats};
+use crate::persistence::TurnServer;
+
+/// A pactl/pw-link call that takes longer than this is killed and reported as failed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tools checked for at startup, see `SystemAudio::missing_tools`
+pub const REQUIRED_TOOLS: [&str; 4] = ["pactl", "pw-link", "pw-cli", "pw-dump"];
+
+/// pactl subcommands that only read, still run on a remote without a pulse server (they answer
+/// for the default one, see `PactlBackend::with_pulse_server`)
+const PACTL_QUERIES: [&str; 3] = ["list", "get-default-sink", "get-sink-volume"];
+
+/// Hears the connection states of a beam started with `SystemAudio::start_beam`. Never called
+/// on a GStreamer streaming thread, so it may stop the beam.
+pub type BeamCallback = Box<dyn Fn(BeamState) + Send + Sync>;
+
+/// An external tool that isn't installed. Found in the error chain of any call that needed it,
+/// see `MissingTool::find`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingTool {
+    pub program: String,
+}
+
+impl MissingTool {
+    /// Package shipping the tool, as Fedora names it
+    pub fn package(&self) -> &'static str {
+        match self.program.as_str() {
+            "pactl" => "pulseaudio-utils",
+            "gst-launch-1.0" => "gstreamer1",
+            _ => "pipewire-utils",
+        }
+    }
+
+    /// The missing tool behind `error`, if that's why it failed
+    pub fn find(error: &anyhow::Error) -> Option<&MissingTool> {
+        error.chain().find_map(|e| e.downcast_ref::<MissingTool>())
+    }
+}
+
+impl fmt::Display for MissingTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} not found; install {}", self.program, self.package())
+    }
+}
+
+impl std::error::Error for MissingTool {}
+
+/// Whether `program` is an executable file in one of the `PATH` directories
+fn on_path(program: &str) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        std::fs::metadata(dir.join(program)).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    })
+}
+
+/// A loaded PulseAudio/PipeWire module as reported by `pactl list modules short`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleInfo {
+    pub id: u32,
+    pub name: String,
+    pub args: String,
+}
+
+impl ModuleInfo {
+    /// Value of a `key=value` module argument, without surrounding quotes
+    pub fn arg(&self, key: &str) -> Option<&str> {
+        self.args.split_whitespace()
+            .find_map(|arg| arg.strip_prefix(key)?.strip_prefix('='))
+            .map(|value| value.trim_matches(|c| c == '"' || c == '\''))
+    }
+}
+
+/// A sink as reported by `pactl list sinks short`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SinkInfo {
+    pub id: u32,
+    pub name: String,
+}
+
+/// Every side effect the core has on the system audio server goes through this trait.
+/// `PactlBackend` talks to the real server, `MockBackend` records calls for tests.
+pub trait SystemAudio: Send + Sync {
+    /// Load a module-combine-sink running at `rate` Hz and return its module ID
+    fn load_combine_sink(&self, sink_name: &str, slaves: &[String], rate: u32, channels: u32, channel_map: &str) -> Result<u32>;
+    /// Load a module-null-sink (used for mock devices) and return its module ID
+    fn load_null_sink(&self, sink_name: &str, description: &str) -> Result<u32>;
+    /// Load a module-loopback from `source` into `sink` with the given latency and return its module ID
+    fn load_loopback(&self, source: &str, sink: &str, latency_ms: u32) -> Result<u32>;
+    /// Sink with its own channel map (e.g. "mono") that plays into `master`
+    fn load_remap_sink(&self, sink_name: &str, master: &str, channel_map: &str) -> Result<u32>;
+    fn unload_module(&self, module_id: u32) -> Result<()>;
+    fn list_modules(&self) -> Result<Vec<ModuleInfo>>;
+    fn list_sinks(&self) -> Result<Vec<SinkInfo>>;
+    /// Current latency of a sink as reported by the server, in milliseconds
+    fn sink_latency_ms(&self, sink_name: &str) -> Result<u32>;
+    /// Formats a node offers and the one it's using, from its params
+    fn node_formats(&self, node_id: u32) -> Result<NodeFormats>;
+    /// IDs of all active sink-inputs (streams)
+    fn list_sink_inputs(&self) -> Result<Vec<u32>>;
+    /// IDs of the sink-inputs currently playing to the given sink
+    fn list_sink_inputs_on(&self, sink_name: &str) -> Result<Vec<u32>>;
+    fn move_sink_input(&self, input_id: u32, sink_name: &str) -> Result<()>;
+    fn get_default_sink(&self) -> Result<String>;
+    fn set_default_sink(&self, sink_name: &str) -> Result<()>;
+    fn toggle_sink_mute(&self, sink_name: &str) -> Result<()>;
+    /// Suspend a sink so the device can power down, or wake it up again
+    fn suspend_sink(&self, sink_name: &str, suspend: bool) -> Result<()>;
+    /// Set a sink's volume on all channels, 100 being unamplified
+    fn set_sink_volume(&self, sink_name: &str, percent: u32) -> Result<()>;
+    /// A sink's volume, its channels averaged, 100 being unamplified
+    fn sink_volume(&self, sink_name: &str) -> Result<u32>;
+    /// Link an output port/node to an input port/node (pw-link)
+    fn link(&self, output: &str, input: &str) -> Result<()>;
+    /// Remove a link by its PipeWire ID (pw-link -d)
+    fn unlink(&self, link_id: u32) -> Result<()>;
+    /// Play a test tone straight to the given node, blocking until it has finished
+    fn play_test_tone(&self, node_name: &str, duration_ms: u32) -> Result<()>;
+    /// Play `id` from the desktop's sound theme (e.g. "complete"), blocking until it has finished
+    fn play_sound(&self, id: &str, description: &str) -> Result<()>;
+    /// Which of `REQUIRED_TOOLS` aren't installed
+    fn missing_tools(&self) -> Vec<MissingTool>;
+    /// Stream what plays on `sink_name` to a peer as beam `session_id`, relayed through `turn`
+    /// if set. Fails with `MissingPlugins` (auralis-net) if GStreamer lacks an element of it.
+    fn start_beam(&self, session_id: &str, sink_name: &str, turn: Option<&TurnServer>, on_state: BeamCallback) -> Result<()>;
+    /// Stop a beam started with `start_beam`; unknown sessions are ignored
+    fn stop_beam(&self, session_id: &str);
+
+    /// Move every active sink-input to the given sink. Returns the ones that couldn't be moved;
+    /// streams can vanish between listing and moving, so some failures are expected.
+    fn move_all_sink_inputs(&self, sink_name: &str) -> Vec<u32> {
+        let inputs = match self.list_sink_inputs() {
+            Ok(inputs) => inputs,
+            Err(e) => {
+                error!("Failed to list sink-inputs: {}", e);
+                return Vec::new();
+            }
+        };
+
+        inputs.into_iter()
+            .filter(|input_id| self.move_sink_input(*input_id, sink_name).is_err())
+            .collect()
+    }
+
+    /// Move the sink-inputs playing to `from` over to `to`, leaving other streams alone
+    fn move_sink_inputs_between(&self, from: &str, to: &str) -> usize {
+        let inputs = match self.list_sink_inputs_on(from) {
+            Ok(inputs) => inputs,
+            Err(e) => {
+                error!("Failed to list sink-inputs on {}: {}", from, e);
+                return 0;
+            }
+        };
+
+        inputs.into_iter()
+            .filter(|input_id| self.move_sink_input(*input_id, to).is_ok())
+            .count()
+    }
+}
+
+// `pactl load-module` arguments of the modules Auralis loads. `PactlBackend` runs them,
+// `Cluster::to_shell_script` writes them out for users to run themselves.
+
+/// Combine-sink playing to all of `slaves`
+pub fn combine_sink_args(sink_name: &str, slaves: &[String], rate: u32, channels: u32, channel_map: &str) -> Vec<String> {
+    vec![
+        "load-module".to_string(),
+        "module-combine-sink".to_string(),
+        format!("sink_name={}", sink_name),
+        format!("slaves={}", slaves.join(",")),
+        "latency_compensate=yes".to_string(), // Enable automatic latency compensation
+        format!("rate={}", rate),              // Negotiated, see `formats::negotiate_cluster_format`
+        format!("channels={}", channels),
+        format!("channel_map={}", channel_map),
+    ]
+}
+
+pub fn null_sink_args(sink_name: &str, description: &str) -> Vec<String> {
+    vec![
+        "load-module".to_string(),
+        "module-null-sink".to_string(),
+        format!("sink_name={}", sink_name),
+        format!("sink_properties=device.description={}", description),
+    ]
+}
+
+pub fn loopback_args(source: &str, sink: &str, latency_ms: u32) -> Vec<String> {
+    vec![
+        "load-module".to_string(),
+        "module-loopback".to_string(),
+        format!("source={}", source),
+        format!("sink={}", sink),
+        format!("latency_msec={}", latency_ms),
+        "source_dont_move=true".to_string(),
+        "sink_dont_move=true".to_string(),
+    ]
+}
+
+pub fn remap_sink_args(sink_name: &str, master: &str, channel_map: &str) -> Vec<String> {
+    vec![
+        "load-module".to_string(),
+        "module-remap-sink".to_string(),
+        format!("sink_name={}", sink_name),
+        format!("master={}", master),
+        format!("channels={}", channel_map.split(',').count()),
+        format!("channel_map={}", channel_map),
+        "remix=yes".to_string(), // Mix stereo input down instead of dropping channels
+    ]
+}
+
+/// `pactl` with `args`, quoted for a POSIX shell
+pub fn pactl_command_line(args: &[String]) -> String {
+    let quoted: Vec<String> = args.iter()
+        .map(|arg| {
+            let safe = !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_.,=/:@%+".contains(c));
+            if safe { arg.clone() } else { format!("'{}'", arg.replace('\'', r"'\''")) }
+        })
+        .collect();
+    format!("pactl {}", quoted.join(" "))
+}
+
+// `pactl ... short` output is tab-separated; names and arguments may contain spaces,
+// so columns are split on tabs only. The commands run with LC_ALL=C (see `run_command`).
+
+/// Parse the first column of `pactl ... short` output as IDs
+pub fn parse_short_ids(stdout: &str) -> Vec<u32> {
+    stdout
+        .lines()
+        .filter_map(|line| line.split('\t').next())
+        .filter_map(|id| id.trim().parse().ok())
+        .collect()
+}
+
+/// `pactl list modules short`, e.g. "536870932\tmodule-combine-sink\tsink_name=... slaves=..."
+pub fn parse_modules_short(stdout: &str) -> Vec<ModuleInfo> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let id = parts.next()?.trim().parse().ok()?;
+            let name = parts.next()?.trim().to_string();
+            let args = parts.next().unwrap_or("").trim().to_string();
+            Some(ModuleInfo { id, name, args })
+        })
+        .collect()
+}
+
+/// `pactl list sinks short`, e.g. "57\talsa_output.pci-0000_00_1f.3.analog-stereo\tPipeWire\ts32le 2ch 48000Hz\tSUSPENDED"
+pub fn parse_sinks_short(stdout: &str) -> Vec<SinkInfo> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\t');
+            let id = parts.next()?.trim().parse().ok()?;
+            let name = parts.next()?.trim().to_string();
+            Some(SinkInfo { id, name })
+        })
+        .collect()
+}
+
+/// `pactl list sink-inputs short` as (sink-input, sink) index pairs,
+/// e.g. "104\t57\t95\tPipeWire\tfloat32le 2ch 48000Hz"
+pub fn parse_sink_inputs_short(stdout: &str) -> Vec<(u32, u32)> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\t');
+            let id = parts.next()?.trim().parse().ok()?;
+            let sink_id = parts.next()?.trim().parse().ok()?;
+            Some((id, sink_id))
+        })
+        .collect()
+}
+
+/// ID of a module that was just loaded. The module listing is trusted over the ID pactl
+/// printed, since the latter's format isn't guaranteed; the newest instance wins.
+pub fn resolve_module_id(modules: &[ModuleInfo], module: &str, identifying_arg: &str, stdout: &str) -> Result<u32> {
+    let listed = identifying_arg.split_once('=').and_then(|(key, value)| {
+        modules.iter()
+            .filter(|m| m.name == module && m.arg(key) == Some(value))
+            .map(|m| m.id)
+            .max()
+    });
+    if let Some(id) = listed {
+        return Ok(id);
+    }
+
+    match stdout.trim().parse::<u32>() {
+        Ok(id) => Ok(id),
+        Err(_) => bail!(
+            "{} ({}) may have been loaded, but its module ID is unknown (pactl said {:?}); Reset Audio Routing removes it",
+            module, identifying_arg, stdout.trim()
+        ),
+    }
+}
+
+/// Find the `Latency: N usec` line of the named sink in `pactl list sinks` output
+pub fn parse_sink_latency_usec(stdout: &str, sink_name: &str) -> Option<u64> {
+    let mut in_sink = false;
+    for line in stdout.lines() {
+        let line = line.trim();
+        if line.starts_with("Sink #") {
+            in_sink = false;
+        } else if let Some(name) = line.strip_prefix("Name:") {
+            in_sink = name.trim() == sink_name;
+        } else if in_sink {
+            // "Latency: 23219 usec, configured 40000 usec"
+            if let Some(rest) = line.strip_prefix("Latency:") {
+                return rest.split_whitespace().next()?.parse().ok();
+            }
+        }
+    }
+    None
+}
+
+/// Volume in `pactl get-sink-volume` output, the channels' percentages averaged
+pub fn parse_sink_volume_percent(stdout: &str) -> Option<u32> {
+    // "Volume: front-left: 42598 /  65% / -11.23 dB,   front-right: 42598 /  65% / -11.23 dB"
+    let line = stdout.lines().map(str::trim).find(|line| line.starts_with("Volume:"))?;
+    let percents: Vec<u32> = line.split_whitespace()
+        .filter_map(|word| word.strip_suffix('%')?.parse().ok())
+        .collect();
+    if percents.is_empty() {
+        return None;
+    }
+    let sum: u32 = percents.iter().sum();
+    Some((sum as f64 / percents.len() as f64).round() as u32)
+}
+
+/// Real backend that shells out to `pactl` and `pw-link`
+pub struct PactlBackend {
+    dry_run: bool,
+    /// PipeWire remote the pw-* tools talk to, `None` for their default
+    remote: Option<String>,
+    /// `PULSE_SERVER` for pactl, the pipewire-pulse of `remote`
+    pulse_server: Option<String>,
+    next_dry_run_module: Mutex<u32>,
+    /// Running beams by session ID, see `start_beam`
+    beams: Mutex<HashMap<String, BeamSession>>,
+    /// Runs the child processes, so a hung pactl can be timed out and killed.
+    /// Only `None` while being dropped.
+    runtime: Option<Runtime>,
+}
+
+impl PactlBackend {
+    pub fn new(dry_run: bool) -> Self {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("auralis-pactl")
+            .enable_all()
+            .build()
+            .expect("Failed to start the command runtime");
+
+        Self {
+            dry_run,
+            remote: None,
+            pulse_server: None,
+            // Fake module IDs handed out by `load-module` in dry-run mode
+            next_dry_run_module: Mutex::new(900_000),
+            beams: Mutex::new(HashMap::new()),
+            runtime: Some(runtime),
+        }
+    }
+
+    /// Point pw-link and pw-dump at `remote` (see `AuralisConfig::pipewire_remote`).
+    /// pactl goes through pipewire-pulse instead, see `with_pulse_server`.
+    pub fn with_remote(mut self, remote: Option<String>) -> Self {
+        self.remote = remote.filter(|r| !r.trim().is_empty());
+        self
+    }
+
+    /// Point pactl at `server`, the pipewire-pulse of the remote. With a remote but no server
+    /// pactl would change the default instance, so only its queries run.
+    pub fn with_pulse_server(mut self, server: Option<String>) -> Self {
+        self.pulse_server = server.filter(|s| !s.trim().is_empty());
+        self
+    }
+
+    /// Single place where external commands are spawned. Returns stdout on success.
+    /// In dry-run mode the command is only logged and a canned success is returned.
+    fn run_command(&self, program: &str, args: &[&str]) -> Result<String> {
+        if self.dry_run {
+            info!("🧪 [DRY-RUN] {} {}", program, args.join(" "));
+            if program == "pactl" && args.first() == Some(&"load-module") {
+                // Callers parse the module ID from stdout, so hand out a fake one
+                let mut next = self.next_dry_run_module.lock().unwrap();
+                *next += 1;
+                return Ok(format!("{}\n", next));
+            }
+            return Ok(String::new());
+        }
+
+        let command_line = format!("{} {}", program, args.join(" "));
+        let mut command = Command::new(program);
+        // Output gets parsed, so it must not be translated or reformatted per locale
+        command.args(args).env("LC_ALL", "C").kill_on_drop(true);
+        if let (Some(server), "pactl") = (&self.pulse_server, program) {
+            command.env("PULSE_SERVER", server);
+        }
+
+        // Spawned rather than block_on'd: callers may themselves be inside a tokio runtime (the CLI)
+        let (tx, rx) = mpsc::channel();
+        let runtime = self.runtime.as_ref().ok_or_else(|| anyhow!("Backend is shutting down"))?;
+        runtime.spawn(async move {
+            let _ = tx.send(tokio::time::timeout(COMMAND_TIMEOUT, command.output()).await);
+        });
+
+        let out = match rx.recv() {
+            Ok(Ok(Err(e))) if e.kind() == io::ErrorKind::NotFound => {
+                error!("❓ [PACTL] {} is not installed", program);
+                return Err(MissingTool { program: program.to_string() }.into());
+            }
+            Ok(Ok(out)) => out?,
+            Ok(Err(_)) => {
+                warn!("⏱️ [PACTL] Gave up on `{}` after {:?}", command_line, COMMAND_TIMEOUT);
+                bail!("{} timed out after {:?}", command_line, COMMAND_TIMEOUT);
+            }
+            Err(_) => bail!("{} was dropped before it finished", command_line),
+        };
+        if !out.status.success() {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            bail!("{} failed: {}", command_line, stderr.trim());
+        }
+        Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+    }
+
+    fn run_pactl(&self, args: &[&str]) -> Result<String> {
+        if let (Some(remote), None) = (&self.remote, &self.pulse_server) {
+            if !self.dry_run && !args.first().is_some_and(|a| PACTL_QUERIES.contains(a)) {
+                bail!("pactl {} would change the default PulseAudio server, not remote {}; give its pipewire-pulse server (PULSE_SERVER or --pulse-server)",
+                    args.join(" "), remote);
+            }
+        }
+        self.run_command("pactl", args)
+    }
+
+    /// A pw-* tool, on the configured remote
+    fn run_pw(&self, program: &str, args: &[&str]) -> Result<String> {
+        let Some(remote) = &self.remote else {
+            return self.run_command(program, args);
+        };
+        let remote = format!("--remote={}", remote);
+        let args: Vec<&str> = std::iter::once(remote.as_str()).chain(args.iter().copied()).collect();
+        self.run_command(program, &args)
+    }
+
+    /// `args` is `["load-module", <module>, <identifying arg>, ...]`, where the identifying
+    /// arg (`sink_name=...`, `source=...`) is unique to this instance
+    fn load_module(&self, args: &[String]) -> Result<u32> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let stdout = self.run_pactl(&args)?;
+        // Still loaded if this fails, so it's found through the listing as usual
+        let modules = self.list_modules().unwrap_or_else(|e| {
+            warn!("Failed to list modules after loading {}: {}", args[1], e);
+            Vec::new()
+        });
+        resolve_module_id(&modules, args[1], args[2], &stdout)
+    }
+}
+
+impl Drop for PactlBackend {
+    fn drop(&mut self) {
+        // A plain drop panics when it happens inside another runtime
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl SystemAudio for PactlBackend {
+    fn load_combine_sink(&self, sink_name: &str, slaves: &[String], rate: u32, channels: u32, channel_map: &str) -> Result<u32> {
+        self.load_module(&combine_sink_args(sink_name, slaves, rate, channels, channel_map))
+    }
+
+    fn load_null_sink(&self, sink_name: &str, description: &str) -> Result<u32> {
+        self.load_module(&null_sink_args(sink_name, description))
+    }
+
+    fn load_loopback(&self, source: &str, sink: &str, latency_ms: u32) -> Result<u32> {
+        self.load_module(&loopback_args(source, sink, latency_ms))
+    }
+
+    fn load_remap_sink(&self, sink_name: &str, master: &str, channel_map: &str) -> Result<u32> {
+        self.load_module(&remap_sink_args(sink_name, master, channel_map))
+    }
+
+    fn unload_module(&self, module_id: u32) -> Result<()> {
+        self.run_pactl(&["unload-module", &module_id.to_string()])?;
+        Ok(())
+    }
+
+    fn list_modules(&self) -> Result<Vec<ModuleInfo>> {
+        let stdout = self.run_pactl(&["list", "modules", "short"])?;
+        Ok(parse_modules_short(&stdout))
+    }
+
+    fn list_sinks(&self) -> Result<Vec<SinkInfo>> {
+        let stdout = self.run_pactl(&["list", "sinks", "short"])?;
+        Ok(parse_sinks_short(&stdout))
+    }
+
+    fn sink_latency_ms(&self, sink_name: &str) -> Result<u32> {
+        let stdout = self.run_pactl(&["list", "sinks"])?;
+        match parse_sink_latency_usec(&stdout, sink_name) {
+            Some(usec) => Ok(((usec + 500) / 1000) as u32),
+            None => bail!("No latency reported for sink {}", sink_name),
+        }
+    }
+
+    fn node_formats(&self, node_id: u32) -> Result<NodeFormats> {
+        let stdout = self.run_pw("pw-dump", &[&node_id.to_string()])?;
+        parse_pw_dump(&stdout, node_id)
+    }
+
+    fn list_sink_inputs(&self) -> Result<Vec<u32>> {
+        let stdout = self.run_pactl(&["list", "sink-inputs", "short"])?;
+        Ok(parse_short_ids(&stdout))
+    }
+
+    fn list_sink_inputs_on(&self, sink_name: &str) -> Result<Vec<u32>> {
+        // sink-inputs only report the sink index, so resolve the name first
+        let Some(sink) = self.list_sinks()?.into_iter().find(|s| s.name == sink_name) else {
+            bail!("No such sink: {}", sink_name);
+        };
+
+        let stdout = self.run_pactl(&["list", "sink-inputs", "short"])?;
+        Ok(parse_sink_inputs_short(&stdout)
+            .into_iter()
+            .filter_map(|(id, sink_id)| (sink_id == sink.id).then_some(id))
+            .collect())
+    }
+
+    fn move_sink_input(&self, input_id: u32, sink_name: &str) -> Result<()> {
+        self.run_pactl(&["move-sink-input", &input_id.to_string(), sink_name])?;
+        Ok(())
+    }
+
+    fn get_default_sink(&self) -> Result<String> {
+        Ok(self.run_pactl(&["get-default-sink"])?.trim().to_string())
+    }
+
+    fn set_default_sink(&self, sink_name: &str) -> Result<()> {
+        self.run_pactl(&["set-default-sink", sink_name])?;
+        Ok(())
+    }
+
+    fn toggle_sink_mute(&self, sink_name: &str) -> Result<()> {
+        self.run_pactl(&["set-sink-mute", sink_name, "toggle"])?;
+        Ok(())
+    }
+
+    fn suspend_sink(&self, sink_name: &str, suspend: bool) -> Result<()> {
+        self.run_pactl(&["suspend-sink", sink_name, if suspend { "1" } else { "0" }])?;
+        Ok(())
+    }
+
+    fn set_sink_volume(&self, sink_name: &str, percent: u32) -> Result<()> {
+        self.run_pactl(&["set-sink-volume", sink_name, &format!("{}%", percent)])?;
+        Ok(())
+    }
+
+    fn sink_volume(&self, sink_name: &str) -> Result<u32> {
+        let stdout = self.run_pactl(&["get-sink-volume", sink_name])?;
+        parse_sink_volume_percent(&stdout).ok_or_else(|| anyhow!("No volume reported for sink {}", sink_name))
+    }
+
+    fn link(&self, output: &str, input: &str) -> Result<()> {
+        self.run_pw("pw-link", &[output, input])?;
+        Ok(())
+    }
+
+    fn unlink(&self, link_id: u32) -> Result<()> {
+        self.run_pw("pw-link", &["-d", &link_id.to_string()])?;
+        Ok(())
+    }
+
+    fn play_test_tone(&self, node_name: &str, duration_ms: u32) -> Result<()> {
+        // 4800 samples at 48 kHz = 100 ms per buffer
+        let buffers = (duration_ms / 100).max(1);
+        self.run_command("gst-launch-1.0", &[
+            "-q",
+            "audiotestsrc", &format!("num-buffers={}", buffers), "samplesperbuffer=4800", "freq=660",
+            "!", "audio/x-raw,rate=48000",
+            "!", "audioconvert",
+            "!", "pipewiresink", &format!("target-object={}", node_name),
+        ])?;
+        Ok(())
+    }
+
+    fn play_sound(&self, id: &str, description: &str) -> Result<()> {
+        self.run_command("canberra-gtk-play", &["--id", id, "--description", description])?;
+        Ok(())
+    }
+
+    /// Nothing is run in dry-run mode, so nothing is missing
+    fn missing_tools(&self) -> Vec<MissingTool> {
+        if self.dry_run {
+            return Vec::new();
+        }
+        REQUIRED_TOOLS.iter()
+            .filter(|program| !on_path(program))
+            .map(|program| MissingTool { program: program.to_string() })
+            .collect()
+    }
+
+    fn start_beam(&self, session_id: &str, sink_name: &str, turn: Option<&TurnServer>, on_state: BeamCallback) -> Result<()> {
+        if self.dry_run {
+            info!("🧪 [DRY-RUN] beam {} of {}", session_id, sink_name);
+            return Ok(());
+        }
+        let mut config = BeamConfig::default();
+        if let Some(turn) = turn {
+            config.turn_url = Some(turn.url.clone());
+            config.username = turn.username.clone();
+            config.credential = turn.credential.clone();
+        }
+        // Fails with `MissingPlugins` (what to install) if GStreamer lacks an element of it
+        let session = BeamSession::for_sink(session_id, sink_name, &config)?;
+        let on_state = Arc::new(on_state);
+        session.on_state_change(move |beam_state| {
+            // A streaming thread can't stop its own pipeline, and the callback may
+            let on_state = on_state.clone();
+            thread::spawn(move || (*on_state)(beam_state));
+        });
+        session.start()?;
+        self.beams.lock().unwrap().insert(session_id.to_string(), session);
+        Ok(())
+    }
+
+    fn stop_beam(&self, session_id: &str) {
+        let Some(session) = self.beams.lock().unwrap().remove(session_id) else { return };
+        if let Err(e) = session.stop() {
+            warn!("Failed to stop beam {}: {}", session_id, e);
+        }
+    }
+}
+
+/// A side effect recorded by `MockBackend`
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackendCall {
+    LoadCombineSink { sink_name: String, slaves: Vec<String>, rate: u32, channels: u32, channel_map: String },
+    LoadNullSink { sink_name: String, description: String },
+    LoadLoopback { source: String, sink: String, latency_ms: u32 },
+    LoadRemapSink { sink_name: String, master: String, channel_map: String },
+    UnloadModule(u32),
+    MoveSinkInput { input_id: u32, sink_name: String },
+    SetDefaultSink(String),
+    ToggleSinkMute(String),
+    SuspendSink { sink_name: String, suspend: bool },
+    SetSinkVolume { sink_name: String, percent: u32 },
+    Link { output: String, input: String },
+    Unlink(u32),
+    PlayTestTone { node_name: String, duration_ms: u32 },
+    PlaySound(String),
+    StartBeam { session_id: String, sink_name: String },
+    StopBeam(String),
+}
+
+/// In-memory backend for tests. Records every mutating call and keeps just enough
+/// state (modules, default sink, sink-inputs) to answer queries consistently.
+#[derive(Default)]
+pub struct MockBackend {
+    calls: Mutex<Vec<BackendCall>>,
+    modules: Mutex<Vec<ModuleInfo>>,
+    sinks: Mutex<Vec<SinkInfo>>,
+    latencies: Mutex<HashMap<String, u32>>,
+    volumes: Mutex<HashMap<String, u32>>,
+    formats: Mutex<HashMap<u32, NodeFormats>>,
+    sink_inputs: Mutex<Vec<u32>>,
+    sink_inputs_by_sink: Mutex<HashMap<String, Vec<u32>>>,
+    default_sink: Mutex<String>,
+    next_module_id: Mutex<u32>,
+    /// Remaining set-default-sink/move-sink-input calls that fail, see `refuse_sink_switches`
+    refused_switches: Mutex<u32>,
+    /// Tools that aren't installed, see `uninstall`
+    missing: Mutex<Vec<String>>,
+    /// State callbacks of the running beams, see `set_beam_state`
+    beams: Mutex<HashMap<String, Arc<BeamCallback>>>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All mutating calls made so far, in order
+    pub fn calls(&self) -> Vec<BackendCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    pub fn clear_calls(&self) {
+        self.calls.lock().unwrap().clear();
+    }
+
+    pub fn set_sinks(&self, sinks: Vec<SinkInfo>) {
+        *self.sinks.lock().unwrap() = sinks;
+    }
+
+    pub fn set_sink_latency(&self, sink_name: &str, latency_ms: u32) {
+        self.latencies.lock().unwrap().insert(sink_name.to_string(), latency_ms);
+    }
+
+    /// What `sink_volume` reports for a sink, until it's set through `set_sink_volume`
+    pub fn set_volume_of(&self, sink_name: &str, percent: u32) {
+        self.volumes.lock().unwrap().insert(sink_name.to_string(), percent);
+    }
+
+    pub fn set_node_formats(&self, node_id: u32, formats: NodeFormats) {
+        self.formats.lock().unwrap().insert(node_id, formats);
+    }
+
+    pub fn set_sink_inputs(&self, inputs: Vec<u32>) {
+        *self.sink_inputs.lock().unwrap() = inputs;
+    }
+
+    /// Streams playing to one specific sink (see `list_sink_inputs_on`)
+    pub fn set_sink_inputs_on(&self, sink_name: &str, inputs: Vec<u32>) {
+        self.sink_inputs_by_sink.lock().unwrap().insert(sink_name.to_string(), inputs);
+    }
+
+    pub fn set_default_sink_name(&self, name: &str) {
+        *self.default_sink.lock().unwrap() = name.to_string();
+    }
+
+    /// Make the next `times` set-default-sink and move-sink-input calls fail,
+    /// like they do for a sink that isn't ready yet
+    pub fn refuse_sink_switches(&self, times: u32) {
+        *self.refused_switches.lock().unwrap() = times;
+    }
+
+    /// Act as if `program` weren't installed: it's reported by `missing_tools`, and the
+    /// calls running it (link and unlink for pw-link) fail without being recorded
+    pub fn uninstall(&self, program: &str) {
+        self.missing.lock().unwrap().push(program.to_string());
+    }
+
+    fn require(&self, program: &str) -> Result<()> {
+        match self.missing.lock().unwrap().iter().any(|p| p == program) {
+            true => Err(MissingTool { program: program.to_string() }.into()),
+            false => Ok(()),
+        }
+    }
+
+    /// Consume one refusal, if any are left
+    fn refuse_switch(&self) -> Result<()> {
+        let mut refused = self.refused_switches.lock().unwrap();
+        if *refused > 0 {
+            *refused -= 1;
+            bail!("Sink not ready");
+        }
+        Ok(())
+    }
+
+    /// Act as if beam `session_id` went to `beam_state`, like its session would report it
+    pub fn set_beam_state(&self, session_id: &str, beam_state: BeamState) {
+        let on_state = self.beams.lock().unwrap().get(session_id).cloned();
+        if let Some(on_state) = on_state {
+            (*on_state)(beam_state);
+        }
+    }
+
+    pub fn add_module(&self, module: ModuleInfo) {
+        self.modules.lock().unwrap().push(module);
+    }
+
+    fn record(&self, call: BackendCall) {
+        self.calls.lock().unwrap().push(call);
+    }
+
+    fn load_module(&self, name: &str, args: String) -> u32 {
+        let mut next = self.next_module_id.lock().unwrap();
+        *next += 1;
+        self.modules.lock().unwrap().push(ModuleInfo { id: *next, name: name.to_string(), args });
+        *next
+    }
+}
+
+impl SystemAudio for MockBackend {
+    fn load_combine_sink(&self, sink_name: &str, slaves: &[String], rate: u32, channels: u32, channel_map: &str) -> Result<u32> {
+        self.record(BackendCall::LoadCombineSink {
+            sink_name: sink_name.to_string(),
+            slaves: slaves.to_vec(),
+            rate,
+            channels,
+            channel_map: channel_map.to_string(),
+        });
+        let args = format!("sink_name={} slaves={}", sink_name, slaves.join(","));
+        Ok(self.load_module("module-combine-sink", args))
+    }
+
+    fn load_null_sink(&self, sink_name: &str, description: &str) -> Result<u32> {
+        self.record(BackendCall::LoadNullSink {
+            sink_name: sink_name.to_string(),
+            description: description.to_string(),
+        });
+        Ok(self.load_module("module-null-sink", format!("sink_name={}", sink_name)))
+    }
+
+    fn load_loopback(&self, source: &str, sink: &str, latency_ms: u32) -> Result<u32> {
+        self.record(BackendCall::LoadLoopback {
+            source: source.to_string(),
+            sink: sink.to_string(),
+            latency_ms,
+        });
+        let args = format!("source={} sink={} latency_msec={}", source, sink, latency_ms);
+        Ok(self.load_module("module-loopback", args))
+    }
+
+    fn load_remap_sink(&self, sink_name: &str, master: &str, channel_map: &str) -> Result<u32> {
+        self.record(BackendCall::LoadRemapSink {
+            sink_name: sink_name.to_string(),
+            master: master.to_string(),
+            channel_map: channel_map.to_string(),
+        });
+        let args = format!("sink_name={} master={} channel_map={}", sink_name, master, channel_map);
+        Ok(self.load_module("module-remap-sink", args))
+    }
+
+    fn unload_module(&self, module_id: u32) -> Result<()> {
+        self.record(BackendCall::UnloadModule(module_id));
+        let mut modules = self.modules.lock().unwrap();
+        let before = modules.len();
+        modules.retain(|m| m.id != module_id);
+        if modules.len() == before {
+            bail!("No such module: {}", module_id);
+        }
+        Ok(())
+    }
+
+    fn list_modules(&self) -> Result<Vec<ModuleInfo>> {
+        Ok(self.modules.lock().unwrap().clone())
+    }
+
+    /// Sinks set with `set_sinks`, plus one per loaded module that creates a sink
+    fn list_sinks(&self) -> Result<Vec<SinkInfo>> {
+        let mut sinks = self.sinks.lock().unwrap().clone();
+        for module in self.modules.lock().unwrap().iter() {
+            let Some(name) = module.arg("sink_name") else { continue };
+            if !sinks.iter().any(|s| s.name == name) {
+                sinks.push(SinkInfo { id: module.id, name: name.to_string() });
+            }
+        }
+        Ok(sinks)
+    }
+
+    fn sink_latency_ms(&self, sink_name: &str) -> Result<u32> {
+        self.latencies.lock().unwrap()
+            .get(sink_name)
+            .copied()
+            .ok_or_else(|| anyhow!("No latency reported for sink {}", sink_name))
+    }
+
+    fn node_formats(&self, node_id: u32) -> Result<NodeFormats> {
+        self.formats.lock().unwrap()
+            .get(&node_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("No formats reported for node {}", node_id))
+    }
+
+    fn list_sink_inputs(&self) -> Result<Vec<u32>> {
+        Ok(self.sink_inputs.lock().unwrap().clone())
+    }
+
+    fn list_sink_inputs_on(&self, sink_name: &str) -> Result<Vec<u32>> {
+        Ok(self.sink_inputs_by_sink.lock().unwrap().get(sink_name).cloned().unwrap_or_default())
+    }
+
+    fn move_sink_input(&self, input_id: u32, sink_name: &str) -> Result<()> {
+        self.record(BackendCall::MoveSinkInput {
+            input_id,
+            sink_name: sink_name.to_string(),
+        });
+        self.refuse_switch()
+    }
+
+    fn get_default_sink(&self) -> Result<String> {
+        Ok(self.default_sink.lock().unwrap().clone())
+    }
+
+    fn set_default_sink(&self, sink_name: &str) -> Result<()> {
+        self.record(BackendCall::SetDefaultSink(sink_name.to_string()));
+        self.refuse_switch()?;
+        *self.default_sink.lock().unwrap() = sink_name.to_string();
+        Ok(())
+    }
+
+    fn toggle_sink_mute(&self, sink_name: &str) -> Result<()> {
+        self.record(BackendCall::ToggleSinkMute(sink_name.to_string()));
+        Ok(())
+    }
+
+    fn suspend_sink(&self, sink_name: &str, suspend: bool) -> Result<()> {
+        self.record(BackendCall::SuspendSink {
+            sink_name: sink_name.to_string(),
+            suspend,
+        });
+        Ok(())
+    }
+
+    fn set_sink_volume(&self, sink_name: &str, percent: u32) -> Result<()> {
+        self.record(BackendCall::SetSinkVolume {
+            sink_name: sink_name.to_string(),
+            percent,
+        });
+        self.volumes.lock().unwrap().insert(sink_name.to_string(), percent);
+        Ok(())
+    }
+
+    fn sink_volume(&self, sink_name: &str) -> Result<u32> {
+        self.volumes.lock().unwrap()
+            .get(sink_name)
+            .copied()
+            .ok_or_else(|| anyhow!("No volume reported for sink {}", sink_name))
+    }
+
+    fn link(&self, output: &str, input: &str) -> Result<()> {
+        self.require("pw-link")?;
+        self.record(BackendCall::Link {
+            output: output.to_string(),
+            input: input.to_string(),
+        });
+        Ok(())
+    }
+
+    fn unlink(&self, link_id: u32) -> Result<()> {
+        self.require("pw-link")?;
+        self.record(BackendCall::Unlink(link_id));
+        Ok(())
+    }
+
+    fn play_test_tone(&self, node_name: &str, duration_ms: u32) -> Result<()> {
+        self.record(BackendCall::PlayTestTone {
+            node_name: node_name.to_string(),
+            duration_ms,
+        });
+        Ok(())
+    }
+
+    fn play_sound(&self, id: &str, _description: &str) -> Result<()> {
+        self.record(BackendCall::PlaySound(id.to_string()));
+        Ok(())
+    }
+
+    fn missing_tools(&self) -> Vec<MissingTool> {
+        self.missing.lock().unwrap().iter()
+            .map(|program| MissingTool { program: program.clone() })
+            .collect()
+    }
+
+    fn start_beam(&self, session_id: &str, sink_name: &str, _turn: Option<&TurnServer>, on_state: BeamCallback) -> Result<()> {
+        self.record(BackendCall::StartBeam {
+            session_id: session_id.to_string(),
+            sink_name: sink_name.to_string(),
+        });
+        self.beams.lock().unwrap().insert(session_id.to_string(), Arc::new(on_state));
+        Ok(())
+    }
+
+    fn stop_beam(&self, session_id: &str) {
+        self.record(BackendCall::StopBeam(session_id.to_string()));
+        self.beams.lock().unwrap().remove(session_id);
+    }
+}
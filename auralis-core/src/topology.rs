@@ -0,0 +1,210 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use crate::graph::{AudioGraph, Orb, OrbKind};
+use crate::media_roles;
+use crate::persistence;
+
+// The routing Auralis should end up with, for `auralis-cli apply` (cron jobs, a layout kept in
+// git). Unlike a layout bundle (see `bundle`) nothing is merged: whatever Auralis manages that
+// the file doesn't list goes away, and applying the same file twice changes nothing the
+// second time.
+//
+//   {
+//     "clusters": [{"name": "Living Room", "devices": ["alsa_output.usb-speaker", "Kitchen"]}],
+//     "default_sink": "Living Room",
+//     "roles": {"Music": "Living Room", "Communication": "Headset"}
+//   }
+//
+// Devices are named by node name or by the name shown for them (alias or description). An
+// output (default sink, role) is a device or a cluster of the file by its name. Clusters made
+// by other tools ("external") are left alone, as is the default sink when the file has none.
+
+/// Desired clusters, default output and media role outputs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Topology {
+    #[serde(default)]
+    pub clusters: Vec<ClusterSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_sink: Option<String>,
+    /// Media role (one of `media_roles::ROLES`) -> output; roles not listed play on the default
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub roles: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterSpec {
+    /// Left as it is (or as remembered for these devices) when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub devices: Vec<String>,
+}
+
+/// One change that brings the current routing closer to a `Topology`. Clusters and outputs
+/// are named by key (node name, or `persistence::cluster_key` for clusters), so later steps
+/// can refer to clusters earlier ones build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Separate { cluster: String, name: String },
+    /// `devices` by node name
+    Create { devices: Vec<String>, name: Option<String> },
+    Rename { cluster: String, from: String, to: String },
+    SetDefault { output: String, name: String },
+    /// `output: None` sends the role back to the default output
+    SetRole { role: String, output: Option<String>, name: Option<String> },
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Separate { name, .. } => write!(f, "Separate '{}'", name),
+            Step::Create { devices, name: Some(name) } => write!(f, "Cluster {} as '{}'", devices.join(", "), name),
+            Step::Create { devices, name: None } => write!(f, "Cluster {}", devices.join(", ")),
+            Step::Rename { from, to, .. } => write!(f, "Rename '{}' to '{}'", from, to),
+            Step::SetDefault { name, .. } => write!(f, "Play on '{}' by default", name),
+            Step::SetRole { role, name: Some(name), .. } => write!(f, "Play {} on '{}'", role, name),
+            Step::SetRole { role, name: None, .. } => write!(f, "Play {} on the default output", role),
+        }
+    }
+}
+
+impl Topology {
+    pub fn parse(contents: &str) -> Result<Self> {
+        Ok(serde_json::from_str(contents)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// The steps from `graph` (with `role_targets` of `Settings`) to this topology, in the
+    /// order to take them: separating first frees devices the new clusters need. Nothing
+    /// when it's already in place. Fails, without any steps, if the file names a device
+    /// that isn't there or contradicts itself.
+    pub fn plan(&self, graph: &AudioGraph, role_targets: &BTreeMap<String, String>) -> Result<Vec<Step>> {
+        // Desired clusters by key, with their node names
+        let mut wanted: Vec<(String, Vec<String>, Option<String>)> = Vec::new();
+        for spec in &self.clusters {
+            let mut node_names: Vec<String> = Vec::new();
+            for device in &spec.devices {
+                let node_name = resolve_device(graph, device)?;
+                if wanted.iter().any(|(_, names, _)| names.contains(&node_name)) {
+                    bail!("'{}' is in more than one cluster", device);
+                }
+                if !node_names.contains(&node_name) {
+                    node_names.push(node_name);
+                }
+            }
+            if node_names.len() < 2 {
+                bail!("A cluster needs two devices, {:?} has {}", spec.devices, node_names.len());
+            }
+            let name = spec.name.as_ref().map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+            wanted.push((persistence::cluster_key(&node_names), node_names, name));
+        }
+
+        // Where a default sink or role should play: a cluster of the file by name, or a device
+        let output_key = |reference: &str| -> Result<String> {
+            let cluster = wanted.iter().find(|(_, _, name)| name.as_deref() == Some(reference.trim()));
+            match cluster {
+                Some((key, _, _)) => Ok(key.clone()),
+                None => resolve_device(graph, reference),
+            }
+        };
+        let default_key = self.default_sink.as_deref().map(output_key).transpose()?;
+        let mut roles: BTreeMap<&'static str, String> = BTreeMap::new();
+        for (role, output) in &self.roles {
+            let Some(canonical) = media_roles::canonical(role) else {
+                bail!("Unknown media role '{}'", role);
+            };
+            roles.insert(canonical, output_key(output)?);
+        }
+
+        let mut current: Vec<(String, &Orb)> = graph.clusters.values()
+            .filter(|cluster| !cluster.external)
+            .filter_map(|cluster| Some((graph.cluster_key(cluster.id)?, graph.orbs.get(&cluster.id)?)))
+            .collect();
+        current.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut steps = Vec::new();
+        for (key, orb) in &current {
+            if !wanted.iter().any(|(k, _, _)| k == key) {
+                steps.push(Step::Separate { cluster: key.clone(), name: orb.name.clone() });
+            }
+        }
+        for (key, node_names, name) in &wanted {
+            match current.iter().find(|(k, _)| k == key) {
+                Some((_, orb)) => {
+                    if let Some(name) = name.as_ref().filter(|&n| n != &orb.name) {
+                        steps.push(Step::Rename { cluster: key.clone(), from: orb.name.clone(), to: name.clone() });
+                    }
+                }
+                None => steps.push(Step::Create { devices: node_names.clone(), name: name.clone() }),
+            }
+        }
+
+        let output_name = |key: &str| -> String {
+            wanted.iter()
+                .find(|(k, _, _)| k == key)
+                .and_then(|(_, _, name)| name.clone())
+                .or_else(|| current.iter().find(|(k, _)| k == key).map(|(_, orb)| orb.name.clone()))
+                .unwrap_or_else(|| shown_name(graph, key))
+        };
+
+        if let Some(key) = default_key {
+            if current_default_key(graph).as_deref() != Some(key.as_str()) {
+                steps.push(Step::SetDefault { name: output_name(&key), output: key });
+            }
+        }
+        for (role, _) in media_roles::ROLES {
+            let want = roles.get(role);
+            if role_targets.get(role) != want {
+                steps.push(Step::SetRole {
+                    role: role.to_string(),
+                    output: want.cloned(),
+                    name: want.map(|key| output_name(key)),
+                });
+            }
+        }
+        Ok(steps)
+    }
+}
+
+/// Node name of the device `reference` names, by node name or as shown (alias, description)
+fn resolve_device(graph: &AudioGraph, reference: &str) -> Result<String> {
+    let reference = reference.trim();
+    let devices = || graph.orbs.values().filter(|o| matches!(o.kind, OrbKind::PhysicalSink { .. }));
+    if let Some(orb) = devices().find(|o| o.node_name == reference) {
+        return Ok(orb.node_name.clone());
+    }
+    let mut matches: Vec<&str> = devices()
+        .filter(|o| o.name == reference || matches!(&o.kind, OrbKind::PhysicalSink { description } if description == reference))
+        .map(|o| o.node_name.as_str())
+        .collect();
+    matches.sort_unstable();
+    matches.dedup();
+    match matches.as_slice() {
+        [node_name] => Ok(node_name.to_string()),
+        [] => bail!("No device '{}'", reference),
+        _ => bail!("More than one device is called '{}', name it by node name ({})", reference, matches.join(", ")),
+    }
+}
+
+/// A device's name as shown, its node name if it isn't around
+fn shown_name(graph: &AudioGraph, node_name: &str) -> String {
+    graph.find_sink(node_name).map_or_else(|| node_name.to_string(), |o| o.name.clone())
+}
+
+/// The default sink as a key: a cluster's, or the device's node name
+fn current_default_key(graph: &AudioGraph) -> Option<String> {
+    let sink_name = graph.default_sink.as_deref()?;
+    match graph.clusters.values().find(|c| c.sink_name == sink_name) {
+        Some(cluster) => graph.cluster_key(cluster.id),
+        None => Some(sink_name.to_string()),
+    }
+}
@@ -0,0 +1,2106 @@
+// Test file for command handling against the MockBackend
+// Verifies which system audio calls the core makes, without touching PipeWire
+
+mod common;
+
+use auralis_core::pipewire_client::SharedState;
+use auralis_core::system_audio::{resolve_module_id, BackendCall, ModuleInfo, SystemAudio};
+use auralis_core::formats::{AudioFormat, NodeFormats};
+use auralis_core::bundle::Bundle;
+use auralis_core::persistence::{cluster_key, validate_turn_url, ClusterPrefs, PersistedState};
+use auralis_core::{DeviceBus, MockBackend, OrbEvent, OrbKind, PactlBackend, PipeWireClient, Profiles, TurnServer, UiCommand};
+use auralis_net::webrtc::BeamState;
+use common::{orb, register_sink};
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+fn beam_kind(session_id: &str) -> OrbKind {
+    OrbKind::BeamOutput {
+        session_id: session_id.to_string(),
+        peer_url: "https://phone.local:8443".to_string(),
+        codec: "opus".to_string(),
+        bitrate_kbps: 128,
+        started_at: SystemTime::now(),
+    }
+}
+
+#[test]
+fn test_sink_sink_connect_loads_combine_sink() {
+    // Dropping one sink onto another should combine exactly those two sinks
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.speakers", "Speakers");
+    let b = register_sink(&state, 11, "bluez_output.headphones", "Headphones");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+
+    let slaves = backend.calls().into_iter().find_map(|call| match call {
+        BackendCall::LoadCombineSink { slaves, .. } => Some(slaves),
+        _ => None,
+    });
+    assert_eq!(
+        slaves,
+        Some(vec!["alsa_output.speakers".to_string(), "bluez_output.headphones".to_string()])
+    );
+}
+
+#[test]
+fn test_connect_sets_cluster_as_default_and_moves_streams() {
+    // The new combine-sink becomes the default and existing streams follow it
+    let backend = Arc::new(MockBackend::new());
+    backend.set_sink_inputs(vec![7, 8]);
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+
+    let calls = backend.calls();
+    let sink_name = match &calls[0] {
+        BackendCall::LoadCombineSink { sink_name, .. } => sink_name.clone(),
+        other => panic!("Expected LoadCombineSink first, got {:?}", other),
+    };
+    assert!(calls.contains(&BackendCall::SetDefaultSink(sink_name.clone())));
+    assert!(calls.contains(&BackendCall::MoveSinkInput { input_id: 7, sink_name: sink_name.clone() }));
+    assert!(calls.contains(&BackendCall::MoveSinkInput { input_id: 8, sink_name }));
+}
+
+#[test]
+fn test_connect_retries_default_sink_once() {
+    // A sink that isn't ready on the first try gets one more, without bothering the user
+    let backend = Arc::new(MockBackend::new());
+    backend.refuse_sink_switches(1);
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+
+    let calls = backend.calls();
+    let sink_name = match &calls[0] {
+        BackendCall::LoadCombineSink { sink_name, .. } => sink_name.clone(),
+        other => panic!("Expected LoadCombineSink first, got {:?}", other),
+    };
+    let attempts = calls.iter().filter(|c| **c == BackendCall::SetDefaultSink(sink_name.clone())).count();
+    assert_eq!(attempts, 2);
+    assert_eq!(backend.get_default_sink().unwrap(), sink_name);
+    assert!(!rx.try_iter().any(|e| matches!(e, OrbEvent::Error { .. })));
+}
+
+#[test]
+fn test_connect_reports_default_sink_and_move_failures() {
+    // When retrying doesn't help either, the user is told the audio didn't follow
+    let backend = Arc::new(MockBackend::new());
+    backend.set_sink_inputs(vec![7]);
+    backend.refuse_sink_switches(u32::MAX);
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+
+    let errors: Vec<String> = rx.try_iter()
+        .filter_map(|e| match e {
+            OrbEvent::Error { message } => Some(message),
+            _ => None,
+        })
+        .collect();
+    assert!(errors.iter().any(|m| m.starts_with("Couldn't make") && m.ends_with("the default output")), "{:?}", errors);
+    assert!(errors.iter().any(|m| m.starts_with("Couldn't move 1 stream to")), "{:?}", errors);
+}
+
+#[test]
+fn test_connect_refuses_member_without_audio_format() {
+    // A device with no channels is reported by name instead of making a broken combine-sink
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let mut ghost = orb(11, "alsa_output.ghost", OrbKind::PhysicalSink { description: "Ghost".to_string() });
+    ghost.channels = Some(0);
+    let ghost_id = ghost.id;
+    state.graph().add_orb(ghost);
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: ghost_id });
+
+    assert!(!backend.calls().iter().any(|c| matches!(c, BackendCall::LoadCombineSink { .. })));
+    assert!(state.graph().clusters.is_empty());
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    assert!(matches!(events.last(), Some(OrbEvent::Error { message }) if message.contains("alsa_output.ghost has no usable audio format")), "{:?}", events);
+}
+
+#[test]
+fn test_connect_emits_cluster_and_hides_members() {
+    // UI gets one cluster orb and removals for both member devices
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    let clusters = events
+        .iter()
+        .filter(|e| matches!(e, OrbEvent::Add(o) if matches!(o.kind, OrbKind::Cluster { .. })))
+        .count();
+    assert_eq!(clusters, 1);
+    // Members stay known to the UI, marked as belonging to the cluster
+    let member_of = |id| events.iter().find_map(|e| match e {
+        OrbEvent::Update(o) if o.id == id => o.membership(),
+        _ => None,
+    });
+    assert!(member_of(a).is_some() && member_of(a) == member_of(b));
+}
+
+#[test]
+fn test_disconnect_unloads_module_and_restores_default() {
+    // Separating a cluster unloads its module and restores the previous default sink
+    let backend = Arc::new(MockBackend::new());
+    backend.set_default_sink_name("alsa_output.a");
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster_id = rx
+        .try_iter()
+        .find_map(|e| match e {
+            OrbEvent::Add(o) if matches!(o.kind, OrbKind::Cluster { .. }) => Some(o.id),
+            _ => None,
+        })
+        .expect("cluster orb");
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: cluster_id, target: cluster_id });
+
+    let calls = backend.calls();
+    assert!(calls.iter().any(|c| matches!(c, BackendCall::UnloadModule(_))));
+    assert!(calls.contains(&BackendCall::SetDefaultSink("alsa_output.a".to_string())));
+}
+
+#[test]
+fn test_suspend_and_resume_routing_keep_cluster() {
+    // Pausing hands the default back without unloading the cluster; resuming gives it back
+    let backend = Arc::new(MockBackend::new());
+    backend.set_default_sink_name("alsa_output.a");
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster_sink = backend.get_default_sink().unwrap();
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SuspendRouting);
+    assert_eq!(backend.get_default_sink().unwrap(), "alsa_output.a");
+    assert!(!backend.calls().iter().any(|c| matches!(c, BackendCall::UnloadModule(_))));
+    assert!(state.store().data.settings.routing_suspended);
+    assert!(!PipeWireClient::wants_auto_switch(&state, "bluez_output.headset", Duration::from_secs(60)));
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ResumeRouting);
+    assert_eq!(backend.get_default_sink().unwrap(), cluster_sink);
+    assert!(!state.store().data.settings.routing_suspended);
+    assert_eq!(state.graph().clusters.len(), 1);
+    assert!(!rx.try_iter().any(|e| matches!(e, OrbEvent::Error { .. })));
+}
+
+#[test]
+fn test_cluster_created_while_suspended_waits_for_resume() {
+    // A cluster made while paused doesn't take the default until routing resumes
+    let backend = Arc::new(MockBackend::new());
+    backend.set_default_sink_name("alsa_output.a");
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SuspendRouting);
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    assert_eq!(backend.get_default_sink().unwrap(), "alsa_output.a");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ResumeRouting);
+    let cluster_sink = state.graph().clusters.values().next().map(|c| c.sink_name.clone());
+    assert_eq!(backend.get_default_sink().ok(), cluster_sink);
+}
+
+#[test]
+fn test_source_to_sink_connect_links() {
+    // Application source -> sink uses pw-link, not a combine-sink
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let sink = register_sink(&state, 10, "alsa_output.a", "A");
+    let orb = orb(20, "Firefox", OrbKind::ApplicationSource { app_name: "Firefox".to_string() });
+    let source = orb.id;
+    state.graph().add_orb(orb);
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source, target: sink });
+
+    assert_eq!(
+        backend.calls(),
+        vec![BackendCall::Link { output: "Firefox".to_string(), input: "alsa_output.a".to_string() }]
+    );
+}
+
+#[test]
+fn test_link_without_pw_link_says_what_to_install() {
+    // The drop looks done on the canvas, so the user has to hear why nothing plays
+    let backend = Arc::new(MockBackend::new());
+    backend.uninstall("pw-link");
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let sink = register_sink(&state, 10, "alsa_output.a", "A");
+    let orb = orb(20, "Firefox", OrbKind::ApplicationSource { app_name: "Firefox".to_string() });
+    let source = orb.id;
+    state.graph().add_orb(orb);
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source, target: sink });
+
+    let errors: Vec<String> = rx.try_iter()
+        .filter_map(|e| match e {
+            OrbEvent::Error { message } => Some(message),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(errors, vec!["pw-link not found; install pipewire-utils"]);
+    assert!(state.graph().own_links.is_empty());
+}
+
+#[test]
+fn test_unlink_removes_only_the_links_auralis_made() {
+    // Links pw-link made for a route are tracked as PipeWire announces them, and unlinking
+    // removes those and nothing else the app plays to
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let sink = register_sink(&state, 10, "alsa_output.a", "A");
+    register_sink(&state, 11, "alsa_output.b", "B");
+    let orb = orb(20, "Firefox", OrbKind::ApplicationSource { app_name: "Firefox".to_string() });
+    let source = orb.id;
+    {
+        let mut graph = state.graph();
+        graph.add_orb(orb);
+        // The session manager's link to B
+        graph.add_link(99, 20, 11);
+    }
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source, target: sink });
+    {
+        let mut graph = state.graph();
+        graph.add_link(100, 20, 10);
+        graph.add_link(101, 20, 10);
+        graph.add_link(102, 20, 10);
+        assert_eq!(graph.own_links[&(source, sink)].link_ids, vec![100, 101, 102]);
+
+        // One went away on its own
+        graph.remove_link(102);
+        assert_eq!(graph.own_links[&(source, sink)].link_ids, vec![100, 101]);
+        graph.routes = graph.playback_routes(|_| None);
+    }
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Unlink { source, target: sink });
+
+    // Still playing to B, so it isn't moved anywhere
+    assert_eq!(backend.calls(), vec![BackendCall::Unlink(100), BackendCall::Unlink(101)]);
+    assert!(state.graph().own_links.is_empty());
+}
+
+#[test]
+fn test_unlink_removes_links_and_falls_back_to_default() {
+    // Unlinking removes every channel's link, then the app goes back to the default output
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let sink = register_sink(&state, 10, "alsa_output.a", "A");
+    register_sink(&state, 11, "alsa_output.b", "B");
+    let orb = orb(20, "Firefox", OrbKind::ApplicationSource { app_name: "Firefox".to_string() });
+    let source = orb.id;
+    {
+        let mut graph = state.graph();
+        graph.add_orb(orb);
+        graph.add_link(100, 20, 10);
+        graph.add_link(101, 20, 10);
+        graph.routes = graph.playback_routes(|_| None);
+    }
+    backend.set_default_sink("alsa_output.b").unwrap();
+    backend.set_sink_inputs(vec![20]);
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Unlink { source, target: sink });
+
+    assert_eq!(
+        backend.calls(),
+        vec![
+            BackendCall::Unlink(100),
+            BackendCall::Unlink(101),
+            BackendCall::MoveSinkInput { input_id: 20, sink_name: "alsa_output.b".to_string() },
+        ]
+    );
+}
+
+#[test]
+fn test_role_target_moves_streams_and_is_remembered() {
+    // Streams with the role move to the chosen device, others stay; clearing sends them back
+    let backend = Arc::new(MockBackend::new());
+    backend.set_default_sink_name("alsa_output.default");
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let speaker = register_sink(&state, 10, "alsa_output.speaker", "Speaker");
+    let chat = orb(20, "Chat", OrbKind::ApplicationSource { app_name: "Chat".to_string() });
+    let player = orb(21, "Player", OrbKind::ApplicationSource { app_name: "Player".to_string() });
+    {
+        let mut graph = state.graph();
+        graph.media_roles.insert(chat.id, "Notification".to_string());
+        graph.media_roles.insert(player.id, "Music".to_string());
+        graph.add_orb(chat);
+        graph.add_orb(player);
+    }
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetRoleTarget { role: "event".to_string(), sink: Some(speaker) });
+
+    assert_eq!(state.store().data.settings.role_targets.get("Notification").map(String::as_str), Some("alsa_output.speaker"));
+    assert_eq!(backend.calls(), vec![BackendCall::MoveSinkInput { input_id: 20, sink_name: "alsa_output.speaker".to_string() }]);
+
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetRoleTarget { role: "Notification".to_string(), sink: None });
+
+    assert!(state.store().data.settings.role_targets.is_empty());
+    assert_eq!(backend.calls(), vec![BackendCall::MoveSinkInput { input_id: 20, sink_name: "alsa_output.default".to_string() }]);
+}
+
+#[test]
+fn test_notifications_stay_off_a_default_cluster() {
+    // With the setting on, notification sounds go to the device the cluster took the default from
+    let backend = Arc::new(MockBackend::new());
+    backend.set_default_sink_name("alsa_output.a");
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    let chat = orb(20, "Chat", OrbKind::ApplicationSource { app_name: "Chat".to_string() });
+    let player = orb(21, "Player", OrbKind::ApplicationSource { app_name: "Player".to_string() });
+    {
+        let mut graph = state.graph();
+        graph.media_roles.insert(chat.id, "Notification".to_string());
+        graph.media_roles.insert(player.id, "Music".to_string());
+        graph.add_orb(chat);
+        graph.add_orb(player);
+    }
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster_sink = backend.get_default_sink().unwrap();
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetNotificationsOffClusters { enabled: true });
+
+    assert!(state.store().data.settings.notifications_off_clusters);
+    assert_eq!(backend.calls(), vec![BackendCall::MoveSinkInput { input_id: 20, sink_name: "alsa_output.a".to_string() }]);
+
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetNotificationsOffClusters { enabled: false });
+
+    assert_eq!(backend.calls(), vec![BackendCall::MoveSinkInput { input_id: 20, sink_name: cluster_sink }]);
+}
+
+#[test]
+fn test_disconnect_app_detaches_stream_and_raw_source() {
+    // Disconnecting an app removes all its links; only real streams get moved to the default
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    register_sink(&state, 10, "alsa_output.a", "A");
+    let stream = orb(20, "Firefox", OrbKind::ApplicationSource { app_name: "Firefox".to_string() });
+    let monitor = orb(30, "alsa_input.mic", OrbKind::ApplicationSource { app_name: String::new() });
+    let (stream_id, monitor_id) = (stream.id, monitor.id);
+    {
+        let mut graph = state.graph();
+        graph.add_orb(stream);
+        graph.add_orb(monitor);
+        graph.add_link(100, 20, 10);
+        graph.add_link(101, 30, 10);
+        graph.routes = graph.playback_routes(|_| None);
+    }
+    backend.set_default_sink("alsa_output.b").unwrap();
+    backend.set_sink_inputs(vec![20]);
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: stream_id, target: stream_id });
+    assert_eq!(
+        backend.calls(),
+        vec![
+            BackendCall::Unlink(100),
+            BackendCall::MoveSinkInput { input_id: 20, sink_name: "alsa_output.b".to_string() },
+        ]
+    );
+
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: monitor_id, target: monitor_id });
+    assert_eq!(backend.calls(), vec![BackendCall::Unlink(101)]);
+}
+
+fn cluster_id_from(events: &[OrbEvent]) -> Uuid {
+    events
+        .iter()
+        .find_map(|e| match e {
+            OrbEvent::Add(o) if matches!(o.kind, OrbKind::Cluster { .. }) => Some(o.id),
+            _ => None,
+        })
+        .expect("cluster orb")
+}
+
+#[test]
+fn test_requested_cluster_plays_confirmation_sound() {
+    // Clustering on request sounds once it's built; with notifications off it stays quiet
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+    let sounds = |backend: &MockBackend| backend.calls().into_iter().filter(|c| matches!(c, BackendCall::PlaySound(_))).collect::<Vec<_>>();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    assert_eq!(sounds(&backend), vec![BackendCall::PlaySound("complete".to_string())]);
+
+    // Nothing new to build
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::CreateCluster { devices: vec![a, b] });
+    assert!(sounds(&backend).is_empty());
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetShowNotifications { enabled: false });
+    let c = register_sink(&state, 12, "alsa_output.c", "C");
+    let d = register_sink(&state, 13, "alsa_output.d", "D");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::CreateCluster { devices: vec![c, d] });
+    assert_eq!(state.graph().clusters.len(), 2);
+    assert!(sounds(&backend).is_empty());
+}
+
+#[test]
+fn test_set_cluster_zone_emits_update() {
+    // Assigning a zone updates the existing cluster orb instead of re-adding it
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+
+    let zone = Some("Kitchen".to_string());
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetClusterZone { cluster, zone: zone.clone() });
+
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Update(o) if o.id == cluster && o.zone == zone)));
+    assert_eq!(state.graph().orbs[&cluster].zone, zone);
+}
+
+#[test]
+fn test_import_applies_to_live_clusters() {
+    // An imported layout renames a cluster that's already up and rebuilds it with its delays
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    register_sink(&state, 12, "alsa_output.c", "C");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+
+    let mut layout = PersistedState::default();
+    layout.clusters.insert(cluster_key(&["alsa_output.a".to_string(), "alsa_output.b".to_string()]), ClusterPrefs {
+        name: Some("Kitchen".to_string()),
+        delays: HashMap::from([("alsa_output.b".to_string(), 40)]),
+        ..Default::default()
+    });
+    // Devices that aren't clustered here are only remembered
+    layout.clusters.insert(cluster_key(&["alsa_output.c".to_string()]), ClusterPrefs {
+        name: Some("Garage".to_string()),
+        ..Default::default()
+    });
+    let path = std::env::temp_dir().join(format!("auralis-test-{}-import-live.json", std::process::id()));
+    std::fs::write(&path, Bundle::new(&layout, &Profiles::default()).to_json().unwrap()).unwrap();
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ImportLayout { path: path.clone(), replace: false });
+    let _ = std::fs::remove_file(&path);
+
+    let calls = backend.calls();
+    assert!(calls.iter().any(|c| matches!(c, BackendCall::UnloadModule(_))));
+    assert!(calls.iter().any(|c| matches!(c, BackendCall::LoadLoopback { sink, latency_ms: 40, .. } if sink == "alsa_output.b")));
+    let graph = state.graph();
+    assert_eq!(graph.clusters.len(), 1);
+    assert!(!graph.clusters.contains_key(&cluster));
+    let rebuilt = graph.clusters.keys().next().unwrap();
+    assert_eq!(graph.orbs[rebuilt].name, "Kitchen");
+    assert!(rx.try_iter().any(|e| matches!(e, OrbEvent::LayoutImported { clusters: 2, profiles: 0 })));
+}
+
+#[test]
+fn test_cluster_prefs_restored_for_same_devices() {
+    // Re-clustering the same devices brings back the name and zone the user picked
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    PipeWireClient::handle_command(&state, &tx, UiCommand::RenameCluster { cluster, name: "Party".to_string() });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetClusterZone { cluster, zone: Some("Patio".to_string()) });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: cluster, target: cluster });
+    let _ = rx.try_iter().count();
+
+    // Opposite drop direction, same member set
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: b, target: a });
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    let cluster = cluster_id_from(&events);
+
+    let orb = &state.graph().orbs[&cluster];
+    assert_eq!(orb.name, "Party");
+    assert_eq!(orb.zone.as_deref(), Some("Patio"));
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Update(o) if o.id == cluster)));
+}
+
+#[test]
+fn test_measure_latency_reports_changes_only() {
+    // Latency is read from the combine-sink and only re-sent when it changes
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    let sink_name = state.graph().clusters[&cluster].sink_name.clone();
+
+    backend.set_sink_latency(&sink_name, 45);
+    PipeWireClient::measure_latency(&state, &tx);
+    PipeWireClient::measure_latency(&state, &tx);
+
+    let updates: Vec<Option<u32>> = rx
+        .try_iter()
+        .filter_map(|e| match e {
+            OrbEvent::Update(o) if o.id == cluster => Some(o.latency_ms),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(updates, vec![Some(45)]);
+    assert_eq!(state.graph().clusters[&cluster].latency_ms, 45);
+}
+
+#[test]
+fn test_nudge_member_delay_routes_through_loopback() {
+    // A delayed member is fed by a loopback from its own delay sink
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::NudgeMemberDelay { cluster, device: b, delta_ms: 20 });
+
+    let calls = backend.calls();
+    let delay_sink = calls
+        .iter()
+        .find_map(|c| match c {
+            BackendCall::LoadLoopback { source, sink, latency_ms } => {
+                assert_eq!(sink, "alsa_output.b");
+                assert_eq!(*latency_ms, 20);
+                source.strip_suffix(".monitor").map(|s| s.to_string())
+            }
+            _ => None,
+        })
+        .expect("loopback for delayed member");
+
+    let slaves = calls.iter().find_map(|c| match c {
+        BackendCall::LoadCombineSink { slaves, .. } => Some(slaves.clone()),
+        _ => None,
+    });
+    assert_eq!(slaves, Some(vec!["alsa_output.a".to_string(), delay_sink]));
+}
+
+#[test]
+fn test_mono_member_downmixed_by_default_and_overridable() {
+    // A single-channel member is fed through a mono remap sink until the user turns it off
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let sub = register_sink(&state, 11, "alsa_output.sub", "Sub");
+    state.graph().orbs.get_mut(&sub).unwrap().channels = Some(1);
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: sub });
+
+    let calls = backend.calls();
+    let mono_sink = calls
+        .iter()
+        .find_map(|c| match c {
+            BackendCall::LoadRemapSink { sink_name, master, channel_map } => {
+                assert_eq!(master, "alsa_output.sub");
+                assert_eq!(channel_map, "mono");
+                Some(sink_name.clone())
+            }
+            _ => None,
+        })
+        .expect("remap sink for mono member");
+    let slaves = calls.iter().find_map(|c| match c {
+        BackendCall::LoadCombineSink { slaves, .. } => Some(slaves.clone()),
+        _ => None,
+    });
+    assert_eq!(slaves, Some(vec!["alsa_output.a".to_string(), mono_sink]));
+
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    let cluster = cluster_id_from(&events);
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Update(orb)
+        if matches!(&orb.kind, OrbKind::Cluster { mono, .. } if mono == &vec![sub]))));
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetMemberDownmix { cluster, device: sub, enabled: false });
+
+    let calls = backend.calls();
+    assert!(!calls.iter().any(|c| matches!(c, BackendCall::LoadRemapSink { .. })));
+    let slaves = calls.iter().find_map(|c| match c {
+        BackendCall::LoadCombineSink { slaves, .. } => Some(slaves.clone()),
+        _ => None,
+    });
+    assert_eq!(slaves, Some(vec!["alsa_output.a".to_string(), "alsa_output.sub".to_string()]));
+}
+
+#[test]
+fn test_member_volume_applies_to_device_and_survives_recreate() {
+    // The member's own sink gets the volume without rebuilding the cluster, and gets it again next time
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetMemberVolume { cluster, device: b, percent: 400 });
+
+    assert_eq!(backend.calls(), vec![BackendCall::SetSinkVolume { sink_name: "alsa_output.b".to_string(), percent: 150 }]);
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Update(orb)
+        if matches!(&orb.kind, OrbKind::Cluster { volumes, .. } if volumes.get(&b) == Some(&150)))));
+
+    // Separate and cluster the same devices again
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: cluster, target: cluster });
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+
+    assert!(backend.calls().contains(&BackendCall::SetSinkVolume { sink_name: "alsa_output.b".to_string(), percent: 150 }));
+    assert!(!backend.calls().iter().any(|c| matches!(c, BackendCall::SetSinkVolume { sink_name, .. } if sink_name == "alsa_output.a")));
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    assert!(matches!(&state.graph().orbs[&cluster].kind, OrbKind::Cluster { volumes, .. } if volumes.get(&b) == Some(&150)));
+}
+
+#[test]
+fn test_set_volume_reports_the_new_volume() {
+    // A lone device just gets the volume; a member's is remembered for its cluster too
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    let c = register_sink(&state, 12, "alsa_output.c", "C");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetVolume { device: a, percent: 35 });
+
+    assert_eq!(backend.calls(), vec![BackendCall::SetSinkVolume { sink_name: "alsa_output.a".to_string(), percent: 35 }]);
+    assert!(rx.try_iter().any(|e| matches!(e, OrbEvent::Volume { device, percent: 35 } if device == a)));
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: b, target: c });
+    rx.try_iter().count();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetVolume { device: c, percent: 60 });
+
+    let key = cluster_key(&["alsa_output.b".to_string(), "alsa_output.c".to_string()]);
+    assert_eq!(state.store().data.clusters[&key].volumes["alsa_output.c"], 60);
+    assert!(rx.try_iter().any(|e| matches!(e, OrbEvent::Volume { device, percent: 60 } if device == c)));
+}
+
+#[test]
+fn test_balance_sets_members_to_their_average_volume() {
+    // Asked for on the card, or done when a new cluster is made with the setting on
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    let c = register_sink(&state, 12, "alsa_output.c", "C");
+    backend.set_volume_of("alsa_output.a", 40);
+    backend.set_volume_of("alsa_output.b", 100);
+    backend.set_volume_of("alsa_output.c", 90);
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::BalanceClusterVolumes { cluster });
+
+    assert_eq!(backend.calls(), vec![
+        BackendCall::SetSinkVolume { sink_name: "alsa_output.a".to_string(), percent: 70 },
+        BackendCall::SetSinkVolume { sink_name: "alsa_output.b".to_string(), percent: 70 },
+    ]);
+    assert!(matches!(&state.graph().orbs[&cluster].kind, OrbKind::Cluster { volumes, .. } if volumes.get(&a) == Some(&70)));
+
+    // New cluster with the setting on: balanced once it's built
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: cluster, target: cluster });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetBalanceNewClusters { enabled: true });
+    backend.set_volume_of("alsa_output.a", 40);
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: c });
+
+    let volumes: Vec<BackendCall> = backend.calls().into_iter().filter(|c| matches!(c, BackendCall::SetSinkVolume { .. })).collect();
+    assert_eq!(volumes, vec![
+        BackendCall::SetSinkVolume { sink_name: "alsa_output.a".to_string(), percent: 65 },
+        BackendCall::SetSinkVolume { sink_name: "alsa_output.c".to_string(), percent: 65 },
+    ]);
+}
+
+#[test]
+fn test_cluster_channels_default_and_change() {
+    // Two 5.1 receivers make a 5.1 cluster; stereo can be picked, 7.1 can't
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    for id in [a, b] {
+        state.graph().orbs.get_mut(&id).unwrap().channels = Some(6);
+    }
+    let layout = |calls: Vec<BackendCall>| calls.into_iter().find_map(|c| match c {
+        BackendCall::LoadCombineSink { channels, channel_map, .. } => Some((channels, channel_map)),
+        _ => None,
+    });
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    assert_eq!(layout(backend.calls()).map(|(c, _)| c), Some(6));
+    assert_eq!(state.graph().orbs[&cluster].channels, Some(6));
+
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetClusterChannels { cluster, channels: 8 });
+    assert!(backend.calls().is_empty());
+    assert!(matches!(rx.try_iter().last(), Some(OrbEvent::Error { message }) if message.contains("7.1")));
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetClusterChannels { cluster, channels: 2 });
+    assert_eq!(layout(backend.calls()), Some((2, "front-left,front-right".to_string())));
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    assert_eq!(state.graph().orbs[&cluster].channels, Some(2));
+}
+
+#[test]
+fn test_cluster_with_duplicate_descriptions_uses_node_names() {
+    // The combine-sink is built from the exact devices dropped, not whichever shares their description
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let _usb1 = register_sink(&state, 10, "alsa_output.usb-dac-1", "USB Audio");
+    let usb2 = register_sink(&state, 11, "alsa_output.usb-dac-2", "USB Audio");
+    let speakers = register_sink(&state, 12, "alsa_output.speakers", "Speakers");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: usb2, target: speakers });
+
+    let slaves = backend.calls().into_iter().find_map(|call| match call {
+        BackendCall::LoadCombineSink { slaves, .. } => Some(slaves),
+        _ => None,
+    });
+    assert_eq!(
+        slaves,
+        Some(vec!["alsa_output.usb-dac-2".to_string(), "alsa_output.speakers".to_string()])
+    );
+
+    let graph = state.graph();
+    assert!(graph.is_cluster_member("alsa_output.usb-dac-2"));
+    assert!(!graph.is_cluster_member("alsa_output.usb-dac-1"));
+}
+
+#[test]
+fn test_move_all_streams_only_moves_streams_on_source() {
+    // Device swap moves the streams on `from` and leaves other sinks alone
+    let backend = Arc::new(MockBackend::new());
+    backend.set_sink_inputs_on("alsa_output.speakers", vec![3, 4]);
+    backend.set_sink_inputs_on("alsa_output.hdmi", vec![9]);
+    backend.set_default_sink_name("alsa_output.speakers");
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let speakers = register_sink(&state, 10, "alsa_output.speakers", "Speakers");
+    let headphones = register_sink(&state, 11, "bluez_output.headphones", "Headphones");
+    register_sink(&state, 12, "alsa_output.hdmi", "HDMI");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::MoveAllStreams { from: speakers, to: headphones });
+
+    let to = "bluez_output.headphones".to_string();
+    assert_eq!(
+        backend.calls(),
+        vec![
+            BackendCall::MoveSinkInput { input_id: 3, sink_name: to.clone() },
+            BackendCall::MoveSinkInput { input_id: 4, sink_name: to.clone() },
+            BackendCall::SetDefaultSink(to),
+        ]
+    );
+}
+
+#[test]
+fn test_move_all_streams_from_cluster_uses_combine_sink() {
+    // Streams on a cluster live on its combine-sink; default is kept if it was elsewhere
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    let c = register_sink(&state, 12, "alsa_output.c", "C");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    let sink_name = state.graph().clusters[&cluster].sink_name.clone();
+    backend.set_sink_inputs_on(&sink_name, vec![5]);
+    backend.set_default_sink_name("alsa_output.c");
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::MoveAllStreams { from: cluster, to: c });
+
+    assert_eq!(
+        backend.calls(),
+        vec![BackendCall::MoveSinkInput { input_id: 5, sink_name: "alsa_output.c".to_string() }]
+    );
+}
+
+#[test]
+fn test_duplicate_plays_on_both_and_is_torn_down() {
+    // A loopback from the first device's monitor feeds the second, the default stays put
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Duplicate { from: a, also: b });
+
+    assert_eq!(
+        backend.calls(),
+        vec![BackendCall::LoadLoopback {
+            source: "alsa_output.a.monitor".to_string(),
+            sink: "alsa_output.b".to_string(),
+            latency_ms: 50,
+        }]
+    );
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Duplicates { pairs } if pairs == &vec![(a, b)])));
+    let module_id = state.graph().duplicates[&(a, b)];
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::StopDuplicate { from: a, also: b });
+
+    assert_eq!(backend.calls().last(), Some(&BackendCall::UnloadModule(module_id)));
+    assert!(state.graph().duplicates.is_empty());
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Duplicates { pairs } if pairs.is_empty())));
+}
+
+#[test]
+fn test_duplicate_refuses_feedback_loops() {
+    // A -> B -> C, then C back to A would play A's audio into itself forever
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    let c = register_sink(&state, 12, "alsa_output.c", "C");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Duplicate { from: a, also: b });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Duplicate { from: b, also: c });
+    rx.try_iter().count();
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Duplicate { from: c, also: a });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Duplicate { from: a, also: a });
+
+    assert!(backend.calls().is_empty());
+    let errors = rx.try_iter().filter(|e| matches!(e, OrbEvent::Error { .. })).count();
+    assert_eq!(errors, 2);
+    assert_eq!(state.graph().duplicate_pairs().len(), 2);
+}
+
+#[test]
+fn test_shutdown_unloads_duplicates() {
+    // Duplicates are Auralis' modules like clusters are, and go with it
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Duplicate { from: a, also: b });
+    let module_id = state.graph().duplicates[&(a, b)];
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Shutdown);
+
+    assert_eq!(backend.calls(), vec![BackendCall::UnloadModule(module_id)]);
+}
+
+#[test]
+fn test_connect_clustered_device_never_duplicates_slaves() {
+    // A member dropped on its own cluster changes nothing; dropped on another cluster it moves
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    let c = register_sink(&state, 12, "alsa_output.c", "C");
+    let d = register_sink(&state, 13, "alsa_output.d", "D");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let first = state.graph().orbs[&a].membership().expect("a is clustered");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: first });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: c, target: d });
+    let second = state.graph().orbs[&c].membership().expect("c is clustered");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: second });
+
+    let loads: Vec<Vec<String>> = backend.calls().into_iter()
+        .filter_map(|call| match call {
+            BackendCall::LoadCombineSink { slaves, .. } => Some(slaves),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(loads.len(), 3);
+    assert_eq!(loads[2], vec!["alsa_output.c".to_string(), "alsa_output.d".to_string(), "alsa_output.a".to_string()]);
+
+    // B was left on its own, so the first cluster was separated
+    let graph = state.graph();
+    assert_eq!(graph.clusters.len(), 1);
+    assert_eq!(graph.orbs[&b].membership(), None);
+    assert_eq!(graph.orbs[&a].membership(), graph.orbs[&d].membership());
+}
+
+#[test]
+fn test_connect_to_itself_is_ignored() {
+    // A device or cluster dropped onto itself is silently left alone
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: a });
+    assert!(backend.calls().is_empty());
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = state.graph().orbs[&a].membership().expect("a is clustered");
+    let calls = backend.calls().len();
+    let _ = rx.try_iter().count();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: cluster, target: cluster });
+    assert_eq!(backend.calls().len(), calls);
+    assert_eq!(rx.try_iter().count(), 0);
+    assert!(state.graph().clusters.contains_key(&cluster));
+}
+
+#[test]
+fn test_clusters_rebuilt_after_pipewire_restart() {
+    // After a restart no phantom cluster is left; it's rebuilt once its devices are back
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    let c = register_sink(&state, 12, "alsa_output.c", "C");
+    let d = register_sink(&state, 13, "alsa_output.d", "D");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: c, target: d });
+    backend.clear_calls();
+
+    PipeWireClient::connection_lost(&state, &tx);
+    // Its modules went with the old daemon, their IDs may be taken by the new one's
+    assert!(!backend.calls().iter().any(|c| matches!(c, BackendCall::UnloadModule(_))));
+    assert!(state.graph().clusters.is_empty());
+    assert!(state.graph().orbs.is_empty());
+    assert!(rx.try_iter().any(|e| matches!(e, OrbEvent::Clear)));
+
+    // D doesn't come back with the new daemon
+    register_sink(&state, 40, "alsa_output.a", "A");
+    register_sink(&state, 41, "alsa_output.b", "B");
+    register_sink(&state, 42, "alsa_output.c", "C");
+    let loads_before = backend.calls().iter().filter(|c| matches!(c, BackendCall::LoadCombineSink { .. })).count();
+    PipeWireClient::rebuild_lost_clusters(&state, &tx);
+
+    let loads: Vec<Vec<String>> = backend.calls().into_iter()
+        .filter_map(|call| match call {
+            BackendCall::LoadCombineSink { slaves, .. } => Some(slaves),
+            _ => None,
+        })
+        .skip(loads_before)
+        .collect();
+    assert_eq!(loads, vec![vec!["alsa_output.a".to_string(), "alsa_output.b".to_string()]]);
+    assert_eq!(state.graph().clusters.len(), 1);
+    assert!(rx.try_iter().any(|e| matches!(e, OrbEvent::Error { message } if message.contains("alsa_output.d"))));
+
+    // Only once: the next discovery has nothing to rebuild
+    PipeWireClient::rebuild_lost_clusters(&state, &tx);
+    assert_eq!(state.graph().clusters.len(), 1);
+}
+
+#[test]
+fn test_rebuild_clusters_setting_leaves_devices_apart() {
+    // With rebuilding turned off, the devices come back on their own
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, _rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetRebuildClusters { enabled: false });
+
+    PipeWireClient::connection_lost(&state, &tx);
+    register_sink(&state, 40, "alsa_output.a", "A");
+    register_sink(&state, 41, "alsa_output.b", "B");
+    PipeWireClient::rebuild_lost_clusters(&state, &tx);
+    assert!(state.graph().clusters.is_empty());
+}
+
+#[test]
+fn test_cluster_script_recreates_cluster() {
+    // The script builds the same chain as the core, under names Auralis won't sweep up
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "bluez_output.b", "B");
+    state.store().data.clusters.insert("alsa_output.a,bluez_output.b".to_string(), ClusterPrefs {
+        name: Some("Living Room".to_string()),
+        delays: HashMap::from([("bluez_output.b".to_string(), 120)]),
+        volumes: HashMap::from([("alsa_output.a".to_string(), 80)]),
+        ..Default::default()
+    });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = state.graph().orbs[&a].membership().unwrap();
+    let _ = rx.try_iter().count();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::CopyClusterScript { cluster });
+    let script = rx.try_iter()
+        .find_map(|e| match e {
+            OrbEvent::ClusterScript { script } => Some(script),
+            _ => None,
+        })
+        .expect("script sent");
+
+    assert!(script.starts_with("#!/bin/sh\n# Auralis cluster \"Living Room\" (alsa_output.a, bluez_output.b)\n"));
+    assert!(script.contains("pactl load-module module-null-sink sink_name=living_room_delay_2 'sink_properties=device.description=Auralis delay (bluez_output.b)'\n"));
+    assert!(script.contains("pactl load-module module-loopback source=living_room_delay_2.monitor sink=bluez_output.b latency_msec=120 "));
+    assert!(script.contains("pactl load-module module-combine-sink sink_name=living_room slaves=alsa_output.a,living_room_delay_2 "));
+    assert!(script.contains("pactl set-sink-volume alsa_output.a 80%\n"));
+    assert!(script.contains("pactl set-default-sink living_room\n"));
+    assert!(script.contains("pactl move-sink-input \"$input\" living_room;"));
+    assert!(!script.contains("auralis_"));
+
+    assert!(PipeWireClient::cluster_script(&state, Uuid::new_v4()).is_none());
+}
+
+#[test]
+fn test_read_formats_updates_device_once() {
+    // Formats end up on the orb; reading the same ones again sends nothing
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ReadFormats { device: a });
+    assert!(state.graph().orbs[&a].formats.is_empty());
+    assert_eq!(rx.try_iter().count(), 0);
+
+    let stereo = AudioFormat { rates: vec![44100, 48000], max_channels: Some(2), ..Default::default() };
+    backend.set_node_formats(10, NodeFormats { supported: vec![stereo.clone()], current: None });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ReadFormats { device: a });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ReadFormats { device: a });
+
+    let updates: Vec<OrbEvent> = rx.try_iter().collect();
+    assert_eq!(updates.len(), 1);
+    assert!(matches!(&updates[0], OrbEvent::Update(orb) if orb.formats == vec![stereo.clone()] && orb.format.is_none()));
+}
+
+#[test]
+fn test_beam_closed_removes_orb_and_reports() {
+    // A failed beam drops its orb and tells the user why
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let orb = orb(0, "beam", beam_kind("phone"));
+    let beam = orb.id;
+    state.graph().add_orb(orb);
+
+    PipeWireClient::handle_command(
+        &state,
+        &tx,
+        UiCommand::BeamClosed { session_id: "phone".to_string(), reason: Some("peer disconnected".to_string()) },
+    );
+
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    assert!(matches!(&events[0], OrbEvent::Remove(id) if *id == beam));
+    assert!(matches!(&events[1], OrbEvent::Error { message } if message.contains("peer disconnected")));
+    assert!(state.graph().orbs.is_empty());
+    assert_eq!(backend.calls(), vec![BackendCall::StopBeam("phone".to_string())]);
+}
+
+#[test]
+fn test_beam_status_updates_orb() {
+    // State changes of a beam session end up in its orb's status, unknown sessions are ignored
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let orb = orb(0, "beam", beam_kind("phone"));
+    let beam = orb.id;
+    state.graph().add_orb(orb);
+
+    let status = |session_id: &str, status: &str| UiCommand::BeamStatus {
+        session_id: session_id.to_string(),
+        status: status.to_string(),
+    };
+    PipeWireClient::handle_command(&state, &tx, status("phone", "Connected"));
+    PipeWireClient::handle_command(&state, &tx, status("phone", "Connected"));
+    PipeWireClient::handle_command(&state, &tx, status("tablet", "Failed"));
+
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(&events[0], OrbEvent::Update(orb) if orb.id == beam && orb.status == "Connected"));
+    assert_eq!(state.graph().orbs[&beam].status, "Connected");
+    assert!(backend.calls().is_empty());
+}
+
+#[test]
+fn test_pactl_refuses_changes_on_a_remote_without_pulse_server() {
+    // pactl doesn't follow --remote; without the remote's pulse server it would change the default instance
+    let backend = PactlBackend::new(false).with_remote(Some("pipewire-0-manager".to_string()));
+
+    let error = backend.set_default_sink("alsa_output.a").unwrap_err();
+    assert!(error.to_string().contains("pipewire-0-manager"));
+    assert!(backend.unload_module(42).is_err());
+}
+
+#[test]
+fn test_start_beam_adds_connecting_orb() {
+    // Beaming a device adds one connecting beam orb for its sink; apps and repeats are refused
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let speakers = register_sink(&state, 10, "alsa_output.speakers", "Speakers");
+    let app = orb(20, "Firefox", OrbKind::ApplicationSource { app_name: "Firefox".to_string() });
+    let app_id = app.id;
+    state.graph().add_orb(app);
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::StartBeam { source: speakers });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::StartBeam { source: speakers });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::StartBeam { source: app_id });
+
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    assert_eq!(events.len(), 3);
+    assert!(matches!(&events[0], OrbEvent::Add(orb)
+        if matches!(orb.kind, OrbKind::BeamOutput { .. }) && orb.node_name == "alsa_output.speakers" && orb.status == "Connecting"));
+    assert!(matches!(&events[1], OrbEvent::Error { message } if message.contains("already being beamed")));
+    assert!(matches!(&events[2], OrbEvent::Error { message } if message.contains("only devices and clusters")));
+    assert_eq!(state.graph().orbs.len(), 3);
+    assert!(matches!(backend.calls().as_slice(), [BackendCall::StartBeam { sink_name, .. }] if sink_name == "alsa_output.speakers"));
+}
+
+#[test]
+fn test_beam_session_states_reach_its_orb() {
+    // What the session reports shows on the orb; once it has closed the orb goes
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let speakers = register_sink(&state, 10, "alsa_output.speakers", "Speakers");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::StartBeam { source: speakers });
+    let (beam, session_id) = rx.try_iter()
+        .find_map(|e| match e {
+            OrbEvent::Add(orb) => match orb.kind {
+                OrbKind::BeamOutput { session_id, .. } => Some((orb.id, session_id)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .expect("beam orb");
+
+    backend.set_beam_state(&session_id, BeamState::Connected);
+    assert_eq!(state.graph().orbs[&beam].status, "Connected");
+
+    backend.set_beam_state(&session_id, BeamState::Closed);
+    assert!(!state.graph().orbs.contains_key(&beam));
+    assert!(backend.calls().contains(&BackendCall::StopBeam(session_id)));
+}
+
+#[test]
+fn test_auto_switch_setting_gates_new_devices() {
+    // Only opted-in, genuinely new, non-ignored, non-Auralis sinks trigger a switch
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, rx) = channel();
+    let later = Duration::from_secs(60);
+
+    assert!(!PipeWireClient::wants_auto_switch(&state, "bluez_output.headset", later));
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetAutoSwitch { enabled: true });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetAutoSwitch { enabled: true });
+    let settings_events = rx.try_iter().filter(|e| matches!(e, OrbEvent::Settings(s) if s.auto_switch_new_devices)).count();
+    assert_eq!(settings_events, 1);
+
+    assert!(PipeWireClient::wants_auto_switch(&state, "bluez_output.headset", later));
+    // Devices present at connect time are not "new"
+    assert!(!PipeWireClient::wants_auto_switch(&state, "bluez_output.headset", Duration::from_millis(100)));
+    assert!(!PipeWireClient::wants_auto_switch(&state, "auralis_cluster_abc", later));
+
+    state.store().data.settings.ignored_devices.push("bluez_output.headset".to_string());
+    assert!(!PipeWireClient::wants_auto_switch(&state, "bluez_output.headset", later));
+}
+
+#[test]
+fn test_first_run_tour_dismissed_and_reset() {
+    // The tour shows until dismissed, and "Show tour again" brings it back
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, rx) = channel();
+    assert!(state.store().data.settings.first_run);
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetFirstRun { first_run: false });
+    assert!(!state.store().data.settings.first_run);
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetFirstRun { first_run: true });
+    let tours: Vec<bool> = rx.try_iter()
+        .filter_map(|e| match e {
+            OrbEvent::Settings(settings) => Some(settings.first_run),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(tours, vec![false, true]);
+}
+
+#[test]
+fn test_canvas_positions_saved_and_cleared() {
+    // Where an orb was left is kept by name; taking it off the canvas forgets it
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, rx) = channel();
+    let set = |position| UiCommand::SetCanvasPosition { name: "Kitchen Speaker".to_string(), position };
+
+    PipeWireClient::handle_command(&state, &tx, set(Some((120.0, 80.0))));
+    PipeWireClient::handle_command(&state, &tx, set(Some((120.0, 80.0))));
+    assert_eq!(state.store().data.settings.canvas_positions.get("Kitchen Speaker"), Some(&(120.0, 80.0)));
+    // The unchanged second drop isn't saved or announced again
+    assert_eq!(rx.try_iter().filter(|e| matches!(e, OrbEvent::Settings(_))).count(), 1);
+
+    PipeWireClient::handle_command(&state, &tx, set(None));
+    assert!(state.store().data.settings.canvas_positions.is_empty());
+}
+
+#[test]
+fn test_validate_turn_url() {
+    // RFC 7065 URLs with optional port and transport; credentials belong in their own fields
+    for url in ["turn:turn.example.com", "turns:turn.example.com:5349", "turn:203.0.113.7:3478?transport=tcp", "turn://[2001:db8::1]:3478"] {
+        assert_eq!(validate_turn_url(url), Ok(()), "{}", url);
+    }
+    for url in ["turn.example.com", "stun:stun.example.com", "turn:", "turn:user:pw@host", "turn:host:99999", "turn:host?transport=sctp"] {
+        assert!(validate_turn_url(url).is_err(), "{}", url);
+    }
+}
+
+#[test]
+fn test_set_beam_turn_rejects_invalid_url() {
+    // A bad URL is reported and leaves the setting alone; an empty one clears it
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, rx) = channel();
+    let turn = |url: &str| UiCommand::SetBeamTurn {
+        turn: Some(TurnServer { url: url.to_string(), username: "me".to_string(), credential: "secret".to_string() }),
+    };
+
+    PipeWireClient::handle_command(&state, &tx, turn(" turn:relay.example.com:3478 "));
+    assert_eq!(state.store().data.settings.beam_turn.as_ref().map(|t| t.url.as_str()), Some("turn:relay.example.com:3478"));
+
+    PipeWireClient::handle_command(&state, &tx, turn("http://relay.example.com"));
+    assert!(state.store().data.settings.beam_turn.is_some());
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    assert!(matches!(events.last(), Some(OrbEvent::Error { message }) if message.starts_with("TURN server")));
+
+    PipeWireClient::handle_command(&state, &tx, turn(""));
+    assert_eq!(state.store().data.settings.beam_turn, None);
+}
+
+#[test]
+fn test_dropped_receiver_cleans_up_modules() {
+    // If the UI goes away mid-connect, the cluster it would never see must not be left loaded
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+    drop(rx);
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+
+    assert!(state.ui_gone());
+    assert!(state.graph().clusters.is_empty());
+    let calls = backend.calls();
+    let loaded = calls.iter().filter(|c| matches!(c, BackendCall::LoadCombineSink { .. })).count();
+    let unloaded = calls.iter().filter(|c| matches!(c, BackendCall::UnloadModule(_))).count();
+    assert_eq!(loaded, 1);
+    assert_eq!(unloaded, loaded);
+
+    // Later commands are dropped instead of loading more modules
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    assert_eq!(backend.calls().len(), calls.len());
+}
+
+#[test]
+fn test_channel_check_plays_each_member_directly() {
+    // Each member gets its own tone, addressed by node name rather than via the combine-sink
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.left", "Left");
+    let b = register_sink(&state, 11, "alsa_output.right", "Right");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ClusterChannelCheck { cluster });
+
+    let played: Vec<String> = backend.calls().into_iter().filter_map(|call| match call {
+        BackendCall::PlayTestTone { node_name, .. } => Some(node_name),
+        _ => None,
+    }).collect();
+    assert_eq!(played, vec!["alsa_output.left".to_string(), "alsa_output.right".to_string()]);
+
+    let progress: Vec<Option<String>> = rx.try_iter().filter_map(|e| match e {
+        OrbEvent::ChannelCheck { playing, .. } => Some(playing),
+        _ => None,
+    }).collect();
+    assert_eq!(progress, vec![
+        Some("Left left".to_string()),
+        Some("Right right".to_string()),
+        None,
+    ]);
+}
+
+#[test]
+fn test_test_tone_plays_on_one_device() {
+    // The outputs wizard's tone goes to the device's own node, clustered or not; clusters and apps get none
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.left", "Left");
+    let b = register_sink(&state, 11, "alsa_output.right", "Right");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    backend.clear_calls();
+
+    for device in [b, cluster, Uuid::new_v4()] {
+        PipeWireClient::handle_command(&state, &tx, UiCommand::PlayTestTone { device });
+    }
+
+    assert_eq!(backend.calls(), vec![BackendCall::PlayTestTone { node_name: "alsa_output.right".to_string(), duration_ms: 1500 }]);
+}
+
+#[test]
+fn test_set_default_and_mute_target_device_node() {
+    // Context menu actions address the device's node, not its description
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.speakers", "Speakers");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetDefaultSink { device: a });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ToggleMute { device: a });
+
+    assert_eq!(backend.calls(), vec![
+        BackendCall::SetDefaultSink("alsa_output.speakers".to_string()),
+        BackendCall::ToggleSinkMute("alsa_output.speakers".to_string()),
+    ]);
+}
+
+#[test]
+fn test_suspend_sink_remembered_and_woken() {
+    // Suspending a device is remembered by node name; waking it forgets that
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SuspendSink { orb: a, suspend: true });
+    assert!(backend.calls().contains(&BackendCall::SuspendSink { sink_name: "alsa_output.a".to_string(), suspend: true }));
+    assert_eq!(state.store().data.settings.suspended_outputs, vec!["alsa_output.a".to_string()]);
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SuspendSink { orb: a, suspend: false });
+    assert!(backend.calls().contains(&BackendCall::SuspendSink { sink_name: "alsa_output.a".to_string(), suspend: false }));
+    assert!(state.store().data.settings.suspended_outputs.is_empty());
+}
+
+#[test]
+fn test_suspended_cluster_suspends_members_and_again_when_rebuilt() {
+    // A cluster suspends its combine-sink and every member, and stays suspended when rebuilt
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster_id = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SuspendSink { orb: cluster_id, suspend: true });
+
+    let suspended = |calls: Vec<BackendCall>| -> Vec<String> {
+        calls.into_iter()
+            .filter_map(|c| match c {
+                BackendCall::SuspendSink { sink_name, suspend: true } => Some(sink_name),
+                _ => None,
+            })
+            .collect()
+    };
+    let sinks = suspended(backend.calls());
+    assert_eq!(sinks.len(), 3);
+    assert!(sinks[0].starts_with("auralis_cluster"));
+    assert!(sinks.contains(&"alsa_output.a".to_string()) && sinks.contains(&"alsa_output.b".to_string()));
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: cluster_id, target: cluster_id });
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    assert_eq!(suspended(backend.calls()).len(), 3);
+}
+
+#[test]
+fn test_reset_all_sweeps_legacy_and_delay_modules() {
+    // Every kind of Auralis sink goes, including names older releases used
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let module = |id, name: &str, args: &str| ModuleInfo { id, name: name.to_string(), args: args.to_string() };
+    backend.add_module(module(1, "module-combine-sink", "sink_name=auralis_combined_old slaves=alsa_output.a"));
+    backend.add_module(module(2, "module-null-sink", "sink_name=auralis_delay_x"));
+    backend.add_module(module(3, "module-loopback", "source=auralis_delay_x.monitor sink=alsa_output.a"));
+    backend.add_module(module(4, "module-remap-sink", "sink_name=auralis_mono_y master=alsa_output.b"));
+    backend.add_module(module(5, "module-combine-sink", "sink_name=my_combined slaves=alsa_output.a"));
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ResetAll);
+
+    let left: Vec<u32> = backend.list_modules().unwrap().iter().map(|m| m.id).collect();
+    assert_eq!(left, vec![5]);
+}
+
+#[test]
+fn test_reset_all_leaves_no_auralis_modules() {
+    // A reset unloads tracked and untracked Auralis modules and leaves a real default sink
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+
+    // Left behind by something the graph doesn't know about
+    backend.add_module(ModuleInfo {
+        id: 4242,
+        name: "module-combine-sink".to_string(),
+        args: "sink_name=auralis_cluster_stale slaves=alsa_output.a".to_string(),
+    });
+    let _ = rx.try_iter().count();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ResetAll);
+
+    let modules = backend.list_modules().unwrap();
+    assert!(modules.iter().all(|m| !m.args.contains("auralis_")), "leaked: {:?}", modules);
+    assert!(state.graph().clusters.is_empty());
+
+    let default = backend.get_default_sink().unwrap();
+    assert!(default == "alsa_output.a" || default == "alsa_output.b", "default sink: {:?}", default);
+
+    let shown: Vec<Uuid> = rx.try_iter().filter_map(|e| match e {
+        OrbEvent::Add(o) => Some(o.id),
+        _ => None,
+    }).collect();
+    assert!(shown.contains(&a) && shown.contains(&b));
+}
+
+#[test]
+fn test_clean_up_orphans_keeps_running_clusters() {
+    // Only modules this instance loaded and no cluster tracks go; the other cluster keeps
+    // playing, and another instance's sinks are left alone
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    let c = register_sink(&state, 12, "alsa_output.c", "C");
+    let d = register_sink(&state, 13, "alsa_output.d", "D");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: c, target: d });
+    let lost = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    let orphan = state.graph().clusters.remove(&lost).expect("cluster").module_id;
+    backend.add_module(ModuleInfo {
+        id: 4242,
+        name: "module-combine-sink".to_string(),
+        args: "sink_name=auralis_cluster_otherinstance slaves=alsa_output.a".to_string(),
+    });
+    backend.add_module(ModuleInfo {
+        id: 4243,
+        name: "module-combine-sink".to_string(),
+        args: "sink_name=someone_elses slaves=alsa_output.a".to_string(),
+    });
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::CleanUpOrphans);
+
+    assert_eq!(backend.calls(), vec![BackendCall::UnloadModule(orphan)]);
+    assert_eq!(state.graph().clusters.len(), 1);
+    let cleaned: Vec<usize> = rx.try_iter().filter_map(|e| match e {
+        OrbEvent::OrphansCleaned { count } => Some(count),
+        _ => None,
+    }).collect();
+    assert_eq!(cleaned, vec![1]);
+}
+
+#[test]
+fn test_watchdog_unloads_orphans_seen_twice() {
+    // The first pass only notes an untracked module, in case its cluster is still being built
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    let orphan = state.graph().clusters.remove(&cluster).expect("cluster").module_id;
+    backend.add_module(ModuleInfo {
+        id: 4242,
+        name: "module-null-sink".to_string(),
+        args: "sink_name=auralis_delay_otherinstance".to_string(),
+    });
+    backend.clear_calls();
+
+    let suspects = PipeWireClient::sweep_orphans(&state, &tx, &HashSet::new());
+    assert_eq!(suspects, HashSet::from([orphan]));
+    assert!(backend.calls().is_empty());
+    assert_eq!(rx.try_iter().count(), 0);
+
+    let suspects = PipeWireClient::sweep_orphans(&state, &tx, &suspects);
+    assert!(suspects.is_empty());
+    assert_eq!(backend.calls(), vec![BackendCall::UnloadModule(orphan)]);
+    assert!(matches!(rx.try_iter().next(), Some(OrbEvent::OrphansCleaned { count: 1 })));
+}
+
+#[test]
+fn test_external_combine_sink_is_adopted_and_survives_cleanup() {
+    // A combine-sink someone else loaded shows up as a cluster but is never swept as stale
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    register_sink(&state, 12, "my_combined", "My Combined");
+    backend.add_module(ModuleInfo {
+        id: 77,
+        name: "module-combine-sink".to_string(),
+        args: "sink_name=my_combined slaves=alsa_output.a,alsa_output.b".to_string(),
+    });
+
+    PipeWireClient::adopt_external_combine_sinks(&state, &tx);
+
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    {
+        let graph = state.graph();
+        let adopted = &graph.clusters[&cluster];
+        assert!(adopted.external);
+        assert_eq!(adopted.module_id, 77);
+        assert_eq!(adopted.members(), vec![a, b]);
+        assert_eq!(graph.orbs[&cluster].pw_id, 12);
+    }
+
+    // Scanning again doesn't adopt it twice
+    PipeWireClient::adopt_external_combine_sinks(&state, &tx);
+    assert_eq!(state.graph().clusters.len(), 1);
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ResetAll);
+    assert!(backend.list_modules().unwrap().iter().any(|m| m.id == 77));
+
+    // Separating it is an explicit request, so then it does get unloaded
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: cluster, target: cluster });
+    assert!(backend.calls().contains(&BackendCall::UnloadModule(77)));
+}
+
+#[test]
+fn test_module_info_arg_parsing() {
+    // Module arguments are looked up by key, quotes stripped
+    let module = ModuleInfo {
+        id: 1,
+        name: "module-combine-sink".to_string(),
+        args: "sink_name=\"combined\" slaves=a,b rate=48000".to_string(),
+    };
+    assert_eq!(module.arg("sink_name"), Some("combined"));
+    assert_eq!(module.arg("slaves"), Some("a,b"));
+    assert_eq!(module.arg("sink"), None);
+}
+
+#[test]
+fn test_resolve_module_id_prefers_module_listing() {
+    // The listing decides the ID; pactl's own output is only a fallback
+    let modules = vec![
+        ModuleInfo { id: 7, name: "module-combine-sink".to_string(), args: "sink_name=auralis_cluster_a slaves=x".to_string() },
+        ModuleInfo { id: 9, name: "module-null-sink".to_string(), args: "sink_name=auralis_cluster_b".to_string() },
+        ModuleInfo { id: 12, name: "module-combine-sink".to_string(), args: "sink_name=auralis_cluster_b slaves=y".to_string() },
+    ];
+
+    let id = resolve_module_id(&modules, "module-combine-sink", "sink_name=auralis_cluster_b", "Modul 12 geladen\n").unwrap();
+    assert_eq!(id, 12);
+
+    // Not listed (yet): fall back to stdout
+    let id = resolve_module_id(&modules, "module-combine-sink", "sink_name=auralis_cluster_c", "536870932\n").unwrap();
+    assert_eq!(id, 536870932);
+
+    // Neither: the error says a module may be left behind
+    let err = resolve_module_id(&[], "module-combine-sink", "sink_name=auralis_cluster_c", "garbage").unwrap_err();
+    assert!(err.to_string().contains("may have been loaded"));
+}
+
+#[test]
+fn test_metrics_count_commands_and_clusters() {
+    // Creating and separating a cluster shows up in the counters, as do the orbs left
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster_id = rx
+        .try_iter()
+        .find_map(|e| match e {
+            OrbEvent::Add(o) if matches!(o.kind, OrbKind::Cluster { .. }) => Some(o.id),
+            _ => None,
+        })
+        .expect("cluster orb");
+
+    let metrics = PipeWireClient::metrics_of(&state);
+    assert_eq!((metrics.commands_processed, metrics.clusters_created, metrics.clusters), (1, 1, 1));
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: cluster_id, target: cluster_id });
+    let metrics = PipeWireClient::metrics_of(&state);
+    assert_eq!(metrics.commands_processed, 2);
+    assert_eq!(metrics.clusters_dissolved, 1);
+    assert_eq!((metrics.orbs, metrics.clusters), (2, 0));
+    assert_eq!(metrics.errors_reported, 0);
+    assert!(!metrics.connected);
+}
+
+#[test]
+fn test_cleanup_leaves_lookalike_modules() {
+    // Names that only resemble ours, and loopbacks not reading one of our monitors, stay loaded
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let module = |id, name: &str, args: &str| ModuleInfo { id, name: name.to_string(), args: args.to_string() };
+    backend.add_module(module(1, "module-null-sink", "sink_name=auralis_music"));
+    backend.add_module(module(2, "module-null-sink", "sink_name=MockingBird"));
+    backend.add_module(module(3, "module-loopback", "source=auralis_delay_x sink=alsa_output.a"));
+    backend.add_module(module(4, "module-null-sink", "sink_name=Mock3"));
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::ResetAll);
+
+    let left: Vec<u32> = backend.list_modules().unwrap().iter().map(|m| m.id).collect();
+    assert_eq!(left, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_cluster_runs_at_negotiated_rate() {
+    // Two CD-rate devices get a 44.1 kHz combine-sink, devices that don't say get 48 kHz
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+
+    let cd = AudioFormat { rates: vec![44100], max_channels: Some(2), ..Default::default() };
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    for id in [a, b] {
+        state.graph().orbs.get_mut(&id).unwrap().formats = vec![cd.clone()];
+    }
+    let c = register_sink(&state, 12, "alsa_output.c", "C");
+    let d = register_sink(&state, 13, "alsa_output.d", "D");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: c, target: d });
+
+    let rates: Vec<u32> = backend.calls().into_iter().filter_map(|call| match call {
+        BackendCall::LoadCombineSink { rate, .. } => Some(rate),
+        _ => None,
+    }).collect();
+    assert_eq!(rates, vec![44100, 48000]);
+}
+
+#[test]
+fn test_external_default_change_is_restored_on_separate() {
+    // Someone else picks another output while the cluster is the default: separating goes there
+    let backend = Arc::new(MockBackend::new());
+    backend.set_default_sink_name("alsa_output.a");
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    register_sink(&state, 12, "alsa_output.c", "C");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    let cluster_id = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+    let cluster_sink = backend.get_default_sink().unwrap();
+    assert_eq!(state.graph().default_sink.as_deref(), Some(cluster_sink.as_str()));
+
+    backend.set_default_sink_name("alsa_output.c");
+    PipeWireClient::reconcile_default_sink(&state, &tx);
+    PipeWireClient::reconcile_default_sink(&state, &tx);
+    let reported: Vec<Option<String>> = rx.try_iter().filter_map(|e| match e {
+        OrbEvent::DefaultSink { sink_name } => Some(sink_name),
+        _ => None,
+    }).collect();
+    assert_eq!(reported, vec![Some("alsa_output.c".to_string())]);
+    assert_eq!(state.graph().clusters[&cluster_id].saved_default_sink.as_deref(), Some("alsa_output.c"));
+
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: cluster_id, target: cluster_id });
+    assert!(backend.calls().contains(&BackendCall::SetDefaultSink("alsa_output.c".to_string())));
+    assert!(!backend.calls().contains(&BackendCall::SetDefaultSink("alsa_output.a".to_string())));
+}
+
+#[test]
+fn test_staged_cluster_loads_nothing_until_a_second_device_joins() {
+    // One device makes a card of its own; the combine-sink comes with the second device
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::StageCluster { device: a });
+
+    assert!(backend.calls().is_empty());
+    let card = rx.try_iter()
+        .find_map(|e| match e {
+            OrbEvent::Add(o) if o.is_staged() => Some(o),
+            _ => None,
+        })
+        .expect("staged cluster card");
+    assert!(matches!(&card.kind, OrbKind::Cluster { members, .. } if members == &vec![a]));
+    assert_eq!(state.graph().orbs[&a].membership(), Some(card.id));
+    assert!(state.graph().clusters.is_empty());
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: b, target: card.id });
+
+    let slaves = backend.calls().into_iter().find_map(|call| match call {
+        BackendCall::LoadCombineSink { slaves, .. } => Some(slaves),
+        _ => None,
+    });
+    assert_eq!(slaves, Some(vec!["alsa_output.a".to_string(), "alsa_output.b".to_string()]));
+    let graph = state.graph();
+    assert!(!graph.orbs.contains_key(&card.id));
+    assert!(graph.staged.is_empty());
+    assert_eq!(graph.clusters.len(), 1);
+}
+
+#[test]
+fn test_separating_a_staged_cluster_only_frees_its_device() {
+    // Nothing was loaded, so nothing is unloaded or restored; the device is shown again
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::StageCluster { device: a });
+    let card = state.graph().staged.keys().next().copied().expect("staged cluster");
+    rx.try_iter().for_each(drop);
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: card, target: card });
+
+    assert!(backend.calls().is_empty());
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Remove(id) if *id == card)));
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Add(o) if o.id == a && o.membership().is_none())));
+    assert!(state.graph().staged.is_empty());
+}
+
+#[test]
+fn test_staged_cluster_goes_with_its_device() {
+    // A staged device that's unplugged takes its card with it
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, _rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::StageCluster { device: a });
+    let card = state.graph().staged.keys().next().copied().expect("staged cluster");
+
+    let events = state.graph().remove_orb(a);
+
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Remove(id) if *id == card)));
+    assert!(!state.graph().orbs.contains_key(&card));
+}
+
+#[test]
+fn test_rename_device_shows_alias_and_keeps_node_name() {
+    // The alias is remembered by node name and shown on the device and its cluster card
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.pci-0000_00_1f.3.analog-stereo", "Built-in Audio Analog Stereo");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: b });
+    rx.try_iter().for_each(drop);
+
+    let rename = |name: Option<&str>| UiCommand::RenameDevice { device: a, name: name.map(str::to_string) };
+    PipeWireClient::handle_command(&state, &tx, rename(Some("  Desk Speakers ")));
+
+    let aliases = state.store().data.settings.device_aliases.clone();
+    assert_eq!(aliases.get("alsa_output.pci-0000_00_1f.3.analog-stereo").map(String::as_str), Some("Desk Speakers"));
+    let events: Vec<OrbEvent> = rx.try_iter().collect();
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Update(o) if o.id == a && o.name == "Desk Speakers")));
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Update(o) if matches!(&o.kind, OrbKind::Cluster { devices, .. } if devices[0] == "Desk Speakers"))));
+    assert_eq!(state.graph().orbs[&a].node_name, "alsa_output.pci-0000_00_1f.3.analog-stereo");
+
+    // Empty goes back to the description
+    PipeWireClient::handle_command(&state, &tx, rename(Some("")));
+    assert!(state.store().data.settings.device_aliases.is_empty());
+    assert_eq!(state.graph().orbs[&a].name, "Built-in Audio Analog Stereo");
+}
+
+#[test]
+fn test_create_cluster_from_selected_devices() {
+    // Three devices clustered at once; one of them leaves the cluster it was in
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let a = register_sink(&state, 10, "alsa_output.a", "A");
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+    let c = register_sink(&state, 12, "alsa_output.c", "C");
+    let d = register_sink(&state, 13, "alsa_output.d", "D");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: c, target: d });
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::CreateCluster { devices: vec![a, b, a, c] });
+
+    let slaves: Vec<Vec<String>> = backend.calls().into_iter().filter_map(|call| match call {
+        BackendCall::LoadCombineSink { slaves, .. } => Some(slaves),
+        _ => None,
+    }).collect();
+    assert_eq!(slaves, vec![vec!["alsa_output.a".to_string(), "alsa_output.b".to_string(), "alsa_output.c".to_string()]]);
+    let graph = state.graph();
+    assert_eq!(graph.clusters.len(), 1);
+    assert_eq!(graph.orbs[&d].membership(), None);
+    drop(graph);
+
+    // One device is no cluster
+    rx.try_iter().for_each(drop);
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::CreateCluster { devices: vec![d] });
+    assert!(backend.calls().is_empty());
+    assert!(rx.try_iter().any(|e| matches!(e, OrbEvent::Error { .. })));
+}
+
+#[test]
+fn test_clustering_an_hdmi_sink_warns_about_the_monitor() {
+    // The user hears why the cluster goes quiet on the TV while the monitor sleeps
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, rx) = channel();
+
+    let tv = register_sink(&state, 10, "alsa_output.pci-0000_01_00.1.hdmi-stereo", "TV");
+    state.graph().orbs.get_mut(&tv).unwrap().bus = DeviceBus::Hdmi;
+    let b = register_sink(&state, 11, "alsa_output.b", "B");
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: b, target: tv });
+
+    let warning = rx.try_iter().find_map(|e| match e {
+        OrbEvent::Error { message } => Some(message),
+        _ => None,
+    });
+    assert_eq!(
+        warning.as_deref(),
+        Some("TV plays through a monitor (HDMI/DisplayPort); while the monitor sleeps the cluster waits up to 5 minutes for it")
+    );
+    assert_eq!(state.graph().clusters.len(), 1);
+}
+
+#[test]
+fn test_profile_switch_rebuilds_the_cluster_for_the_new_node() {
+    // A member's card switching profile keeps the cluster, rebuilt on the new node with its name and delays
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let speaker = register_sink(&state, 10, "alsa_output.usb-speaker", "Speaker");
+    let tv = register_sink(&state, 11, "alsa_output.pci.analog-stereo", "Built-in Audio");
+    state.graph().sink_devices.insert(tv, "alsa_card.pci".to_string());
+    let old_key = cluster_key(&["alsa_output.usb-speaker".to_string(), "alsa_output.pci.analog-stereo".to_string()]);
+    state.store().data.clusters.insert(old_key.clone(), ClusterPrefs {
+        name: Some("Living Room".to_string()),
+        delays: HashMap::from([("alsa_output.pci.analog-stereo".to_string(), 40)]),
+        ..Default::default()
+    });
+    state.store().data.settings.role_targets.insert("Music".to_string(), old_key.clone());
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: speaker, target: tv });
+    rx.try_iter().count();
+    backend.clear_calls();
+
+    // The old node goes, then one of the same card for the new profile comes
+    state.graph().begin_replacement(tv).unwrap();
+    let replaced = state.graph().replace_node("alsa_card.pci", 12, "alsa_output.pci.hdmi-stereo", "HDMI", "HDMI");
+    let (device, old_node_name, _) = replaced.unwrap();
+    PipeWireClient::device_replaced(&state, &tx, device, &old_node_name);
+
+    let delays: Vec<(String, u32)> = backend.calls().into_iter()
+        .filter_map(|call| match call {
+            BackendCall::LoadLoopback { sink, latency_ms, .. } => Some((sink, latency_ms)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(delays, vec![("alsa_output.pci.hdmi-stereo".to_string(), 40)]);
+    let new_key = cluster_key(&["alsa_output.usb-speaker".to_string(), "alsa_output.pci.hdmi-stereo".to_string()]);
+    let cluster = state.graph().cluster_of(tv).expect("still clustered");
+    assert_eq!(state.graph().orbs[&cluster].name, "Living Room");
+    assert_eq!(state.graph().cluster_of(speaker), Some(cluster));
+    assert_eq!(state.store().data.settings.role_targets["Music"], new_key);
+    // Switching back finds the old profile's preferences where they were
+    assert!(state.store().data.clusters.contains_key(&old_key));
+}
+
+#[test]
+fn test_wired_members_wait_for_bluetooth_ones() {
+    // The wired speaker gets the Bluetooth offset on top of its own delay; setting it to 0 rebuilds without
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let bt = register_sink(&state, 10, "bluez_output.AA_BB.1", "Headphones");
+    state.graph().orbs.get_mut(&bt).unwrap().bus = DeviceBus::Bluetooth;
+    let usb = register_sink(&state, 11, "alsa_output.usb-speaker", "Speaker");
+    let key = cluster_key(&["alsa_output.usb-speaker".to_string(), "bluez_output.AA_BB.1".to_string()]);
+    state.store().data.clusters.insert(
+        key.clone(),
+        ClusterPrefs { delays: HashMap::from([("alsa_output.usb-speaker".to_string(), 30)]), ..Default::default() },
+    );
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: usb, target: bt });
+
+    let loopbacks = |calls: Vec<BackendCall>| -> Vec<(String, u32)> {
+        calls.into_iter()
+            .filter_map(|call| match call {
+                BackendCall::LoadLoopback { sink, latency_ms, .. } => Some((sink, latency_ms)),
+                _ => None,
+            })
+            .collect()
+    };
+    assert_eq!(loopbacks(backend.calls()), vec![("alsa_output.usb-speaker".to_string(), 230)]);
+    assert_eq!(state.store().data.clusters[&key].delays["alsa_output.usb-speaker"], 30);
+    rx.try_iter().count();
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetBluetoothOffset { ms: 0 });
+
+    assert_eq!(loopbacks(backend.calls()), vec![("alsa_output.usb-speaker".to_string(), 30)]);
+    assert_eq!(state.graph().clusters.len(), 1);
+    assert_eq!(state.store().data.settings.bluetooth_offset_ms, 0);
+}
+
+#[test]
+fn test_moved_member_keeps_its_place_in_the_cluster() {
+    // Moving a member up rebuilds with the slaves swapped, and the same devices cluster in that order again
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    let left = register_sink(&state, 10, "alsa_output.left", "Left");
+    let right = register_sink(&state, 11, "alsa_output.right", "Right");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: right, target: left });
+    let cluster = cluster_id_from(&rx.try_iter().collect::<Vec<_>>());
+
+    let slaves = |calls: Vec<BackendCall>| calls.into_iter().find_map(|call| match call {
+        BackendCall::LoadCombineSink { slaves, .. } => Some(slaves),
+        _ => None,
+    });
+    assert_eq!(slaves(backend.calls()), Some(vec!["alsa_output.right".to_string(), "alsa_output.left".to_string()]));
+    backend.clear_calls();
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::MoveClusterMember { cluster, device: left, up: true });
+    assert_eq!(slaves(backend.calls()), Some(vec!["alsa_output.left".to_string(), "alsa_output.right".to_string()]));
+    let key = cluster_key(&["alsa_output.left".to_string(), "alsa_output.right".to_string()]);
+    assert_eq!(state.store().data.clusters[&key].order, vec!["alsa_output.left", "alsa_output.right"]);
+
+    // Already first: nothing to rebuild
+    let cluster = state.graph().cluster_of(left).unwrap();
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::MoveClusterMember { cluster, device: left, up: true });
+    assert!(backend.calls().is_empty());
+
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Disconnect { source: cluster, target: cluster });
+    backend.clear_calls();
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: right, target: left });
+    assert_eq!(slaves(backend.calls()), Some(vec!["alsa_output.left".to_string(), "alsa_output.right".to_string()]));
+}
@@ -0,0 +1,143 @@
+// Test file for layout bundles (export/import)
+// Builds bundles in memory and through files in a temp dir
+
+use auralis_core::bundle::{self, Bundle, BUNDLE_VERSION};
+use auralis_core::persistence::{ClusterPrefs, PersistedState, StateStore, STATE_VERSION};
+use auralis_core::{Profile, Profiles, TurnServer};
+use std::path::PathBuf;
+
+/// Fresh directory of its own for one test
+fn test_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("auralis-test-{}-bundle-{}", std::process::id(), test));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn kitchen_state() -> PersistedState {
+    let mut state = PersistedState::default();
+    state.clusters.insert("alsa_output.a,bluez_output.b".to_string(), ClusterPrefs {
+        name: Some("Kitchen".to_string()),
+        ..Default::default()
+    });
+    state.settings.role_targets.insert("music".to_string(), "alsa_output.a,bluez_output.b".to_string());
+    state.settings.canvas_positions.insert("alsa_output.a".to_string(), (120.0, 80.0));
+    state.settings.ignored_devices.push("hdmi_output.tv".to_string());
+    state.settings.beam_turn = Some(TurnServer {
+        url: "turn:relay.example.org".to_string(),
+        username: "me".to_string(),
+        credential: "secret".to_string(),
+    });
+    state
+}
+
+fn movie_profiles() -> Profiles {
+    let mut profiles = Profiles::default();
+    profiles.profiles.insert("Movie Night".to_string(), Profile {
+        clusters: vec![vec!["Speakers".to_string(), "Subwoofer".to_string()]],
+        default_sink: Some("Speakers".to_string()),
+    });
+    profiles
+}
+
+#[test]
+fn test_bundle_round_trip_leaves_out_password() {
+    // Everything survives a round trip except the TURN password, which never leaves the machine
+    let bundle = Bundle::new(&kitchen_state(), &movie_profiles());
+    let json = bundle.to_json().unwrap();
+    assert!(!json.contains("secret"));
+
+    let parsed = Bundle::parse(&json).unwrap();
+    assert_eq!(parsed, bundle);
+    assert_eq!(parsed.version, BUNDLE_VERSION);
+    assert_eq!(parsed.state.clusters["alsa_output.a,bluez_output.b"].name.as_deref(), Some("Kitchen"));
+    assert_eq!(parsed.profiles["Movie Night"].default_sink.as_deref(), Some("Speakers"));
+    assert_eq!(parsed.state.settings.beam_turn.unwrap().username, "me");
+}
+
+#[test]
+fn test_bundle_rejects_newer_and_foreign_files() {
+    // A bundle from a newer Auralis and JSON that isn't a bundle at all are refused
+    let newer = format!(r#"{{ "version": {}, "state": {{ "version": 1 }} }}"#, BUNDLE_VERSION + 1);
+    assert!(Bundle::parse(&newer).unwrap_err().to_string().contains("newer Auralis"));
+    assert!(Bundle::parse(r#"{ "clusters": {} }"#).unwrap_err().to_string().contains("not an Auralis layout bundle"));
+    assert!(Bundle::parse("not json").is_err());
+}
+
+#[test]
+fn test_bundle_migrates_old_state() {
+    // The state inside is upgraded like a state file, even from before its version field
+    let contents = r#"{
+      "version": 1,
+      "state": {
+        "clusters": { "alsa_output.a,bluez_output.b": { "name": "Kitchen" } },
+        "settings": { "dropout_grace_ms": 5000 }
+      }
+    }"#;
+
+    let bundle = Bundle::parse(contents).unwrap();
+    assert_eq!(bundle.state.version, STATE_VERSION);
+    assert_eq!(bundle.state.settings.dropout_grace_ms, 5000);
+    assert!(bundle.profiles.is_empty());
+}
+
+#[test]
+fn test_bundle_merge_keeps_local_settings() {
+    // Merging adds clusters and profiles; local settings win and lists aren't duplicated
+    let mut state = PersistedState::default();
+    state.clusters.insert("alsa_output.c,alsa_output.d".to_string(), ClusterPrefs::default());
+    state.settings.ignored_devices.push("hdmi_output.tv".to_string());
+    state.settings.dropout_grace_ms = 1234;
+    let mut profiles = Profiles::default();
+    profiles.profiles.insert("Party".to_string(), Profile::default());
+
+    let summary = Bundle::new(&kitchen_state(), &movie_profiles()).apply(&mut state, &mut profiles, false);
+
+    assert_eq!((summary.clusters, summary.profiles), (1, 1));
+    assert_eq!(state.clusters.len(), 2);
+    assert_eq!(state.settings.dropout_grace_ms, 1234);
+    assert_eq!(state.settings.ignored_devices, vec!["hdmi_output.tv".to_string()]);
+    assert_eq!(state.settings.role_targets["music"], "alsa_output.a,bluez_output.b");
+    assert_eq!(state.settings.canvas_positions["alsa_output.a"], (120.0, 80.0));
+    assert!(state.settings.beam_turn.is_none());
+    assert_eq!(profiles.profiles.len(), 2);
+}
+
+#[test]
+fn test_bundle_replace_keeps_password_for_same_relay() {
+    // Replacing takes the bundle as is, but the local password stays for the same relay
+    let mut state = kitchen_state();
+    state.clusters.insert("alsa_output.c,alsa_output.d".to_string(), ClusterPrefs::default());
+    let mut profiles = Profiles::default();
+    profiles.profiles.insert("Party".to_string(), Profile::default());
+
+    Bundle::new(&kitchen_state(), &movie_profiles()).apply(&mut state, &mut profiles, true);
+
+    assert_eq!(state.clusters.len(), 1);
+    assert_eq!(state.settings.beam_turn.unwrap().credential, "secret");
+    assert_eq!(profiles, movie_profiles());
+}
+
+#[test]
+fn test_export_import_files() {
+    // A layout exported on one machine is imported into another's state.json and profiles.toml
+    let dir = test_dir("files");
+    let (bundle_path, profiles_path) = (dir.join("layout.json"), dir.join("profiles.toml"));
+    movie_profiles().save(&profiles_path).unwrap();
+    let mut source = StateStore::in_memory();
+    source.data = kitchen_state();
+    bundle::export(&source, Some(&profiles_path), &bundle_path).unwrap();
+
+    let (state_path, other_profiles) = (dir.join("other").join("state.json"), dir.join("other").join("profiles.toml"));
+    let mut target = StateStore::load(state_path.clone());
+    let summary = bundle::import(&mut target, Some(&other_profiles), &bundle_path, false).unwrap();
+
+    assert_eq!((summary.clusters, summary.profiles), (1, 1));
+    assert_eq!(StateStore::load(state_path).data.clusters.len(), 1);
+    assert_eq!(Profiles::load(&other_profiles).unwrap(), movie_profiles());
+
+    // A file that isn't a bundle changes nothing
+    std::fs::write(&bundle_path, "{}").unwrap();
+    assert!(bundle::import(&mut target, Some(&other_profiles), &bundle_path, true).is_err());
+    assert_eq!(target.data.clusters.len(), 1);
+}
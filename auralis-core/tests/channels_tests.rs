@@ -0,0 +1,30 @@
+// Test file for cluster channel layouts
+// Which layout a set of members gets by default, and which ones they can be given
+
+use auralis_core::channels::{common_layout, label, validate};
+
+#[test]
+fn test_common_layout_follows_narrowest_member() {
+    // Surround only when every member has it; unknown members count as stereo
+    assert_eq!(common_layout(&[Some(6), Some(8)]).0, 6);
+    assert_eq!(common_layout(&[Some(6), Some(2)]).0, 2);
+    assert_eq!(common_layout(&[Some(1), Some(1)]), (1, "mono"));
+    assert_eq!(common_layout(&[Some(6), None]).0, 2);
+    // 3 channels isn't a standard layout, stereo is the widest that fits
+    assert_eq!(common_layout(&[Some(3), Some(6)]).0, 2);
+}
+
+#[test]
+fn test_validate_layout() {
+    // Standard layouts up to the widest member are fine, anything else is refused with a reason
+    assert_eq!(validate(6, &[Some(6), Some(2)]).unwrap(), "front-left,front-right,front-center,lfe,rear-left,rear-right");
+    assert_eq!(validate(1, &[Some(2), Some(2)]).unwrap(), "mono");
+
+    let err = validate(5, &[Some(6)]).unwrap_err();
+    assert!(err.to_string().contains("no standard speaker layout"));
+    let err = validate(8, &[Some(6), Some(2)]).unwrap_err();
+    assert!(err.to_string().contains("widest member has 6"));
+
+    assert_eq!(label(6), "5.1");
+    assert_eq!(label(3), "3 channels");
+}
@@ -0,0 +1,181 @@
+// Test file for a whole PipeWireClient running on the MockBackend
+// Commands go through the real command thread; discovery is off and orbs are injected
+
+mod common;
+
+use auralis_core::instance_lock::InstanceLock;
+use auralis_core::system_audio::{BackendCall, ModuleInfo};
+use auralis_core::{AuralisConfig, MockBackend, OrbEvent, OrbKind, PipeWireClient, UiCommand};
+use common::sink;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn offline_config() -> AuralisConfig {
+    AuralisConfig { state_path: None, profiles_path: None, discovery: false, lock_path: None, ..Default::default() }
+}
+
+/// First event `pick` accepts, waiting for the command thread
+fn wait_for<T>(rx: &Receiver<OrbEvent>, pick: impl Fn(OrbEvent) -> Option<T>) -> Option<T> {
+    let deadline = Instant::now() + EVENT_TIMEOUT;
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        if let Some(found) = rx.recv_timeout(left).ok().and_then(&pick) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+#[test]
+fn test_client_connect_creates_cluster() {
+    // Two injected sinks dropped onto each other become one combine-sink and one cluster orb
+    let backend = Arc::new(MockBackend::new());
+    let (event_tx, event_rx) = channel();
+    let (cmd_tx, cmd_rx) = channel();
+    let client = PipeWireClient::with_backend(event_tx, cmd_rx, offline_config(), backend.clone()).unwrap();
+
+    let (a, b) = (sink(10, "alsa_output.speakers", "Speakers"), sink(11, "bluez_output.headphones", "Headphones"));
+    let (a_id, b_id) = (a.id, b.id);
+    client.inject_orb(a);
+    client.inject_orb(b);
+    cmd_tx.send(UiCommand::Connect { source: a_id, target: b_id }).unwrap();
+
+    let cluster = wait_for(&event_rx, |event| match event {
+        OrbEvent::Add(orb) if matches!(orb.kind, OrbKind::Cluster { .. }) => Some(orb),
+        _ => None,
+    });
+    assert!(cluster.is_some(), "no cluster orb was announced");
+
+    let slaves = backend.calls().into_iter().find_map(|call| match call {
+        BackendCall::LoadCombineSink { slaves, .. } => Some(slaves),
+        _ => None,
+    });
+    assert_eq!(slaves, Some(vec!["alsa_output.speakers".to_string(), "bluez_output.headphones".to_string()]));
+}
+
+#[test]
+fn test_client_injected_orb_is_announced() {
+    // An injected orb reaches the UI like a discovered one and shows up in snapshots
+    let (event_tx, event_rx) = channel();
+    let (_cmd_tx, cmd_rx) = channel();
+    let client = PipeWireClient::with_backend(event_tx, cmd_rx, offline_config(), Arc::new(MockBackend::new())).unwrap();
+
+    let orb = sink(10, "alsa_output.speakers", "Speakers");
+    let id = orb.id;
+    client.inject_orb(orb);
+
+    assert_eq!(wait_for(&event_rx, |event| match event {
+        OrbEvent::Add(orb) => Some(orb.id),
+        _ => None,
+    }), Some(id));
+    assert!(client.snapshot().orbs.contains_key(&id));
+}
+
+#[test]
+fn test_client_graph_view_is_current() {
+    // The read-only view has a change by the time its event arrives, and never lags behind
+    let (event_tx, event_rx) = channel();
+    let (cmd_tx, cmd_rx) = channel();
+    let client = PipeWireClient::with_backend(event_tx, cmd_rx, offline_config(), Arc::new(MockBackend::new())).unwrap();
+    let view = client.graph_view();
+    assert!(view.read().orbs.is_empty());
+
+    let (a, b) = (sink(10, "alsa_output.speakers", "Speakers"), sink(11, "bluez_output.headphones", "Headphones"));
+    let (a_id, b_id) = (a.id, b.id);
+    client.inject_orb(a);
+    client.inject_orb(b);
+    assert!(view.read().orbs.contains_key(&a_id) && view.read().orbs.contains_key(&b_id));
+
+    cmd_tx.send(UiCommand::Connect { source: a_id, target: b_id }).unwrap();
+    let cluster = wait_for(&event_rx, |event| match event {
+        OrbEvent::Add(orb) if matches!(orb.kind, OrbKind::Cluster { .. }) => Some(orb.id),
+        _ => None,
+    }).expect("no cluster orb was announced");
+    let graph = view.read();
+    assert!(graph.clusters.contains_key(&cluster));
+    assert_eq!(graph.orbs[&a_id].membership(), Some(cluster));
+}
+
+/// Lock file path in a directory of its own
+fn lock_path(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("auralis-test-{}-instance-{}", std::process::id(), test));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("auralis.lock")
+}
+
+#[test]
+fn test_client_refuses_second_instance() {
+    // While another instance holds the lock, a client doesn't start and says why
+    let path = lock_path("refuse");
+    let _first = InstanceLock::acquire(&path).unwrap();
+
+    let (event_tx, _event_rx) = channel();
+    let (_cmd_tx, cmd_rx) = channel();
+    let config = AuralisConfig { lock_path: Some(path), ..offline_config() };
+    let err = PipeWireClient::with_backend(event_tx, cmd_rx, config, Arc::new(MockBackend::new())).err().unwrap();
+    assert!(err.to_string().contains("already running (PID"), "{}", err);
+}
+
+#[test]
+fn test_client_allowed_second_instance_leaves_modules() {
+    // An intentional second instance starts, but never sweeps the first one's modules
+    let path = lock_path("allow");
+    let _first = InstanceLock::acquire(&path).unwrap();
+
+    let backend = Arc::new(MockBackend::new());
+    backend.add_module(ModuleInfo {
+        id: 7,
+        name: "module-combine-sink".to_string(),
+        args: "sink_name=auralis_cluster_theirs slaves=alsa_output.a".to_string(),
+    });
+    let (event_tx, event_rx) = channel();
+    let (_cmd_tx, cmd_rx) = channel();
+    let config = AuralisConfig { lock_path: Some(path), allow_multiple: true, ..offline_config() };
+    let _client = PipeWireClient::with_backend(event_tx, cmd_rx, config, backend.clone()).unwrap();
+
+    assert!(!backend.calls().contains(&BackendCall::UnloadModule(7)));
+    assert!(event_rx.try_iter().any(|e| matches!(e, OrbEvent::Error { message } if message.contains("already running"))));
+}
+
+#[test]
+fn test_client_safe_mode_skips_sweep() {
+    // Safe mode leaves even our own leftovers loaded
+    let backend = Arc::new(MockBackend::new());
+    backend.add_module(ModuleInfo {
+        id: 7,
+        name: "module-combine-sink".to_string(),
+        args: "sink_name=auralis_cluster_old slaves=alsa_output.a".to_string(),
+    });
+    let (event_tx, _event_rx) = channel();
+    let (_cmd_tx, cmd_rx) = channel();
+    let config = AuralisConfig { safe_mode: true, ..offline_config() };
+    let _client = PipeWireClient::with_backend(event_tx, cmd_rx, config, backend.clone()).unwrap();
+
+    assert!(!backend.calls().contains(&BackendCall::UnloadModule(7)));
+}
+
+#[test]
+fn test_client_reports_missing_tools_once_at_startup() {
+    // A missing tool is named up front, with the package to install
+    let backend = Arc::new(MockBackend::new());
+    backend.uninstall("pw-link");
+    backend.uninstall("pactl");
+    let (event_tx, event_rx) = channel();
+    let (_cmd_tx, cmd_rx) = channel();
+    let _client = PipeWireClient::with_backend(event_tx, cmd_rx, offline_config(), backend).unwrap();
+
+    let errors: Vec<String> = event_rx.try_iter()
+        .filter_map(|e| match e {
+            OrbEvent::Error { message } => Some(message),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        vec!["Some actions won't work: pw-link not found; install pipewire-utils, pactl not found; install pulseaudio-utils"]
+    );
+}
@@ -0,0 +1,45 @@
+// Helpers shared by the test files, pulled in with `mod common;`
+// Each file uses only some of them
+#![allow(dead_code)]
+
+use auralis_core::pipewire_client::SharedState;
+use auralis_core::{DeviceBus, Orb, OrbKind, OrbState};
+use uuid::Uuid;
+
+/// Floating orb of the given kind, named after its node; tests change what they need
+pub fn orb(pw_id: u32, node_name: &str, kind: OrbKind) -> Orb {
+    Orb {
+        id: Uuid::new_v4(),
+        pw_id,
+        node_name: node_name.to_string(),
+        kind,
+        name: node_name.to_string(),
+        icon_name: "audio-card".to_string(),
+        status: "Active".to_string(),
+        state: OrbState::Floating,
+        zone: None,
+        latency_ms: None,
+        bus: DeviceBus::Unknown,
+        channels: None,
+        formats: Vec::new(),
+        format: None,
+        position: (0.0, 0.0),
+        velocity: (0.0, 0.0),
+    }
+}
+
+/// Output device shown as `description`
+pub fn sink(pw_id: u32, node_name: &str, description: &str) -> Orb {
+    Orb {
+        name: description.to_string(),
+        ..orb(pw_id, node_name, OrbKind::PhysicalSink { description: description.to_string() })
+    }
+}
+
+/// Add an output device to the graph, as discovery would
+pub fn register_sink(state: &SharedState, pw_id: u32, node_name: &str, description: &str) -> Uuid {
+    let sink = sink(pw_id, node_name, description);
+    let id = sink.id;
+    state.graph().add_orb(sink);
+    id
+}
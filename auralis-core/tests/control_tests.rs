@@ -0,0 +1,123 @@
+// Test file for the control socket's JSON-RPC requests
+// Answers come from a mock-backed state; one test goes through a real socket in a temp dir
+
+mod common;
+
+use auralis_core::control;
+use auralis_core::pipewire_client::SharedState;
+use auralis_core::{MockBackend, UiCommand};
+use serde_json::{json, Value};
+use common::sink;
+use std::path::PathBuf;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn socket_path(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("auralis-test-{}-control-{}", std::process::id(), test));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("auralis.sock")
+}
+
+#[test]
+fn test_list_answers_with_every_orb() {
+    // Orbs with their kind and node name, under the request's id
+    let state = SharedState::new(Arc::new(MockBackend::new()));
+    let orb = sink(10, "alsa_output.speakers", "Speakers");
+    let id = orb.id;
+    state.graph().add_orb(orb);
+    let (tx, _rx) = channel();
+
+    let response = control::respond(&state, &tx, r#"{"jsonrpc": "2.0", "id": 7, "method": "list"}"#);
+
+    assert_eq!(response, json!({
+        "jsonrpc": "2.0",
+        "id": 7,
+        "result": [{ "id": id.to_string(), "kind": "output", "name": "Speakers", "node_name": "alsa_output.speakers", "cluster": null }],
+    }));
+}
+
+#[test]
+fn test_command_is_queued_like_one_from_the_ui() {
+    // The params are a UiCommand as serde writes it
+    let state = SharedState::new(Arc::new(MockBackend::new()));
+    let (tx, rx) = channel();
+    let (source, target) = (Uuid::new_v4(), Uuid::new_v4());
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "command", "params": { "Connect": { "source": source, "target": target } } });
+
+    let response = control::respond(&state, &tx, &request.to_string());
+
+    assert_eq!(response["result"], Value::Null);
+    assert!(matches!(rx.try_recv(), Ok(UiCommand::Connect { source: s, target: t }) if s == source && t == target));
+    control::respond(&state, &tx, r#"{"id": 2, "method": "command", "params": "ResetAll"}"#);
+    assert!(matches!(rx.try_recv(), Ok(UiCommand::ResetAll)));
+}
+
+#[test]
+fn test_bad_requests_get_json_rpc_errors() {
+    // Broken JSON, unknown methods and unknown commands each have their code
+    let state = SharedState::new(Arc::new(MockBackend::new()));
+    let (tx, rx) = channel();
+    let code = |line: &str| control::respond(&state, &tx, line)["error"]["code"].as_i64();
+
+    assert_eq!(code("{not json"), Some(-32700));
+    assert_eq!(code(r#"{"id": 1}"#), Some(-32600));
+    assert_eq!(code(r#"{"id": 1, "method": "reboot"}"#), Some(-32601));
+    assert_eq!(code(r#"{"id": 1, "method": "command", "params": {"Explode": {}}}"#), Some(-32602));
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn test_call_talks_to_a_running_socket() {
+    // What auralis-cli does when Auralis is already running
+    let state = SharedState::new(Arc::new(MockBackend::new()));
+    state.graph().add_orb(sink(10, "alsa_output.speakers", "Speakers"));
+    let (tx, _rx) = channel();
+    let path = socket_path("call");
+
+    control::serve(&path, state, tx).unwrap();
+
+    let listed = control::call(&path, "list", Value::Null).unwrap();
+    assert_eq!(listed[0]["name"], "Speakers");
+    let unknown = control::call(&path, "reboot", Value::Null).unwrap_err();
+    assert_eq!(unknown.to_string(), "Unknown method 'reboot'");
+    // A second one doesn't take over the socket
+    let (tx, _rx) = channel();
+    assert!(control::serve(&path, SharedState::new(Arc::new(MockBackend::new())), tx).is_err());
+}
+
+#[test]
+fn test_snapshot_in_either_dump_format() {
+    // JSON as an object, Graphviz as a string, like `auralis-cli dump --format`
+    let state = SharedState::new(Arc::new(MockBackend::new()));
+    state.graph().add_orb(sink(10, "alsa_output.speakers", "Speakers"));
+    let (tx, _rx) = channel();
+
+    let json = control::respond(&state, &tx, r#"{"id": 1, "method": "snapshot"}"#);
+    assert!(json["result"].is_object());
+    let dot = control::respond(&state, &tx, r#"{"id": 2, "method": "snapshot", "params": {"format": "dot"}}"#);
+    assert!(dot["result"].as_str().is_some_and(|dot| dot.starts_with("digraph") && dot.contains("Speakers")));
+    let svg = control::respond(&state, &tx, r#"{"id": 3, "method": "snapshot", "params": {"format": "svg"}}"#);
+    assert_eq!(svg["error"]["code"], -32602);
+}
+
+#[test]
+fn test_apply_answers_with_the_plan_and_queues_it() {
+    // The steps come back right away; a layout already in place queues nothing
+    let state = SharedState::new(Arc::new(MockBackend::new()));
+    state.graph().add_orb(sink(10, "alsa_output.a", "A"));
+    state.graph().add_orb(sink(10, "alsa_output.b", "B"));
+    let (tx, rx) = channel();
+
+    let request = json!({ "id": 1, "method": "apply", "params": { "clusters": [{ "devices": ["A", "B"] }] } });
+    let response = control::respond(&state, &tx, &request.to_string());
+
+    assert_eq!(response["result"], json!(["Cluster alsa_output.a, alsa_output.b"]));
+    assert!(matches!(rx.try_recv(), Ok(UiCommand::ApplyTopology { .. })));
+    let empty = control::respond(&state, &tx, r#"{"id": 2, "method": "apply", "params": {}}"#);
+    assert_eq!(empty["result"], json!([]));
+    assert!(rx.try_recv().is_err());
+    let missing = control::respond(&state, &tx, r#"{"id": 3, "method": "apply", "params": {"default_sink": "C"}}"#);
+    assert_eq!(missing["error"], json!({ "code": -32602, "message": "No device 'C'" }));
+}
@@ -0,0 +1,178 @@
+// Test file for the registry discovery filters
+// Feeds synthetic PipeWire property maps through classify_global
+
+use auralis_core::discovery::{classify_global, display_name, DiscoveryDecision};
+use auralis_core::sink_names;
+use auralis_core::DeviceBus;
+use std::collections::HashMap;
+
+fn props(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+fn classify(pairs: &[(&str, &str)]) -> Option<DiscoveryDecision> {
+    classify_global(&props(pairs), |_| false)
+}
+
+#[test]
+fn test_classify_normal_sink() {
+    // A regular output becomes a device orb with its bus detected
+    let decision = classify(&[
+        ("media.class", "Audio/Sink"),
+        ("node.name", "bluez_output.AA_BB.1"),
+        ("node.description", "Headphones"),
+        ("device.api", "bluez5"),
+        ("audio.channels", "2"),
+    ]);
+
+    assert_eq!(decision, Some(DiscoveryDecision::AddSink {
+        node_name: "bluez_output.AA_BB.1".to_string(),
+        description: "Headphones".to_string(),
+        bus: DeviceBus::Bluetooth,
+        channels: Some(2),
+        device: None,
+    }));
+}
+
+#[test]
+fn test_classify_stream() {
+    // Playback streams become app orbs named after the application
+    let decision = classify(&[
+        ("media.class", "Stream/Output/Audio"),
+        ("node.name", "Firefox"),
+        ("application.name", "Firefox"),
+    ]);
+
+    assert_eq!(decision, Some(DiscoveryDecision::AddStream {
+        node_name: "Firefox".to_string(),
+        app_name: "Firefox".to_string(),
+        description: "Firefox".to_string(),
+        channels: None,
+        media_role: None,
+    }));
+}
+
+#[test]
+fn test_display_name_precedence() {
+    // The friendliest property wins, empty ones are skipped and node.name is the last resort
+    let sink = |pairs: &[(&str, &str)]| display_name(&props(pairs), true);
+    let stream = |pairs: &[(&str, &str)]| display_name(&props(pairs), false);
+    let raw = ("node.name", "alsa_output.pci-0000_00_1f.3.analog-stereo");
+
+    assert_eq!(sink(&[raw, ("node.nick", "Built-in Audio"), ("node.description", "Built-in Audio Analog Stereo")]), "Built-in Audio Analog Stereo");
+    assert_eq!(sink(&[raw, ("node.nick", "Built-in Audio"), ("device.description", "HDA Intel PCH")]), "Built-in Audio");
+    assert_eq!(sink(&[raw, ("node.description", " "), ("device.description", "HDA Intel PCH")]), "HDA Intel PCH");
+    assert_eq!(sink(&[raw, ("media.name", "Playback")]), "alsa_output.pci-0000_00_1f.3.analog-stereo");
+
+    assert_eq!(stream(&[("node.name", "Firefox"), ("application.name", "Firefox"), ("media.name", "Song")]), "Firefox");
+    assert_eq!(stream(&[("node.name", "stream.42"), ("media.name", "Song"), ("node.description", "Player")]), "Song");
+    assert_eq!(stream(&[("node.name", "stream.42"), ("application.name", ""), ("node.nick", "Player")]), "Player");
+    assert_eq!(stream(&[]), "Unknown");
+}
+
+#[test]
+fn test_classify_keeps_node_name_for_routing() {
+    // A sink without a description is shown by its nick but still routed by node.name
+    let decision = classify(&[
+        ("media.class", "Audio/Sink"),
+        ("node.name", "alsa_output.pci-0000_00_1f.3.analog-stereo"),
+        ("node.nick", "Built-in Audio"),
+    ]);
+
+    assert!(matches!(decision, Some(DiscoveryDecision::AddSink { node_name, description, .. })
+        if node_name == "alsa_output.pci-0000_00_1f.3.analog-stereo" && description == "Built-in Audio"));
+}
+
+#[test]
+fn test_classify_ignores_mutter_and_dummy() {
+    // System sounds and dummy outputs never show up
+    let mutter = classify(&[
+        ("media.class", "Stream/Output/Audio"),
+        ("node.name", "event-stream"),
+        ("application.name", "Mutter"),
+    ]);
+    let dummy = classify(&[("media.class", "Audio/Sink"), ("node.name", "auto_null.Dummy-Output")]);
+
+    assert_eq!(mutter, Some(DiscoveryDecision::Ignore));
+    assert_eq!(dummy, Some(DiscoveryDecision::Ignore));
+}
+
+#[test]
+fn test_classify_ignores_formatless_sinks() {
+    // Placeholder sinks reporting zero or garbage channels are skipped; not reporting any is fine
+    for channels in ["0", "", "stereo"] {
+        let decision = classify(&[("media.class", "Audio/Sink"), ("node.name", "alsa_output.ghost"), ("audio.channels", channels)]);
+        assert_eq!(decision, Some(DiscoveryDecision::Ignore), "{:?}", channels);
+    }
+    let decision = classify(&[("media.class", "Audio/Sink"), ("node.name", "alsa_output.quiet")]);
+    assert!(matches!(decision, Some(DiscoveryDecision::AddSink { channels: None, .. })));
+}
+
+#[test]
+fn test_classify_sink_card() {
+    // A sink knows its card by device.name, or by the device object when the name is missing
+    let card = |extra: &[(&str, &str)]| {
+        let mut pairs = vec![("media.class", "Audio/Sink"), ("node.name", "alsa_output.pci.hdmi-stereo")];
+        pairs.extend_from_slice(extra);
+        match classify(&pairs) {
+            Some(DiscoveryDecision::AddSink { device, .. }) => device,
+            other => panic!("not a sink: {:?}", other),
+        }
+    };
+
+    assert_eq!(card(&[("device.name", "alsa_card.pci"), ("device.id", "51")]).as_deref(), Some("alsa_card.pci"));
+    assert_eq!(card(&[("device.id", "51")]).as_deref(), Some("device:51"));
+    assert_eq!(card(&[]), None);
+}
+
+#[test]
+fn test_classify_own_sinks() {
+    // Auralis' combine, delay and mono sinks are recognised, not added
+    for name in ["auralis_cluster_1234", "auralis_combined_1", "auralis_delay_ab", "auralis_mono_cd"] {
+        let decision = classify(&[("media.class", "Audio/Sink"), ("node.name", name)]);
+        assert_eq!(decision, Some(DiscoveryDecision::OwnSink(name.to_string())));
+    }
+}
+
+#[test]
+fn test_classify_parks_cluster_member() {
+    // A sink that's hidden inside a cluster is parked, streams with the same name are not
+    let member = props(&[("media.class", "Audio/Sink"), ("node.name", "alsa_output.a")]);
+    let decision = classify_global(&member, |name| name == "alsa_output.a");
+    assert_eq!(decision, Some(DiscoveryDecision::Park("alsa_output.a".to_string())));
+
+    let stream = props(&[("media.class", "Stream/Output/Audio"), ("node.name", "alsa_output.a")]);
+    let decision = classify_global(&stream, |name| name == "alsa_output.a");
+    assert!(matches!(decision, Some(DiscoveryDecision::AddStream { .. })));
+}
+
+#[test]
+fn test_classify_other_objects() {
+    // Sources, video and objects without a media class aren't ours to classify
+    assert_eq!(classify(&[("media.class", "Audio/Source"), ("node.name", "mic")]), None);
+    assert_eq!(classify(&[("media.class", "Video/Source"), ("node.name", "cam")]), None);
+    assert_eq!(classify(&[("node.name", "x")]), None);
+}
+
+#[test]
+fn test_fresh_sink_name_skips_taken_names() {
+    // A taken name is never handed out; a new one is drawn instead
+    let tries = std::cell::Cell::new(0);
+    let name = sink_names::fresh(sink_names::CLUSTER, |_| {
+        tries.set(tries.get() + 1);
+        tries.get() < 3
+    });
+
+    assert_eq!(tries.get(), 3);
+    assert!(name.starts_with(sink_names::CLUSTER));
+    assert!(sink_names::is_own(&name));
+    assert!(!sink_names::is_own("alsa_output.a"));
+}
+
+#[test]
+fn test_own_sink_names_need_a_known_kind() {
+    // A user's sink that merely starts with "auralis_" is not ours
+    for name in ["auralis_music", "auralis_cluster_", "auralis_delay_my sink", "auralis_mono_x.monitor"] {
+        assert!(!sink_names::is_own(name), "{} taken for ours", name);
+    }
+}
@@ -0,0 +1,92 @@
+// Test file for the queue that holds core events for a stalled UI
+// Pushes event sequences and checks what's left to deliver
+
+mod common;
+
+use auralis_core::event_queue::{EventQueue, QueueStats};
+use auralis_core::{OrbEvent, Settings};
+use common::sink;
+use std::sync::Arc;
+
+fn drain(queue: &mut EventQueue) -> Vec<OrbEvent> {
+    std::iter::from_fn(|| queue.pop()).collect()
+}
+
+#[test]
+fn test_queue_folds_updates_into_waiting_add() {
+    // Updates of a waiting orb end up in its Add; other orbs and events keep their order
+    let stats = Arc::new(QueueStats::default());
+    let mut queue = EventQueue::new(100, stats.clone());
+    let (mut a, b) = (sink(10, "alsa_output.a", "alsa_output.a"), sink(10, "alsa_output.b", "alsa_output.b"));
+
+    queue.push(OrbEvent::Add(a.clone()));
+    queue.push(OrbEvent::Add(b.clone()));
+    a.status = "Running".to_string();
+    queue.push(OrbEvent::Update(a.clone()));
+    a.status = "Suspended".to_string();
+    queue.push(OrbEvent::Update(a.clone()));
+
+    let events = drain(&mut queue);
+    assert_eq!(events.len(), 2);
+    assert!(matches!(&events[0], OrbEvent::Add(orb) if orb.id == a.id && orb.status == "Suspended"));
+    assert!(matches!(&events[1], OrbEvent::Add(orb) if orb.id == b.id));
+    assert_eq!((stats.coalesced(), stats.dropped()), (2, 0));
+}
+
+#[test]
+fn test_queue_remove_and_clear_discard_waiting_orbs() {
+    // A Remove takes the orb's waiting events with it, a Clear every waiting orb event
+    let stats = Arc::new(QueueStats::default());
+    let mut queue = EventQueue::new(100, stats.clone());
+    let (a, b) = (sink(10, "alsa_output.a", "alsa_output.a"), sink(10, "alsa_output.b", "alsa_output.b"));
+
+    queue.push(OrbEvent::Add(a.clone()));
+    queue.push(OrbEvent::Add(b.clone()));
+    queue.push(OrbEvent::Remove(a.id));
+    let events = drain(&mut queue);
+    assert_eq!(events.len(), 2);
+    assert!(matches!(&events[0], OrbEvent::Add(orb) if orb.id == b.id));
+    assert!(matches!(&events[1], OrbEvent::Remove(id) if *id == a.id));
+
+    queue.push(OrbEvent::Add(a.clone()));
+    queue.push(OrbEvent::Error { message: "oops".to_string() });
+    queue.push(OrbEvent::Remove(b.id));
+    queue.push(OrbEvent::Clear);
+    let events = drain(&mut queue);
+    assert_eq!(events.len(), 2);
+    assert!(matches!(&events[0], OrbEvent::Error { .. }));
+    assert!(matches!(&events[1], OrbEvent::Clear));
+    assert_eq!(stats.coalesced(), 3);
+}
+
+#[test]
+fn test_queue_keeps_latest_snapshot() {
+    // Only the newest settings and command counts are delivered
+    let stats = Arc::new(QueueStats::default());
+    let mut queue = EventQueue::new(100, stats.clone());
+
+    queue.push(OrbEvent::CommandStatus { in_flight: 1, queued: 0 });
+    queue.push(OrbEvent::Settings(Settings::default()));
+    queue.push(OrbEvent::CommandStatus { in_flight: 0, queued: 0 });
+    queue.push(OrbEvent::Settings(Settings { dropout_grace_ms: 1234, ..Settings::default() }));
+
+    let events = drain(&mut queue);
+    assert_eq!(events.len(), 2);
+    assert!(matches!(&events[0], OrbEvent::CommandStatus { in_flight: 0, queued: 0 }));
+    assert!(matches!(&events[1], OrbEvent::Settings(settings) if settings.dropout_grace_ms == 1234));
+    assert_eq!(stats.coalesced(), 2);
+}
+
+#[test]
+fn test_queue_drops_oldest_past_limit() {
+    // Memory stays bounded: past the limit the oldest events go, and are counted
+    let stats = Arc::new(QueueStats::default());
+    let mut queue = EventQueue::new(3, stats.clone());
+
+    for i in 0..5 {
+        queue.push(OrbEvent::Error { message: i.to_string() });
+    }
+    assert_eq!(queue.len(), 3);
+    assert!(matches!(queue.pop(), Some(OrbEvent::Error { message }) if message == "2"));
+    assert_eq!((stats.coalesced(), stats.dropped()), (0, 2));
+}
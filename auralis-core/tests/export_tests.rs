@@ -0,0 +1,68 @@
+// Test file for graph dumps (DOT/JSON)
+// Verifies the export contains what a bug report needs
+
+mod common;
+
+use auralis_core::export::export_graph;
+use auralis_core::pipewire_client::SharedState;
+use auralis_core::{ExportFormat, MockBackend, PipeWireClient, UiCommand};
+use common::register_sink;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn clustered_state() -> (SharedState, Uuid) {
+    let state = SharedState::new(Arc::new(MockBackend::new()));
+    let (tx, rx) = channel();
+
+    let mut ids = Vec::new();
+    for (pw_id, node_name) in [(10, "alsa_output.a"), (11, "alsa_output.b")] {
+        ids.push(register_sink(&state, pw_id, node_name, node_name));
+    }
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: ids[0], target: ids[1] });
+    drop(rx);
+
+    let cluster = *state.graph().clusters.keys().next().expect("cluster");
+    (state, cluster)
+}
+
+#[test]
+fn test_export_format_parsing() {
+    // The CLI passes the format as a string
+    assert_eq!("dot".parse::<ExportFormat>().unwrap(), ExportFormat::Dot);
+    assert_eq!("json".parse::<ExportFormat>().unwrap(), ExportFormat::Json);
+    assert!("yaml".parse::<ExportFormat>().is_err());
+}
+
+#[test]
+fn test_json_export_lists_cluster_members_and_modules() {
+    // Clusters carry their module and members by node name
+    let (state, cluster) = clustered_state();
+    let graph = state.snapshot();
+    let module_id = graph.clusters[&cluster].module_id;
+
+    let dump = export_graph(&graph, Some("alsa_output.a"), ExportFormat::Json);
+    let value: serde_json::Value = serde_json::from_str(&dump).unwrap();
+
+    assert_eq!(value["default_sink"], "alsa_output.a");
+    assert_eq!(value["clusters"][0]["module_id"], module_id);
+    let members: Vec<&str> = value["clusters"][0]["members"].as_array().unwrap().iter()
+        .map(|m| m["node_name"].as_str().unwrap())
+        .collect();
+    assert_eq!(members, vec!["alsa_output.a", "alsa_output.b"]);
+    assert_eq!(value["orbs"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn test_dot_export_links_cluster_to_members() {
+    // Every member gets an edge from its cluster
+    let (state, cluster) = clustered_state();
+    let graph = state.snapshot();
+
+    let dump = export_graph(&graph, None, ExportFormat::Dot);
+
+    assert!(dump.starts_with("digraph auralis {"));
+    for member in graph.clusters[&cluster].members() {
+        assert!(dump.contains(&format!("\"{}\" -> \"{}\";", cluster, member)));
+    }
+}
@@ -0,0 +1,154 @@
+// Test file for reading device formats from pw-dump output
+// Parses trimmed-down pw-dump objects and checks the labels shown in the Properties panel
+
+use auralis_core::formats::{self, parse_pw_dump, AudioFormat};
+
+const USB_DAC: &str = r#"[
+  {
+    "id": 57,
+    "type": "PipeWire:Interface:Node",
+    "info": {
+      "params": {
+        "EnumFormat": [
+          {
+            "mediaType": "audio",
+            "mediaSubtype": "raw",
+            "format": { "default": "S32LE", "alt1": "S32LE", "alt2": "S24_32LE", "alt3": "S16LE" },
+            "rate": { "default": 48000, "alt1": 44100, "alt2": 48000, "alt3": 96000, "alt10": 192000 },
+            "channels": 2,
+            "position": [ "FL", "FR" ]
+          },
+          { "mediaType": "video", "mediaSubtype": "raw" }
+        ],
+        "Format": [
+          { "mediaType": "audio", "mediaSubtype": "raw", "format": "S32LE", "rate": 48000, "channels": 2 }
+        ]
+      }
+    }
+  },
+  { "id": 58, "type": "PipeWire:Interface:Port", "info": {} }
+]"#;
+
+#[test]
+fn test_parse_enum_format_and_current_format() {
+    // Choices come out default first, alternatives in order, video entries skipped
+    let formats = parse_pw_dump(USB_DAC, 57).unwrap();
+    assert_eq!(formats.supported, vec![AudioFormat {
+        sample_formats: vec!["S32LE".to_string(), "S24_32LE".to_string(), "S16LE".to_string()],
+        rates: vec![48000, 44100, 96000, 192000],
+        rate_range: None,
+        max_channels: Some(2),
+    }]);
+    assert_eq!(formats.current.unwrap().label(), "S32LE · 48 kHz · 2 ch");
+
+    assert_eq!(formats::rates_label(&formats.supported).as_deref(), Some("44.1, 48, 96, 192 kHz"));
+    assert_eq!(formats::max_channels(&formats.supported), Some(2));
+}
+
+#[test]
+fn test_parse_ranges_and_missing_params() {
+    // Ranges give a span, a resampling adapter "any"; a node without params has no formats
+    let dump = r#"[{ "id": 70, "info": { "params": { "EnumFormat": [
+        { "mediaType": "audio", "mediaSubtype": "raw", "format": "F32P",
+          "rate": { "default": 48000, "min": 1, "max": 2147483647 },
+          "channels": { "default": 2, "min": 1, "max": 64 } },
+        { "mediaType": "audio", "mediaSubtype": "raw",
+          "rate": { "default": 48000, "min": 8000, "max": 192000 } }
+    ] } } }, { "id": 71, "info": {} }]"#;
+
+    let formats = parse_pw_dump(dump, 70).unwrap();
+    assert_eq!(formats::max_channels(&formats.supported), Some(64));
+    assert_eq!(formats::rates_label(&formats.supported).as_deref(), Some("any (resampled)"));
+    assert_eq!(formats::rates_label(&formats.supported[1..]).as_deref(), Some("8–192 kHz"));
+    assert!(formats.current.is_none());
+
+    let bare = parse_pw_dump(dump, 71).unwrap();
+    assert!(bare.supported.is_empty() && bare.current.is_none());
+    assert_eq!(formats::rates_label(&bare.supported), None);
+    assert_eq!(AudioFormat::default().label(), "Unknown");
+
+    assert!(parse_pw_dump(dump, 99).is_err());
+    assert!(parse_pw_dump("not json", 70).is_err());
+}
+
+fn playing(rates: &[u32], channels: u32, sample_formats: &[&str]) -> AudioFormat {
+    AudioFormat {
+        sample_formats: sample_formats.iter().map(|f| f.to_string()).collect(),
+        rates: rates.to_vec(),
+        rate_range: None,
+        max_channels: Some(channels),
+    }
+}
+
+#[test]
+fn test_negotiate_stereo_48k() {
+    // Members that all play 48 kHz stereo give exactly that
+    let members = [playing(&[48000], 2, &["S16LE"]), playing(&[48000], 2, &["S16LE"])];
+    let format = formats::negotiate_cluster_format(&members);
+    assert_eq!(format, playing(&[48000], 2, &["S16LE"]));
+    assert_eq!(format.label(), "S16LE · 48 kHz · 2 ch");
+}
+
+#[test]
+fn test_negotiate_highest_common_rate() {
+    // The highest rate everyone plays wins; with none in common it's 48 kHz and the rest resample
+    let both = formats::negotiate_cluster_format(&[playing(&[44100, 48000], 2, &[]), playing(&[44100, 48000, 96000], 2, &[])]);
+    assert_eq!(both.rate(), Some(48000));
+
+    let ranged = AudioFormat { rate_range: Some((8000, 96000)), ..Default::default() };
+    let high = formats::negotiate_cluster_format(&[playing(&[44100, 96000, 192000], 2, &[]), ranged]);
+    assert_eq!(high.rate(), Some(96000));
+
+    let cd_only = formats::negotiate_cluster_format(&[playing(&[44100], 2, &[]), playing(&[44100], 2, &[])]);
+    assert_eq!(cd_only.rate(), Some(44100));
+
+    let disjoint = formats::negotiate_cluster_format(&[playing(&[44100], 2, &[]), playing(&[48000], 2, &[])]);
+    assert_eq!(disjoint.rate(), Some(formats::DEFAULT_RATE));
+}
+
+#[test]
+fn test_negotiate_mono_member() {
+    // One mono speaker makes the cluster format mono
+    let members = [playing(&[48000], 2, &[]), playing(&[48000], 1, &[]), playing(&[48000], 6, &[])];
+    assert_eq!(formats::negotiate_cluster_format(&members).max_channels, Some(1));
+}
+
+#[test]
+fn test_negotiate_unknown_members_do_not_constrain() {
+    // Members without formats, or resampling anything, leave the choice to the others
+    let any = AudioFormat { rate_range: Some((1, i32::MAX as u32)), max_channels: Some(64), ..Default::default() };
+    let format = formats::negotiate_cluster_format(&[AudioFormat::default(), any.clone(), playing(&[44100], 2, &["S24LE"])]);
+    assert_eq!(format, playing(&[44100], 2, &["S24LE"]));
+
+    let unknown = formats::negotiate_cluster_format(&[AudioFormat::default(), any]);
+    assert_eq!(unknown.rate(), Some(formats::DEFAULT_RATE));
+    assert!(unknown.sample_formats.is_empty());
+    assert_eq!(formats::negotiate_cluster_format(&[]).max_channels, None);
+}
+
+#[test]
+fn test_negotiate_best_common_sample_format() {
+    // The best format both list, or none if they share none
+    let shared = formats::negotiate_cluster_format(&[
+        playing(&[48000], 2, &["S16LE", "S32LE"]),
+        playing(&[48000], 2, &["S16LE", "S24LE", "S32LE"]),
+    ]);
+    assert_eq!(shared.sample_formats, vec!["S32LE".to_string()]);
+
+    let apart = formats::negotiate_cluster_format(&[playing(&[48000], 2, &["F32LE"]), playing(&[48000], 2, &["S16LE"])]);
+    assert!(apart.sample_formats.is_empty());
+}
+
+#[test]
+fn test_combined_formats_of_a_device() {
+    // A device's formats merge into one: all rates and sample formats, the most channels
+    let device = [
+        playing(&[48000, 44100], 2, &["S32LE"]),
+        AudioFormat { sample_formats: vec!["S16LE".to_string()], rates: vec![96000, 48000], rate_range: None, max_channels: Some(8) },
+    ];
+    let combined = AudioFormat::combined(&device);
+    assert_eq!(combined.rates, vec![44100, 48000, 96000]);
+    assert_eq!(combined.sample_formats, vec!["S32LE".to_string(), "S16LE".to_string()]);
+    assert_eq!(combined.max_channels, Some(8));
+    assert_eq!(AudioFormat::combined(&[]), AudioFormat::default());
+}
@@ -1,7 +1,10 @@
 // Test file for graph types and commands
 // Tests UiCommand enum and other graph data structures
 
-use auralis_core::{UiCommand, OrbEvent, Orb, OrbKind, OrbState};
+mod common;
+
+use auralis_core::{UiCommand, OrbEvent, Orb, OrbKind, OrbState, AudioGraph, DeviceBus, ConnectionKind, classify_connection};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[test]
@@ -53,6 +56,7 @@ fn test_orb_event_add() {
     let orb = Orb {
         id,
         pw_id: 100,
+        node_name: "test_node_100".to_string(),
         kind: OrbKind::PhysicalSink {
             description: "Test".to_string(),
         },
@@ -60,6 +64,12 @@ fn test_orb_event_add() {
         icon_name: "audio-card".to_string(),
         status: "Active".to_string(),
         state: OrbState::Floating,
+        zone: None,
+        latency_ms: None,
+        bus: DeviceBus::Unknown,
+        channels: None,
+        formats: Vec::new(),
+        format: None,
         position: (0.0, 0.0),
         velocity: (0.0, 0.0),
     };
@@ -102,6 +112,9 @@ fn test_orb_kind_variants() {
     // Cluster
     let cluster = OrbKind::Cluster {
         devices: vec!["Dev1".to_string()],
+        members: vec![],
+        mono: vec![],
+        volumes: HashMap::new(),
     };
     assert!(matches!(cluster, OrbKind::Cluster { .. }));
     
@@ -112,6 +125,23 @@ fn test_orb_kind_variants() {
     assert!(matches!(app, OrbKind::ApplicationSource { .. }));
 }
 
+#[test]
+fn test_classify_connection() {
+    // Drops are classified the same way the core handles them, so the UI can ask before merges
+    let sink = OrbKind::PhysicalSink { description: "Sink".to_string() };
+    let cluster = OrbKind::Cluster { devices: vec![], members: vec![], mono: vec![], volumes: HashMap::new() };
+    let app = OrbKind::ApplicationSource { app_name: "App".to_string() };
+
+    assert_eq!(classify_connection(&sink, &sink), ConnectionKind::NewCluster);
+    assert_eq!(classify_connection(&sink, &cluster), ConnectionKind::AddToCluster);
+    assert_eq!(classify_connection(&cluster, &sink), ConnectionKind::AddToCluster);
+    assert_eq!(classify_connection(&cluster, &cluster), ConnectionKind::MergeClusters);
+    assert_eq!(classify_connection(&app, &sink), ConnectionKind::Link);
+    assert_eq!(classify_connection(&app, &cluster), ConnectionKind::Link);
+    assert_eq!(classify_connection(&sink, &app), ConnectionKind::Invalid);
+    assert_eq!(classify_connection(&app, &app), ConnectionKind::Invalid);
+}
+
 #[test]
 fn test_orb_state_variants() {
     // Test all OrbState variants
@@ -130,10 +160,13 @@ fn test_cluster_with_empty_devices() {
     // Test that cluster can be created with empty device list
     let cluster = OrbKind::Cluster {
         devices: vec![],
+        members: vec![],
+        mono: vec![],
+        volumes: HashMap::new(),
     };
     
     match cluster {
-        OrbKind::Cluster { devices } => {
+        OrbKind::Cluster { devices, .. } => {
             assert_eq!(devices.len(), 0);
         }
         _ => panic!("Wrong kind"),
@@ -149,10 +182,13 @@ fn test_cluster_with_many_devices() {
     
     let cluster = OrbKind::Cluster {
         devices: devices.clone(),
+        members: vec![],
+        mono: vec![],
+        volumes: HashMap::new(),
     };
     
     match cluster {
-        OrbKind::Cluster { devices: devs } => {
+        OrbKind::Cluster { devices: devs, .. } => {
             assert_eq!(devs.len(), 10);
             assert_eq!(devs[0], "Device0");
             assert_eq!(devs[9], "Device9");
@@ -160,3 +196,259 @@ fn test_cluster_with_many_devices() {
         _ => panic!("Wrong kind"),
     }
 }
+
+fn sink(pw_id: u32, description: &str) -> Orb {
+    common::sink(pw_id, &format!("alsa_output.{}", pw_id), description)
+}
+
+#[test]
+fn test_graph_create_cluster_hides_members() {
+    // Members start orbiting the cluster and the UI is told to hide them
+    let mut graph = AudioGraph::new();
+    let a = sink(1, "A");
+    let b = sink(2, "B");
+    let (a_id, b_id) = (a.id, b.id);
+    graph.add_orb(a);
+    graph.add_orb(b);
+
+    let (cluster_id, events) = graph.create_cluster(&[a_id, b_id], 42, "auralis_cluster_x".to_string(), None);
+
+    assert!(matches!(&events[0], OrbEvent::Add(o) if o.id == cluster_id));
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Update(o) if o.id == a_id && o.membership() == Some(cluster_id))));
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Update(o) if o.id == b_id && o.membership() == Some(cluster_id))));
+    assert_eq!(graph.cluster_of(a_id), Some(cluster_id));
+    assert!(graph.is_cluster_member("alsa_output.2"));
+    assert_eq!(graph.clusters[&cluster_id].members(), vec![a_id, b_id]);
+    assert_eq!(graph.clusters[&cluster_id].module_id, 42);
+}
+
+#[test]
+fn test_graph_dissolve_cluster_restores_members() {
+    // Dissolving re-floats members and re-announces them
+    let mut graph = AudioGraph::new();
+    let a = sink(1, "A");
+    let b = sink(2, "B");
+    let (a_id, b_id) = (a.id, b.id);
+    graph.add_orb(a);
+    graph.add_orb(b);
+    let (cluster_id, _) = graph.create_cluster(&[a_id, b_id], 42, "auralis_cluster_x".to_string(), None);
+
+    let (cluster, events) = graph.dissolve_cluster(cluster_id, true).expect("cluster exists");
+
+    assert_eq!(cluster.module_id, 42);
+    assert!(matches!(&events[0], OrbEvent::Remove(id) if *id == cluster_id));
+    assert_eq!(events.iter().filter(|e| matches!(e, OrbEvent::Add(_))).count(), 2);
+    assert_eq!(graph.cluster_of(a_id), None);
+    assert!(!graph.orbs.contains_key(&cluster_id));
+    assert!(graph.clusters.is_empty());
+}
+
+#[test]
+fn test_graph_dissolve_without_restore_keeps_members_hidden() {
+    // Re-clustering shouldn't flash members back into the UI
+    let mut graph = AudioGraph::new();
+    let a = sink(1, "A");
+    let b = sink(2, "B");
+    let (a_id, b_id) = (a.id, b.id);
+    graph.add_orb(a);
+    graph.add_orb(b);
+    let (cluster_id, _) = graph.create_cluster(&[a_id, b_id], 42, "auralis_cluster_x".to_string(), None);
+
+    let (_, events) = graph.dissolve_cluster(cluster_id, false).expect("cluster exists");
+
+    assert_eq!(events.len(), 1);
+    assert!(graph.orbs.contains_key(&a_id));
+}
+
+#[test]
+fn test_graph_dissolve_picks_up_parked_id() {
+    // A member re-announced while hidden comes back with its new PipeWire ID
+    let mut graph = AudioGraph::new();
+    let a = sink(1, "A");
+    let b = sink(2, "B");
+    let (a_id, b_id) = (a.id, b.id);
+    graph.add_orb(a);
+    graph.add_orb(b);
+    let (cluster_id, _) = graph.create_cluster(&[a_id, b_id], 42, "auralis_cluster_x".to_string(), None);
+
+    graph.park("alsa_output.2", 77);
+    graph.dissolve_cluster(cluster_id, true);
+
+    assert_eq!(graph.orbs[&b_id].pw_id, 77);
+    assert!(graph.parked.is_empty());
+}
+
+#[test]
+fn test_graph_pw_id_lookup_skips_clusters() {
+    // Cluster orbs carry a placeholder pw_id that must never match a real global
+    let mut graph = AudioGraph::new();
+    let a = sink(999, "A");
+    let b = sink(2, "B");
+    let (a_id, b_id) = (a.id, b.id);
+    graph.add_orb(a);
+    graph.add_orb(b);
+    graph.create_cluster(&[a_id, b_id], 42, "auralis_cluster_x".to_string(), None);
+
+    assert_eq!(graph.orb_id_by_pw_id(999), Some(a_id));
+    assert_eq!(graph.orb_id_by_pw_id(1234), None);
+}
+
+#[test]
+fn test_graph_identical_descriptions_tracked_by_node_name() {
+    // Two identical USB DACs share a description; clustering one must not hide the other
+    let mut graph = AudioGraph::new();
+    let usb1 = sink(1, "USB Audio");
+    let usb2 = sink(2, "USB Audio");
+    let c = sink(3, "Speakers");
+    let (usb1_id, usb2_id, c_id) = (usb1.id, usb2.id, c.id);
+    graph.add_orb(usb1);
+    graph.add_orb(usb2);
+    graph.add_orb(c);
+
+    let (cluster_id, _) = graph.create_cluster(&[usb1_id, c_id], 42, "auralis_cluster_x".to_string(), None);
+
+    assert!(graph.is_cluster_member("alsa_output.1"));
+    assert!(!graph.is_cluster_member("alsa_output.2"));
+    assert_eq!(graph.cluster_of(usb2_id), None);
+
+    // Parking the clustered DAC restores exactly that one
+    graph.park("alsa_output.1", 77);
+    graph.dissolve_cluster(cluster_id, true);
+    assert_eq!(graph.orbs[&usb1_id].pw_id, 77);
+    assert_eq!(graph.orbs[&usb2_id].pw_id, 2);
+}
+
+#[test]
+fn test_graph_member_dropout_returns_within_grace() {
+    // A Bluetooth member that comes back in time keeps its cluster and takes the new ID
+    let mut graph = AudioGraph::new();
+    let a = sink(1, "Speaker A");
+    let b = sink(2, "Speaker B");
+    let (a_id, b_id) = (a.id, b.id);
+    graph.add_orb(a);
+    graph.add_orb(b);
+    let (cluster_id, _) = graph.create_cluster(&[a_id, b_id], 42, "auralis_cluster_x".to_string(), None);
+
+    let token = graph.begin_dropout(b_id).expect("b is a cluster member");
+    let events = graph.finish_dropout("alsa_output.2", 88).expect("b was pending");
+
+    assert!(events.is_empty(), "hidden member must stay hidden");
+    assert_eq!(graph.orbs[&b_id].pw_id, 88);
+    assert_eq!(graph.cluster_of(b_id), Some(cluster_id));
+    // The timer firing afterwards must not remove anything
+    assert!(!graph.expire_dropout(b_id, token));
+}
+
+#[test]
+fn test_graph_member_dropout_expires() {
+    // Only the latest dropout counts, and floating orbs don't get a grace period
+    let mut graph = AudioGraph::new();
+    let a = sink(1, "Speaker A");
+    let b = sink(2, "Speaker B");
+    let c = sink(3, "Floating");
+    let (a_id, b_id, c_id) = (a.id, b.id, c.id);
+    graph.add_orb(a);
+    graph.add_orb(b);
+    graph.add_orb(c);
+    graph.create_cluster(&[a_id, b_id], 42, "auralis_cluster_x".to_string(), None);
+
+    assert_eq!(graph.begin_dropout(c_id), None);
+    assert!(graph.finish_dropout("alsa_output.3", 90).is_none());
+
+    let first = graph.begin_dropout(a_id).unwrap();
+    let second = graph.begin_dropout(a_id).unwrap();
+    assert!(!graph.expire_dropout(a_id, first));
+    assert!(graph.expire_dropout(a_id, second));
+    assert!(graph.pending_removal.is_empty());
+}
+
+#[test]
+fn test_graph_profile_switch_replaces_the_node() {
+    // A card's node going away and another of its nodes coming up is the same device, clustered or not
+    let mut graph = AudioGraph::new();
+    let a = sink(1, "Speaker A");
+    let tv = sink(2, "Built-in Audio Analog Stereo");
+    let other = sink(3, "USB DAC");
+    let (a_id, tv_id, other_id) = (a.id, tv.id, other.id);
+    graph.add_orb(a);
+    graph.add_orb(tv);
+    graph.add_orb(other);
+    graph.sink_devices.insert(tv_id, "alsa_card.pci".to_string());
+    graph.sink_devices.insert(other_id, "alsa_card.usb".to_string());
+    let (cluster_id, _) = graph.create_cluster(&[a_id, tv_id], 42, "auralis_cluster_x".to_string(), None);
+
+    // Devices of unknown cards aren't waited for; a node of a card with nothing pending is new
+    assert_eq!(graph.begin_replacement(a_id), None);
+    assert!(graph.replace_node("alsa_card.usb", 90, "alsa_output.usb.iec958", "USB DAC Digital", "USB DAC Digital").is_none());
+
+    let token = graph.begin_replacement(tv_id).expect("tv's card is known");
+    let (orb_id, old_node_name, events) = graph
+        .replace_node("alsa_card.pci", 88, "alsa_output.pci.hdmi-stereo", "Built-in Audio Digital Stereo (HDMI)", "TV")
+        .expect("tv was pending");
+
+    assert_eq!((orb_id, old_node_name.as_str()), (tv_id, "alsa_output.2"));
+    let orb = &graph.orbs[&tv_id];
+    assert_eq!((orb.pw_id, orb.node_name.as_str(), orb.name.as_str()), (88, "alsa_output.pci.hdmi-stereo", "TV"));
+    assert_eq!(graph.cluster_of(tv_id), Some(cluster_id));
+    assert!(matches!(&graph.orbs[&cluster_id].kind, OrbKind::Cluster { devices, .. } if devices.contains(&"TV".to_string())));
+    assert!(events.iter().any(|e| matches!(e, OrbEvent::Update(o) if o.id == cluster_id)));
+    assert!(!graph.expire_dropout(tv_id, token));
+    assert!(graph.find_sink("alsa_output.2").is_none());
+}
+
+#[test]
+fn test_graph_playback_routes() {
+    // Links resolve to the app's output: a device, or the cluster behind a combine-sink or member
+    let mut graph = AudioGraph::new();
+    let speaker = sink(1, "Speaker");
+    let a = sink(2, "A");
+    let b = sink(3, "B");
+    let (speaker_id, a_id, b_id) = (speaker.id, a.id, b.id);
+    graph.add_orb(speaker);
+    graph.add_orb(a);
+    graph.add_orb(b);
+    let (cluster_id, _) = graph.create_cluster(&[a_id, b_id], 42, "auralis_cluster_x".to_string(), None);
+
+    let mut firefox = sink(50, "Firefox");
+    firefox.kind = OrbKind::ApplicationSource { app_name: "Firefox".to_string() };
+    let mut mpv = sink(51, "mpv");
+    mpv.kind = OrbKind::ApplicationSource { app_name: "mpv".to_string() };
+    let (firefox_id, mpv_id) = (firefox.id, mpv.id);
+    graph.add_orb(firefox);
+    graph.add_orb(mpv);
+
+    // Stereo: one link per channel
+    graph.add_link(100, 50, 1);
+    graph.add_link(101, 50, 1);
+    // 77 is the combine-sink's real node ID
+    graph.add_link(102, 51, 77);
+    let sink_name_of = |pw_id| (pw_id == 77).then(|| "auralis_cluster_x".to_string());
+
+    let mut expected = vec![(firefox_id, speaker_id), (mpv_id, cluster_id)];
+    expected.sort();
+    assert_eq!(graph.playback_routes(sink_name_of), expected);
+
+    // Only changes are reported
+    assert_eq!(graph.refresh_routes(sink_name_of).len(), 1);
+    assert!(graph.refresh_routes(sink_name_of).is_empty());
+
+    // Moving firefox straight onto a cluster member counts as the cluster
+    graph.remove_link(100);
+    graph.remove_link(101);
+    graph.add_link(103, 50, 3);
+    assert!(graph.playback_routes(sink_name_of).contains(&(firefox_id, cluster_id)));
+}
+
+#[test]
+fn test_graph_set_node_status() {
+    // Node state changes update the device's status once, clusters are left alone
+    let mut graph = AudioGraph::new();
+    let a = sink(1, "A");
+    let a_id = a.id;
+    graph.add_orb(a);
+
+    assert_eq!(graph.set_node_status(1, "Playing").len(), 1);
+    assert!(graph.set_node_status(1, "Playing").is_empty());
+    assert_eq!(graph.orbs[&a_id].status, "Playing");
+    assert!(graph.set_node_status(99, "Idle").is_empty());
+}
@@ -0,0 +1,37 @@
+// Test file for the lock that keeps two Auralis instances apart
+// Uses a lock file in a temp dir of its own per test
+
+use auralis_core::instance_lock::InstanceLock;
+use std::path::PathBuf;
+
+/// Fresh lock file path in a directory of its own
+fn lock_path(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("auralis-test-{}-lock-{}", std::process::id(), test));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("auralis.lock")
+}
+
+#[test]
+fn test_lock_held_until_dropped() {
+    // A second taker is told the holder's PID; once the holder is gone the lock is free again
+    let path = lock_path("held");
+    let first = InstanceLock::acquire(&path).unwrap();
+    assert!(first.is_some());
+
+    let running = InstanceLock::acquire(&path).unwrap_err();
+    assert_eq!(running.pid, Some(std::process::id()));
+    assert_eq!(running.to_string(), format!("Auralis is already running (PID {})", std::process::id()));
+
+    drop(first);
+    assert!(InstanceLock::acquire(&path).unwrap().is_some());
+}
+
+#[test]
+fn test_lock_left_by_dead_process_is_taken() {
+    // An unlocked file with an old PID in it (a crash) doesn't block anyone
+    let path = lock_path("stale");
+    std::fs::write(&path, "4294967").unwrap();
+    assert!(InstanceLock::acquire(&path).unwrap().is_some());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), std::process::id().to_string());
+}
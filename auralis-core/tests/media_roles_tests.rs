@@ -0,0 +1,50 @@
+// Test file for routing streams by media role
+// Which roles are recognised and how their outputs are remembered
+
+mod common;
+
+use auralis_core::media_roles::{canonical, find_target, target_key};
+use auralis_core::{Orb, OrbKind, OrbState};
+use common::orb;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[test]
+fn test_canonical_roles_and_aliases() {
+    // WirePlumber names in any case, PulseAudio aliases mapped, everything else unrouted
+    assert_eq!(canonical("Music"), Some("Music"));
+    assert_eq!(canonical("notification"), Some("Notification"));
+    assert_eq!(canonical("event"), Some("Notification"));
+    assert_eq!(canonical("phone"), Some("Communication"));
+    assert_eq!(canonical(" video "), Some("Movie"));
+    assert_eq!(canonical("Production"), None);
+    assert_eq!(canonical(""), None);
+}
+
+#[test]
+fn test_target_key_survives_cluster_rebuild() {
+    // Devices are remembered by node name, clusters by their members rather than their sink name
+    let speaker = orb(0, "alsa_output.speaker", OrbKind::PhysicalSink { description: "Speaker".to_string() });
+    let mut a = orb(0, "alsa_output.a", OrbKind::PhysicalSink { description: "A".to_string() });
+    let mut b = orb(0, "bluez_output.b", OrbKind::PhysicalSink { description: "B".to_string() });
+    let cluster_kind = |members: Vec<Uuid>| OrbKind::Cluster { devices: vec![], members, mono: vec![], volumes: HashMap::new() };
+    let cluster = orb(0, "auralis_cluster_1", cluster_kind(vec![b.id, a.id]));
+    a.state = OrbState::Orbiting { parent_id: cluster.id };
+    b.state = OrbState::Orbiting { parent_id: cluster.id };
+
+    let orbs: HashMap<Uuid, Orb> = [speaker.clone(), a.clone(), b.clone(), cluster.clone()]
+        .into_iter()
+        .map(|o| (o.id, o))
+        .collect();
+    assert_eq!(target_key(&orbs, &speaker).as_deref(), Some("alsa_output.speaker"));
+    assert_eq!(target_key(&orbs, &cluster).as_deref(), Some("alsa_output.a,bluez_output.b"));
+    assert_eq!(find_target(&orbs, "alsa_output.speaker").map(|o| o.id), Some(speaker.id));
+
+    // The same devices clustered again under a new sink name are still the target; members aren't
+    let rebuilt = orb(0, "auralis_cluster_2", cluster_kind(vec![a.id, b.id]));
+    let mut orbs = orbs;
+    orbs.remove(&cluster.id);
+    orbs.insert(rebuilt.id, rebuilt.clone());
+    assert_eq!(find_target(&orbs, "alsa_output.a,bluez_output.b").map(|o| o.id), Some(rebuilt.id));
+    assert!(find_target(&orbs, "alsa_output.a").is_none());
+}
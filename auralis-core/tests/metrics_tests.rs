@@ -0,0 +1,27 @@
+// Test file for the monitoring counters
+// Covers the JSON and Prometheus renderings of a snapshot
+
+use auralis_core::metrics::Metrics;
+
+#[test]
+fn test_snapshot_renders_json_and_prometheus() {
+    // Every counter appears under its name, the connection as 0/1
+    let metrics = Metrics::default();
+    metrics.command_processed();
+    metrics.command_processed();
+    metrics.discovery_event();
+    metrics.set_connected(true);
+    let snapshot = metrics.snapshot(3, 1);
+
+    let json: serde_json::Value = serde_json::from_str(&snapshot.to_json()).unwrap();
+    assert_eq!(json["commands_processed"], 2);
+    assert_eq!(json["discovery_events"], 1);
+    assert_eq!(json["connected"], true);
+    assert_eq!(json["orbs"], 3);
+
+    let text = snapshot.to_prometheus();
+    assert!(text.contains("# TYPE auralis_commands_processed_total counter\nauralis_commands_processed_total 2\n"));
+    assert!(text.contains("\nauralis_connected 1\n"));
+    assert!(text.contains("\nauralis_clusters 1\n"));
+    assert_eq!(text.lines().filter(|l| !l.starts_with('#')).count(), 9);
+}
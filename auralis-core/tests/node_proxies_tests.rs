@@ -0,0 +1,46 @@
+// Test file for per-node proxy bookkeeping
+// Simulates hotplug churn and checks every proxy is dropped once its node goes away
+
+use auralis_core::node_proxies::NodeProxies;
+use std::rc::Rc;
+
+#[test]
+fn test_node_proxies_churn_stays_bounded() {
+    // Many add/remove cycles: nothing accumulates and no proxy outlives its node
+    let alive = Rc::new(());
+    let mut proxies = NodeProxies::new();
+
+    for cycle in 0..10_000u32 {
+        // A few nodes come and go at a time, with IDs being reused
+        for pw_id in (cycle % 50)..(cycle % 50 + 4) {
+            proxies.track(pw_id, alive.clone());
+        }
+        assert!(proxies.len() <= 4);
+        for pw_id in (cycle % 50)..(cycle % 50 + 4) {
+            assert!(proxies.release(pw_id));
+        }
+    }
+
+    assert!(proxies.is_empty());
+    assert_eq!(Rc::strong_count(&alive), 1);
+}
+
+#[test]
+fn test_node_proxies_reused_id_replaces_proxy() {
+    // A node ID announced again before its removal drops the stale proxy
+    let first = Rc::new(());
+    let second = Rc::new(());
+    let mut proxies = NodeProxies::new();
+
+    proxies.track(42, first.clone());
+    proxies.track(42, second.clone());
+
+    assert_eq!(proxies.len(), 1);
+    assert_eq!(Rc::strong_count(&first), 1);
+    assert!(proxies.contains(42));
+
+    // Removing an unknown node is harmless
+    assert!(!proxies.release(7));
+    assert!(proxies.release(42));
+    assert_eq!(Rc::strong_count(&second), 1);
+}
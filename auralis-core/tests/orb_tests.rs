@@ -1,7 +1,9 @@
 // Test file for Orb data structures
 // Tests basic creation and properties without touching PipeWire
 
-use auralis_core::{Orb, OrbKind, OrbState};
+use auralis_core::graph::HDMI_DROPOUT_GRACE_MS;
+use auralis_core::{DeviceBus, Orb, OrbKind, OrbState};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[test]
@@ -11,6 +13,7 @@ fn test_create_physical_sink_orb() {
     let orb = Orb {
         id,
         pw_id: 123,
+        node_name: "test_node_123".to_string(),
         kind: OrbKind::PhysicalSink {
             description: "Test Speaker".to_string(),
         },
@@ -18,6 +21,12 @@ fn test_create_physical_sink_orb() {
         icon_name: "audio-card".to_string(),
         status: "Active".to_string(),
         state: OrbState::Floating,
+        zone: None,
+        latency_ms: None,
+        bus: DeviceBus::Unknown,
+        channels: None,
+        formats: Vec::new(),
+        format: None,
         position: (0.0, 0.0),
         velocity: (0.0, 0.0),
     };
@@ -50,13 +59,23 @@ fn test_create_cluster_orb() {
     let orb = Orb {
         id,
         pw_id: 456,
+        node_name: "test_node_456".to_string(),
         kind: OrbKind::Cluster {
             devices: devices.clone(),
+            members: vec![],
+            mono: vec![],
+            volumes: HashMap::new(),
         },
         name: "Test Cluster".to_string(),
         icon_name: "view-grid-symbolic".to_string(),
         status: "Active".to_string(),
         state: OrbState::Floating,
+        zone: None,
+        latency_ms: None,
+        bus: DeviceBus::Unknown,
+        channels: None,
+        formats: Vec::new(),
+        format: None,
         position: (100.0, 200.0),
         velocity: (0.0, 0.0),
     };
@@ -67,7 +86,7 @@ fn test_create_cluster_orb() {
     
     // Verify cluster contains devices
     match orb.kind {
-        OrbKind::Cluster { devices: devs } => {
+        OrbKind::Cluster { devices: devs, .. } => {
             assert_eq!(devs.len(), 2);
             assert!(devs.contains(&"Device1".to_string()));
             assert!(devs.contains(&"Device2".to_string()));
@@ -84,6 +103,7 @@ fn test_create_application_source_orb() {
     let orb = Orb {
         id,
         pw_id: 789,
+        node_name: "test_node_789".to_string(),
         kind: OrbKind::ApplicationSource {
             app_name: "Firefox".to_string(),
         },
@@ -91,6 +111,12 @@ fn test_create_application_source_orb() {
         icon_name: "audio-x-generic".to_string(),
         status: "Playing".to_string(),
         state: OrbState::Floating,
+        zone: None,
+        latency_ms: None,
+        bus: DeviceBus::Unknown,
+        channels: None,
+        formats: Vec::new(),
+        format: None,
         position: (50.0, 50.0),
         velocity: (1.0, 1.0),
     };
@@ -115,6 +141,7 @@ fn test_orb_state_transitions() {
     let orb_floating = Orb {
         id,
         pw_id: 100,
+        node_name: "test_node_100".to_string(),
         kind: OrbKind::PhysicalSink {
             description: "Test".to_string(),
         },
@@ -122,6 +149,12 @@ fn test_orb_state_transitions() {
         icon_name: "audio-card".to_string(),
         status: "Active".to_string(),
         state: OrbState::Floating,
+        zone: None,
+        latency_ms: None,
+        bus: DeviceBus::Unknown,
+        channels: None,
+        formats: Vec::new(),
+        format: None,
         position: (0.0, 0.0),
         velocity: (0.0, 0.0),
     };
@@ -133,6 +166,7 @@ fn test_orb_state_transitions() {
     let orb_orbiting = Orb {
         id,
         pw_id: 101,
+        node_name: "test_node_101".to_string(),
         kind: OrbKind::PhysicalSink {
             description: "Test2".to_string(),
         },
@@ -140,6 +174,12 @@ fn test_orb_state_transitions() {
         icon_name: "audio-card".to_string(),
         status: "Active".to_string(),
         state: OrbState::Orbiting { parent_id },
+        zone: None,
+        latency_ms: None,
+        bus: DeviceBus::Unknown,
+        channels: None,
+        formats: Vec::new(),
+        format: None,
         position: (10.0, 10.0),
         velocity: (0.5, 0.5),
     };
@@ -169,6 +209,7 @@ fn test_orb_position_and_velocity() {
     let mut orb = Orb {
         id,
         pw_id: 200,
+        node_name: "test_node_200".to_string(),
         kind: OrbKind::PhysicalSink {
             description: "Moving Device".to_string(),
         },
@@ -176,6 +217,12 @@ fn test_orb_position_and_velocity() {
         icon_name: "audio-card".to_string(),
         status: "Active".to_string(),
         state: OrbState::Floating,
+        zone: None,
+        latency_ms: None,
+        bus: DeviceBus::Unknown,
+        channels: None,
+        formats: Vec::new(),
+        format: None,
         position: (100.0, 100.0),
         velocity: (5.0, -3.0),
     };
@@ -186,3 +233,36 @@ fn test_orb_position_and_velocity() {
     
     assert_eq!(orb.position, (105.0, 97.0));
 }
+
+#[test]
+fn test_device_bus_detection() {
+    // Properties win, node names are the fallback
+    assert_eq!(DeviceBus::detect(Some("bluez5"), None, None, "bluez_output.AA_BB.1"), DeviceBus::Bluetooth);
+    assert_eq!(DeviceBus::detect(Some("alsa"), Some("usb"), None, "alsa_output.usb-Focusrite-00.analog-stereo"), DeviceBus::Usb);
+    assert_eq!(DeviceBus::detect(Some("alsa"), Some("pci"), None, "alsa_output.pci-0000_00_1f.3.hdmi-stereo"), DeviceBus::Hdmi);
+    assert_eq!(DeviceBus::detect(Some("alsa"), Some("pci"), None, "alsa_output.pci-0000_00_1f.3.analog-stereo"), DeviceBus::Internal);
+    assert_eq!(DeviceBus::detect(None, None, None, "alsa_output.usb-Generic_USB_Audio-00.analog-stereo"), DeviceBus::Usb);
+    assert_eq!(DeviceBus::detect(None, None, None, "Mock1"), DeviceBus::Unknown);
+    assert_eq!(DeviceBus::Unknown.icon_name(), None);
+}
+
+#[test]
+fn test_hdmi_members_wait_longer_for_their_monitor() {
+    // A sleeping monitor takes its HDMI sink with it for minutes; other buses keep the setting
+    assert!(DeviceBus::detect(Some("alsa"), Some("pci"), None, "alsa_output.pci-0000_01_00.1.hdmi-stereo-extra2").follows_monitor());
+    assert_eq!(DeviceBus::Hdmi.dropout_grace_ms(3000), HDMI_DROPOUT_GRACE_MS);
+    assert_eq!(DeviceBus::Hdmi.dropout_grace_ms(HDMI_DROPOUT_GRACE_MS * 2), HDMI_DROPOUT_GRACE_MS * 2);
+    assert_eq!(DeviceBus::Hdmi.dropout_grace_ms(0), 0);
+    assert_eq!(DeviceBus::Bluetooth.dropout_grace_ms(3000), 3000);
+    assert!(!DeviceBus::Usb.follows_monitor());
+}
+
+#[test]
+fn test_bluetooth_offset_only_for_mixed_clusters() {
+    // Wired members wait for Bluetooth ones; all-Bluetooth or all-wired clusters play as they are
+    use DeviceBus::{Bluetooth, Hdmi, Usb};
+    assert_eq!(DeviceBus::bluetooth_offset_ms(&[Bluetooth, Usb, Hdmi], 200), Some(200));
+    assert_eq!(DeviceBus::bluetooth_offset_ms(&[Bluetooth, Bluetooth], 200), None);
+    assert_eq!(DeviceBus::bluetooth_offset_ms(&[Usb, Hdmi], 200), None);
+    assert_eq!(DeviceBus::bluetooth_offset_ms(&[Bluetooth, Usb], 0), None);
+}
@@ -0,0 +1,73 @@
+// Test file for the pactl output parsers
+// Sample `pactl ... short` outputs, including translated columns and values with spaces
+
+use auralis_core::system_audio::{
+    parse_modules_short, parse_short_ids, parse_sink_inputs_short, parse_sink_latency_usec, parse_sink_volume_percent,
+    parse_sinks_short, ModuleInfo, SinkInfo,
+};
+
+#[test]
+fn test_parse_modules_short() {
+    // Arguments keep their spaces; only tabs separate columns
+    let stdout = "536870912\tlibpipewire-module-protocol-pulse\t\n\
+                  536870932\tmodule-combine-sink\tsink_name=auralis_cluster_ab slaves=a,b latency_compensate=yes\n\
+                  536870933\tmodule-null-sink\tsink_name=Mock sink_properties=device.description='Living Room'\n";
+
+    let modules = parse_modules_short(stdout);
+
+    assert_eq!(modules.len(), 3);
+    assert_eq!(modules[0], ModuleInfo { id: 536870912, name: "libpipewire-module-protocol-pulse".to_string(), args: String::new() });
+    assert_eq!(modules[1].arg("sink_name"), Some("auralis_cluster_ab"));
+    assert_eq!(modules[2].args, "sink_name=Mock sink_properties=device.description='Living Room'");
+}
+
+#[test]
+fn test_parse_sinks_short_with_translated_state() {
+    // The state column is whatever the locale made of it; only ID and name matter
+    let stdout = "57\talsa_output.pci-0000_00_1f.3.analog-stereo\tPipeWire\ts32le 2ch 48000Hz\tSUSPENDU\n\
+                  58\tbluez_output.AA_BB.1\tPipeWire\ts16le 2ch 48000Hz\tEN EJECUCIÓN\n";
+
+    assert_eq!(parse_sinks_short(stdout), vec![
+        SinkInfo { id: 57, name: "alsa_output.pci-0000_00_1f.3.analog-stereo".to_string() },
+        SinkInfo { id: 58, name: "bluez_output.AA_BB.1".to_string() },
+    ]);
+}
+
+#[test]
+fn test_parse_sink_inputs_short() {
+    // Sink-input and sink index, with the client column missing ("-") on some versions
+    let stdout = "104\t57\t95\tPipeWire\tfloat32le 2ch 48000Hz\n\
+                  105\t58\t-\tPipeWire\ts16le 1ch 44100Hz\n";
+
+    assert_eq!(parse_sink_inputs_short(stdout), vec![(104, 57), (105, 58)]);
+    assert_eq!(parse_short_ids(stdout), vec![104, 105]);
+}
+
+#[test]
+fn test_parse_short_ignores_noise() {
+    // Blank lines, warnings and malformed rows are skipped rather than failing the whole list
+    let stdout = "\nW: [pulseaudio] connection warning\n57\tsink.a\tPipeWire\nnot-a-number\tsink.b\n";
+
+    assert_eq!(parse_sinks_short(stdout), vec![SinkInfo { id: 57, name: "sink.a".to_string() }]);
+    assert_eq!(parse_short_ids(stdout), vec![57]);
+}
+
+#[test]
+fn test_parse_sink_latency_usec() {
+    // Long format (C locale): the latency of the named sink, not the first one listed
+    let stdout = "Sink #57\n\tState: RUNNING\n\tName: sink.a\n\tLatency: 1000 usec, configured 2000 usec\n\
+                  Sink #58\n\tState: RUNNING\n\tName: sink.b\n\tLatency: 23219 usec, configured 40000 usec\n";
+
+    assert_eq!(parse_sink_latency_usec(stdout, "sink.b"), Some(23219));
+    assert_eq!(parse_sink_latency_usec(stdout, "sink.c"), None);
+}
+
+#[test]
+fn test_parse_sink_volume_percent() {
+    // `get-sink-volume`: channels averaged, the balance line ignored
+    let stdout = "Volume: front-left: 42598 /  65% / -11.23 dB,   front-right: 45875 /  70% / -9.29 dB\n        balance 0.07\n";
+
+    assert_eq!(parse_sink_volume_percent(stdout), Some(68));
+    assert_eq!(parse_sink_volume_percent("Volume: mono: 65536 / 100% / 0.00 dB\n"), Some(100));
+    assert_eq!(parse_sink_volume_percent(""), None);
+}
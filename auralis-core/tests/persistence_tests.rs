@@ -0,0 +1,87 @@
+// Test file for loading state.json
+// Writes state files of various versions to a temp dir and loads them through StateStore
+
+use auralis_core::persistence::{backup_path, CanvasLayout, PersistedState, StateStore, STATE_VERSION};
+use std::path::PathBuf;
+
+/// Fresh `state.json` path in a directory of its own
+fn state_path(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("auralis-test-{}-{}", std::process::id(), test));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("state.json")
+}
+
+const V1_STATE: &str = r#"{
+  "clusters": {
+    "alsa_output.a,bluez_output.b": { "name": "Kitchen", "delays": { "bluez_output.b": 120 } }
+  },
+  "settings": { "auto_switch_new_devices": true, "dropout_grace_ms": 5000 }
+}"#;
+
+#[test]
+fn test_load_migrates_v1_state() {
+    // A file from before the version field keeps its clusters and settings and is saved as current
+    let path = state_path("v1");
+    std::fs::write(&path, V1_STATE).unwrap();
+
+    let store = StateStore::load(path.clone());
+    assert_eq!(store.data.version, STATE_VERSION);
+    let prefs = &store.data.clusters["alsa_output.a,bluez_output.b"];
+    assert_eq!(prefs.name.as_deref(), Some("Kitchen"));
+    assert_eq!(prefs.delays["bluez_output.b"], 120);
+    assert!(store.data.settings.auto_switch_new_devices);
+    assert_eq!(store.data.settings.dropout_grace_ms, 5000);
+    assert!(store.data.settings.show_notifications);
+    assert!(store.data.settings.auto_cluster_on_drop);
+    assert!(!store.data.settings.notifications_off_clusters);
+    assert!(!backup_path(&path).exists());
+
+    store.save();
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["version"], STATE_VERSION);
+    assert_eq!(StateStore::load(path).data, store.data);
+}
+
+#[test]
+fn test_load_backs_up_newer_state() {
+    // A file from a newer Auralis isn't guessed at: it's copied aside and loading starts fresh
+    let path = state_path("newer");
+    let newer = format!(r#"{{ "version": {}, "clusters": {{}}, "hologram": true }}"#, STATE_VERSION + 1);
+    std::fs::write(&path, &newer).unwrap();
+
+    let store = StateStore::load(path.clone());
+    assert_eq!(store.data, PersistedState::default());
+    assert_eq!(std::fs::read_to_string(backup_path(&path)).unwrap(), newer);
+}
+
+#[test]
+fn test_load_backs_up_unreadable_state() {
+    // Broken JSON and bad versions start fresh too, and a missing file is no reason for a backup
+    for (test, contents) in [("broken", "{ \"clusters\": "), ("bad-version", r#"{ "version": "two" }"#), ("array", "[]")] {
+        let path = state_path(test);
+        std::fs::write(&path, contents).unwrap();
+
+        assert_eq!(StateStore::load(path.clone()).data, PersistedState::default());
+        assert_eq!(std::fs::read_to_string(backup_path(&path)).unwrap(), contents);
+    }
+
+    let path = state_path("missing");
+    assert_eq!(StateStore::load(path.clone()).data, PersistedState::default());
+    assert!(!backup_path(&path).exists());
+}
+
+#[test]
+fn test_canvas_layout_is_saved_by_name() {
+    // Older files have no layout and keep orbs where they were dropped
+    let path = state_path("layout");
+    std::fs::write(&path, V1_STATE).unwrap();
+    let mut store = StateStore::load(path.clone());
+    assert_eq!(store.data.settings.canvas_layout, CanvasLayout::Free);
+
+    store.data.settings.canvas_layout = CanvasLayout::Grid;
+    store.save();
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["settings"]["canvas_layout"], "grid");
+    assert_eq!(StateStore::load(path).data.settings.canvas_layout, CanvasLayout::Grid);
+}
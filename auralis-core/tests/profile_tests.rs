@@ -0,0 +1,87 @@
+// Test file for cluster profiles (profiles.toml)
+// Parses profile definitions and applies them against the MockBackend
+
+mod common;
+
+use auralis_core::pipewire_client::SharedState;
+use auralis_core::system_audio::BackendCall;
+use auralis_core::{MockBackend, OrbEvent, PipeWireClient, Profile, Profiles};
+use common::register_sink;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+#[test]
+fn test_parse_profiles() {
+    // Profiles are keyed by name, each listing clusters by device description
+    let profiles = Profiles::parse(
+        r#"
+        [profiles."Movie Night"]
+        clusters = [["Living Room", "Subwoofer"]]
+        default_sink = "Living Room"
+
+        [profiles.Party]
+        clusters = [["Kitchen", "Patio", "Living Room"]]
+        "#,
+    )
+    .expect("valid profiles");
+
+    assert_eq!(profiles.names(), vec!["Movie Night".to_string(), "Party".to_string()]);
+    let movie = &profiles.profiles["Movie Night"];
+    assert_eq!(movie.clusters, vec![vec!["Living Room".to_string(), "Subwoofer".to_string()]]);
+    assert_eq!(movie.default_sink.as_deref(), Some("Living Room"));
+    assert_eq!(profiles.profiles["Party"].default_sink, None);
+}
+
+#[test]
+fn test_apply_profile_builds_clusters_and_default() {
+    // The profile's cluster is built and its combine-sink becomes the default
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, rx) = channel();
+
+    register_sink(&state, 10, "alsa_output.living", "Living Room");
+    register_sink(&state, 11, "alsa_output.sub", "Subwoofer");
+
+    let profile = Profile {
+        clusters: vec![vec!["Living Room".to_string(), "Subwoofer".to_string()]],
+        default_sink: Some("Subwoofer".to_string()),
+    };
+    PipeWireClient::apply_profile_to(&state, &tx, "Movie Night", &profile);
+
+    let graph = state.graph();
+    assert_eq!(graph.clusters.len(), 1);
+    let sink_name = graph.clusters.values().next().unwrap().sink_name.clone();
+    drop(graph);
+
+    let last_default = backend.calls().into_iter().rev().find(|c| matches!(c, BackendCall::SetDefaultSink(_)));
+    assert_eq!(last_default, Some(BackendCall::SetDefaultSink(sink_name)));
+    assert!(!rx.try_iter().any(|e| matches!(e, OrbEvent::Error { .. })));
+}
+
+#[test]
+fn test_apply_profile_reports_missing_devices() {
+    // Missing devices are reported, the rest of the profile is still built
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, rx) = channel();
+
+    register_sink(&state, 10, "alsa_output.kitchen", "Kitchen");
+    register_sink(&state, 11, "alsa_output.patio", "Patio");
+
+    let profile = Profile {
+        clusters: vec![vec!["Kitchen".to_string(), "Patio".to_string(), "Garage".to_string()]],
+        default_sink: None,
+    };
+    PipeWireClient::apply_profile_to(&state, &tx, "Party", &profile);
+
+    let errors: Vec<String> = rx
+        .try_iter()
+        .filter_map(|e| match e {
+            OrbEvent::Error { message } => Some(message),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("Garage"));
+    assert_eq!(state.graph().clusters.len(), 1);
+}
@@ -0,0 +1,107 @@
+// Test file for declarative topologies (`auralis-cli apply`)
+// Plans against a mock-backed state and applies them the way the core does
+
+mod common;
+
+use auralis_core::pipewire_client::SharedState;
+use auralis_core::topology::{Step, Topology};
+use auralis_core::{MockBackend, PipeWireClient, UiCommand};
+use common::register_sink;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+fn plan(state: &SharedState, topology: &Topology) -> anyhow::Result<Vec<Step>> {
+    let role_targets = state.store().data.settings.role_targets.clone();
+    topology.plan(&state.graph(), &role_targets)
+}
+
+const LIVING_ROOM: &str = r#"{
+    "clusters": [{"name": "Living Room", "devices": ["alsa_output.a", "Speaker B"]}],
+    "default_sink": "Living Room",
+    "roles": {"music": "Living Room", "Communication": "Headset"}
+}"#;
+
+#[test]
+fn test_apply_builds_what_is_missing_and_then_nothing() {
+    // Applying the same file again has nothing left to do
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend.clone());
+    let (tx, _rx) = channel();
+    register_sink(&state, 10, "alsa_output.a", "Speaker A");
+    register_sink(&state, 11, "alsa_output.b", "Speaker B");
+    register_sink(&state, 12, "bluez_output.headset", "Headset");
+    let topology = Topology::parse(LIVING_ROOM).unwrap();
+
+    let steps = PipeWireClient::apply_topology_to(&state, &tx, &topology).unwrap();
+
+    let described: Vec<String> = steps.iter().map(ToString::to_string).collect();
+    assert_eq!(described, vec![
+        "Cluster alsa_output.a, alsa_output.b as 'Living Room'",
+        "Play on 'Living Room' by default",
+        "Play Music on 'Living Room'",
+        "Play Communication on 'Headset'",
+    ]);
+    let cluster = *state.graph().clusters.keys().next().unwrap();
+    assert_eq!(state.graph().orbs[&cluster].name, "Living Room");
+    let sink_name = state.graph().clusters[&cluster].sink_name.clone();
+    assert_eq!(state.graph().default_sink, Some(sink_name));
+    assert_eq!(state.store().data.settings.role_targets["Communication"], "bluez_output.headset");
+
+    backend.clear_calls();
+    assert_eq!(PipeWireClient::apply_topology_to(&state, &tx, &topology).unwrap(), Vec::new());
+    assert!(backend.calls().is_empty());
+}
+
+#[test]
+fn test_apply_removes_what_the_file_does_not_list() {
+    // A cluster of other devices is separated, a role not listed goes back to the default
+    let backend = Arc::new(MockBackend::new());
+    let state = SharedState::new(backend);
+    let (tx, _rx) = channel();
+    let a = register_sink(&state, 10, "alsa_output.a", "Speaker A");
+    register_sink(&state, 11, "alsa_output.b", "Speaker B");
+    let c = register_sink(&state, 12, "alsa_output.c", "Speaker C");
+    PipeWireClient::handle_command(&state, &tx, UiCommand::Connect { source: a, target: c });
+    PipeWireClient::handle_command(&state, &tx, UiCommand::SetRoleTarget { role: "Game".to_string(), sink: Some(c) });
+    let old = *state.graph().clusters.keys().next().unwrap();
+    let old_name = state.graph().orbs[&old].name.clone();
+    let topology = Topology::parse(r#"{"clusters": [{"devices": ["alsa_output.b", "alsa_output.c"]}]}"#).unwrap();
+
+    let steps = plan(&state, &topology).unwrap();
+    assert_eq!(steps.iter().map(ToString::to_string).collect::<Vec<_>>(), vec![
+        format!("Separate '{}'", old_name),
+        "Cluster alsa_output.b, alsa_output.c".to_string(),
+        "Play Game on the default output".to_string(),
+    ]);
+
+    PipeWireClient::apply_topology_to(&state, &tx, &topology).unwrap();
+
+    let graph = state.graph();
+    let clusters: Vec<String> = graph.clusters.keys().filter_map(|id| graph.cluster_key(*id)).collect();
+    assert_eq!(clusters, vec![auralis_core::persistence::cluster_key(&["alsa_output.b".to_string(), "alsa_output.c".to_string()])]);
+    assert!(state.store().data.settings.role_targets.is_empty());
+}
+
+#[test]
+fn test_plan_refuses_files_it_cannot_follow() {
+    // Nothing is planned (or done) for unknown devices, clashes and unknown roles
+    let state = SharedState::new(Arc::new(MockBackend::new()));
+    register_sink(&state, 10, "alsa_output.a", "Speaker");
+    register_sink(&state, 11, "alsa_output.b", "Speaker");
+    register_sink(&state, 12, "alsa_output.c", "Other");
+    let refused = |json: &str| plan(&state, &Topology::parse(json).unwrap()).unwrap_err().to_string();
+
+    assert_eq!(refused(r#"{"clusters": [{"devices": ["alsa_output.a", "Missing"]}]}"#), "No device 'Missing'");
+    assert_eq!(
+        refused(r#"{"clusters": [{"devices": ["Speaker", "Other"]}]}"#),
+        "More than one device is called 'Speaker', name it by node name (alsa_output.a, alsa_output.b)"
+    );
+    assert_eq!(
+        refused(r#"{"clusters": [{"devices": ["alsa_output.a", "Other"]}, {"devices": ["alsa_output.b", "Other"]}]}"#),
+        "'Other' is in more than one cluster"
+    );
+    assert_eq!(refused(r#"{"clusters": [{"devices": ["Other", "alsa_output.c"]}]}"#), r#"A cluster needs two devices, ["Other", "alsa_output.c"] has 1"#);
+    assert_eq!(refused(r#"{"roles": {"Karaoke": "Other"}}"#), "Unknown media role 'Karaoke'");
+    assert!(Topology::parse(r#"{"cluster": []}"#).is_err());
+    assert_eq!(plan(&state, &Topology::default()).unwrap(), Vec::new());
+}
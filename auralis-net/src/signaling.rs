@@ -1,4 +1,14 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// A message between two beam peers, relayed by whatever signaling channel connects them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Signal {
+    Offer { sdp: String },
+    Answer { sdp: String },
+    Candidate { mline_index: u32, candidate: String },
+}
 
 pub struct SignalingServer {
     // TODO: WebSocket server
@@ -0,0 +1,96 @@
+use gtk4::prelude::*;
+use std::collections::BTreeMap;
+use auralis_core::{Orb, OrbKind};
+use uuid::Uuid;
+use crate::state::SharedState;
+
+/// Handles to the activity page widgets ("what's playing where")
+pub struct ActivityView {
+    pub container: gtk4::Box,
+    /// One section per output with something playing, rebuilt by `update_list`
+    pub outputs: gtk4::Box,
+}
+
+pub fn build(state: SharedState) -> ActivityView {
+    let container = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    container.set_margin_start(24);
+    container.set_margin_end(24);
+    container.set_margin_top(24);
+    container.set_margin_bottom(24);
+
+    let title = gtk4::Label::builder()
+        .label("Now Playing")
+        .halign(gtk4::Align::Start)
+        .css_classes(vec!["heading"])
+        .build();
+    container.append(&title);
+
+    let outputs = gtk4::Box::new(gtk4::Orientation::Vertical, 16);
+    outputs.set_valign(gtk4::Align::Start);
+    update_list(&outputs, &state);
+
+    container.append(&outputs);
+    ActivityView { container, outputs }
+}
+
+pub fn update_list(outputs: &gtk4::Box, state: &SharedState) {
+    while let Some(child) = outputs.first_child() {
+        outputs.remove(&child);
+    }
+
+    let state = state.borrow();
+
+    // Output -> apps feeding it, outputs by name so the list doesn't jump around
+    let mut playing: BTreeMap<(&str, Uuid), Vec<&Orb>> = BTreeMap::new();
+    for (app, output) in &state.routes {
+        let (Some(app), Some(output)) = (state.orbs.get(app), state.orbs.get(output)) else { continue };
+        playing.entry((output.name.as_str(), output.id)).or_default().push(app);
+    }
+
+    if playing.is_empty() {
+        let empty_lbl = gtk4::Label::new(Some("Nothing is playing right now."));
+        empty_lbl.add_css_class("caption");
+        empty_lbl.set_halign(gtk4::Align::Start);
+        outputs.append(&empty_lbl);
+        return;
+    }
+
+    for ((name, output_id), mut apps) in playing {
+        apps.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let section = gtk4::Box::new(gtk4::Orientation::Vertical, 6);
+        section.add_css_class("device-card");
+
+        let is_cluster = state.orbs.get(&output_id)
+            .map(|o| matches!(o.kind, OrbKind::Cluster { .. }))
+            .unwrap_or(false);
+        let header = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+        let icon = gtk4::Image::from_icon_name(if is_cluster { "view-grid-symbolic" } else { "audio-speakers-symbolic" });
+        let header_lbl = gtk4::Label::builder()
+            .label(name)
+            .halign(gtk4::Align::Start)
+            .css_classes(vec!["device-name"])
+            .ellipsize(gtk4::pango::EllipsizeMode::End)
+            .build();
+        header.append(&icon);
+        header.append(&header_lbl);
+        section.append(&header);
+
+        for app in apps {
+            let app_row = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+            app_row.set_margin_start(24);
+            let app_icon = gtk4::Image::from_icon_name("audio-x-generic-symbolic");
+            let app_lbl = gtk4::Label::builder()
+                .label(&app.name)
+                .halign(gtk4::Align::Start)
+                .css_classes(vec!["device-status"])
+                .ellipsize(gtk4::pango::EllipsizeMode::End)
+                .build();
+            app_row.append(&app_icon);
+            app_row.append(&app_lbl);
+            section.append(&app_row);
+        }
+
+        outputs.append(&section);
+    }
+}
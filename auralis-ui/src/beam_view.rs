@@ -0,0 +1,137 @@
+use gtk4::prelude::*;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, SystemTime};
+use auralis_core::{Orb, OrbKind, UiCommand};
+use crate::state::SharedState;
+
+/// Handles to the Beam page widgets (active remote sessions)
+pub struct BeamView {
+    pub container: gtk4::Box,
+    /// One card per beam session, rebuilt by `update_list`
+    pub sessions: gtk4::Box,
+}
+
+pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>) -> BeamView {
+    let container = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    container.set_margin_start(24);
+    container.set_margin_end(24);
+    container.set_margin_top(24);
+    container.set_margin_bottom(24);
+
+    let title = gtk4::Label::builder()
+        .label("Beam Sessions")
+        .halign(gtk4::Align::Start)
+        .css_classes(vec!["heading"])
+        .build();
+    container.append(&title);
+
+    let sessions = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    sessions.set_valign(gtk4::Align::Start);
+    update_list(&sessions, &state, &cmd_tx);
+
+    // Keep the elapsed times ticking while the page is on screen
+    let sessions_weak = sessions.downgrade();
+    gtk4::glib::timeout_add_seconds_local(1, move || {
+        let Some(sessions) = sessions_weak.upgrade() else {
+            return gtk4::glib::ControlFlow::Break;
+        };
+        if sessions.is_mapped() && sessions.first_child().is_some_and(|c| c.has_css_class("device-card")) {
+            update_list(&sessions, &state, &cmd_tx);
+        }
+        gtk4::glib::ControlFlow::Continue
+    });
+
+    container.append(&sessions);
+    BeamView { container, sessions }
+}
+
+pub fn update_list(sessions: &gtk4::Box, state: &SharedState, cmd_tx: &Sender<UiCommand>) {
+    while let Some(child) = sessions.first_child() {
+        sessions.remove(&child);
+    }
+
+    let state = state.borrow();
+
+    let mut beams: Vec<&Orb> = state.orbs.values()
+        .filter(|o| matches!(o.kind, OrbKind::BeamOutput { .. }))
+        .collect();
+
+    if beams.is_empty() {
+        let empty_lbl = gtk4::Label::new(Some("No active beams. Start a beam by dragging a device to Beam."));
+        empty_lbl.add_css_class("caption");
+        empty_lbl.set_halign(gtk4::Align::Start);
+        sessions.append(&empty_lbl);
+        return;
+    }
+
+    beams.sort_by(|a, b| a.name.cmp(&b.name));
+    for beam in beams {
+        sessions.append(&build_card(beam, cmd_tx));
+    }
+}
+
+/// `1:05` or `1:02:03`
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs / 3600 {
+        0 => format!("{}:{:02}", secs / 60, secs % 60),
+        hours => format!("{}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+    }
+}
+
+fn build_card(orb: &Orb, cmd_tx: &Sender<UiCommand>) -> gtk4::Box {
+    let OrbKind::BeamOutput { session_id, peer_url, codec, bitrate_kbps, started_at } = &orb.kind else {
+        unreachable!("only beam orbs get a beam card");
+    };
+
+    let card = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    card.add_css_class("device-card");
+
+    let icon = gtk4::Image::from_icon_name("network-wireless-symbolic");
+    icon.set_pixel_size(24);
+    icon.set_valign(gtk4::Align::Center);
+    card.append(&icon);
+
+    let details = gtk4::Box::new(gtk4::Orientation::Vertical, 4);
+    details.set_hexpand(true);
+
+    let name_lbl = gtk4::Label::builder()
+        .label(&orb.name)
+        .halign(gtk4::Align::Start)
+        .css_classes(vec!["device-name"])
+        .ellipsize(gtk4::pango::EllipsizeMode::End)
+        .build();
+    details.append(&name_lbl);
+
+    // Filled in by the session once a peer has answered
+    let peer_lbl = gtk4::Label::builder()
+        .label(if peer_url.is_empty() { "Waiting for a peer…" } else { peer_url.as_str() })
+        .halign(gtk4::Align::Start)
+        .css_classes(vec!["caption"])
+        .ellipsize(gtk4::pango::EllipsizeMode::Middle)
+        .selectable(true)
+        .build();
+    details.append(&peer_lbl);
+
+    // A clock that went backwards just shows 0:00
+    let elapsed = SystemTime::now().duration_since(*started_at).unwrap_or_default();
+    let info_lbl = gtk4::Label::builder()
+        .label(&format!("{} · {} · {} kbps · {}", orb.status, codec, bitrate_kbps, format_elapsed(elapsed)))
+        .halign(gtk4::Align::Start)
+        .css_classes(vec!["device-status"])
+        .build();
+    details.append(&info_lbl);
+    card.append(&details);
+
+    let stop_btn = gtk4::Button::with_label("Stop");
+    stop_btn.add_css_class("btn-destructive");
+    stop_btn.set_valign(gtk4::Align::Center);
+    let cmd_tx_stop = cmd_tx.clone();
+    let session_id = session_id.clone();
+    stop_btn.connect_clicked(move |_| {
+        let _ = cmd_tx_stop.send(UiCommand::BeamClosed { session_id: session_id.clone(), reason: None });
+    });
+    card.append(&stop_btn);
+
+    card
+}
@@ -1,20 +1,64 @@
 use gtk4::prelude::*;
-use gtk4::{DrawingArea, DropTarget, GestureClick};
+use gtk4::{gio, pango, DrawingArea, DropTarget, GestureClick};
 use std::sync::mpsc::Sender;
-use auralis_core::{Orb, OrbKind, UiCommand};
-use crate::state::SharedState;
+use auralis_core::{classify_connection, ConnectionKind, DeviceBus, Orb, OrbKind, UiCommand};
+use crate::state::{AppState, SharedState};
+use crate::theme::AuralisTheme;
+use crate::{cluster_dialog, latency_dialog, layout, merge_dialog};
 use uuid::Uuid;
+use std::time::{Duration, Instant};
 use cairo;
 
+/// Cluster cards are this wide, and at least `CLUSTER_CARD_MIN_H` tall
+const CLUSTER_CARD_W: f64 = 300.0;
+const CLUSTER_CARD_MIN_H: f64 = 150.0;
+/// Member names listed on a card before the rest collapse into "+N more"
+const CLUSTER_CARD_MAX_MEMBERS: usize = 4;
+const CLUSTER_CARD_LINE_H: f64 = 14.0;
+
+/// Released orbs only merge when their centers are this close (or the center is on a card)
+const CLUSTER_OVERLAP_RADIUS: f64 = 40.0;
+/// Drags shorter than this are treated as nudging an orb into place, never as clustering
+const CLUSTER_MIN_DRAG: f64 = 48.0;
+/// Clicks this close to an app -> output line unlink it
+const LINK_HIT_DISTANCE: f64 = 6.0;
+/// The Beam drop zone's center is this far from the canvas' right and bottom edges
+const BEAM_ZONE_INSET: f64 = 72.0;
+const BEAM_ZONE_RADIUS: f64 = 44.0;
+/// Scrolling zooms by this factor per step, between these bounds
+const ZOOM_STEP: f64 = 1.1;
+const MIN_ZOOM: f64 = 0.25;
+const MAX_ZOOM: f64 = 3.0;
+/// A new cluster card flashes this long
+const FORMED_FLASH: Duration = Duration::from_millis(900);
+
 pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>, on_drop: impl Fn() + 'static) -> DrawingArea {
     let drawing_area = DrawingArea::builder()
         .hexpand(true)
         .vexpand(true)
         .build();
 
-    // Animation Loop
-    drawing_area.add_tick_callback(|da, _clock| {
-        da.queue_draw();
+    // Animation Loop: arrange the floating orbs, then draw them. Frames are only drawn while
+    // something moves; changes (events, drags, hovering) ask for their own redraw.
+    // In the background nothing is animated at all.
+    let state_tick = state.clone();
+    drawing_area.add_tick_callback(move |da, _clock| {
+        let focused = da.root().and_downcast::<gtk4::Window>().is_some_and(|w| w.is_active());
+        if focused {
+            let mut state = state_tick.borrow_mut();
+            let (left, top) = state.view.to_canvas(0.0, 0.0);
+            let (right, bottom) = state.view.to_canvas(da.width() as f64, da.height() as f64);
+            let moved = layout::step(&mut state, (left, top, right - left, bottom - top));
+            let pulsing = !state.settings.reduce_motion && state.orbs.values().any(layout::is_floating);
+            // One more frame once the flash is over, to clear it
+            let flashing = state.formed.is_some();
+            if state.formed.is_some_and(|(_, since)| since.elapsed() >= FORMED_FLASH) {
+                state.formed = None;
+            }
+            if moved || pulsing || flashing {
+                da.queue_draw();
+            }
+        }
         gtk4::glib::ControlFlow::Continue
     });
 
@@ -27,45 +71,116 @@ pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>, on_drop: impl Fn() +
         
         let mut has_clusters = false;
 
-        for orb in state.orbs.values() {
+        // Orbs, cards and links are zoomed and panned; the Beam zone and the empty text stay put
+        cr.save().unwrap();
+        cr.translate(state.view.pan.0, state.view.pan.1);
+        cr.scale(state.view.zoom, state.view.zoom);
+
+        // Links first, so the orbs sit on top of the line ends
+        for (_, _, from, to) in link_lines(&state) {
+            draw_link(cr, from, to, &state.theme);
+        }
+        for beam in state.orbs.values().filter(|o| matches!(o.kind, OrbKind::BeamOutput { .. })) {
+            if let (Some(from), Some(to)) = (beam_source(&state, beam).and_then(orb_center), orb_center(beam)) {
+                draw_beam_link(cr, from, to, beam_color(&beam.status));
+            }
+        }
+
+        // Cluster members are shown by their cluster's card
+        for orb in state.orbs.values().filter(|o| o.membership().is_none()) {
+            // Suspended outputs are drawn faded
+            let suspended = state.is_suspended(orb);
+            if suspended {
+                cr.push_group();
+            }
             match &orb.kind {
-                OrbKind::Cluster { devices } => {
+                OrbKind::Cluster { devices, members, .. } => {
                     has_clusters = true;
-                    draw_cluster(cr, orb, devices);
+                    let buses: Vec<DeviceBus> = members.iter().filter_map(|id| state.orbs.get(id)).map(|o| o.bus).collect();
+                    let bluetooth_ms = DeviceBus::bluetooth_offset_ms(&buses, state.settings.bluetooth_offset_ms);
+                    draw_cluster(cr, orb, devices, bluetooth_ms, &state.theme);
                 },
+                OrbKind::BeamOutput { .. } => {
+                    if orb.position != (0.0, 0.0) {
+                        has_clusters = true;
+                        draw_beam_orb(cr, orb, &state.theme);
+                    }
+                }
                 _ => {
                     // Draw floating orbs if they are in the zone
                     if orb.position != (0.0, 0.0) {
                         has_clusters = true; // Treat as content so we don't show "empty" text
-                        draw_floating_orb(cr, orb);
+                        draw_floating_orb(cr, orb, &state.theme, !state.settings.reduce_motion);
                     }
                 }
             }
+            if suspended {
+                cr.pop_group_to_source().unwrap();
+                cr.paint_with_alpha(0.4).unwrap();
+            }
+        }
+
+        if let Some((id, since)) = state.formed {
+            let progress = since.elapsed().as_secs_f64() / FORMED_FLASH.as_secs_f64();
+            if let Some(orb) = state.orbs.get(&id).filter(|_| progress < 1.0) {
+                draw_formed_flash(cr, orb, progress, !state.settings.reduce_motion, &state.theme);
+            }
+        }
+
+        for orb in state.gathered.iter().filter_map(|id| state.orbs.get(id)) {
+            draw_gathered_marker(cr, orb, &state.theme);
+        }
+
+        // Where a device dragged from the list would land
+        if let Some((target, allowed)) = state.drop_preview {
+            if let Some(orb) = state.orbs.get(&target) {
+                draw_drop_highlight(cr, orb, allowed, &state.theme);
+            }
         }
+        cr.restore().unwrap();
+
+        draw_beam_zone(cr, beam_zone_center(w as f64, h as f64), state.beam_preview, &state.theme);
 
         if !has_clusters {
             // Draw "Drop here" text if empty? 
             // Or handled by overlay widget?
             // Let's draw it manually for simplicity
-            cr.set_source_rgb(1.0, 1.0, 1.0);
-            cr.select_font_face("Space Grotesk", cairo::FontSlant::Normal, cairo::FontWeight::Bold);
-            cr.set_font_size(24.0);
-            let text = "Clustering Zone";
-            let extents = cr.text_extents(text).unwrap();
-            cr.move_to((w as f64 - extents.width()) / 2.0, (h as f64) / 2.0 - 20.0);
-            cr.show_text(text).unwrap();
+            let text_w = (w as f64 - 40.0).max(1.0);
+
+            // Nothing to cluster yet: say whether we're still looking or there's nothing there
+            let empty = state.empty_message();
+            set_ink(cr, &state.theme, 1.0);
+            let title = text_layout(cr, empty.unwrap_or("Clustering Zone"), 24.0, true, text_w);
+            title.set_alignment(pango::Alignment::Center);
+            draw_layout(cr, &title, 20.0, (h as f64) / 2.0 - 20.0);
             
-            cr.set_source_rgba(0.6, 0.7, 0.8, 0.5);
-            cr.set_font_size(14.0);
-            let subtext = "Drag and drop device icons here to form or modify a cluster.";
-            let sub_extents = cr.text_extents(subtext).unwrap();
-            cr.move_to((w as f64 - sub_extents.width()) / 2.0, (h as f64) / 2.0 + 10.0);
-            cr.show_text(subtext).unwrap();
+            // Wraps on narrow windows instead of running off the edges
+            if empty.is_none() {
+                cr.set_source_rgba(0.6, 0.7, 0.8, 0.5);
+                let subtext = text_layout(cr, "Drag and drop device icons here to form or modify a cluster.", 14.0, false, text_w);
+                subtext.set_ellipsize(pango::EllipsizeMode::None);
+                subtext.set_wrap(pango::WrapMode::WordChar);
+                subtext.set_alignment(pango::Alignment::Center);
+                draw_layout(cr, &subtext, 20.0, (h as f64) / 2.0 + 10.0);
+            }
         }
     });
 
-    // Drop Target
+    // Drop Target. The dragged ID is preloaded so hovering can show whether the drop would work.
     let target = DropTarget::new(gtk4::glib::Type::STRING, gtk4::gdk::DragAction::COPY);
+    target.set_preload(true);
+    let (state_enter, da_enter) = (state.clone(), drawing_area.clone());
+    target.connect_enter(move |target, x, y| drop_action(&state_enter, &da_enter, target, x, y));
+    let (state_motion, da_motion) = (state.clone(), drawing_area.clone());
+    target.connect_motion(move |target, x, y| drop_action(&state_motion, &da_motion, target, x, y));
+    let (state_leave, da_leave) = (state.clone(), drawing_area.clone());
+    target.connect_leave(move |_| {
+        let mut state = state_leave.borrow_mut();
+        state.drop_preview = None;
+        state.beam_preview = None;
+        da_leave.queue_draw();
+    });
+
     let state_drop = state.clone();
     let cmd_tx_drop = cmd_tx.clone();
     let da_drop = drawing_area.clone();
@@ -74,82 +189,63 @@ pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>, on_drop: impl Fn() +
         let id_str = value.get::<String>().unwrap();
         if let Ok(dropped_id) = Uuid::parse_str(&id_str) {
             println!("Dropped ID: {}", dropped_id);
-            
-            // Logic:
-            // 1. If dropped on empty space -> Create new cluster (if single device) or move it?
-            //    Actually, dropping a single device into the zone should probably just "park" it there ready to be clustered?
-            //    Or does it immediately create a cluster?
-            //    The mockup shows "Living Room Audio" with 2 devices.
-            //    If I drop 1 device, maybe it stays as a "Potential Cluster"?
-            //    For now, let's say dropping a device creates a "Cluster of 1" or just moves it visually.
-            //    BUT, our Core logic requires 2 devices to make a cluster.
-            //    So maybe we need a "Staging Area" in the UI state?
-            
-            //    SIMPLIFICATION: If dropped on an EXISTING cluster -> Add to it.
-            //    If dropped on EMPTY space -> Wait for a second drop?
-            //    Let's implement: Drop 1 device -> It becomes a "Floating Orb" in the zone (removed from list).
-            //    Drop 2nd device on it -> Merge.
-            
-            //    We need to update the OrbState to "Floating" (in zone) vs "Listed" (in list).
-            //    Currently OrbState has Floating/Orbiting.
-            //    We can use that.
-            
-            //    Let's send a command to Core? No, Core doesn't know about "List vs Zone".
-            //    We handle this in UI state.
-            
-            //    TODO: We need to update the Orb's position to where it was dropped.
-            {
+
+            // On the Beam zone it's beamed, and stays where it was
+            if on_beam_zone(&da_drop, x, y) {
+                let allowed = {
+                    let mut state_ref = state_drop.borrow_mut();
+                    state_ref.beam_preview = None;
+                    can_beam(&state_ref, dropped_id)
+                };
+                if allowed {
+                    let _ = cmd_tx_drop.send(UiCommand::StartBeam { source: dropped_id });
+                }
+                da_drop.queue_draw();
+                return allowed;
+            }
+
+            // Unknown orbs and combinations that can't be connected are refused outright
+            let (x, y) = state_drop.borrow().view.to_canvas(x, y);
+            let preview = {
                 let mut state_ref = state_drop.borrow_mut();
-                if let Some(orb) = state_ref.orbs.get_mut(&dropped_id) {
-                    orb.position = (x, y);
-                    println!("Moved {} to zone at ({}, {})", orb.name, x, y);
+                state_ref.drop_preview = None;
+                if !state_ref.orbs.contains_key(&dropped_id) {
+                    return false;
                 }
+                drop_preview(&state_ref, dropped_id, x, y)
+            };
+            if let Some((tid, false)) = preview {
+                tracing::debug!("Refusing drop of {} onto {}", dropped_id, tid);
+                return false;
             }
             
-            
-            // Auto-Clustering Logic  
-            // If there are ANY other devices/clusters in the zone, automatically cluster with them
-            let mut target_id = None;
-            {
-                let state_ref = state_drop.borrow();
-                
-                println!("Auto-clustering: Checking for existing devices in zone...");
-                
-                // First, check if there is an existing cluster (clusters might be at 0,0 initially)
-                for other in state_ref.orbs.values() {
-                    if other.id == dropped_id { continue; }
-                    
-                    println!("  Found orb: {} (kind: {:?}, pos: {:?})", other.name, other.kind, other.position);
-                    
-                    // Prefer clustering with existing clusters (ignore position for clusters)
-                    if matches!(other.kind, OrbKind::Cluster { .. }) {
-                        println!("  → Found cluster! Will connect to it.");
-                        target_id = Some(other.id);
-                        break;
-                    }
+            // Dropped right onto another orb or card: connect the two. On empty space a device
+            // starts a cluster of its own (a card waiting for a second device) if drops cluster
+            // right away, anything else is just parked where it was dropped.
+            let (target_id, is_device) = {
+                let mut state_ref = state_drop.borrow_mut();
+                let Some(orb) = state_ref.orbs.get_mut(&dropped_id) else { return false };
+                orb.position = (x, y);
+                let is_device = matches!(orb.kind, OrbKind::PhysicalSink { .. });
+                let (cx, cy) = (x + 32.0, y + 32.0); // Center of the dropped orb
+                (merge_target(&state_ref, dropped_id, cx, cy), is_device)
+            };
+            match target_id {
+                Some(tid) => {
+                    println!("Auto-clustering: {} -> {}", dropped_id, tid);
+                    connect_or_confirm(&mut state_drop.borrow_mut(), &da_drop, &cmd_tx_drop, dropped_id, tid);
                 }
-                
-                // If no cluster found, cluster with the first floating orb in the zone
-                if target_id.is_none() {
-                    println!("  No cluster found, looking for floating orbs...");
-                    for other in state_ref.orbs.values() {
-                        if other.id == dropped_id { continue; }
-                        if other.position == (0.0, 0.0) { continue; } // Skip devices not in zone
-                        
-                        println!("  → Found floating orb: {}. Will connect to it.", other.name);
-                        target_id = Some(other.id);
-                        break;
+                None => {
+                    // The card is named after the device, so it shows up where the device was dropped
+                    let mut state_ref = state_drop.borrow_mut();
+                    state_ref.gathered.retain(|id| *id != dropped_id);
+                    if let Some(cmd) = state_ref.save_position(dropped_id) {
+                        let _ = cmd_tx_drop.send(cmd);
+                    }
+                    if is_device && state_ref.settings.auto_cluster_on_drop {
+                        let _ = cmd_tx_drop.send(UiCommand::StageCluster { device: dropped_id });
                     }
                 }
-                
-                if target_id.is_none() {
-                    println!("  No clustering target found. Device will stay as floating orb.");
-                }
-            }
-            
-            if let Some(tid) = target_id {
-                println!("Auto-clustering: {} -> {}", dropped_id, tid);
-                let _ = cmd_tx_drop.send(UiCommand::Connect { source: dropped_id, target: tid });
             }
             
             // Trigger redraw
@@ -172,30 +268,47 @@ pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>, on_drop: impl Fn() +
     
     click.connect_pressed(move |_, _, x, y| {
         let state = state_click.borrow();
-        for orb in state.orbs.values() {
-            if !matches!(orb.kind, OrbKind::Cluster { .. }) { continue; }
-            
-            // Check button bounds (relative to orb pos)
-            // Button is at bottom of card
-            let ox = orb.position.0;
-            let oy = orb.position.1;
-            let w = 300.0;
-            let h = 150.0;
-            
-            let btn_x = ox + w - 100.0 - 20.0;
-            let btn_y = oy + h - 40.0;
-            let btn_w = 100.0;
-            let btn_h = 30.0;
-            
-            if x >= btn_x && x <= btn_x + btn_w && y >= btn_y && y <= btn_y + btn_h {
-                println!("Separate clicked for {}", orb.name);
-                let _ = cmd_tx_click.send(UiCommand::Disconnect { source: orb.id, target: orb.id });
-                break;
+        let (x, y) = state.view.to_canvas(x, y);
+        if let Some(orb) = state.orbs.values().find(|o| on_separate_button(o, x, y)) {
+            println!("Separate clicked for {}", orb.name);
+            let _ = cmd_tx_click.send(UiCommand::Disconnect { source: orb.id, target: orb.id });
+        } else if hit_test(&state, x, y).is_none() {
+            if let Some((source, target)) = link_at(&state, x, y) {
+                tracing::debug!("Unlink clicked: {} -> {}", source, target);
+                let _ = cmd_tx_click.send(UiCommand::Unlink { source, target });
             }
         }
     });
     drawing_area.add_controller(click);
 
+    // Right-click context menu. One popover for the canvas, its model is swapped per orb.
+    let context_menu = gtk4::PopoverMenu::from_model(None::<&gio::MenuModel>);
+    context_menu.set_parent(&drawing_area);
+    context_menu.set_has_arrow(false);
+    context_menu.set_position(gtk4::PositionType::Bottom);
+
+    let right_click = GestureClick::new();
+    right_click.set_button(gtk4::gdk::BUTTON_SECONDARY);
+    let state_menu = state.clone();
+    let cmd_tx_menu = cmd_tx.clone();
+    let da_menu = drawing_area.clone();
+    right_click.connect_pressed(move |_, _, x, y| {
+        let orb = {
+            let state = state_menu.borrow();
+            let (cx, cy) = state.view.to_canvas(x, y);
+            hit_test(&state, cx, cy).and_then(|id| state.orbs.get(&id).cloned())
+        };
+        let Some((menu, actions)) = orb.and_then(|orb| context_actions(&state_menu, &orb, &da_menu, &cmd_tx_menu)) else {
+            return;
+        };
+
+        da_menu.insert_action_group("orb", Some(&actions));
+        context_menu.set_menu_model(Some(&menu));
+        context_menu.set_pointing_to(Some(&gtk4::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+        context_menu.popup();
+    });
+    drawing_area.add_controller(right_click);
+
     // Drag Controller for moving floating orbs
     let drag = gtk4::GestureDrag::new();
     let state_drag = state.clone();
@@ -211,49 +324,23 @@ pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>, on_drop: impl Fn() +
     
     drag.connect_drag_begin(move |_, x, y| {
         let state = state_drag.borrow();
-        for orb in state.orbs.values() {
-            // Check hit test
-            // Floating Orb: Circle radius ~32
-            // Cluster: Rect 300x150
-            
-            // IMPORTANT: Don't start drag if clicking Separate button
-            if matches!(orb.kind, OrbKind::Cluster { .. }) {
-                let ox = orb.position.0;
-                let oy = orb.position.1;
-                let w = 300.0;
-                let h = 150.0;
-                
-                let btn_x = ox + w - 100.0 - 20.0;
-                let btn_y = oy + h - 40.0;
-                let btn_w = 100.0;
-                let btn_h = 30.0;
-                
-                // If clicking Separate button, don't start drag
-                if x >= btn_x && x <= btn_x + btn_w && y >= btn_y && y <= btn_y + btn_h {
-                    println!("Click on Separate button - not starting drag");
-                    return;
-                }
-            }
-            
-            let hit = match orb.kind {
-                OrbKind::Cluster { .. } => {
-                     x >= orb.position.0 && x <= orb.position.0 + 300.0 &&
-                     y >= orb.position.1 && y <= orb.position.1 + 150.0
-                },
-                _ => {
-                    let dx = x - (orb.position.0 + 32.0);
-                    let dy = y - (orb.position.1 + 32.0);
-                    (dx*dx + dy*dy).sqrt() < 40.0 // Slightly larger hit area
-                }
-            };
-            
-            if hit {
-                println!("Drag begin on {}", orb.name);
-                *dragged_id_begin.borrow_mut() = Some(orb.id);
-                *start_pos_begin.borrow_mut() = orb.position;
-                break;
-            }
+        let (x, y) = state.view.to_canvas(x, y);
+
+        // IMPORTANT: Don't start drag if clicking Separate button
+        if state.orbs.values().any(|o| on_separate_button(o, x, y)) {
+            println!("Click on Separate button - not starting drag");
+            return;
+        }
+
+        if let Some(orb) = hit_test(&state, x, y).and_then(|id| state.orbs.get(&id)) {
+            println!("Drag begin on {}", orb.name);
+            *dragged_id_begin.borrow_mut() = Some(orb.id);
+            *start_pos_begin.borrow_mut() = orb.position;
         }
+        // The layout leaves it alone until it's let go
+        let dragged = *dragged_id_begin.borrow();
+        drop(state);
+        state_drag.borrow_mut().dragged_orb_id = dragged;
     });
     
     let state_update = state.clone();
@@ -264,9 +351,16 @@ pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>, on_drop: impl Fn() +
     drag.connect_drag_update(move |_, offset_x, offset_y| {
         if let Some(id) = *dragged_id_update.borrow() {
             let mut state = state_update.borrow_mut();
+            let view = state.view;
             if let Some(orb) = state.orbs.get_mut(&id) {
+                // The pointer moves in widget pixels, the orb in canvas units
                 let (sx, sy) = *start_pos_update.borrow();
-                orb.position = (sx + offset_x, sy + offset_y);
+                orb.position = (sx + offset_x / view.zoom, sy + offset_y / view.zoom);
+                let over_beam_zone = orb_center(orb)
+                    .map(|(cx, cy)| view.to_widget(cx, cy))
+                    .is_some_and(|(wx, wy)| on_beam_zone(&da_update, wx, wy));
+                let preview = over_beam_zone.then(|| can_beam(&state, id));
+                state.beam_preview = preview;
                 da_update.queue_draw();
             }
         }
@@ -274,53 +368,56 @@ pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>, on_drop: impl Fn() +
     
     let state_end = state.clone();
     let dragged_id_end = dragged_orb_id.clone();
+    let start_pos_end = start_pos.clone();
     let cmd_tx_end = cmd_tx.clone();
+    let da_end = drawing_area.clone();
     
-    drag.connect_drag_end(move |_, _offset_x, _offset_y| {
+    drag.connect_drag_end(move |_, offset_x, offset_y| {
         let dragged_id = dragged_id_end.borrow().clone();
-        
+        state_end.borrow_mut().dragged_orb_id = None;
+        da_end.queue_draw();
+
         if let Some(id) = dragged_id {
             println!("Drag end for {}", id);
+
+            // Released on the Beam zone: beam it and put it back where the drag started
+            let beam = state_end.borrow_mut().beam_preview.take();
+            if let Some(allowed) = beam {
+                if allowed {
+                    let _ = cmd_tx_end.send(UiCommand::StartBeam { source: id });
+                }
+                if let Some(orb) = state_end.borrow_mut().orbs.get_mut(&id) {
+                    orb.position = *start_pos_end.borrow();
+                }
+                *dragged_id_end.borrow_mut() = None;
+                return;
+            }
             
-            // Check for collision/clustering
+            // Short drags are repositioning; only a deliberate move onto another orb clusters
             let mut target_id = None;
-            {
+            if offset_x.hypot(offset_y) >= CLUSTER_MIN_DRAG {
                 let state = state_end.borrow();
                 if let Some(dragged_orb) = state.orbs.get(&id) {
-                    for other in state.orbs.values() {
-                        if other.id == id { continue; }
-                        
-                        // Simple distance check for now
-                        // If dragging ONTO a cluster, check cluster bounds
-                        // If dragging ONTO a floating orb, check distance
-                        
-                        let hit = match other.kind {
-                            OrbKind::Cluster { .. } => {
-                                // Center of dragged orb
-                                let cx = dragged_orb.position.0 + 32.0;
-                                let cy = dragged_orb.position.1 + 32.0;
-                                
-                                cx >= other.position.0 && cx <= other.position.0 + 300.0 &&
-                                cy >= other.position.1 && cy <= other.position.1 + 150.0
-                            },
-                            _ => {
-                                let dx = dragged_orb.position.0 - other.position.0;
-                                let dy = dragged_orb.position.1 - other.position.1;
-                                (dx*dx + dy*dy).sqrt() < 80.0 // Overlap threshold
-                            }
-                        };
-                        
-                        if hit {
-                            target_id = Some(other.id);
-                            break;
-                        }
-                    }
+                    // Center of dragged orb
+                    let cx = dragged_orb.position.0 + 32.0;
+                    let cy = dragged_orb.position.1 + 32.0;
+                    target_id = merge_target(&state, id, cx, cy);
                 }
             }
             
             if let Some(tid) = target_id {
                 println!("Triggering Connect (Drag): {} -> {}", id, tid);
-                let _ = cmd_tx_end.send(UiCommand::Connect { source: id, target: tid });
+                connect_or_confirm(&mut state_end.borrow_mut(), &da_end, &cmd_tx_end, id, tid);
+            } else {
+                let mut state = state_end.borrow_mut();
+                // Moved away from what it was gathered with
+                if offset_x.hypot(offset_y) >= CLUSTER_MIN_DRAG {
+                    state.gathered.retain(|gathered| *gathered != id);
+                }
+                // Saved once the drag is over, not on every motion update
+                if let Some(cmd) = state.save_position(id) {
+                    let _ = cmd_tx_end.send(cmd);
+                }
             }
             
             *dragged_id_end.borrow_mut() = None;
@@ -329,45 +426,633 @@ pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>, on_drop: impl Fn() +
     
     drawing_area.add_controller(drag);
 
+    // Middle-drag pans the canvas
+    let pan = gtk4::GestureDrag::new();
+    pan.set_button(gtk4::gdk::BUTTON_MIDDLE);
+    let pan_start = std::rc::Rc::new(std::cell::Cell::new((0.0, 0.0)));
+    let (state_pan, pan_start_begin) = (state.clone(), pan_start.clone());
+    pan.connect_drag_begin(move |_, _, _| pan_start_begin.set(state_pan.borrow().view.pan));
+    let (state_pan, da_pan) = (state.clone(), drawing_area.clone());
+    pan.connect_drag_update(move |_, offset_x, offset_y| {
+        let (px, py) = pan_start.get();
+        state_pan.borrow_mut().view.pan = (px + offset_x, py + offset_y);
+        da_pan.queue_draw();
+    });
+    drawing_area.add_controller(pan);
+
+    // Scrolling zooms around the pointer, which a motion controller keeps track of
+    let pointer = std::rc::Rc::new(std::cell::Cell::new((0.0, 0.0)));
+    let motion = gtk4::EventControllerMotion::new();
+    let pointer_motion = pointer.clone();
+    motion.connect_motion(move |_, x, y| pointer_motion.set((x, y)));
+    drawing_area.add_controller(motion);
+
+    let scroll = gtk4::EventControllerScroll::new(gtk4::EventControllerScrollFlags::VERTICAL);
+    let (state_zoom, da_zoom) = (state.clone(), drawing_area.clone());
+    scroll.connect_scroll(move |_, _, dy| {
+        let mut state = state_zoom.borrow_mut();
+        let zoom = (state.view.zoom * ZOOM_STEP.powf(-dy)).clamp(MIN_ZOOM, MAX_ZOOM);
+        if zoom != state.view.zoom {
+            state.view.zoom_at(zoom, pointer.get());
+            da_zoom.queue_draw();
+        }
+        gtk4::glib::Propagation::Stop
+    });
+    drawing_area.add_controller(scroll);
+
     drawing_area}
 
-fn draw_cluster(cr: &cairo::Context, orb: &Orb, _devices: &Vec<String>) {
-    let x = orb.position.0;
-    let y = orb.position.1;
-    let w = 300.0;
-    let h = 150.0;
-    
-    // Draw Card Background
-    cr.set_source_rgba(0.17, 0.42, 0.93, 0.2); // Primary/20
-    // Rounded Rect
-    let r = 12.0;
+/// The orb drawn under a canvas point.
+/// Floating orbs are circles of radius ~32, clusters are cards (see `cluster_card_height`).
+fn hit_test(state: &AppState, x: f64, y: f64) -> Option<Uuid> {
+    state.orbs.values()
+        .filter(|orb| orb.membership().is_none())
+        .find(|orb| match orb.kind {
+            OrbKind::Cluster { .. } => {
+                x >= orb.position.0 && x <= orb.position.0 + CLUSTER_CARD_W &&
+                y >= orb.position.1 && y <= orb.position.1 + cluster_card_height(orb)
+            },
+            _ => {
+                let dx = x - (orb.position.0 + 32.0);
+                let dy = y - (orb.position.1 + 32.0);
+                (dx*dx + dy*dy).sqrt() < 40.0 // Slightly larger hit area
+            }
+        })
+        .map(|orb| orb.id)
+}
+
+/// The orb that an orb released with its center at (cx, cy) should merge into.
+/// Cards count when the center is on them, floating orbs only when clearly overlapping.
+fn merge_target(state: &AppState, moving: Uuid, cx: f64, cy: f64) -> Option<Uuid> {
+    state.orbs.values()
+        .filter(|other| other.id != moving && other.membership().is_none())
+        .find(|other| match other.kind {
+            OrbKind::Cluster { .. } => {
+                cx >= other.position.0 && cx <= other.position.0 + CLUSTER_CARD_W &&
+                cy >= other.position.1 && cy <= other.position.1 + cluster_card_height(other)
+            },
+            _ => {
+                // Orbs that aren't on the canvas can't be dropped onto
+                if other.position == (0.0, 0.0) {
+                    return false;
+                }
+                let dx = cx - (other.position.0 + 32.0);
+                let dy = cy - (other.position.1 + 32.0);
+                dx.hypot(dy) < CLUSTER_OVERLAP_RADIUS
+            }
+        })
+        .map(|other| other.id)
+}
+
+/// What dropping `dropped` with its corner at (x, y) would hit: the orb under it and whether
+/// the two can be connected. `None` when it would just be parked on empty canvas.
+fn drop_preview(state: &AppState, dropped: Uuid, x: f64, y: f64) -> Option<(Uuid, bool)> {
+    let source = state.orbs.get(&dropped)?;
+    let target = merge_target(state, dropped, x + 32.0, y + 32.0)?;
+    // Apps can't play to a staged cluster, there's no sink yet
+    let allowed = state.orbs.get(&target).is_some_and(|t| match classify_connection(&source.kind, &t.kind) {
+        ConnectionKind::Invalid => false,
+        ConnectionKind::Link => !t.is_staged(),
+        _ => true,
+    });
+    Some((target, allowed))
+}
+
+/// Drag action while a list drag hovers the canvas: no-drop over an orb it can't be
+/// connected to, over the Beam zone if it can't be beamed (or for an ID we don't know),
+/// copy otherwise
+fn drop_action(state: &SharedState, canvas: &DrawingArea, target: &DropTarget, x: f64, y: f64) -> gtk4::gdk::DragAction {
+    // The outline follows the pointer
+    canvas.queue_draw();
+    let mut state = state.borrow_mut();
+    state.beam_preview = None;
+    // Not loaded yet; the next motion event will know
+    let Some(value) = target.value() else {
+        state.drop_preview = None;
+        return gtk4::gdk::DragAction::COPY;
+    };
+    let dropped = value.get::<String>().ok()
+        .and_then(|s| Uuid::parse_str(&s).ok())
+        .filter(|id| state.orbs.contains_key(id));
+    if let Some(id) = dropped.filter(|_| on_beam_zone(canvas, x, y)) {
+        let allowed = can_beam(&state, id);
+        state.drop_preview = None;
+        state.beam_preview = Some(allowed);
+        return if allowed { gtk4::gdk::DragAction::COPY } else { gtk4::gdk::DragAction::empty() };
+    }
+    let (x, y) = state.view.to_canvas(x, y);
+    state.drop_preview = dropped.and_then(|id| drop_preview(&state, id, x, y));
+    match (dropped, state.drop_preview) {
+        (None, _) | (_, Some((_, false))) => gtk4::gdk::DragAction::empty(),
+        _ => gtk4::gdk::DragAction::COPY,
+    }
+}
+
+/// Center of the Beam drop zone on a canvas of the given size (bottom right corner)
+fn beam_zone_center(width: f64, height: f64) -> (f64, f64) {
+    (width - BEAM_ZONE_INSET, height - BEAM_ZONE_INSET)
+}
+
+fn on_beam_zone(canvas: &DrawingArea, x: f64, y: f64) -> bool {
+    let (zx, zy) = beam_zone_center(canvas.width() as f64, canvas.height() as f64);
+    (x - zx).hypot(y - zy) < BEAM_ZONE_RADIUS
+}
+
+/// Whether an orb can be dropped on the Beam zone: a device or cluster that isn't beamed yet,
+/// while the audio server is there to record it
+fn can_beam(state: &AppState, id: Uuid) -> bool {
+    state.backend_connected && state.orbs.get(&id).is_some_and(|orb| {
+        matches!(orb.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. })
+            && orb.membership().is_none()
+            && !orb.is_staged()
+            && !state.orbs.values().any(|o| matches!(o.kind, OrbKind::BeamOutput { .. }) && o.node_name == orb.node_name)
+    })
+}
+
+/// The device or cluster a beam orb carries the audio of
+fn beam_source<'a>(state: &'a AppState, beam: &Orb) -> Option<&'a Orb> {
+    state.orbs.values().find(|o| {
+        matches!(o.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. })
+            && o.membership().is_none()
+            && o.node_name == beam.node_name
+    })
+}
+
+/// Put beam orbs that aren't on the canvas yet next to what they beam, or in the top left
+/// corner if that isn't on the canvas either
+pub fn place_beams(state: &mut AppState) {
+    let spots: Vec<(Uuid, (f64, f64))> = state.orbs.values()
+        .filter(|o| matches!(o.kind, OrbKind::BeamOutput { .. }) && o.position == (0.0, 0.0))
+        .map(|beam| {
+            let spot = match beam_source(state, beam) {
+                Some(source) if matches!(source.kind, OrbKind::Cluster { .. }) => (source.position.0 + CLUSTER_CARD_W + 40.0, source.position.1),
+                Some(source) if source.position != (0.0, 0.0) => (source.position.0 + 120.0, source.position.1),
+                _ => (40.0, 40.0),
+            };
+            (beam.id, spot)
+        })
+        .collect();
+    for (id, spot) in spots {
+        if let Some(orb) = state.orbs.get_mut(&id) {
+            orb.position = spot;
+        }
+    }
+}
+
+/// Connect `source` to the orb it was dropped on. Merging two clusters asks first,
+/// unless the user turned that off. Without `auto_cluster_on_drop` the two are only
+/// gathered for "Cluster These"; apps are still connected.
+fn connect_or_confirm(state: &mut AppState, canvas: &DrawingArea, cmd_tx: &Sender<UiCommand>, source: Uuid, target: Uuid) {
+    // Overlapping hit areas can find the dragged orb itself
+    if source == target {
+        return;
+    }
+    if let (Some(src), Some(tgt)) = (state.orbs.get(&source), state.orbs.get(&target)) {
+        let kind = classify_connection(&src.kind, &tgt.kind);
+        let clusters = matches!(kind, ConnectionKind::NewCluster | ConnectionKind::AddToCluster | ConnectionKind::MergeClusters);
+        if clusters && !state.settings.auto_cluster_on_drop {
+            state.gather(source, target);
+            canvas.queue_draw();
+            return;
+        }
+        // A staged cluster has a single device, there's nothing to lose by merging it
+        let merge = kind == ConnectionKind::MergeClusters;
+        if state.settings.confirm_merges && merge && !src.is_staged() && !tgt.is_staged() {
+            let parent = canvas.root().and_downcast::<gtk4::Window>();
+            merge_dialog::show(parent.as_ref(), src, tgt, cmd_tx.clone());
+            return;
+        }
+    }
+    let _ = cmd_tx.send(UiCommand::Connect { source, target });
+}
+
+/// "Cluster These" and "Cancel" at the bottom of the canvas, shown while two or more devices
+/// are gathered (see `AppState::gathered`). Goes in an overlay on top of `canvas`.
+pub fn build_gathered_bar(state: SharedState, cmd_tx: Sender<UiCommand>, canvas: &DrawingArea) -> gtk4::Box {
+    let bar = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+    bar.set_halign(gtk4::Align::Center);
+    bar.set_valign(gtk4::Align::End);
+    bar.set_margin_bottom(16);
+    bar.set_visible(false);
+
+    let cancel_btn = gtk4::Button::with_label("Cancel");
+    let cluster_btn = gtk4::Button::with_label("Cluster These");
+    cluster_btn.add_css_class("btn-primary");
+    bar.append(&cancel_btn);
+    bar.append(&cluster_btn);
+
+    let (state_cluster, da_cluster) = (state.clone(), canvas.clone());
+    cluster_btn.connect_clicked(move |_| {
+        let mut state = state_cluster.borrow_mut();
+        let devices = state.gathered_devices();
+        state.gathered.clear();
+        if devices.len() >= 2 {
+            let _ = cmd_tx.send(UiCommand::CreateCluster { devices });
+        }
+        da_cluster.queue_draw();
+    });
+    let (state_cancel, da_cancel) = (state.clone(), canvas.clone());
+    cancel_btn.connect_clicked(move |_| {
+        state_cancel.borrow_mut().gathered.clear();
+        da_cancel.queue_draw();
+    });
+
+    // Gathering happens in the canvas' handlers; follow it on the canvas' frames, the bar
+    // gets none while it's hidden
+    let shown = std::cell::Cell::new(0);
+    let bar_tick = bar.clone();
+    canvas.add_tick_callback(move |_, _| {
+        let count = state.borrow().gathered_devices().len();
+        if count != shown.get() {
+            shown.set(count);
+            bar_tick.set_visible(count >= 2);
+            cluster_btn.set_label(&format!("Cluster These ({})", count));
+        }
+        gtk4::glib::ControlFlow::Continue
+    });
+
+    bar
+}
+
+/// A cluster orb was added: flash its card (the core plays the confirmation sound). Staged
+/// cards aren't clusters yet.
+pub fn cluster_formed(state: &mut AppState, orb: &Orb) {
+    if !matches!(orb.kind, OrbKind::Cluster { .. }) || orb.is_staged() {
+        return;
+    }
+    state.formed = Some((orb.id, Instant::now()));
+}
+
+/// Center of an orb as drawn, `None` if it isn't on the canvas
+fn orb_center(orb: &Orb) -> Option<(f64, f64)> {
+    match orb.kind {
+        OrbKind::Cluster { .. } => Some((orb.position.0 + CLUSTER_CARD_W / 2.0, orb.position.1 + cluster_card_height(orb) / 2.0)),
+        _ if orb.position == (0.0, 0.0) => None,
+        _ => Some((orb.position.0 + 32.0, orb.position.1 + 32.0)),
+    }
+}
+
+/// App -> output routes with both ends on the canvas, as (app, output, from, to)
+fn link_lines(state: &AppState) -> Vec<(Uuid, Uuid, (f64, f64), (f64, f64))> {
+    state.routes.iter()
+        .filter_map(|&(app, output)| {
+            let from = orb_center(state.orbs.get(&app)?)?;
+            let to = orb_center(state.orbs.get(&output)?)?;
+            Some((app, output, from, to))
+        })
+        .collect()
+}
+
+/// The route whose line passes under a canvas point
+fn link_at(state: &AppState, x: f64, y: f64) -> Option<(Uuid, Uuid)> {
+    link_lines(state).into_iter()
+        .find(|&(_, _, from, to)| distance_to_segment((x, y), from, to) < LINK_HIT_DISTANCE)
+        .map(|(app, output, _, _)| (app, output))
+}
+
+fn distance_to_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = dx * dx + dy * dy;
+    let t = if len_sq == 0.0 { 0.0 } else { (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len_sq).clamp(0.0, 1.0) };
+    (p.0 - (a.0 + t * dx)).hypot(p.1 - (a.1 + t * dy))
+}
+
+/// Whether the point is on a cluster card's "Separate" button (bottom right of the card)
+fn on_separate_button(orb: &Orb, x: f64, y: f64) -> bool {
+    if !matches!(orb.kind, OrbKind::Cluster { .. }) {
+        return false;
+    }
+
+    let btn_x = orb.position.0 + CLUSTER_CARD_W - 100.0 - 20.0;
+    let btn_y = orb.position.1 + cluster_card_height(orb) - 40.0;
+    x >= btn_x && x <= btn_x + 100.0 && y >= btn_y && y <= btn_y + 30.0
+}
+
+/// Menu model and matching `orb.*` actions for the right-clicked orb
+fn context_actions(
+    state: &SharedState,
+    orb: &Orb,
+    canvas: &DrawingArea,
+    cmd_tx: &Sender<UiCommand>,
+) -> Option<(gio::Menu, gio::SimpleActionGroup)> {
+    let menu = gio::Menu::new();
+    let actions = gio::SimpleActionGroup::new();
+    let id = orb.id;
+
+    // Each action sends one command (or opens a dialog) for the orb the menu was opened on
+    let add_action = |name: &str, run: Box<dyn Fn()>| {
+        let action = gio::SimpleAction::new(name, None);
+        action.connect_activate(move |_, _| run());
+        actions.add_action(&action);
+    };
+    let send = |cmd: UiCommand| {
+        let cmd_tx = cmd_tx.clone();
+        Box::new(move || { let _ = cmd_tx.send(cmd.clone()); }) as Box<dyn Fn()>
+    };
+    let parent = canvas.root().and_downcast::<gtk4::Window>();
+
+    match &orb.kind {
+        OrbKind::PhysicalSink { .. } => {
+            menu.append(Some("Set as Default"), Some("orb.default"));
+            menu.append(Some("Mute"), Some("orb.mute"));
+            add_action("default", send(UiCommand::SetDefaultSink { device: id }));
+            add_action("mute", send(UiCommand::ToggleMute { device: id }));
+
+            // Pin app…: send one of the running apps here
+            let mut apps: Vec<(Uuid, String)> = state.borrow().orbs.values()
+                .filter(|o| matches!(o.kind, OrbKind::ApplicationSource { .. }))
+                .map(|o| (o.id, o.name.clone()))
+                .collect();
+            apps.sort_by(|a, b| a.1.cmp(&b.1));
+
+            let pin_menu = gio::Menu::new();
+            for (app, name) in &apps {
+                pin_menu.append(Some(name), Some(&format!("orb.pin::{}", app)));
+            }
+            if apps.is_empty() {
+                // No such action, so the item shows up greyed out
+                pin_menu.append(Some("No apps playing"), Some("orb.none"));
+            }
+            menu.append_submenu(Some("Pin app…"), &pin_menu);
+
+            let pin = gio::SimpleAction::new("pin", Some(gtk4::glib::VariantTy::STRING));
+            let cmd_tx_pin = cmd_tx.clone();
+            pin.connect_activate(move |_, param| {
+                let app = param.and_then(|p| p.str()).and_then(|s| Uuid::parse_str(s).ok());
+                if let Some(app) = app {
+                    let _ = cmd_tx_pin.send(UiCommand::Connect { source: app, target: id });
+                }
+            });
+            actions.add_action(&pin);
+
+            // Also Play On…: the other devices, ticked where this one's audio plays already
+            let mut others: Vec<(Uuid, String, bool)> = {
+                let state = state.borrow();
+                state.orbs.values()
+                    .filter(|o| o.id != id && matches!(o.kind, OrbKind::PhysicalSink { .. }))
+                    .map(|o| (o.id, o.name.clone(), state.duplicates.contains(&(id, o.id))))
+                    .collect()
+            };
+            others.sort_by(|a, b| a.1.cmp(&b.1));
+
+            let also_menu = gio::Menu::new();
+            for (other, name, playing) in &others {
+                let label = if *playing { format!("✓ {}", name) } else { name.clone() };
+                also_menu.append(Some(&label), Some(&format!("orb.also::{}", other)));
+            }
+            if others.is_empty() {
+                also_menu.append(Some("No other devices"), Some("orb.none"));
+            }
+            menu.append_submenu(Some("Also Play On…"), &also_menu);
+
+            let also = gio::SimpleAction::new("also", Some(gtk4::glib::VariantTy::STRING));
+            let (state_also, cmd_tx_also) = (state.clone(), cmd_tx.clone());
+            also.connect_activate(move |_, param| {
+                let other = param.and_then(|p| p.str()).and_then(|s| Uuid::parse_str(s).ok());
+                if let Some(other) = other {
+                    let cmd = match state_also.borrow().duplicates.contains(&(id, other)) {
+                        true => UiCommand::StopDuplicate { from: id, also: other },
+                        false => UiCommand::Duplicate { from: id, also: other },
+                    };
+                    let _ = cmd_tx_also.send(cmd);
+                }
+            });
+            actions.add_action(&also);
+
+            // Hide: take it off the canvas, it stays in the device list
+            menu.append(Some("Hide"), Some("orb.hide"));
+            let (state_hide, canvas_hide, cmd_tx_hide) = (state.clone(), canvas.clone(), cmd_tx.clone());
+            add_action("hide", Box::new(move || {
+                let mut state = state_hide.borrow_mut();
+                if let Some(orb) = state.orbs.get_mut(&id) {
+                    orb.position = (0.0, 0.0);
+                }
+                if let Some(cmd) = state.save_position(id) {
+                    let _ = cmd_tx_hide.send(cmd);
+                }
+                canvas_hide.queue_draw();
+            }));
+        }
+        // Nothing is loaded for a staged cluster yet, its device can only be taken off again
+        OrbKind::Cluster { .. } if orb.is_staged() => {
+            menu.append(Some("Separate"), Some("orb.separate"));
+            add_action("separate", send(UiCommand::Disconnect { source: id, target: id }));
+        }
+        OrbKind::Cluster { .. } => {
+            menu.append(Some("Rename"), Some("orb.rename"));
+            menu.append(Some("Separate"), Some("orb.separate"));
+            menu.append(Some("Set Latency"), Some("orb.latency"));
+            menu.append(Some("Channel Check"), Some("orb.check"));
+            menu.append(Some("Balance Volumes"), Some("orb.balance"));
+
+            let (orb_rename, cmd_tx_rename, parent_rename) = (orb.clone(), cmd_tx.clone(), parent.clone());
+            add_action("rename", Box::new(move || {
+                cluster_dialog::show(parent_rename.as_ref(), &orb_rename, cmd_tx_rename.clone());
+            }));
+            add_action("separate", send(UiCommand::Disconnect { source: id, target: id }));
+            let (orb_latency, cmd_tx_latency) = (orb.clone(), cmd_tx.clone());
+            add_action("latency", Box::new(move || {
+                latency_dialog::show(parent.as_ref(), &orb_latency, cmd_tx_latency.clone());
+            }));
+            add_action("check", send(UiCommand::ClusterChannelCheck { cluster: id }));
+            add_action("balance", send(UiCommand::BalanceClusterVolumes { cluster: id }));
+            // The core knows the member chain; it answers with OrbEvent::ClusterScript
+            menu.append(Some("Copy as Shell Commands"), Some("orb.script"));
+            add_action("script", send(UiCommand::CopyClusterScript { cluster: id }));
+        }
+        OrbKind::ApplicationSource { .. } => {
+            // Undo any linking or pinning, the app goes back to the default output
+            menu.append(Some("Disconnect"), Some("orb.disconnect"));
+            add_action("disconnect", send(UiCommand::Disconnect { source: id, target: id }));
+        }
+        OrbKind::BeamOutput { session_id, .. } => {
+            menu.append(Some("Stop Beam"), Some("orb.stop"));
+            add_action("stop", send(UiCommand::BeamClosed { session_id: session_id.clone(), reason: None }));
+        }
+    }
+
+    // Devices and clusters can be put to sleep while nothing plays on them
+    if matches!(orb.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. }) && !orb.is_staged() {
+        let suspended = state.borrow().is_suspended(orb);
+        menu.append(Some(if suspended { "Wake Up" } else { "Suspend" }), Some("orb.suspend"));
+        add_action("suspend", send(UiCommand::SuspendSink { orb: id, suspend: !suspended }));
+    }
+
+    Some((menu, actions))
+}
+
+/// Offset of the first member name below the card's top edge (leaves room for the zone)
+fn cluster_members_top(orb: &Orb) -> f64 {
+    if orb.zone.is_some() { 66.0 } else { 50.0 }
+}
+
+/// Member name lines on a card, including the "+N more" line
+fn cluster_member_lines(devices: &[String]) -> usize {
+    if devices.len() > CLUSTER_CARD_MAX_MEMBERS {
+        CLUSTER_CARD_MAX_MEMBERS + 1
+    } else {
+        devices.len()
+    }
+}
+
+/// Cards grow to fit their member list, the button row stays at the bottom
+fn cluster_card_height(orb: &Orb) -> f64 {
+    let OrbKind::Cluster { devices, .. } = &orb.kind else {
+        return CLUSTER_CARD_MIN_H;
+    };
+    // Names, then the device count line, then the button row
+    let count_baseline = cluster_members_top(orb) + cluster_member_lines(devices) as f64 * CLUSTER_CARD_LINE_H;
+    CLUSTER_CARD_MIN_H.max(count_baseline + 50.0)
+}
+
+/// Single-line Pango layout for canvas text, ellipsized at `max_w`.
+/// Unlike `show_text` this shapes RTL text and falls back to fonts that have the glyphs.
+fn text_layout(cr: &cairo::Context, text: &str, size: f64, bold: bool, max_w: f64) -> pango::Layout {
+    let layout = pangocairo::functions::create_layout(cr);
+    let mut font = pango::FontDescription::from_string("Space Grotesk");
+    font.set_absolute_size(size * pango::SCALE as f64);
+    font.set_weight(if bold { pango::Weight::Bold } else { pango::Weight::Normal });
+    layout.set_font_description(Some(&font));
+    layout.set_width((max_w * pango::SCALE as f64) as i32);
+    layout.set_ellipsize(pango::EllipsizeMode::End);
+    layout.set_text(text);
+    layout
+}
+
+/// Draw a layout with its first baseline at `baseline`, like `move_to` + `show_text` did
+fn draw_layout(cr: &cairo::Context, layout: &pango::Layout, x: f64, baseline: f64) {
+    let ascent = layout.baseline() as f64 / pango::SCALE as f64;
+    cr.move_to(x, baseline - ascent);
+    pangocairo::functions::show_layout(cr, layout);
+}
+
+/// Text color that reads on the current scheme's background
+fn set_ink(cr: &cairo::Context, theme: &AuralisTheme, alpha: f64) {
+    let (r, g, b) = theme.ink();
+    cr.set_source_rgba(r, g, b, alpha);
+}
+
+/// Path of a rectangle with rounded corners, ready to fill or stroke
+fn rounded_rect(cr: &cairo::Context, x: f64, y: f64, w: f64, h: f64, r: f64) {
     cr.new_sub_path();
     cr.arc(x + r, y + r, r, std::f64::consts::PI, 3.0 * std::f64::consts::PI / 2.0);
     cr.arc(x + w - r, y + r, r, 3.0 * std::f64::consts::PI / 2.0, 0.0);
     cr.arc(x + w - r, y + h - r, r, 0.0, std::f64::consts::PI / 2.0);
     cr.arc(x + r, y + h - r, r, std::f64::consts::PI / 2.0, std::f64::consts::PI);
     cr.close_path();
+}
+
+/// Ring around the orb or card a dragged device hovers: accent if the drop would connect, red if not
+fn draw_drop_highlight(cr: &cairo::Context, orb: &Orb, allowed: bool, theme: &AuralisTheme) {
+    let (r, g, b) = if allowed { theme.accent } else { (0.9, 0.3, 0.3) };
+    cr.set_source_rgba(r, g, b, 0.9);
+    cr.set_line_width(3.0);
+    // Text drawing leaves a current point behind; don't connect the ring to it
+    cr.new_path();
+    let (x, y) = orb.position;
+    match orb.kind {
+        OrbKind::Cluster { .. } => rounded_rect(cr, x - 4.0, y - 4.0, CLUSTER_CARD_W + 8.0, cluster_card_height(orb) + 8.0, 14.0),
+        _ => cr.arc(x + 32.0, y + 32.0, 40.0, 0.0, 2.0 * std::f64::consts::PI),
+    }
+    cr.stroke().unwrap();
+}
+
+/// Dashed outline of an orb or card gathered for "Cluster These"
+fn draw_gathered_marker(cr: &cairo::Context, orb: &Orb, theme: &AuralisTheme) {
+    let (r, g, b) = theme.accent;
+    cr.set_source_rgba(r, g, b, 0.8);
+    cr.set_line_width(2.0);
+    cr.set_dash(&[6.0, 4.0], 0.0);
+    cr.new_path();
+    let (x, y) = orb.position;
+    match orb.kind {
+        OrbKind::Cluster { .. } => rounded_rect(cr, x - 6.0, y - 6.0, CLUSTER_CARD_W + 12.0, cluster_card_height(orb) + 12.0, 16.0),
+        _ => cr.arc(x + 32.0, y + 32.0, 42.0, 0.0, 2.0 * std::f64::consts::PI),
+    }
+    cr.stroke().unwrap();
+    cr.set_dash(&[], 0.0);
+}
+
+/// A ring that grows out of a new cluster card and fades, `progress` going from 0 to 1.
+/// Without `animate` it stays on the card's edge and only fades.
+fn draw_formed_flash(cr: &cairo::Context, orb: &Orb, progress: f64, animate: bool, theme: &AuralisTheme) {
+    let grow = if animate { 4.0 + 16.0 * progress } else { 4.0 };
+    let (r, g, b) = theme.accent;
+    cr.set_source_rgba(r, g, b, 0.9 * (1.0 - progress));
+    cr.set_line_width(3.0);
+    cr.new_path();
+    let (x, y) = orb.position;
+    rounded_rect(cr, x - grow, y - grow, CLUSTER_CARD_W + 2.0 * grow, cluster_card_height(orb) + 2.0 * grow, 12.0 + grow);
+    cr.stroke().unwrap();
+}
+
+/// `bluetooth_ms`: how long its wired members wait for its Bluetooth ones, if they do
+fn draw_cluster(cr: &cairo::Context, orb: &Orb, devices: &[String], bluetooth_ms: Option<u32>, theme: &AuralisTheme) {
+    let x = orb.position.0;
+    let y = orb.position.1;
+    let w = CLUSTER_CARD_W;
+    let h = cluster_card_height(orb);
+    
+    // Draw Card Background, tinted by zone
+    let (tr, tg, tb) = zone_color(orb.zone.as_deref(), theme);
+    cr.set_source_rgba(tr, tg, tb, 0.2);
+    rounded_rect(cr, x, y, w, h, 12.0);
     cr.fill_preserve().unwrap();
     
     // Border
-    cr.set_source_rgba(0.17, 0.42, 0.93, 0.5);
+    cr.set_source_rgba(tr, tg, tb, 0.5);
     cr.set_line_width(1.0);
     cr.stroke().unwrap();
     
     // Title
-    cr.set_source_rgb(1.0, 1.0, 1.0);
-    cr.select_font_face("Space Grotesk", cairo::FontSlant::Normal, cairo::FontWeight::Bold);
-    cr.set_font_size(16.0);
-    cr.move_to(x + 20.0, y + 30.0);
-    cr.show_text(&orb.name).unwrap();
+    let text_w = w - 40.0;
+    set_ink(cr, theme, 1.0);
+    draw_layout(cr, &text_layout(cr, &orb.name, 16.0, true, text_w), x + 20.0, y + 30.0);
+
+    if let Some(zone) = &orb.zone {
+        cr.set_source_rgba(tr, tg, tb, 0.9);
+        draw_layout(cr, &text_layout(cr, zone, 12.0, true, text_w), x + 20.0, y + 48.0);
+    }
+
+    // Member names, then how many there are
+    set_ink(cr, theme, 0.8);
+    let mut line_y = y + cluster_members_top(orb);
+    for name in devices.iter().take(CLUSTER_CARD_MAX_MEMBERS) {
+        draw_layout(cr, &text_layout(cr, &format!("• {}", name), 11.0, false, text_w), x + 20.0, line_y);
+        line_y += CLUSTER_CARD_LINE_H;
+    }
+    if devices.len() > CLUSTER_CARD_MAX_MEMBERS {
+        let more = format!("+{} more", devices.len() - CLUSTER_CARD_MAX_MEMBERS);
+        draw_layout(cr, &text_layout(cr, &more, 11.0, false, text_w), x + 20.0, line_y);
+        line_y += CLUSTER_CARD_LINE_H;
+    }
+
+    set_ink(cr, theme, 0.5);
+    let count = if orb.is_staged() {
+        "Add another device".to_string()
+    } else if orb.status == "External" {
+        format!("{} devices · external", devices.len())
+    } else if let Some(ms) = bluetooth_ms {
+        format!("{} devices · wired wait {} ms for Bluetooth", devices.len(), ms)
+    } else {
+        format!("{} devices", devices.len())
+    };
+    draw_layout(cr, &text_layout(cr, &count, 11.0, false, text_w), x + 20.0, line_y);
     
     // "Separate" Button
     let btn_x = x + w - 100.0 - 20.0;
     let btn_y = y + h - 40.0;
     let btn_w = 100.0;
     let btn_h = 30.0;
+
+    // Latency, bottom left
+    if let Some(latency) = orb.latency_ms {
+        set_ink(cr, theme, 0.6);
+        let latency = text_layout(cr, &format!("~{} ms", latency), 12.0, true, btn_x - x - 28.0);
+        draw_layout(cr, &latency, x + 20.0, btn_y + 20.0);
+    }
     
-    cr.set_source_rgba(1.0, 1.0, 1.0, 0.1);
+    set_ink(cr, theme, 0.1);
     // Rounded btn
     let br = 6.0;
     cr.new_sub_path();
@@ -378,13 +1063,181 @@ fn draw_cluster(cr: &cairo::Context, orb: &Orb, _devices: &Vec<String>) {
     cr.close_path();
     cr.fill().unwrap();
     
-    cr.set_source_rgb(1.0, 1.0, 1.0);
-    cr.set_font_size(12.0);
-    cr.move_to(btn_x + 24.0, btn_y + 20.0);
-    cr.show_text("Separate").unwrap();
+    set_ink(cr, theme, 1.0);
+    let label = text_layout(cr, "Separate", 12.0, true, btn_w);
+    label.set_alignment(pango::Alignment::Center);
+    draw_layout(cr, &label, btn_x, btn_y + 20.0);
+}
+
+/// Line from an app to the output it plays to, with a small "×" halfway that hints it can be clicked away
+fn draw_link(cr: &cairo::Context, from: (f64, f64), to: (f64, f64), theme: &AuralisTheme) {
+    let (r, g, b) = theme.accent;
+    cr.set_source_rgba(r, g, b, 0.6);
+    cr.set_line_width(2.0);
+    cr.move_to(from.0, from.1);
+    cr.line_to(to.0, to.1);
+    cr.stroke().unwrap();
+
+    let (mx, my) = ((from.0 + to.0) / 2.0, (from.1 + to.1) / 2.0);
+    cr.arc(mx, my, 7.0, 0.0, 2.0 * std::f64::consts::PI);
+    cr.fill().unwrap();
+    set_ink(cr, theme, 0.9);
+    cr.set_line_width(1.5);
+    cr.move_to(mx - 3.0, my - 3.0);
+    cr.line_to(mx + 3.0, my + 3.0);
+    cr.move_to(mx + 3.0, my - 3.0);
+    cr.line_to(mx - 3.0, my + 3.0);
+    cr.stroke().unwrap();
+}
+
+/// Short label for the bus badge; internal cards don't get one
+fn bus_badge(bus: DeviceBus) -> Option<&'static str> {
+    match bus {
+        DeviceBus::Bluetooth => Some("BT"),
+        DeviceBus::Usb => Some("USB"),
+        DeviceBus::Hdmi => Some("HDMI"),
+        DeviceBus::Internal | DeviceBus::Unknown => None,
+    }
+}
+
+/// Stable tint for a zone name. Clusters without a zone use the theme's primary.
+fn zone_color(zone: Option<&str>, theme: &AuralisTheme) -> (f64, f64, f64) {
+    let Some(zone) = zone else {
+        return theme.primary;
+    };
+
+    // FNV-1a, so the same zone gets the same color on every run
+    let hash = zone.bytes().fold(0x811c9dc5u32, |h, b| (h ^ b as u32).wrapping_mul(0x01000193));
+    let hue = (hash % 360) as f64;
+
+    // HSV -> RGB with fixed saturation/value to stay readable on the dark background
+    let (s, v) = (0.65, 0.9);
+    let c = v * s;
+    let hp = hue / 60.0;
+    let x = c * (1.0 - (hp % 2.0 - 1.0).abs());
+    let (r, g, b) = match hp as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = v - c;
+    (r + m, g + m, b + m)
+}
+
+/// Beam state color: amber while connecting, green once connected, red if it failed
+fn beam_color(status: &str) -> (f64, f64, f64) {
+    match status {
+        "Connected" => (0.3, 0.8, 0.45),
+        "Failed" | "Closed" => (0.9, 0.3, 0.3),
+        _ => (0.95, 0.7, 0.2),
+    }
+}
+
+/// A dot with waves either side, in the current source color
+fn draw_broadcast_symbol(cr: &cairo::Context, cx: f64, cy: f64) {
+    cr.new_path();
+    cr.arc(cx, cy, 4.0, 0.0, 2.0 * std::f64::consts::PI);
+    cr.fill().unwrap();
+    cr.set_line_width(2.0);
+    for radius in [10.0, 16.0] {
+        for side in [0.0, std::f64::consts::PI] {
+            cr.new_sub_path();
+            cr.arc(cx, cy, radius, side - 0.7, side + 0.7);
+        }
+    }
+    cr.stroke().unwrap();
+}
+
+/// Dashed line from a device or cluster to its beam, running towards the beam
+fn draw_beam_link(cr: &cairo::Context, from: (f64, f64), to: (f64, f64), (r, g, b): (f64, f64, f64)) {
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as f64;
+    cr.set_source_rgba(r, g, b, 0.6);
+    cr.set_line_width(2.0);
+    cr.set_dash(&[6.0, 4.0], -(time / 40.0) % 10.0);
+    cr.move_to(from.0, from.1);
+    cr.line_to(to.0, to.1);
+    cr.stroke().unwrap();
+    cr.set_dash(&[], 0.0);
 }
 
-fn draw_floating_orb(cr: &cairo::Context, orb: &Orb) {
+/// Beam orb: the broadcast symbol with rings rippling out of it, colored by the connection state
+fn draw_beam_orb(cr: &cairo::Context, orb: &Orb, theme: &AuralisTheme) {
+    let (cx, cy) = (orb.position.0 + 32.0, orb.position.1 + 32.0);
+    let (r, g, b) = beam_color(&orb.status);
+    let full = 2.0 * std::f64::consts::PI;
+
+    // A failed beam doesn't ripple
+    if !matches!(orb.status.as_str(), "Failed" | "Closed") {
+        let time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as f64;
+        cr.set_line_width(2.0);
+        for ring in 0..3 {
+            let phase = (time / 1500.0 + ring as f64 / 3.0) % 1.0;
+            cr.set_source_rgba(r, g, b, 0.5 * (1.0 - phase));
+            cr.new_path();
+            cr.arc(cx, cy, 32.0 + phase * 24.0, 0.0, full);
+            cr.stroke().unwrap();
+        }
+    }
+
+    cr.set_source_rgba(0.12, 0.16, 0.23, 0.9);
+    cr.arc(cx, cy, 32.0, 0.0, full);
+    cr.fill_preserve().unwrap();
+    cr.set_source_rgba(r, g, b, 0.9);
+    cr.set_line_width(3.0);
+    cr.stroke().unwrap();
+
+    cr.set_source_rgba(1.0, 1.0, 1.0, 0.9);
+    draw_broadcast_symbol(cr, cx, cy);
+
+    // Name, and the state in its color below it
+    let label_w = 140.0;
+    let name = text_layout(cr, &orb.name, 12.0, true, label_w);
+    name.set_alignment(pango::Alignment::Center);
+    set_ink(cr, theme, 1.0);
+    draw_layout(cr, &name, cx - label_w / 2.0, cy + 52.0);
+
+    let status = text_layout(cr, &orb.status, 11.0, false, label_w);
+    status.set_alignment(pango::Alignment::Center);
+    cr.set_source_rgba(r, g, b, 1.0);
+    draw_layout(cr, &status, cx - label_w / 2.0, cy + 68.0);
+}
+
+/// The Beam drop zone in the corner. Hovered by something that can be beamed it lights up in
+/// the accent color, by something that can't in red.
+fn draw_beam_zone(cr: &cairo::Context, (cx, cy): (f64, f64), hover: Option<bool>, theme: &AuralisTheme) {
+    let ((r, g, b), alpha) = match hover {
+        Some(true) => (theme.accent, 0.9),
+        Some(false) => ((0.9, 0.3, 0.3), 0.9),
+        None => (theme.ink(), 0.35),
+    };
+
+    cr.new_path();
+    cr.arc(cx, cy, BEAM_ZONE_RADIUS, 0.0, 2.0 * std::f64::consts::PI);
+    cr.set_source_rgba(r, g, b, alpha * 0.15);
+    cr.fill_preserve().unwrap();
+    cr.set_source_rgba(r, g, b, alpha);
+    cr.set_line_width(2.0);
+    cr.set_dash(&[6.0, 4.0], 0.0);
+    cr.stroke().unwrap();
+    cr.set_dash(&[], 0.0);
+
+    draw_broadcast_symbol(cr, cx, cy - 8.0);
+    let label = text_layout(cr, "Beam", 11.0, true, BEAM_ZONE_RADIUS * 2.0);
+    label.set_alignment(pango::Alignment::Center);
+    draw_layout(cr, &label, cx - BEAM_ZONE_RADIUS, cy + 24.0);
+}
+
+/// `pulse`: breathe a little, going by the clock (the tick callback redraws every frame then)
+fn draw_floating_orb(cr: &cairo::Context, orb: &Orb, theme: &AuralisTheme, pulse: bool) {
     let x = orb.position.0;
     let y = orb.position.1;
     
@@ -397,12 +1250,13 @@ fn draw_floating_orb(cr: &cairo::Context, orb: &Orb) {
         .unwrap()
         .as_millis() as f64;
     
-    let pulse = (time / 500.0).sin() * 2.0; // +/- 2px pulse
+    let pulse = if pulse { (time / 500.0).sin() * 2.0 } else { 0.0 }; // +/- 2px pulse
     let size = 64.0 + pulse; 
     
     // Radial Gradient Background
     let pattern = cairo::RadialGradient::new(x + size/2.0, y + size/2.0, 0.0, x + size/2.0, y + size/2.0, size/2.0);
-    pattern.add_color_stop_rgba(0.0, 0.17, 0.42, 0.93, 0.8); // Center (Primary)
+    let (pr, pg, pb) = theme.primary;
+    pattern.add_color_stop_rgba(0.0, pr, pg, pb, 0.8); // Center (Primary)
     pattern.add_color_stop_rgba(1.0, 0.12, 0.16, 0.23, 0.9); // Edge (Dark)
     
     cr.set_source(&pattern).unwrap();
@@ -410,7 +1264,8 @@ fn draw_floating_orb(cr: &cairo::Context, orb: &Orb) {
     cr.fill().unwrap();
     
     // Glow / Border
-    cr.set_source_rgba(0.4, 0.6, 1.0, 0.6); // Lighter Blue Glow
+    let (ar, ag, ab) = theme.accent;
+    cr.set_source_rgba(ar, ag, ab, 0.6); // Lighter glow
     cr.set_line_width(3.0);
     cr.arc(x + size/2.0, y + size/2.0, size/2.0, 0.0, 2.0 * std::f64::consts::PI);
     cr.stroke().unwrap();
@@ -437,21 +1292,37 @@ fn draw_floating_orb(cr: &cairo::Context, orb: &Orb) {
     cr.arc(cx + 4.0, cy, 10.0, -0.6, 0.6);
     cr.stroke().unwrap();
 
+    // Bus badge (top right) so identical-looking orbs can be told apart
+    if let Some(badge) = bus_badge(orb.bus) {
+        let layout = text_layout(cr, badge, 9.0, true, size);
+        let bw = layout.pixel_size().0 as f64 + 8.0;
+        let bh = 14.0;
+        let bx = x + size - bw / 2.0 - 4.0;
+        let by = y - 2.0;
+
+        cr.set_source_rgba(0.12, 0.16, 0.23, 0.95);
+        cr.rectangle(bx, by, bw, bh);
+        cr.fill().unwrap();
+
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.9);
+        draw_layout(cr, &layout, bx + 4.0, by + 10.0);
+    }
+
     // Label (Name) below with shadow
-    cr.select_font_face("Space Grotesk", cairo::FontSlant::Normal, cairo::FontWeight::Bold);
-    cr.set_font_size(12.0);
-    let text = &orb.name;
-    let extents = cr.text_extents(text).unwrap();
-    let text_x = x + size/2.0 - extents.width()/2.0;
+    // Centered under the orb, long names get an ellipsis instead of overlapping neighbours
+    let label_w = 140.0;
+    let layout = text_layout(cr, &orb.name, 12.0, true, label_w);
+    layout.set_alignment(pango::Alignment::Center);
+    let text_x = x + size/2.0 - label_w/2.0;
     let text_y = y + size + 20.0;
 
-    // Text Shadow
-    cr.set_source_rgba(0.0, 0.0, 0.0, 0.5);
-    cr.move_to(text_x + 1.0, text_y + 1.0);
-    cr.show_text(text).unwrap();
+    // Text Shadow, only needed for light text on the dark background
+    if theme.dark {
+        cr.set_source_rgba(0.0, 0.0, 0.0, 0.5);
+        draw_layout(cr, &layout, text_x + 1.0, text_y + 1.0);
+    }
 
     // Text
-    cr.set_source_rgb(1.0, 1.0, 1.0);
-    cr.move_to(text_x, text_y);
-    cr.show_text(text).unwrap();
+    set_ink(cr, theme, 1.0);
+    draw_layout(cr, &layout, text_x, text_y);
 }
@@ -0,0 +1,73 @@
+use gtk4::prelude::*;
+use std::sync::mpsc::Sender;
+use auralis_core::{Orb, UiCommand};
+
+/// Modal dialog for renaming a cluster and assigning it to a room/zone
+pub fn show(parent: Option<&gtk4::Window>, orb: &Orb, cmd_tx: Sender<UiCommand>) {
+    let dialog = gtk4::Window::builder()
+        .title("Edit Cluster")
+        .modal(true)
+        .resizable(false)
+        .default_width(360)
+        .build();
+    dialog.set_transient_for(parent);
+
+    let content = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    content.set_margin_start(24);
+    content.set_margin_end(24);
+    content.set_margin_top(24);
+    content.set_margin_bottom(24);
+
+    let name_label = gtk4::Label::builder().label("Name").halign(gtk4::Align::Start).css_classes(vec!["subheading"]).build();
+    let name_entry = gtk4::Entry::new();
+    name_entry.set_text(&orb.name);
+
+    let zone_label = gtk4::Label::builder().label("Zone").halign(gtk4::Align::Start).css_classes(vec!["subheading"]).build();
+    let zone_entry = gtk4::Entry::new();
+    zone_entry.set_placeholder_text(Some("e.g. Living Room"));
+    zone_entry.set_text(orb.zone.as_deref().unwrap_or(""));
+
+    content.append(&name_label);
+    content.append(&name_entry);
+    content.append(&zone_label);
+    content.append(&zone_entry);
+
+    // Buttons
+    let button_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+    button_box.set_halign(gtk4::Align::End);
+    button_box.set_margin_top(12);
+
+    let cancel_btn = gtk4::Button::with_label("Cancel");
+    let save_btn = gtk4::Button::with_label("Save");
+    save_btn.add_css_class("btn-primary");
+
+    button_box.append(&cancel_btn);
+    button_box.append(&save_btn);
+    content.append(&button_box);
+
+    dialog.set_child(Some(&content));
+
+    let dialog_cancel = dialog.clone();
+    cancel_btn.connect_clicked(move |_| dialog_cancel.close());
+
+    let dialog_save = dialog.clone();
+    let cluster = orb.id;
+    let old_name = orb.name.clone();
+    let old_zone = orb.zone.clone();
+    save_btn.connect_clicked(move |_| {
+        let name = name_entry.text().trim().to_string();
+        if !name.is_empty() && name != old_name {
+            let _ = cmd_tx.send(UiCommand::RenameCluster { cluster, name });
+        }
+
+        // Empty zone = ungrouped
+        let zone = Some(zone_entry.text().trim().to_string()).filter(|z| !z.is_empty());
+        if zone != old_zone {
+            let _ = cmd_tx.send(UiCommand::SetClusterZone { cluster, zone });
+        }
+
+        dialog_save.close();
+    });
+
+    dialog.present();
+}
@@ -1,9 +1,22 @@
 use gtk4::prelude::*;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
 use std::sync::mpsc::Sender;
-use auralis_core::{UiCommand, OrbKind};
+use std::time::Duration;
+use auralis_core::{channels, Orb, UiCommand, OrbKind};
+use uuid::Uuid;
+use crate::cluster_dialog;
 use crate::state::SharedState;
 
-pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>) -> gtk4::Box {
+/// Handles to the clusters page widgets, so callers don't depend on child ordering
+pub struct ClustersView {
+    pub container: gtk4::Box,
+    /// One header + flowbox per zone, rebuilt by `update_list`
+    pub groups: gtk4::Box,
+}
+
+pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>) -> ClustersView {
     let container = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
     container.set_margin_start(24);
     container.set_margin_end(24);
@@ -18,6 +31,17 @@ pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>) -> gtk4::Box {
     
     container.append(&title);
 
+    let groups = gtk4::Box::new(gtk4::Orientation::Vertical, 16);
+    groups.set_valign(gtk4::Align::Start);
+
+    // Initial update
+    update_list(&groups, &state, &cmd_tx);
+
+    container.append(&groups);
+    ClustersView { container, groups }
+}
+
+fn new_flow_box() -> gtk4::FlowBox {
     let flow_box = gtk4::FlowBox::new();
     flow_box.set_valign(gtk4::Align::Start);
     flow_box.set_selection_mode(gtk4::SelectionMode::None);
@@ -25,84 +49,262 @@ pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>) -> gtk4::Box {
     flow_box.set_max_children_per_line(3);
     flow_box.set_column_spacing(12);
     flow_box.set_row_spacing(12);
-
-    // Initial update
-    update_list(&flow_box, &state, &cmd_tx);
-
-    container.append(&flow_box);
-    container
+    flow_box
 }
 
-pub fn update_list(flow_box: &gtk4::FlowBox, state: &SharedState, cmd_tx: &Sender<UiCommand>) {
+pub fn update_list(groups: &gtk4::Box, state: &SharedState, cmd_tx: &Sender<UiCommand>) {
     // Clear existing children
-    while let Some(child) = flow_box.first_child() {
-        flow_box.remove(&child);
+    while let Some(child) = groups.first_child() {
+        groups.remove(&child);
     }
 
     let state = state.borrow();
-    let mut found_any = false;
 
+    // Group clusters by zone; named zones sorted, unassigned ones last
+    let mut zones: BTreeMap<Option<&str>, Vec<&Orb>> = BTreeMap::new();
     for orb in state.orbs.values() {
-        if let OrbKind::Cluster { devices } = &orb.kind {
-            found_any = true;
-            let card = gtk4::Box::new(gtk4::Orientation::Vertical, 8);
-            card.add_css_class("device-card"); // Reuse card styling
-            card.set_width_request(200);
-
-            // Icon
-            let icon_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
-            icon_box.add_css_class("device-icon-container");
-            icon_box.set_halign(gtk4::Align::Center);
-            
-            let icon = gtk4::Image::from_icon_name("view-grid-symbolic");
-            icon.set_pixel_size(24);
-            icon.set_halign(gtk4::Align::Center);
-            icon.set_valign(gtk4::Align::Center);
-            icon_box.set_halign(gtk4::Align::Center);
-            icon_box.set_valign(gtk4::Align::Center);
-            icon_box.append(&icon);
-            
-            card.append(&icon_box);
-
-            // Name
-            let name_lbl = gtk4::Label::builder()
-                .label(&orb.name)
-                .css_classes(vec!["device-name"])
-                .ellipsize(gtk4::pango::EllipsizeMode::End)
-                .build();
-            card.append(&name_lbl);
-
-            // Device Count
-            let count_lbl = gtk4::Label::builder()
-                .label(&format!("{} Devices", devices.len()))
-                .css_classes(vec!["device-status"])
-                .build();
-            card.append(&count_lbl);
-
-            // Separate Button
-            let separate_btn = gtk4::Button::with_label("Separate");
-            separate_btn.add_css_class("btn-destructive"); // Need to define this or use standard
-            let cmd_tx_clone = cmd_tx.clone();
-            let orb_id = orb.id;
-            
-            separate_btn.connect_clicked(move |_| {
-                // Send Disconnect command (Source -> Target, but for cluster separation we just need source)
-                // The core handles Disconnect(cluster_id, _) as separation
-                let _ = cmd_tx_clone.send(UiCommand::Disconnect { 
-                    source: orb_id, 
-                    target: orb_id // Target ignored for separation
-                });
-            });
-            
-            card.append(&separate_btn);
-
-            flow_box.insert(&card, -1);
+        // Staged clusters have nothing to manage yet, they're only on the canvas
+        if matches!(orb.kind, OrbKind::Cluster { .. }) && !orb.is_staged() {
+            zones.entry(orb.zone.as_deref()).or_default().push(orb);
         }
     }
 
-    if !found_any {
+    if zones.is_empty() {
         let empty_lbl = gtk4::Label::new(Some("No active clusters. Drag devices together to create one."));
         empty_lbl.add_css_class("caption");
-        flow_box.insert(&empty_lbl, -1);
+        empty_lbl.set_halign(gtk4::Align::Start);
+        groups.append(&empty_lbl);
+        return;
+    }
+
+    let unassigned = zones.remove(&None);
+    let ordered = zones.into_iter().chain(unassigned.map(|orbs| (None, orbs)));
+
+    for (zone, mut orbs) in ordered {
+        orbs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let header = gtk4::Label::builder()
+            .label(zone.unwrap_or("No Zone"))
+            .halign(gtk4::Align::Start)
+            .css_classes(vec!["subheading"])
+            .build();
+        groups.append(&header);
+
+        let flow_box = new_flow_box();
+        for orb in orbs {
+            flow_box.insert(&build_card(orb, state.is_default(orb), cmd_tx), -1);
+        }
+        groups.append(&flow_box);
+    }
+}
+
+/// How long a volume slider has to rest before the change is sent; every change is a pactl call
+const VOLUME_SETTLE: Duration = Duration::from_millis(250);
+
+/// Slider for one member's device volume, to balance louder and quieter speakers.
+/// Members that were never adjusted start at 100%.
+fn build_volume_scale(cluster: Uuid, device: Uuid, percent: Option<u32>, cmd_tx: &Sender<UiCommand>) -> gtk4::Scale {
+    let scale = gtk4::Scale::with_range(gtk4::Orientation::Horizontal, 0.0, 150.0, 5.0);
+    scale.set_value(f64::from(percent.unwrap_or(100)));
+    scale.add_mark(100.0, gtk4::PositionType::Bottom, None);
+    scale.set_draw_value(true);
+    scale.set_format_value_func(|_, value| format!("{:.0}%", value));
+    scale.set_tooltip_text(Some("Volume of this speaker"));
+
+    let pending: Rc<RefCell<Option<gtk4::glib::SourceId>>> = Rc::new(RefCell::new(None));
+    let cmd_tx = cmd_tx.clone();
+    scale.connect_value_changed(move |scale| {
+        if let Some(source) = pending.borrow_mut().take() {
+            source.remove();
+        }
+        let percent = scale.value().round() as u32;
+        let cmd_tx = cmd_tx.clone();
+        let pending_done = pending.clone();
+        let source = gtk4::glib::timeout_add_local_once(VOLUME_SETTLE, move || {
+            pending_done.borrow_mut().take();
+            let _ = cmd_tx.send(UiCommand::SetMemberVolume { cluster, device, percent });
+        });
+        *pending.borrow_mut() = Some(source);
+    });
+    scale
+}
+
+/// Channel layout picker, showing the layout the cluster was loaded with
+fn build_layout_dropdown(orb: &Orb, cmd_tx: &Sender<UiCommand>) -> gtk4::DropDown {
+    let layout_labels: Vec<&str> = channels::LAYOUTS.iter().map(|(_, label, _)| *label).collect();
+    let layout = gtk4::DropDown::from_strings(&layout_labels);
+    layout.set_halign(gtk4::Align::Center);
+    layout.set_tooltip_text(Some("Channel layout of the cluster"));
+    if let Some(index) = channels::LAYOUTS.iter().position(|(n, _, _)| Some(*n) == orb.channels) {
+        layout.set_selected(index as u32);
     }
+    let cmd_tx = cmd_tx.clone();
+    let cluster = orb.id;
+    layout.connect_selected_notify(move |dropdown| {
+        let Some(&(channels, _, _)) = channels::LAYOUTS.get(dropdown.selected() as usize) else { return };
+        let _ = cmd_tx.send(UiCommand::SetClusterChannels { cluster, channels });
+    });
+    layout
+}
+
+fn build_card(orb: &Orb, is_default: bool, cmd_tx: &Sender<UiCommand>) -> gtk4::Box {
+    let no_volumes = HashMap::new();
+    let (devices, members, mono, volumes): (&[String], &[Uuid], &[Uuid], &HashMap<Uuid, u32>) = match &orb.kind {
+        OrbKind::Cluster { devices, members, mono, volumes } => (devices, members, mono, volumes),
+        _ => (&[], &[], &[], &no_volumes),
+    };
+
+    let card = gtk4::Box::new(gtk4::Orientation::Vertical, 8);
+    card.add_css_class("device-card"); // Reuse card styling
+    card.set_width_request(200);
+
+    // Icon
+    let icon_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    icon_box.add_css_class("device-icon-container");
+    icon_box.set_halign(gtk4::Align::Center);
+    
+    let icon = gtk4::Image::from_icon_name("view-grid-symbolic");
+    icon.set_pixel_size(24);
+    icon.set_halign(gtk4::Align::Center);
+    icon.set_valign(gtk4::Align::Center);
+    icon_box.set_halign(gtk4::Align::Center);
+    icon_box.set_valign(gtk4::Align::Center);
+    icon_box.append(&icon);
+    
+    card.append(&icon_box);
+
+    // Name
+    let name_lbl = gtk4::Label::builder()
+        .label(&orb.name)
+        .css_classes(vec!["device-name"])
+        .ellipsize(gtk4::pango::EllipsizeMode::End)
+        .build();
+    card.append(&name_lbl);
+
+    // Device Count, and whether it's what plays by default
+    let count = match is_default {
+        true => format!("{} Devices · Default output", devices.len()),
+        false => format!("{} Devices", devices.len()),
+    };
+    let count_lbl = gtk4::Label::builder()
+        .label(&count)
+        .css_classes(vec!["device-status"])
+        .build();
+    card.append(&count_lbl);
+
+    // Channel layout of the combine-sink; the core refuses layouts no member can play.
+    // Adopted combine-sinks keep whatever they were loaded with.
+    let external = orb.status == "External";
+    if !external {
+        card.append(&build_layout_dropdown(orb, cmd_tx));
+    }
+
+    // Members in the combine-sink's order, each with a mono downmix toggle (on by default for
+    // single-channel devices). Moving one changes which channels it gets.
+    for (index, (description, &device)) in devices.iter().zip(members).enumerate() {
+        let row = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+        let member_lbl = gtk4::Label::builder()
+            .label(description)
+            .css_classes(vec!["caption"])
+            .halign(gtk4::Align::Start)
+            .hexpand(true)
+            .ellipsize(gtk4::pango::EllipsizeMode::End)
+            .build();
+        let mono_check = gtk4::CheckButton::with_label("Mono");
+        mono_check.set_active(mono.contains(&device));
+        mono_check.set_tooltip_text(Some("Downmix to mono, for single-speaker devices"));
+        // Adopted combine-sinks aren't ours to rebuild
+        mono_check.set_sensitive(!external);
+        let cmd_tx_mono = cmd_tx.clone();
+        let cluster = orb.id;
+        mono_check.connect_toggled(move |check| {
+            let _ = cmd_tx_mono.send(UiCommand::SetMemberDownmix { cluster, device, enabled: check.is_active() });
+        });
+        row.append(&member_lbl);
+        if !external && members.len() > 1 {
+            for (icon, tooltip, up, movable) in [
+                ("go-up-symbolic", "Move Up", true, index > 0),
+                ("go-down-symbolic", "Move Down", false, index + 1 < members.len()),
+            ] {
+                let move_btn = gtk4::Button::from_icon_name(icon);
+                move_btn.add_css_class("flat");
+                move_btn.set_tooltip_text(Some(tooltip));
+                move_btn.set_sensitive(movable);
+                let cmd_tx_move = cmd_tx.clone();
+                move_btn.connect_clicked(move |_| {
+                    let _ = cmd_tx_move.send(UiCommand::MoveClusterMember { cluster, device, up });
+                });
+                row.append(&move_btn);
+            }
+        }
+        row.append(&mono_check);
+        card.append(&row);
+
+        if !external {
+            card.append(&build_volume_scale(orb.id, device, volumes.get(&device).copied(), cmd_tx));
+        }
+    }
+
+    // Adopted combine-sink: Auralis shows it but didn't make it
+    if external {
+        let external_lbl = gtk4::Label::builder()
+            .label("Created outside Auralis")
+            .css_classes(vec!["caption"])
+            .tooltip_text("Left in place on exit; Separate unloads it")
+            .build();
+        card.append(&external_lbl);
+    }
+
+    // Measured latency, once the core has read it
+    if let Some(latency) = orb.latency_ms {
+        let latency_lbl = gtk4::Label::builder()
+            .label(&format!("~{} ms", latency))
+            .css_classes(vec!["device-status"])
+            .tooltip_text("Latency added by the combined sink")
+            .build();
+        card.append(&latency_lbl);
+    }
+
+    let button_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+    button_box.set_halign(gtk4::Align::Center);
+
+    // Edit Button (name + zone)
+    let edit_btn = gtk4::Button::with_label("Edit");
+    let cmd_tx_edit = cmd_tx.clone();
+    let orb_edit = orb.clone();
+    edit_btn.connect_clicked(move |btn| {
+        let parent = btn.root().and_downcast::<gtk4::Window>();
+        cluster_dialog::show(parent.as_ref(), &orb_edit, cmd_tx_edit.clone());
+    });
+    button_box.append(&edit_btn);
+
+    // Channel check: a tone on each member in turn, to confirm which speaker is where
+    let check_btn = gtk4::Button::from_icon_name("audio-speakers-symbolic");
+    check_btn.set_tooltip_text(Some("Channel Check"));
+    let cmd_tx_check = cmd_tx.clone();
+    let cluster_id = orb.id;
+    check_btn.connect_clicked(move |_| {
+        let _ = cmd_tx_check.send(UiCommand::ClusterChannelCheck { cluster: cluster_id });
+    });
+    button_box.append(&check_btn);
+
+    // Separate Button
+    let separate_btn = gtk4::Button::with_label("Separate");
+    separate_btn.add_css_class("btn-destructive"); // Need to define this or use standard
+    let cmd_tx_clone = cmd_tx.clone();
+    let orb_id = orb.id;
+    
+    separate_btn.connect_clicked(move |_| {
+        // Send Disconnect command (Source -> Target, but for cluster separation we just need source)
+        // The core handles Disconnect(cluster_id, _) as separation
+        let _ = cmd_tx_clone.send(UiCommand::Disconnect { 
+            source: orb_id, 
+            target: orb_id // Target ignored for separation
+        });
+    });
+    
+    button_box.append(&separate_btn);
+    card.append(&button_box);
+
+    card
 }
@@ -0,0 +1,76 @@
+use gtk4::prelude::*;
+use std::sync::mpsc::Sender;
+use auralis_core::{Orb, OrbKind, UiCommand};
+
+/// Modal dialog for giving a device a friendlier name. Left empty, it goes back to the
+/// device's own description.
+pub fn show(parent: Option<&gtk4::Window>, orb: &Orb, cmd_tx: Sender<UiCommand>) {
+    let OrbKind::PhysicalSink { description } = &orb.kind else {
+        return;
+    };
+
+    let dialog = gtk4::Window::builder()
+        .title("Rename Device")
+        .modal(true)
+        .resizable(false)
+        .default_width(360)
+        .build();
+    dialog.set_transient_for(parent);
+
+    let content = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    content.set_margin_start(24);
+    content.set_margin_end(24);
+    content.set_margin_top(24);
+    content.set_margin_bottom(24);
+
+    let name_label = gtk4::Label::builder().label("Name").halign(gtk4::Align::Start).css_classes(vec!["subheading"]).build();
+    let name_entry = gtk4::Entry::new();
+    name_entry.set_placeholder_text(Some(description));
+    if orb.name != *description {
+        name_entry.set_text(&orb.name);
+    }
+
+    let hint = gtk4::Label::builder()
+        .label(format!("Leave empty to use \"{}\"", description))
+        .halign(gtk4::Align::Start)
+        .wrap(true)
+        .css_classes(vec!["caption"])
+        .build();
+    hint.set_opacity(0.6);
+
+    content.append(&name_label);
+    content.append(&name_entry);
+    content.append(&hint);
+
+    // Buttons
+    let button_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+    button_box.set_halign(gtk4::Align::End);
+    button_box.set_margin_top(12);
+
+    let cancel_btn = gtk4::Button::with_label("Cancel");
+    let save_btn = gtk4::Button::with_label("Save");
+    save_btn.add_css_class("btn-primary");
+
+    button_box.append(&cancel_btn);
+    button_box.append(&save_btn);
+    content.append(&button_box);
+
+    dialog.set_child(Some(&content));
+
+    let dialog_cancel = dialog.clone();
+    cancel_btn.connect_clicked(move |_| dialog_cancel.close());
+
+    let dialog_save = dialog.clone();
+    let device = orb.id;
+    let old_name = orb.name.clone();
+    save_btn.connect_clicked(move |_| {
+        let name = name_entry.text().trim().to_string();
+        if name != old_name {
+            let name = Some(name).filter(|n| !n.is_empty());
+            let _ = cmd_tx.send(UiCommand::RenameDevice { device, name });
+        }
+        dialog_save.close();
+    });
+
+    dialog.present();
+}
@@ -1,44 +1,126 @@
 use gtk4::prelude::*;
 use std::sync::mpsc::Sender;
-use auralis_core::{UiCommand, OrbKind};
+use auralis_core::{Orb, UiCommand, OrbKind};
+use crate::{device_dialog, properties_view};
 use crate::state::SharedState;
+use uuid::Uuid;
 
-pub fn build(state: SharedState, _cmd_tx: Sender<UiCommand>) -> gtk4::Box {
+/// Handles to the device list widgets, so callers don't depend on child ordering
+pub struct DeviceList {
+    pub container: gtk4::Box,
+    /// Full rows, one device per line
+    pub list_box: gtk4::ListBox,
+    /// Compact mode: icon and name only, as many columns as fit
+    pub flow_box: gtk4::FlowBox,
+    /// Switches between the two, mirrors `settings.compact_device_list`
+    pub compact_toggle: gtk4::ToggleButton,
+    /// "Cluster Selected", shown while two or more devices are selected
+    pub cluster_button: gtk4::Button,
+}
+
+pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>) -> DeviceList {
     let container = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
     container.set_margin_start(24);
     container.set_margin_end(24);
     container.set_margin_top(24);
     container.set_margin_bottom(24);
 
+    let header = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
     let title = gtk4::Label::builder()
         .label("Available Devices")
         .halign(gtk4::Align::Start)
+        .hexpand(true)
         .css_classes(vec!["heading"])
         .build();
-    
-    container.append(&title);
+    header.append(&title);
+
+    // Persisted by the core, the list switches over when the settings come back
+    let compact_toggle = gtk4::ToggleButton::new();
+    compact_toggle.set_icon_name("view-grid-symbolic");
+    compact_toggle.set_tooltip_text(Some("Compact view"));
+    compact_toggle.add_css_class("flat");
+    compact_toggle.set_active(state.borrow().settings.compact_device_list);
+    let (cmd_tx_toggle, cmd_tx_cluster) = (cmd_tx.clone(), cmd_tx.clone());
+    compact_toggle.connect_toggled(move |toggle| {
+        let _ = cmd_tx_toggle.send(UiCommand::SetCompactDeviceList { enabled: toggle.is_active() });
+    });
+    header.append(&compact_toggle);
+    container.append(&header);
+
+    // Ctrl/Shift-click selects more than one device
+    let cluster_button = gtk4::Button::with_label("Cluster Selected");
+    cluster_button.add_css_class("btn-primary");
+    cluster_button.set_visible(false);
+    container.append(&cluster_button);
 
     let list_box = gtk4::ListBox::new();
     list_box.set_valign(gtk4::Align::Start);
-    list_box.set_selection_mode(gtk4::SelectionMode::Single);
+    list_box.set_selection_mode(gtk4::SelectionMode::Multiple);
     list_box.add_css_class("boxed-list"); 
     list_box.add_css_class("device-list"); 
 
+    let flow_box = gtk4::FlowBox::new();
+    flow_box.set_valign(gtk4::Align::Start);
+    flow_box.set_selection_mode(gtk4::SelectionMode::Multiple);
+    flow_box.set_homogeneous(true);
+    flow_box.set_min_children_per_line(2);
+    flow_box.set_max_children_per_line(6);
+    flow_box.set_column_spacing(4);
+    flow_box.set_row_spacing(4);
+    flow_box.add_css_class("device-list");
+
+    let (list_click, flow_click) = (list_box.clone(), flow_box.clone());
+    cluster_button.connect_clicked(move |_| {
+        let devices = selected_devices(&list_click, &flow_click);
+        if devices.len() >= 2 {
+            let _ = cmd_tx_cluster.send(UiCommand::CreateCluster { devices });
+        }
+    });
+    let (button_rows, flow_rows) = (cluster_button.clone(), flow_box.clone());
+    list_box.connect_selected_rows_changed(move |list_box| {
+        button_rows.set_visible(selected_devices(list_box, &flow_rows).len() >= 2);
+    });
+    let (button_children, list_children) = (cluster_button.clone(), list_box.clone());
+    flow_box.connect_selected_children_changed(move |flow_box| {
+        button_children.set_visible(selected_devices(&list_children, flow_box).len() >= 2);
+    });
+
     // Initial update
-    update_list(&list_box, &state);
+    update_list(&list_box, &flow_box, &state, &cmd_tx);
 
     container.append(&list_box);
-    container
+    container.append(&flow_box);
+    DeviceList { container, list_box, flow_box, compact_toggle, cluster_button }
+}
+
+/// Devices selected in whichever of the two views is showing
+pub fn selected_devices(list_box: &gtk4::ListBox, flow_box: &gtk4::FlowBox) -> Vec<Uuid> {
+    let names: Vec<gtk4::glib::GString> = if flow_box.is_visible() {
+        flow_box.selected_children().iter().map(|child| child.widget_name()).collect()
+    } else {
+        list_box.selected_rows().iter().map(|row| row.widget_name()).collect()
+    };
+    names.iter().filter_map(|name| Uuid::parse_str(name).ok()).collect()
 }
 
-pub fn update_list(list_box: &gtk4::ListBox, state: &SharedState) {
+/// Rebuild the list, as rows in `list_box` or, in compact mode, as tiles in `flow_box`
+pub fn update_list(list_box: &gtk4::ListBox, flow_box: &gtk4::FlowBox, state: &SharedState, cmd_tx: &Sender<UiCommand>) {
+    // Rows are rebuilt from scratch, the selection is put back afterwards
+    let selection = selected_devices(list_box, flow_box);
+
     // Clear existing children
     while let Some(child) = list_box.first_child() {
         list_box.remove(&child);
     }
+    while let Some(child) = flow_box.first_child() {
+        flow_box.remove(&child);
+    }
 
+    let shared_state = state.clone();
     let state = state.borrow();
+    let compact = state.settings.compact_device_list;
     let mut found_any = false;
+    let mut reselect: Vec<gtk4::Widget> = Vec::new();
 
     for orb in state.orbs.values() {
         // Filter: Only show physical Sinks
@@ -46,54 +128,48 @@ pub fn update_list(list_box: &gtk4::ListBox, state: &SharedState) {
         let is_monitor = orb.name.to_lowercase().contains("monitor");
         let is_dummy = orb.name.to_lowercase().contains("dummy");
         let is_app = matches!(orb.kind, OrbKind::ApplicationSource { .. });
-        let is_in_zone = orb.position != (0.0, 0.0);
+        // Members may have been dropped on the canvas before clustering; they're listed by membership
+        let cluster = orb.membership().and_then(|id| state.orbs.get(&id));
+        let listed = match orb.membership() {
+            Some(_) => state.settings.show_cluster_members,
+            None => orb.position == (0.0, 0.0),
+        };
 
-        if is_sink && !is_monitor && !is_dummy && !is_app && !is_in_zone {
+        if is_sink && !is_monitor && !is_dummy && !is_app && listed {
             found_any = true;
-            
-            let row = gtk4::ListBoxRow::new();
-            row.add_css_class("device-row");
-            
-            let hbox = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
-            hbox.set_margin_top(8);
-            hbox.set_margin_bottom(8);
-            hbox.set_margin_start(12);
-            hbox.set_margin_end(12);
 
             // Icon
-            let icon_name = if orb.name.to_lowercase().contains("headphone") {
-                "audio-headphones-symbolic"
-            } else if orb.name.to_lowercase().contains("speaker") {
-                "audio-speakers-symbolic"
-            } else if orb.name.to_lowercase().contains("mic") {
-                "audio-input-microphone-symbolic"
+            let icon_name = icon_for(orb);
+            let is_default = state.is_default(orb);
+            let item: gtk4::Widget = if compact {
+                let child = gtk4::FlowBoxChild::new();
+                child.set_child(Some(&compact_content(orb, icon_name, cluster, is_default)));
+                flow_box.append(&child);
+                child.upcast()
             } else {
-                "audio-speakers-symbolic"
+                let row = gtk4::ListBoxRow::new();
+                row.set_child(Some(&row_content(orb, icon_name, cluster, is_default)));
+                list_box.append(&row);
+                row.upcast()
             };
-            
-            let icon = gtk4::Image::from_icon_name(icon_name);
-            icon.set_pixel_size(20); 
-            icon.set_opacity(0.8);
-            
-            hbox.append(&icon);
-
-            // Name
-            let name_lbl = gtk4::Label::builder()
-                .label(&orb.name)
-                .halign(gtk4::Align::Start)
-                .hexpand(true)
-                .ellipsize(gtk4::pango::EllipsizeMode::End)
-                .build();
-            hbox.append(&name_lbl);
-
-            // Status
-            let status_lbl = gtk4::Label::builder()
-                .label("Active")
-                .css_classes(vec!["caption"])
-                .build();
-            hbox.append(&status_lbl);
-
-            row.set_child(Some(&hbox));
+            item.add_css_class("device-row");
+            // Tells the selection handlers which device this is
+            item.set_widget_name(&orb.id.to_string());
+            // The one in the Properties panel last, selecting a row shows it there
+            if state.selected == Some(orb.id) {
+                reselect.push(item.clone());
+            } else if selection.contains(&orb.id) {
+                reselect.insert(0, item.clone());
+            }
+            if state.is_suspended(orb) {
+                item.add_css_class("suspended");
+            }
+
+            // Members are moved with their cluster, not on their own
+            if cluster.is_some() {
+                item.add_css_class("cluster-member");
+                continue;
+            }
 
             // Drag Source Setup
             let drag_source = gtk4::DragSource::new();
@@ -104,25 +180,231 @@ pub fn update_list(list_box: &gtk4::ListBox, state: &SharedState) {
                 Some(content)
             });
 
-            drag_source.connect_drag_begin(|source, _| {
+            drag_source.connect_drag_begin(move |source, _| {
                 let icon_theme = gtk4::IconTheme::default();
-                let paintable = icon_theme.lookup_icon("audio-speakers-symbolic", &[], 32, 1, gtk4::TextDirection::Ltr, gtk4::IconLookupFlags::empty());
+                let paintable = icon_theme.lookup_icon(icon_name, &[], 32, 1, gtk4::TextDirection::Ltr, gtk4::IconLookupFlags::empty());
                 source.set_icon(Some(&paintable), 16, 16);
             });
 
-            row.add_controller(drag_source);
+            item.add_controller(drag_source);
 
-            list_box.append(&row);
+            // Right-click: "Move everything here"
+            let context_click = gtk4::GestureClick::new();
+            context_click.set_button(gtk4::gdk::BUTTON_SECONDARY);
+            let state_menu = shared_state.clone();
+            let cmd_tx_menu = cmd_tx.clone();
+            let item_menu = item.clone();
+            context_click.connect_pressed(move |_, _, _, _| {
+                show_context_menu(&item_menu, orb_id, &state_menu, &cmd_tx_menu);
+            });
+            item.add_controller(context_click);
         }
     }
 
+    // The empty message is a row of the full list in both modes
+    list_box.set_visible(!compact || !found_any);
+    flow_box.set_visible(compact && found_any);
+
     if !found_any {
         let row = gtk4::ListBoxRow::new();
-        let lbl = gtk4::Label::new(Some("No devices found"));
+        let lbl = gtk4::Label::new(Some(state.empty_message().unwrap_or("No devices found")));
         lbl.set_margin_top(12);
         lbl.set_margin_bottom(12);
         lbl.add_css_class("caption");
         row.set_child(Some(&lbl));
         list_box.append(&row);
     }
+
+    // The selection handlers look at the state themselves
+    drop(state);
+    for item in &reselect {
+        if let Some(row) = item.downcast_ref::<gtk4::ListBoxRow>() {
+            list_box.select_row(Some(row));
+        } else if let Some(child) = item.downcast_ref::<gtk4::FlowBoxChild>() {
+            flow_box.select_child(child);
+        }
+    }
+}
+
+/// Star marking the default output
+fn default_star() -> gtk4::Image {
+    let star = gtk4::Image::from_icon_name("starred-symbolic");
+    star.set_tooltip_text(Some("Default output"));
+    star.set_opacity(0.8);
+    star
+}
+
+/// Icon, name, a star if it's the default, and status (or the cluster it plays in) on one line
+fn row_content(orb: &Orb, icon_name: &str, cluster: Option<&Orb>, is_default: bool) -> gtk4::Box {
+    let hbox = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    hbox.set_margin_top(8);
+    hbox.set_margin_bottom(8);
+    hbox.set_margin_start(12);
+    hbox.set_margin_end(12);
+
+    let icon = gtk4::Image::from_icon_name(icon_name);
+    icon.set_pixel_size(20); 
+    icon.set_opacity(0.8);
+    hbox.append(&icon);
+
+    // Name
+    let name_lbl = gtk4::Label::builder()
+        .label(&orb.name)
+        .halign(gtk4::Align::Start)
+        .hexpand(true)
+        .ellipsize(gtk4::pango::EllipsizeMode::End)
+        .build();
+    hbox.append(&name_lbl);
+    if is_default {
+        hbox.append(&default_star());
+    }
+
+    // Status, or the cluster it plays in
+    let status_lbl = gtk4::Label::builder()
+        .label(&orb.status)
+        .css_classes(vec!["caption"])
+        .build();
+    if let Some(cluster) = cluster {
+        status_lbl.set_label(&format!("In {}", cluster.name));
+        status_lbl.add_css_class("cluster-badge");
+    }
+    hbox.append(&status_lbl);
+    hbox
+}
+
+/// Icon and name only; the status moves to the tooltip
+fn compact_content(orb: &Orb, icon_name: &str, cluster: Option<&Orb>, is_default: bool) -> gtk4::Box {
+    let hbox = gtk4::Box::new(gtk4::Orientation::Horizontal, 6);
+    hbox.set_margin_top(4);
+    hbox.set_margin_bottom(4);
+    hbox.set_margin_start(6);
+    hbox.set_margin_end(6);
+
+    let icon = gtk4::Image::from_icon_name(icon_name);
+    icon.set_pixel_size(16);
+    icon.set_opacity(0.8);
+    hbox.append(&icon);
+
+    let name_lbl = gtk4::Label::builder()
+        .label(&orb.name)
+        .halign(gtk4::Align::Start)
+        .hexpand(true)
+        .ellipsize(gtk4::pango::EllipsizeMode::End)
+        .build();
+    hbox.append(&name_lbl);
+    if is_default {
+        hbox.append(&default_star());
+    }
+
+    let status = match cluster {
+        Some(cluster) => format!("In {}", cluster.name),
+        None => orb.status.clone(),
+    };
+    hbox.set_tooltip_text(Some(&format!("{} — {}", orb.name, status)));
+    hbox
+}
+
+/// Icon from the device's bus, or a guess from its name when PipeWire didn't tell us
+pub fn icon_for(orb: &Orb) -> &'static str {
+    if let Some(icon) = orb.bus.icon_name() {
+        return icon;
+    }
+
+    let name = orb.name.to_lowercase();
+    if name.contains("headphone") {
+        "audio-headphones-symbolic"
+    } else if name.contains("speaker") {
+        "audio-speakers-symbolic"
+    } else if name.contains("mic") {
+        "audio-input-microphone-symbolic"
+    } else {
+        "audio-speakers-symbolic"
+    }
+}
+
+/// Popover with "Suspend"/"Wake Up" and every other output whose streams can be moved to `target`
+fn show_context_menu(item: &gtk4::Widget, target: Uuid, state: &SharedState, cmd_tx: &Sender<UiCommand>) {
+    let suspended = {
+        let state = state.borrow();
+        state.orbs.get(&target).is_some_and(|orb| state.is_suspended(orb))
+    };
+    let mut sources: Vec<(Uuid, String)> = state.borrow().orbs.values()
+        .filter(|o| o.id != target && o.membership().is_none() && !o.is_staged())
+        .filter(|o| matches!(o.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. }))
+        .map(|o| (o.id, o.name.clone()))
+        .collect();
+    sources.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let popover = gtk4::Popover::new();
+    popover.set_parent(item);
+    popover.set_position(gtk4::PositionType::Bottom);
+
+    let menu = gtk4::Box::new(gtk4::Orientation::Vertical, 4);
+
+    // What the device can play, more in the Properties panel
+    if let Some(orb) = state.borrow().orbs.get(&target) {
+        let formats = gtk4::Label::builder()
+            .label(properties_view::summary(orb))
+            .halign(gtk4::Align::Start)
+            .css_classes(vec!["caption"])
+            .build();
+        formats.set_opacity(0.6);
+        menu.append(&formats);
+    }
+
+    // Idle speakers in an always-on setup can power down
+    let suspend_btn = gtk4::Button::with_label(if suspended { "Wake Up" } else { "Suspend" });
+    suspend_btn.add_css_class("flat");
+    let cmd_tx_suspend = cmd_tx.clone();
+    let popover_suspend = popover.clone();
+    suspend_btn.connect_clicked(move |_| {
+        let _ = cmd_tx_suspend.send(UiCommand::SuspendSink { orb: target, suspend: !suspended });
+        popover_suspend.popdown();
+    });
+    menu.append(&suspend_btn);
+
+    // Shown under that name everywhere, routing keeps the node name
+    let device = state.borrow().orbs.get(&target).cloned()
+        .filter(|orb| matches!(orb.kind, OrbKind::PhysicalSink { .. }));
+    if let Some(device) = device {
+        let rename_btn = gtk4::Button::with_label("Rename…");
+        rename_btn.add_css_class("flat");
+        let cmd_tx_rename = cmd_tx.clone();
+        let (popover_rename, item_rename) = (popover.clone(), item.clone());
+        rename_btn.connect_clicked(move |_| {
+            popover_rename.popdown();
+            let parent = item_rename.root().and_downcast::<gtk4::Window>();
+            device_dialog::show(parent.as_ref(), &device, cmd_tx_rename.clone());
+        });
+        menu.append(&rename_btn);
+    }
+    menu.append(&gtk4::Separator::new(gtk4::Orientation::Horizontal));
+
+    let title = gtk4::Label::builder()
+        .label("Move everything here from")
+        .halign(gtk4::Align::Start)
+        .css_classes(vec!["caption"])
+        .build();
+    menu.append(&title);
+
+    if sources.is_empty() {
+        menu.append(&gtk4::Label::new(Some("No other devices")));
+    }
+
+    for (source, name) in sources {
+        let item = gtk4::Button::with_label(&name);
+        item.add_css_class("flat");
+        let cmd_tx_item = cmd_tx.clone();
+        let popover_item = popover.clone();
+        item.connect_clicked(move |_| {
+            let _ = cmd_tx_item.send(UiCommand::MoveAllStreams { from: source, to: target });
+            popover_item.popdown();
+        });
+        menu.append(&item);
+    }
+
+    popover.set_child(Some(&menu));
+    // Rows are rebuilt on every event, don't leave the popover attached
+    popover.connect_closed(|p| p.unparent());
+    popover.popup();
 }
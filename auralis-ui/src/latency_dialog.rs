@@ -0,0 +1,77 @@
+use gtk4::prelude::*;
+use std::sync::mpsc::Sender;
+use auralis_core::{Orb, OrbKind, UiCommand};
+
+/// Modal dialog for delaying one cluster member relative to the others
+pub fn show(parent: Option<&gtk4::Window>, orb: &Orb, cmd_tx: Sender<UiCommand>) {
+    let OrbKind::Cluster { devices, members, .. } = &orb.kind else {
+        return;
+    };
+
+    let dialog = gtk4::Window::builder()
+        .title("Set Latency")
+        .modal(true)
+        .resizable(false)
+        .default_width(360)
+        .build();
+    dialog.set_transient_for(parent);
+
+    let content = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    content.set_margin_start(24);
+    content.set_margin_end(24);
+    content.set_margin_top(24);
+    content.set_margin_bottom(24);
+
+    let member_label = gtk4::Label::builder().label("Device").halign(gtk4::Align::Start).css_classes(vec!["subheading"]).build();
+    let names: Vec<&str> = devices.iter().map(String::as_str).collect();
+    let member_dropdown = gtk4::DropDown::from_strings(&names);
+
+    // Relative: the core adds this to the member's current delay
+    let delay_label = gtk4::Label::builder().label("Adjust delay (ms)").halign(gtk4::Align::Start).css_classes(vec!["subheading"]).build();
+    let delay_spin = gtk4::SpinButton::with_range(-500.0, 500.0, 5.0);
+    delay_spin.set_value(0.0);
+
+    let hint = gtk4::Label::builder()
+        .label("Delay a speaker that plays ahead of the others")
+        .halign(gtk4::Align::Start)
+        .css_classes(vec!["caption"])
+        .build();
+
+    content.append(&member_label);
+    content.append(&member_dropdown);
+    content.append(&delay_label);
+    content.append(&delay_spin);
+    content.append(&hint);
+
+    // Buttons
+    let button_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+    button_box.set_halign(gtk4::Align::End);
+    button_box.set_margin_top(12);
+
+    let cancel_btn = gtk4::Button::with_label("Cancel");
+    let apply_btn = gtk4::Button::with_label("Apply");
+    apply_btn.add_css_class("btn-primary");
+
+    button_box.append(&cancel_btn);
+    button_box.append(&apply_btn);
+    content.append(&button_box);
+
+    dialog.set_child(Some(&content));
+
+    let dialog_cancel = dialog.clone();
+    cancel_btn.connect_clicked(move |_| dialog_cancel.close());
+
+    let dialog_apply = dialog.clone();
+    let cluster = orb.id;
+    let members = members.clone();
+    apply_btn.connect_clicked(move |_| {
+        let delta_ms = delay_spin.value_as_int();
+        // The cluster is rebuilt on every change, so this dialog only sends one nudge
+        if let (Some(&device), true) = (members.get(member_dropdown.selected() as usize), delta_ms != 0) {
+            let _ = cmd_tx.send(UiCommand::NudgeMemberDelay { cluster, device, delta_ms });
+        }
+        dialog_apply.close();
+    });
+
+    dialog.present();
+}
@@ -0,0 +1,134 @@
+use std::f64::consts::{FRAC_PI_2, TAU};
+use auralis_core::{CanvasLayout, Orb, OrbKind};
+use uuid::Uuid;
+use crate::state::AppState;
+
+// Where floating orbs go on the canvas, per `Settings::canvas_layout`. Run once per frame from
+// the canvas' tick callback, before drawing; drawing only ever reads `Orb::position`.
+
+/// Floating orbs are drawn this big, `position` being their top-left corner
+const ORB_SIZE: f64 = 64.0;
+/// Grid cells (and the gap to the canvas edges)
+const GRID_SPACING: f64 = 110.0;
+const GRID_MARGIN: f64 = 40.0;
+/// Share of the way to its slot an orb moves each frame, so switching layouts glides
+const EASE: f64 = 0.2;
+/// Physics: orb centers closer than this push each other apart
+const REPEL_DISTANCE: f64 = 100.0;
+const REPEL_STRENGTH: f64 = 0.05;
+/// Share of an orb's velocity left after each frame
+const DAMPING: f64 = 0.85;
+
+/// Move the floating orbs one frame along in `state.settings.canvas_layout`, within the part
+/// of the canvas that's on screen: `(x, y, width, height)` in canvas coordinates, so zooming
+/// and panning change the room there is. Orbs taken off the canvas and the one being dragged
+/// stay put; with `settings.reduce_motion` the others jump to their slots instead of gliding
+/// there. Returns whether any orb moved, i.e. the canvas needs drawing again.
+pub fn step(state: &mut AppState, visible: (f64, f64, f64, f64)) -> bool {
+    let (left, top, width, height) = visible;
+    let layout = state.settings.canvas_layout;
+    if layout == CanvasLayout::Free || width <= 0.0 || height <= 0.0 {
+        return false;
+    }
+    let ease = if state.settings.reduce_motion { 1.0 } else { EASE };
+
+    let mut ids: Vec<(String, Uuid)> = state.orbs.values()
+        .filter(|orb| is_floating(orb) && Some(orb.id) != state.dragged_orb_id)
+        .map(|orb| (orb.name.clone(), orb.id))
+        .collect();
+    ids.sort();
+    let ids: Vec<Uuid> = ids.into_iter().map(|(_, id)| id).collect();
+
+    let slots: Vec<(f64, f64)> = match layout {
+        CanvasLayout::Free => Vec::new(),
+        CanvasLayout::Grid => {
+            let columns = (((width - 2.0 * GRID_MARGIN) / GRID_SPACING).floor() as usize).max(1);
+            (0..ids.len())
+                .map(|i| (
+                    left + GRID_MARGIN + (i % columns) as f64 * GRID_SPACING,
+                    top + GRID_MARGIN + (i / columns) as f64 * GRID_SPACING,
+                ))
+                .collect()
+        }
+        CanvasLayout::Ring => {
+            let (cx, cy) = (left + width / 2.0, top + height / 2.0);
+            let radius = (width.min(height) / 2.0 - ORB_SIZE).max(ORB_SIZE);
+            (0..ids.len())
+                .map(|i| {
+                    // First one at the top, clockwise from there
+                    let angle = i as f64 / ids.len() as f64 * TAU - FRAC_PI_2;
+                    (cx + radius * angle.cos() - ORB_SIZE / 2.0, cy + radius * angle.sin() - ORB_SIZE / 2.0)
+                })
+                .collect()
+        }
+        CanvasLayout::Physics => return push_apart(state, &ids, visible),
+    };
+
+    let mut moved = false;
+    for (id, slot) in ids.iter().zip(slots) {
+        moved |= ease_toward(state, *id, slot, ease);
+    }
+    moved
+}
+
+/// Devices and apps on the canvas, cluster members are on their card
+pub fn is_floating(orb: &Orb) -> bool {
+    matches!(orb.kind, OrbKind::PhysicalSink { .. } | OrbKind::ApplicationSource { .. })
+        && orb.membership().is_none()
+        && orb.position != (0.0, 0.0)
+}
+
+/// Returns whether the orb had to move
+fn ease_toward(state: &mut AppState, id: Uuid, slot: (f64, f64), ease: f64) -> bool {
+    let Some(orb) = state.orbs.get_mut(&id) else { return false };
+    orb.velocity = (0.0, 0.0);
+    if orb.position == slot {
+        return false;
+    }
+    let (dx, dy) = (slot.0 - orb.position.0, slot.1 - orb.position.1);
+    orb.position = if dx.hypot(dy) < 0.5 {
+        slot
+    } else {
+        (orb.position.0 + dx * ease, orb.position.1 + dy * ease)
+    };
+    true
+}
+
+/// Overlapping orbs get a push away from each other, then every orb moves by its velocity,
+/// which dies down, and is kept in the `visible` part of the canvas. Returns whether any orb
+/// is still moving.
+fn push_apart(state: &mut AppState, ids: &[Uuid], visible: (f64, f64, f64, f64)) -> bool {
+    let (left, top, width, height) = visible;
+    let mut moved = false;
+    let positions: Vec<(f64, f64)> = ids.iter().map(|id| state.orbs[id].position).collect();
+    for (i, id) in ids.iter().enumerate() {
+        let mut force = (0.0, 0.0);
+        for (j, other) in positions.iter().enumerate() {
+            let (dx, dy) = (positions[i].0 - other.0, positions[i].1 - other.1);
+            let distance = dx.hypot(dy);
+            if i == j || distance >= REPEL_DISTANCE {
+                continue;
+            }
+            // Right on top of each other: split them sideways, by order
+            let (ux, uy) = if distance < 0.01 {
+                (if i < j { -1.0 } else { 1.0 }, 0.0)
+            } else {
+                (dx / distance, dy / distance)
+            };
+            let overlap = REPEL_DISTANCE - distance;
+            force = (force.0 + ux * overlap * REPEL_STRENGTH, force.1 + uy * overlap * REPEL_STRENGTH);
+        }
+
+        let Some(orb) = state.orbs.get_mut(id) else { continue };
+        let velocity = ((orb.velocity.0 + force.0) * DAMPING, (orb.velocity.1 + force.1) * DAMPING);
+        orb.velocity = if velocity.0.hypot(velocity.1) < 0.05 { (0.0, 0.0) } else { velocity };
+        // (0, 0) means "not on the canvas", so the top-left corner of the view stays just off it
+        let position = (
+            (orb.position.0 + orb.velocity.0).clamp(left + 1.0, (left + width - ORB_SIZE).max(left + 1.0)),
+            (orb.position.1 + orb.velocity.1).clamp(top + 1.0, (top + height - ORB_SIZE).max(top + 1.0)),
+        );
+        moved |= position != orb.position;
+        orb.position = position;
+    }
+    moved
+}
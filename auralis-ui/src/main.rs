@@ -1,36 +1,122 @@
 use gtk4::prelude::*;
-use auralis_core::PipeWireClient;
+use auralis_core::event_queue::{EventQueue, QueueStats};
+use auralis_core::{persistence, AuralisConfig, PipeWireClient, Profiles};
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::time::Duration;
 
 pub mod state;
+pub mod activity_view;
+pub mod beam_view;
 pub mod canvas;
 pub mod device_list;
 pub mod clusters_view;
+pub mod cluster_dialog;
+pub mod device_dialog;
+pub mod latency_dialog;
+pub mod layout;
+pub mod merge_dialog;
+pub mod outputs_wizard;
+pub mod properties_view;
 pub mod settings_view;
+pub mod theme;
 pub mod window;
 
+/// Events the UI channel holds; past that they wait (and coalesce) in the bridge
+const UI_CHANNEL_CAPACITY: usize = 256;
+/// Events the bridge holds for a stalled UI before it drops the oldest
+const BRIDGE_BACKLOG_LIMIT: usize = 10_000;
+/// How often the bridge retries a full UI channel
+const BRIDGE_RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
 fn main() {
     tracing_subscriber::fmt::init();
     
     tracing::info!("🚀 [MAIN] Auralis Audio starting...");
+
+    // Strip our own flags before GTK sees the arguments (it rejects unknown options)
+    let mut args: Vec<String> = std::env::args().collect();
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+    let safe_mode = args.iter().any(|a| a == "--no-cleanup");
+    let allow_multiple = args.iter().any(|a| a == "--allow-multiple");
+    args.retain(|a| !matches!(a.as_str(), "--dry-run" | "--no-cleanup" | "--allow-multiple"));
+    let config = AuralisConfig {
+        dry_run,
+        safe_mode,
+        allow_multiple,
+        control_socket: auralis_core::control::default_path(),
+        ..Default::default()
+    };
+
+    // Without a display GTK can't do anything. Say so before the core loads any modules,
+    // instead of panicking halfway through activation.
+    if let Err(e) = gtk4::init() {
+        tracing::error!("❌ [MAIN] Could not open a display: {}", e);
+        tracing::error!("❌ [MAIN] Set DISPLAY or WAYLAND_DISPLAY to pick one, or use auralis-cli on headless machines");
+        std::process::exit(1);
+    }
+
+    // Profile names for the header menu; the core re-reads the file when one is applied
+    let profile_names = match Profiles::load_or_default(config.profiles_path.as_deref()) {
+        Ok(profiles) => profiles.names(),
+        Err(e) => {
+            tracing::warn!("⚠️ [MAIN] Ignoring profiles: {:#}", e);
+            Vec::new()
+        }
+    };
     
     // 1. Create Core Channel (MPSC) - Core writes to this
     let (core_tx, core_rx) = std::sync::mpsc::channel();
     tracing::info!("📡 [MAIN] Created Core channel (MPSC)");
     
-    // Create a channel for UI events (using async_channel)
-    let (ui_tx, ui_rx) = async_channel::unbounded();
+    // Create a channel for UI events (using async_channel). Bounded, so a stalled main loop
+    // (a long redraw during a device storm) can't make it grow without limit.
+    let (ui_tx, ui_rx) = async_channel::bounded(UI_CHANNEL_CAPACITY);
     tracing::info!("📡 [MAIN] Created UI channel (async-channel)");
 
-    // Bridge thread: Core (MPSC) -> UI (async-channel)
+    // Bridge thread: Core (MPSC) -> UI (async-channel). What doesn't fit waits in a backlog
+    // that coalesces events (see EventQueue); the counts show up in "Copy debug state".
+    let bridge_stats = Arc::new(QueueStats::default());
+    let bridge_stats_thread = bridge_stats.clone();
     std::thread::spawn(move || {
         tracing::info!("🌉 [BRIDGE] Bridge thread started");
-        while let Ok(event) = core_rx.recv() {
-            // Forward event to UI MainContext
-            // send_blocking returns Err if receiver is dropped (app closed)
-            if let Err(_) = ui_tx.send_blocking(event) {
-                tracing::info!("🌉 [BRIDGE] UI channel closed, stopping bridge");
+        let mut backlog = EventQueue::new(BRIDGE_BACKLOG_LIMIT, bridge_stats_thread);
+        loop {
+            // Wait for the core, but keep retrying the UI while events are waiting for it
+            let next = if backlog.is_empty() {
+                core_rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                core_rx.recv_timeout(BRIDGE_RETRY_INTERVAL)
+            };
+            match next {
+                Ok(event) => backlog.push(event),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            for event in core_rx.try_iter() {
+                backlog.push(event);
+            }
+
+            while let Some(event) = backlog.pop() {
+                match ui_tx.try_send(event) {
+                    Ok(()) => {}
+                    Err(async_channel::TrySendError::Full(event)) => {
+                        backlog.push_front(event);
+                        break;
+                    }
+                    // Receiver dropped (app closed)
+                    Err(async_channel::TrySendError::Closed(_)) => {
+                        tracing::info!("🌉 [BRIDGE] UI channel closed, stopping bridge");
+                        return;
+                    }
+                }
+            }
+        }
+        // Deliver what's left, the last events before shutdown matter too
+        while let Some(event) = backlog.pop() {
+            if ui_tx.send_blocking(event).is_err() {
                 break;
             }
         }
@@ -43,8 +129,18 @@ fn main() {
     
     // Init Core
     tracing::info!("⚙️ [MAIN] Initializing PipeWire Core with cmd_rx...");
-    let _client = PipeWireClient::new(core_tx, cmd_rx).expect("Failed to initialize Auralis Core");
+    let client = match PipeWireClient::with_config(core_tx, cmd_rx, config) {
+        Ok(client) => client,
+        // Most likely another instance holding the lock; the message says how to get past it
+        Err(e) => {
+            tracing::error!("❌ [MAIN] {:#}", e);
+            std::process::exit(1);
+        }
+    };
     tracing::info!("✓ [MAIN] PipeWire Core initialized");
+
+    // Lets the UI check its mirrored state against the core's
+    let graph_view = client.graph_view();
     
     // We need to move ui_rx into the closure.
     // Since ui_rx is NOT Clone, we wrap it in Rc<RefCell<Option<...>>>
@@ -55,7 +151,6 @@ fn main() {
     
     // Use Arc<AtomicBool> for thread-safe shutdown signaling
     // (GTK objects can't be sent across threads)
-    use std::sync::Arc;
     use std::sync::atomic::{AtomicBool, Ordering};
     let shutdown_signal = Arc::new(AtomicBool::new(false));
     let shutdown_signal_ctrlc = shutdown_signal.clone();
@@ -74,24 +169,10 @@ fn main() {
         .build();
 
     app.connect_activate(move |app| {
-        // Load CSS
-        let provider = gtk4::CssProvider::new();
-        let style_path_1 = std::path::Path::new("src/style.css");
-        let style_path_2 = std::path::Path::new("auralis-ui/src/style.css");
-        
-        if style_path_1.exists() {
-            provider.load_from_path(style_path_1.to_str().unwrap());
-        } else if style_path_2.exists() {
-            provider.load_from_path(style_path_2.to_str().unwrap());
-        } else {
-            tracing::warn!("⚠️ [MAIN] Could not find style.css in src/ or auralis-ui/src/");
+        match gtk4::gdk::Display::default() {
+            Some(display) => load_css(&display),
+            None => tracing::warn!("⚠️ [MAIN] No default display, running without custom styles"),
         }
-        
-        gtk4::style_context_add_provider_for_display(
-            &gtk4::gdk::Display::default().expect("Could not connect to a display."),
-            &provider,
-            gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
-        );
 
        // Set up shutdown signal polling
         let shutdown_check = shutdown_signal.clone();
@@ -109,12 +190,30 @@ fn main() {
         if let Some(rx) = rx_holder.borrow_mut().take() {
             tracing::info!("🎨 [MAIN] Building UI window...");
             let cmd_tx_for_ui = cmd_tx.clone();
-            window::build(app, rx, cmd_tx_for_ui);
+            window::build(app, rx, cmd_tx_for_ui, graph_view.clone(), bridge_stats.clone(), profile_names.clone());
             tracing::info!("✓ [MAIN] UI window built and activated");
         } else {
             tracing::warn!("Application activated again, but channel is already consumed");
         }
     });
 
-    app.run();
+    app.run_with_args(&args);
+}
+
+/// Built-in stylesheet, plus `$XDG_CONFIG_HOME/auralis/style.css` on top for theming
+fn load_css(display: &gtk4::gdk::Display) {
+    // Compiled in, so an installed binary is styled no matter where it runs from
+    let provider = gtk4::CssProvider::new();
+    provider.load_from_data(include_str!("style.css"));
+    gtk4::style_context_add_provider_for_display(display, &provider, gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION);
+
+    let Some(user_css) = persistence::config_dir().map(|dir| dir.join("style.css")) else {
+        return;
+    };
+    if user_css.exists() {
+        tracing::info!("🎨 [MAIN] Loading style overrides from {}", user_css.display());
+        let user_provider = gtk4::CssProvider::new();
+        user_provider.load_from_path(&user_css);
+        gtk4::style_context_add_provider_for_display(display, &user_provider, gtk4::STYLE_PROVIDER_PRIORITY_USER);
+    }
 }
@@ -0,0 +1,68 @@
+use gtk4::prelude::*;
+use std::sync::mpsc::Sender;
+use auralis_core::{Orb, UiCommand};
+
+/// Ask before merging cluster `source` into `target`. Both are unloaded and rebuilt as one,
+/// so their names, zones and member settings don't carry over.
+pub fn show(parent: Option<&gtk4::Window>, source: &Orb, target: &Orb, cmd_tx: Sender<UiCommand>) {
+    let dialog = gtk4::Window::builder()
+        .title("Merge Clusters")
+        .modal(true)
+        .resizable(false)
+        .default_width(360)
+        .build();
+    dialog.set_transient_for(parent);
+
+    let content = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    content.set_margin_start(24);
+    content.set_margin_end(24);
+    content.set_margin_top(24);
+    content.set_margin_bottom(24);
+
+    let heading = gtk4::Label::builder()
+        .label(format!("Merge '{}' and '{}' into one cluster?", source.name, target.name))
+        .wrap(true)
+        .halign(gtk4::Align::Start)
+        .css_classes(vec!["subheading"])
+        .build();
+    content.append(&heading);
+
+    let message = gtk4::Label::builder()
+        .label("Both clusters are separated and their devices combined into a new one.")
+        .wrap(true)
+        .halign(gtk4::Align::Start)
+        .build();
+    content.append(&message);
+
+    let dont_ask = gtk4::CheckButton::with_label("Don't ask again");
+    content.append(&dont_ask);
+
+    let button_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+    button_box.set_halign(gtk4::Align::End);
+    button_box.set_margin_top(12);
+
+    let cancel_btn = gtk4::Button::with_label("Cancel");
+    let merge_btn = gtk4::Button::with_label("Merge");
+    merge_btn.add_css_class("btn-primary");
+
+    button_box.append(&cancel_btn);
+    button_box.append(&merge_btn);
+    content.append(&button_box);
+
+    dialog.set_child(Some(&content));
+
+    let dialog_cancel = dialog.clone();
+    cancel_btn.connect_clicked(move |_| dialog_cancel.close());
+
+    let dialog_merge = dialog.clone();
+    let (source, target) = (source.id, target.id);
+    merge_btn.connect_clicked(move |_| {
+        if dont_ask.is_active() {
+            let _ = cmd_tx.send(UiCommand::SetConfirmMerges { enabled: false });
+        }
+        let _ = cmd_tx.send(UiCommand::Connect { source, target });
+        dialog_merge.close();
+    });
+
+    dialog.present();
+}
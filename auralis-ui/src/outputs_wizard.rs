@@ -0,0 +1,193 @@
+use gtk4::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
+use auralis_core::{OrbKind, UiCommand};
+use uuid::Uuid;
+use crate::state::SharedState;
+
+// "Test All Outputs": a tone plays on one device at a time (`UiCommand::PlayTestTone`) and the
+// user says whether they heard it where they expected, renaming the device on the spot if its
+// name doesn't tell which speaker it is. Ends with the devices nobody heard.
+
+struct Device {
+    id: Uuid,
+    name: String,
+    description: String,
+}
+
+struct Wizard {
+    dialog: gtk4::Window,
+    progress: gtk4::Label,
+    question: gtk4::Label,
+    name_entry: gtk4::Entry,
+    answer_box: gtk4::Box,
+    close_btn: gtk4::Button,
+    devices: Vec<Device>,
+    index: Cell<usize>,
+    unheard: RefCell<Vec<String>>,
+    cmd_tx: Sender<UiCommand>,
+}
+
+impl Wizard {
+    fn play(&self) {
+        if let Some(device) = self.devices.get(self.index.get()) {
+            let _ = self.cmd_tx.send(UiCommand::PlayTestTone { device: device.id });
+        }
+    }
+
+    /// Ask about the current device and play its tone, or sum up once all were played
+    fn show_current(&self) {
+        let Some(device) = self.devices.get(self.index.get()) else {
+            self.show_summary();
+            return;
+        };
+        self.progress.set_label(&format!("Output {} of {}", self.index.get() + 1, self.devices.len()));
+        self.question.set_label(&format!("Did you hear the tone from {}?", device.name));
+        self.name_entry.set_placeholder_text(Some(&device.description));
+        self.name_entry.set_text(if device.name != device.description { device.name.as_str() } else { "" });
+        self.play();
+    }
+
+    fn show_summary(&self) {
+        let unheard = self.unheard.borrow();
+        self.progress.set_label("Done");
+        let summary = match (self.devices.len(), unheard.as_slice()) {
+            (0, _) => "There are no outputs to test yet.".to_string(),
+            (_, []) => "Every output played where you expected.".to_string(),
+            (_, unheard) => format!(
+                "Not heard: {}. Check that they're switched on and turned up, or listen for them elsewhere.",
+                unheard.join(", "),
+            ),
+        };
+        self.question.set_label(&summary);
+        self.name_entry.set_visible(false);
+        self.answer_box.set_visible(false);
+        self.close_btn.set_visible(true);
+    }
+
+    /// Keep a new name for the current device, note whether it was heard and go to the next
+    fn answer(&self, heard: bool) {
+        let Some(device) = self.devices.get(self.index.get()) else { return };
+        let typed = self.name_entry.text().trim().to_string();
+        let shown = if typed.is_empty() { device.description.clone() } else { typed.clone() };
+        if shown != device.name {
+            let name = Some(typed).filter(|n| !n.is_empty());
+            let _ = self.cmd_tx.send(UiCommand::RenameDevice { device: device.id, name });
+        }
+        if !heard {
+            self.unheard.borrow_mut().push(shown);
+        }
+        self.index.set(self.index.get() + 1);
+        self.show_current();
+    }
+}
+
+/// Open the wizard on every device Auralis knows, cluster members included, by name
+pub fn show(parent: Option<&gtk4::Window>, state: &SharedState, cmd_tx: Sender<UiCommand>) {
+    let mut devices: Vec<Device> = state.borrow().orbs.values()
+        .filter_map(|orb| match &orb.kind {
+            OrbKind::PhysicalSink { description } => Some(Device {
+                id: orb.id,
+                name: orb.name.clone(),
+                description: description.clone(),
+            }),
+            _ => None,
+        })
+        .collect();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let dialog = gtk4::Window::builder()
+        .title("Test All Outputs")
+        .modal(true)
+        .resizable(false)
+        .default_width(400)
+        .build();
+    dialog.set_transient_for(parent);
+
+    let content = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    content.set_margin_start(24);
+    content.set_margin_end(24);
+    content.set_margin_top(24);
+    content.set_margin_bottom(24);
+
+    let progress = gtk4::Label::builder().halign(gtk4::Align::Start).css_classes(vec!["caption"]).build();
+    progress.set_opacity(0.6);
+    let question = gtk4::Label::builder()
+        .halign(gtk4::Align::Start)
+        .wrap(true)
+        .css_classes(vec!["subheading"])
+        .build();
+    let name_entry = gtk4::Entry::new();
+    name_entry.set_tooltip_text(Some("Name it after where it is, e.g. \"Kitchen Speaker\"; leave empty for its own name"));
+
+    content.append(&progress);
+    content.append(&question);
+    content.append(&name_entry);
+
+    // Buttons
+    let answer_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+    answer_box.set_margin_top(12);
+    let again_btn = gtk4::Button::with_label("Play Again");
+    let spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    spacer.set_hexpand(true);
+    let no_btn = gtk4::Button::with_label("No");
+    let yes_btn = gtk4::Button::with_label("Yes");
+    yes_btn.add_css_class("btn-primary");
+    answer_box.append(&again_btn);
+    answer_box.append(&spacer);
+    answer_box.append(&no_btn);
+    answer_box.append(&yes_btn);
+    content.append(&answer_box);
+
+    let close_btn = gtk4::Button::with_label("Close");
+    close_btn.set_halign(gtk4::Align::End);
+    close_btn.set_visible(false);
+    content.append(&close_btn);
+
+    dialog.set_child(Some(&content));
+
+    let wizard = Rc::new(Wizard {
+        dialog,
+        progress,
+        question,
+        name_entry,
+        answer_box,
+        close_btn,
+        devices,
+        index: Cell::new(0),
+        unheard: RefCell::new(Vec::new()),
+        cmd_tx,
+    });
+
+    // The dialog keeps the wizard until it's closed; its buttons only refer to it, the wizard
+    // holds the dialog
+    let keep = RefCell::new(Some(wizard.clone()));
+    wizard.dialog.connect_close_request(move |_| {
+        keep.take();
+        gtk4::glib::Propagation::Proceed
+    });
+    let wizard_again = Rc::downgrade(&wizard);
+    again_btn.connect_clicked(move |_| {
+        if let Some(wizard) = wizard_again.upgrade() {
+            wizard.play();
+        }
+    });
+    let wizard_no = Rc::downgrade(&wizard);
+    no_btn.connect_clicked(move |_| {
+        if let Some(wizard) = wizard_no.upgrade() {
+            wizard.answer(false);
+        }
+    });
+    let wizard_yes = Rc::downgrade(&wizard);
+    yes_btn.connect_clicked(move |_| {
+        if let Some(wizard) = wizard_yes.upgrade() {
+            wizard.answer(true);
+        }
+    });
+    let dialog_close = wizard.dialog.clone();
+    wizard.close_btn.connect_clicked(move |_| dialog_close.close());
+
+    wizard.show_current();
+    wizard.dialog.present();
+}
@@ -0,0 +1,109 @@
+use gtk4::prelude::*;
+use std::sync::mpsc::Sender;
+use auralis_core::formats;
+use auralis_core::{Orb, OrbKind, UiCommand};
+use uuid::Uuid;
+use crate::state::SharedState;
+
+/// The sidebar card showing the device selected in the device list, see `update`
+pub fn build(state: &SharedState) -> gtk4::Box {
+    let card = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    card.add_css_class("properties-card");
+    card.set_height_request(120);
+    update(&card, state);
+    card
+}
+
+/// Show `device` in the card and read its formats and volume again: the format in use changes
+/// as it starts and stops playing. Selecting the same device again (the list re-selects it
+/// after every rebuild) does nothing.
+pub fn select(card: &gtk4::Box, state: &SharedState, cmd_tx: &Sender<UiCommand>, device: Uuid) {
+    if state.borrow().selected == Some(device) {
+        return;
+    }
+    state.borrow_mut().selected = Some(device);
+    let _ = cmd_tx.send(UiCommand::ReadFormats { device });
+    let _ = cmd_tx.send(UiCommand::ReadVolume { device });
+    update(card, state);
+}
+
+/// Rebuild the card: the selected device's formats, or the hint while nothing is selected
+pub fn update(card: &gtk4::Box, state: &SharedState) {
+    while let Some(child) = card.first_child() {
+        card.remove(&child);
+    }
+
+    let state = state.borrow();
+    let selected = state.selected
+        .and_then(|id| state.orbs.get(&id))
+        .filter(|orb| matches!(orb.kind, OrbKind::PhysicalSink { .. }));
+    let Some(orb) = selected else {
+        let icon = gtk4::Image::from_icon_name("touch-symbolic");
+        icon.set_pixel_size(32);
+        icon.set_opacity(0.3);
+
+        let hint = gtk4::Label::new(Some("Select a device\nto see its properties."));
+        hint.set_justify(gtk4::Justification::Center);
+        hint.add_css_class("caption");
+        hint.set_opacity(0.5);
+
+        card.append(&icon);
+        card.append(&hint);
+        card.set_valign(gtk4::Align::Center);
+        card.set_halign(gtk4::Align::Center);
+        return;
+    };
+    card.set_valign(gtk4::Align::Start);
+    card.set_halign(gtk4::Align::Fill);
+
+    let name = gtk4::Label::builder()
+        .label(&orb.name)
+        .halign(gtk4::Align::Start)
+        .ellipsize(gtk4::pango::EllipsizeMode::End)
+        .css_classes(vec!["heading"])
+        .build();
+    card.append(&name);
+
+    let grid = gtk4::Grid::new();
+    grid.set_row_spacing(4);
+    grid.set_column_spacing(12);
+    let volume = state.volumes.get(&orb.id).map(|percent| ("Volume", format!("{}%", percent)));
+    for (row, (label, value)) in volume.into_iter().chain(capabilities(orb)).enumerate() {
+        let key = gtk4::Label::builder().label(label).halign(gtk4::Align::Start).css_classes(vec!["caption"]).build();
+        key.set_opacity(0.6);
+        let value = gtk4::Label::builder()
+            .label(&value)
+            .halign(gtk4::Align::Start)
+            .wrap(true)
+            .css_classes(vec!["caption"])
+            .build();
+        grid.attach(&key, 0, row as i32, 1, 1);
+        grid.attach(&value, 1, row as i32, 1, 1);
+    }
+    card.append(&grid);
+}
+
+/// Format in use, sample rates and channels, "Unknown" for what the device didn't report
+fn capabilities(orb: &Orb) -> Vec<(&'static str, String)> {
+    let unknown = || "Unknown".to_string();
+    let current = match (&orb.format, orb.formats.is_empty()) {
+        (Some(format), _) => format.label(),
+        (None, true) => unknown(),
+        (None, false) => "Not in use".to_string(),
+    };
+    vec![
+        ("Format", current),
+        ("Sample rates", formats::rates_label(&orb.formats).unwrap_or_else(unknown)),
+        ("Max channels", formats::max_channels(&orb.formats).map_or_else(unknown, |c| c.to_string())),
+    ]
+}
+
+/// One line for the device list's context menu, e.g. "Up to 2 ch · 44.1, 48 kHz"
+pub fn summary(orb: &Orb) -> String {
+    let channels = formats::max_channels(&orb.formats).map(|c| format!("Up to {} ch", c));
+    let parts: Vec<String> = channels.into_iter().chain(formats::rates_label(&orb.formats)).collect();
+    match parts.is_empty() {
+        true => "Formats unknown".to_string(),
+        false => parts.join(" · "),
+    }
+}
@@ -1,6 +1,42 @@
 use gtk4::prelude::*;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
+use auralis_core::media_roles;
+use auralis_core::persistence::validate_turn_url;
+use auralis_core::{Appearance, CanvasLayout, ExportFormat, OrbKind, TurnServer, UiCommand};
+use uuid::Uuid;
+use crate::state::SharedState;
+use crate::{outputs_wizard, theme};
 
-pub fn build() -> gtk4::Box {
+/// Handles to the settings page widgets the event loop keeps in sync with the core
+pub struct SettingsView {
+    pub container: gtk4::Box,
+    pub auto_switch: gtk4::Switch,
+    pub show_members: gtk4::Switch,
+    pub confirm_merges: gtk4::Switch,
+    pub auto_cluster: gtk4::Switch,
+    pub balance_new_clusters: gtk4::Switch,
+    pub rebuild_clusters: gtk4::Switch,
+    pub dropout_grace: gtk4::SpinButton,
+    pub bluetooth_offset: gtk4::SpinButton,
+    pub accent: gtk4::ColorButton,
+    pub appearance: gtk4::DropDown,
+    pub canvas_layout: gtk4::DropDown,
+    pub reduce_motion: gtk4::Switch,
+    pub show_notifications: gtk4::Switch,
+    pub notifications_off_clusters: gtk4::Switch,
+    pub turn_url: gtk4::Entry,
+    pub turn_username: gtk4::Entry,
+    pub turn_credential: gtk4::PasswordEntry,
+    /// One output dropdown per media role, rebuilt by `update_role_targets`
+    pub role_targets: gtk4::Box,
+    /// What `role_targets` was last built from
+    pub role_targets_shown: Rc<RefCell<String>>,
+}
+
+pub fn build(state: SharedState, cmd_tx: Sender<UiCommand>) -> SettingsView {
     let container = gtk4::Box::new(gtk4::Orientation::Vertical, 16);
     container.set_margin_start(24);
     container.set_margin_end(24);
@@ -35,9 +71,120 @@ pub fn build() -> gtk4::Box {
         row
     }
 
+    /// Switch row for a setting the core keeps: flipping it sends `command`, and the value
+    /// saved in the core comes back with `OrbEvent::Settings`
+    fn command_switch_row(
+        label: &str,
+        active: bool,
+        tooltip: &str,
+        cmd_tx: &Sender<UiCommand>,
+        command: fn(bool) -> UiCommand,
+    ) -> (gtk4::Box, gtk4::Switch) {
+        let row = create_switch_row(label, active);
+        let switch = row.last_child()
+            .and_downcast::<gtk4::Switch>()
+            .expect("switch is the last child of a switch row");
+        switch.set_tooltip_text(Some(tooltip));
+        let cmd_tx = cmd_tx.clone();
+        switch.connect_active_notify(move |switch| {
+            let _ = cmd_tx.send(command(switch.is_active()));
+        });
+        (row, switch)
+    }
+
     group_general.append(&create_switch_row("Start on Boot", true));
     group_general.append(&create_switch_row("Minimize to Tray", false));
-    group_general.append(&create_switch_row("Show Notifications", true));
+
+    let (show_notifications_row, show_notifications) = command_switch_row(
+        "Show Notifications",
+        true,
+        "Play a soft sound when a cluster you made is ready",
+        &cmd_tx,
+        |enabled| UiCommand::SetShowNotifications { enabled },
+    );
+    group_general.append(&show_notifications_row);
+
+    let appearance_row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    let appearance_lbl = gtk4::Label::new(Some("Appearance"));
+    let appearance_spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    appearance_spacer.set_hexpand(true);
+    let appearance = gtk4::DropDown::from_strings(&["Follow System", "Light", "Dark"]);
+    appearance.set_selected(appearance_index(Appearance::default()));
+    let cmd_tx_appearance = cmd_tx.clone();
+    appearance.connect_selected_notify(move |dropdown| {
+        let Some(&appearance) = APPEARANCES.get(dropdown.selected() as usize) else { return };
+        let _ = cmd_tx_appearance.send(UiCommand::SetAppearance { appearance });
+    });
+    appearance_row.append(&appearance_lbl);
+    appearance_row.append(&appearance_spacer);
+    appearance_row.append(&appearance);
+    group_general.append(&appearance_row);
+
+    // For anyone the drifting gets in the way of: a tidy grid or ring is easier to aim at
+    let layout_row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    let layout_lbl = gtk4::Label::new(Some("Canvas Layout"));
+    let layout_spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    layout_spacer.set_hexpand(true);
+    let canvas_layout = gtk4::DropDown::from_strings(&["Free", "Grid", "Ring", "Physics"]);
+    canvas_layout.set_selected(canvas_layout_index(CanvasLayout::default()));
+    let cmd_tx_layout = cmd_tx.clone();
+    canvas_layout.connect_selected_notify(move |dropdown| {
+        let Some(&layout) = CANVAS_LAYOUTS.get(dropdown.selected() as usize) else { return };
+        let _ = cmd_tx_layout.send(UiCommand::SetCanvasLayout { layout });
+    });
+    layout_row.append(&layout_lbl);
+    layout_row.append(&layout_spacer);
+    layout_row.append(&canvas_layout);
+    group_general.append(&layout_row);
+
+    let (reduce_motion_row, reduce_motion) = command_switch_row(
+        "Reduce Motion",
+        false,
+        "Orbs stop pulsing and the layout puts them in place without gliding",
+        &cmd_tx,
+        |enabled| UiCommand::SetReduceMotion { enabled },
+    );
+    group_general.append(&reduce_motion_row);
+
+    // Canvas accent; the core persists it and echoes it back with OrbEvent::Settings
+    let accent_row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    let accent_lbl = gtk4::Label::new(Some("Accent Color"));
+    let accent_spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    accent_spacer.set_hexpand(true);
+    let accent = gtk4::ColorButton::new();
+    accent.set_use_alpha(false);
+    let (r, g, b) = theme::AuralisTheme::default().primary;
+    accent.set_rgba(&gtk4::gdk::RGBA::new(r as f32, g as f32, b as f32, 1.0));
+    accent.set_tooltip_text(Some("Color of orbs and cluster cards"));
+    let cmd_tx_accent = cmd_tx.clone();
+    accent.connect_color_set(move |button| {
+        let _ = cmd_tx_accent.send(UiCommand::SetAccentColor { color: Some(theme::to_hex(&button.rgba())) });
+    });
+    let accent_reset = gtk4::Button::with_label("Default");
+    accent_reset.add_css_class("flat");
+    let cmd_tx_accent_reset = cmd_tx.clone();
+    accent_reset.connect_clicked(move |_| {
+        let _ = cmd_tx_accent_reset.send(UiCommand::SetAccentColor { color: None });
+    });
+    accent_row.append(&accent_lbl);
+    accent_row.append(&accent_spacer);
+    accent_row.append(&accent_reset);
+    accent_row.append(&accent);
+    group_general.append(&accent_row);
+
+    let tour_row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    let tour_lbl = gtk4::Label::new(Some("Getting Started Tour"));
+    let tour_spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    tour_spacer.set_hexpand(true);
+    let tour_btn = gtk4::Button::with_label("Show Tour Again");
+    let cmd_tx_tour = cmd_tx.clone();
+    tour_btn.connect_clicked(move |_| {
+        let _ = cmd_tx_tour.send(UiCommand::SetFirstRun { first_run: true });
+    });
+    tour_row.append(&tour_lbl);
+    tour_row.append(&tour_spacer);
+    tour_row.append(&tour_btn);
+    group_general.append(&tour_row);
 
     container.append(&group_general);
 
@@ -52,8 +199,297 @@ pub fn build() -> gtk4::Box {
     group_audio.append(&create_switch_row("High Quality Resampling", true));
     group_audio.append(&create_switch_row("Low Latency Mode", false));
 
+    // Backed by the core's persisted settings, real value arrives with OrbEvent::Settings
+    let (auto_switch_row, auto_switch) = command_switch_row(
+        "Auto-switch to New Devices",
+        false,
+        "Make newly plugged-in devices the default output",
+        &cmd_tx,
+        |enabled| UiCommand::SetAutoSwitch { enabled },
+    );
+    group_audio.append(&auto_switch_row);
+
+    let (show_members_row, show_members) = command_switch_row(
+        "Show Cluster Members in Device List",
+        false,
+        "Keep clustered devices listed, marked with their cluster",
+        &cmd_tx,
+        |enabled| UiCommand::SetShowClusterMembers { enabled },
+    );
+    group_audio.append(&show_members_row);
+
+    let (confirm_merges_row, confirm_merges) = command_switch_row(
+        "Confirm Cluster Merges",
+        true,
+        "Ask before a cluster dropped onto another merges the two",
+        &cmd_tx,
+        |enabled| UiCommand::SetConfirmMerges { enabled },
+    );
+    group_audio.append(&confirm_merges_row);
+
+    let (auto_cluster_row, auto_cluster) = command_switch_row(
+        "Auto-cluster on Drop",
+        true,
+        "Cluster a device dropped onto another right away; when off, gather them and press \"Cluster These\"",
+        &cmd_tx,
+        |enabled| UiCommand::SetAutoClusterOnDrop { enabled },
+    );
+    group_audio.append(&auto_cluster_row);
+
+    let (balance_row, balance_new_clusters) = command_switch_row(
+        "Balance Volumes of New Clusters",
+        false,
+        "Set the speakers of a new cluster to the same volume, the average of theirs",
+        &cmd_tx,
+        |enabled| UiCommand::SetBalanceNewClusters { enabled },
+    );
+    group_audio.append(&balance_row);
+
+    let (rebuild_clusters_row, rebuild_clusters) = command_switch_row(
+        "Rebuild Clusters After PipeWire Restarts",
+        true,
+        "Put clusters back together once their devices return, instead of leaving them apart",
+        &cmd_tx,
+        |enabled| UiCommand::SetRebuildClusters { enabled },
+    );
+    group_audio.append(&rebuild_clusters_row);
+
+    // Seconds in the UI, milliseconds in the core
+    let grace_row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    let grace_lbl = gtk4::Label::new(Some("Keep Clusters Through Dropouts (s)"));
+    let grace_spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    grace_spacer.set_hexpand(true);
+    let dropout_grace = gtk4::SpinButton::with_range(0.0, 30.0, 0.5);
+    dropout_grace.set_digits(1);
+    dropout_grace.set_value(3.0);
+    dropout_grace.set_tooltip_text(Some("How long a disconnected speaker may be gone before its cluster is separated"));
+    let cmd_tx_grace = cmd_tx.clone();
+    dropout_grace.connect_value_changed(move |spin| {
+        let ms = (spin.value() * 1000.0).round() as u32;
+        let _ = cmd_tx_grace.send(UiCommand::SetDropoutGrace { ms });
+    });
+    grace_row.append(&grace_lbl);
+    grace_row.append(&grace_spacer);
+    grace_row.append(&dropout_grace);
+    group_audio.append(&grace_row);
+
+    // Milliseconds, it's fine-tuning
+    let bluetooth_row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    let bluetooth_lbl = gtk4::Label::new(Some("Bluetooth Delay (ms)"));
+    let bluetooth_spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    bluetooth_spacer.set_hexpand(true);
+    let bluetooth_offset = gtk4::SpinButton::with_range(0.0, 500.0, 10.0);
+    bluetooth_offset.set_value(200.0);
+    bluetooth_offset.set_tooltip_text(Some("How long wired speakers in a cluster wait for its Bluetooth ones, 0 to not wait"));
+    let cmd_tx_bluetooth = cmd_tx.clone();
+    bluetooth_offset.connect_value_changed(move |spin| {
+        let _ = cmd_tx_bluetooth.send(UiCommand::SetBluetoothOffset { ms: spin.value_as_int() as u32 });
+    });
+    bluetooth_row.append(&bluetooth_lbl);
+    bluetooth_row.append(&bluetooth_spacer);
+    bluetooth_row.append(&bluetooth_offset);
+    group_audio.append(&bluetooth_row);
+
+    // Which cryptic device name is which speaker: a tone on each in turn
+    let test_outputs_row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    let test_outputs_lbl = gtk4::Label::new(Some("Test All Outputs"));
+    let test_outputs_spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    test_outputs_spacer.set_hexpand(true);
+    let test_outputs_btn = gtk4::Button::with_label("Start…");
+    test_outputs_btn.set_tooltip_text(Some("Play a tone on each device in turn and name them after where you heard it"));
+    let cmd_tx_test = cmd_tx.clone();
+    test_outputs_btn.connect_clicked(move |btn| {
+        let parent = btn.root().and_downcast::<gtk4::Window>();
+        outputs_wizard::show(parent.as_ref(), &state, cmd_tx_test.clone());
+    });
+    test_outputs_row.append(&test_outputs_lbl);
+    test_outputs_row.append(&test_outputs_spacer);
+    test_outputs_row.append(&test_outputs_btn);
+    group_audio.append(&test_outputs_row);
+
+    // Sinks left loaded by a failed or interrupted action; clusters stay as they are
+    let orphans_row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    let orphans_lbl = gtk4::Label::new(Some("Clean Up Orphaned Sinks"));
+    let orphans_spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    orphans_spacer.set_hexpand(true);
+    let orphans_btn = gtk4::Button::with_label("Clean Up");
+    orphans_btn.set_tooltip_text(Some("Remove Auralis sinks that no cluster uses anymore"));
+    let cmd_tx_orphans = cmd_tx.clone();
+    orphans_btn.connect_clicked(move |_| {
+        let _ = cmd_tx_orphans.send(UiCommand::CleanUpOrphans);
+    });
+    orphans_row.append(&orphans_lbl);
+    orphans_row.append(&orphans_spacer);
+    orphans_row.append(&orphans_btn);
+    group_audio.append(&orphans_row);
+
+    // Emergency exit when clusters and the audio server disagree
+    let reset_row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    let reset_lbl = gtk4::Label::new(Some("Reset Audio Routing"));
+    let reset_spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    reset_spacer.set_hexpand(true);
+    let reset_btn = gtk4::Button::with_label("Reset…");
+    reset_btn.add_css_class("btn-destructive");
+    reset_btn.set_tooltip_text(Some("Separate all clusters and remove every module Auralis loaded"));
+    let cmd_tx_reset = cmd_tx.clone();
+    reset_btn.connect_clicked(move |btn| {
+        let parent = btn.root().and_downcast::<gtk4::Window>();
+        confirm_reset(parent.as_ref(), cmd_tx_reset.clone());
+    });
+    reset_row.append(&reset_lbl);
+    reset_row.append(&reset_spacer);
+    reset_row.append(&reset_btn);
+    group_audio.append(&reset_row);
+
     container.append(&group_audio);
 
+    container.append(&gtk4::Separator::new(gtk4::Orientation::Horizontal));
+
+    // Group 3: Media Roles
+    let group_roles = gtk4::Box::new(gtk4::Orientation::Vertical, 8);
+    let roles_title = gtk4::Label::builder().label("Media Roles").halign(gtk4::Align::Start).css_classes(vec!["subheading"]).build();
+    group_roles.append(&roles_title);
+
+    let roles_hint = gtk4::Label::builder()
+        .label("Play these sounds somewhere other than the default output, e.g. notifications on the laptop speaker while music goes to a cluster.")
+        .halign(gtk4::Align::Start)
+        .wrap(true)
+        .css_classes(vec!["caption"])
+        .build();
+    group_roles.append(&roles_hint);
+
+    let role_targets = gtk4::Box::new(gtk4::Orientation::Vertical, 8);
+    group_roles.append(&role_targets);
+
+    let (notifications_off_clusters_row, notifications_off_clusters) = command_switch_row(
+        "Keep Notifications Off Clusters",
+        false,
+        "While a cluster is the default, play notification sounds on the device that was the default before it. An output picked for notifications above wins.",
+        &cmd_tx,
+        |enabled| UiCommand::SetNotificationsOffClusters { enabled },
+    );
+    group_roles.append(&notifications_off_clusters_row);
+    container.append(&group_roles);
+
+    container.append(&gtk4::Separator::new(gtk4::Orientation::Horizontal));
+
+    // Group 4: Beam / Network
+    let group_network = gtk4::Box::new(gtk4::Orientation::Vertical, 8);
+    let network_title = gtk4::Label::builder().label("Beam / Network").halign(gtk4::Align::Start).css_classes(vec!["subheading"]).build();
+    group_network.append(&network_title);
+
+    let network_hint = gtk4::Label::builder()
+        .label("Beams connect directly on the same network. To reach devices elsewhere, add a TURN relay.")
+        .halign(gtk4::Align::Start)
+        .wrap(true)
+        .css_classes(vec!["caption"])
+        .build();
+    group_network.append(&network_hint);
+
+    fn create_entry_row(label: &str, entry: &impl IsA<gtk4::Widget>) -> gtk4::Box {
+        let row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+        let lbl = gtk4::Label::new(Some(label));
+        let spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+        spacer.set_hexpand(true);
+        entry.set_width_request(260);
+
+        row.append(&lbl);
+        row.append(&spacer);
+        row.append(entry);
+        row
+    }
+
+    let turn_url = gtk4::Entry::new();
+    turn_url.set_placeholder_text(Some("turn:relay.example.com:3478"));
+    // Flag a malformed URL while typing; the core rejects it on Apply anyway
+    turn_url.connect_changed(|entry| {
+        let url = entry.text();
+        match validate_turn_url(url.trim()) {
+            Err(e) if !url.trim().is_empty() => {
+                entry.add_css_class("error");
+                entry.set_tooltip_text(Some(&e));
+            }
+            _ => {
+                entry.remove_css_class("error");
+                entry.set_tooltip_text(None);
+            }
+        }
+    });
+    group_network.append(&create_entry_row("TURN Server", &turn_url));
+
+    let turn_username = gtk4::Entry::new();
+    group_network.append(&create_entry_row("Username", &turn_username));
+
+    let turn_credential = gtk4::PasswordEntry::new();
+    turn_credential.set_show_peek_icon(true);
+    group_network.append(&create_entry_row("Password", &turn_credential));
+
+    let turn_apply = gtk4::Button::with_label("Apply");
+    turn_apply.set_halign(gtk4::Align::End);
+    turn_apply.set_tooltip_text(Some("Used by beams started from now on. Leave the server empty to connect directly only."));
+    let cmd_tx_turn = cmd_tx.clone();
+    let (turn_url_apply, turn_username_apply, turn_credential_apply) = (turn_url.clone(), turn_username.clone(), turn_credential.clone());
+    turn_apply.connect_clicked(move |_| {
+        let turn = TurnServer {
+            url: turn_url_apply.text().to_string(),
+            username: turn_username_apply.text().to_string(),
+            credential: turn_credential_apply.text().to_string(),
+        };
+        let _ = cmd_tx_turn.send(UiCommand::SetBeamTurn { turn: Some(turn) });
+    });
+    group_network.append(&turn_apply);
+
+    container.append(&group_network);
+
+    container.append(&gtk4::Separator::new(gtk4::Orientation::Horizontal));
+
+    // Group 5: Layout, moved between machines as one file (see auralis_core::bundle)
+    let group_layout = gtk4::Box::new(gtk4::Orientation::Vertical, 8);
+    let layout_title = gtk4::Label::builder().label("Layout").halign(gtk4::Align::Start).css_classes(vec!["subheading"]).build();
+    group_layout.append(&layout_title);
+
+    let layout_hint = gtk4::Label::builder()
+        .label("Cluster names, zones, delays, profiles, media roles and canvas positions in one file, to take to another machine or share. The TURN password is left out.")
+        .halign(gtk4::Align::Start)
+        .wrap(true)
+        .css_classes(vec!["caption"])
+        .build();
+    group_layout.append(&layout_hint);
+
+    let layout_row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    let layout_replace = gtk4::CheckButton::with_label("Replace instead of merging");
+    layout_replace.set_tooltip_text(Some("Importing normally adds to what's here and keeps your settings"));
+    let layout_spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+    layout_spacer.set_hexpand(true);
+
+    let export_btn = gtk4::Button::with_label("Export…");
+    let cmd_tx_export = cmd_tx.clone();
+    export_btn.connect_clicked(move |btn| {
+        let parent = btn.root().and_downcast::<gtk4::Window>();
+        let cmd_tx_export = cmd_tx_export.clone();
+        choose_file(parent.as_ref(), "Export Layout", gtk4::FileChooserAction::Save, "Export", move |path| {
+            let _ = cmd_tx_export.send(UiCommand::ExportLayout { path });
+        });
+    });
+
+    let import_btn = gtk4::Button::with_label("Import…");
+    let cmd_tx_import = cmd_tx.clone();
+    let layout_replace_import = layout_replace.clone();
+    import_btn.connect_clicked(move |btn| {
+        let parent = btn.root().and_downcast::<gtk4::Window>();
+        let (cmd_tx_import, replace) = (cmd_tx_import.clone(), layout_replace_import.is_active());
+        choose_file(parent.as_ref(), "Import Layout", gtk4::FileChooserAction::Open, "Import", move |path| {
+            let _ = cmd_tx_import.send(UiCommand::ImportLayout { path, replace });
+        });
+    });
+
+    layout_row.append(&layout_replace);
+    layout_row.append(&layout_spacer);
+    layout_row.append(&export_btn);
+    layout_row.append(&import_btn);
+    group_layout.append(&layout_row);
+    container.append(&group_layout);
+
     // About
     container.append(&gtk4::Separator::new(gtk4::Orientation::Horizontal));
     
@@ -63,8 +499,177 @@ pub fn build() -> gtk4::Box {
     
     about_box.append(&app_name);
     about_box.append(&version);
+
+    // Tucked away for bug reports; the core answers with OrbEvent::GraphExport
+    let debug_btn = gtk4::Button::with_label("Copy debug state");
+    debug_btn.add_css_class("flat");
+    debug_btn.add_css_class("caption");
+    debug_btn.set_halign(gtk4::Align::Start);
+    debug_btn.connect_clicked(move |_| {
+        let _ = cmd_tx.send(UiCommand::ExportGraph { format: ExportFormat::Json });
+    });
+    about_box.append(&debug_btn);
     
     container.append(&about_box);
 
-    container
+    SettingsView {
+        container,
+        auto_switch,
+        show_members,
+        confirm_merges,
+        auto_cluster,
+        balance_new_clusters,
+        rebuild_clusters,
+        dropout_grace,
+        bluetooth_offset,
+        accent,
+        appearance,
+        canvas_layout,
+        reduce_motion,
+        show_notifications,
+        notifications_off_clusters,
+        turn_url,
+        turn_username,
+        turn_credential,
+        role_targets,
+        role_targets_shown: Rc::new(RefCell::new(String::new())),
+    }
+}
+
+/// Ask for a layout file and hand its path to `done`
+fn choose_file(parent: Option<&gtk4::Window>, title: &str, action: gtk4::FileChooserAction, accept: &str, done: impl Fn(PathBuf) + 'static) {
+    let dialog = gtk4::FileChooserNative::new(Some(title), parent, action, Some(accept), Some("Cancel"));
+    if action == gtk4::FileChooserAction::Save {
+        dialog.set_current_name("auralis-layout.json");
+    }
+    // Native dialogs aren't kept alive by being shown; the handler holds on to it until answered
+    let keep = dialog.clone();
+    dialog.connect_response(move |dialog, response| {
+        if response == gtk4::ResponseType::Accept {
+            if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                done(path);
+            }
+        }
+        keep.destroy();
+    });
+    dialog.show();
+}
+
+/// Fill the media role rows: each role gets a dropdown of the outputs (devices and clusters).
+/// Rebuilt only when the outputs or the targets change, so unrelated events don't close an open dropdown.
+pub fn update_role_targets(rows: &gtk4::Box, shown: &RefCell<String>, state: &SharedState, cmd_tx: &Sender<UiCommand>) {
+    let state = state.borrow();
+
+    let mut outputs: Vec<(Uuid, String)> = state.orbs.values()
+        .filter(|o| o.membership().is_none() && !o.is_staged() && matches!(o.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. }))
+        .map(|o| (o.id, o.name.clone()))
+        .collect();
+    outputs.sort_by(|a, b| a.1.cmp(&b.1));
+
+    // 0 is "Default Output", also shown when the remembered output isn't around
+    let selected: Vec<u32> = media_roles::ROLES.iter()
+        .map(|(role, _)| {
+            state.settings.role_targets.get(*role)
+                .and_then(|key| media_roles::find_target(&state.orbs, key))
+                .and_then(|target| outputs.iter().position(|(id, _)| *id == target.id))
+                .map_or(0, |i| i as u32 + 1)
+        })
+        .collect();
+
+    let signature = format!("{:?} {:?}", outputs, selected);
+    if *shown.borrow() == signature {
+        return;
+    }
+    *shown.borrow_mut() = signature;
+
+    while let Some(child) = rows.first_child() {
+        rows.remove(&child);
+    }
+
+    let mut names = vec!["Default Output"];
+    names.extend(outputs.iter().map(|(_, name)| name.as_str()));
+    let ids: Vec<Uuid> = outputs.iter().map(|(id, _)| *id).collect();
+
+    for (&(role, label), selected) in media_roles::ROLES.iter().zip(selected) {
+        let row = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+        let lbl = gtk4::Label::new(Some(label));
+        let spacer = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
+        spacer.set_hexpand(true);
+
+        let dropdown = gtk4::DropDown::from_strings(&names);
+        dropdown.set_selected(selected);
+        let ids = ids.clone();
+        let cmd_tx = cmd_tx.clone();
+        dropdown.connect_selected_notify(move |dropdown| {
+            let sink = (dropdown.selected() as usize).checked_sub(1).and_then(|i| ids.get(i).copied());
+            let _ = cmd_tx.send(UiCommand::SetRoleTarget { role: role.to_string(), sink });
+        });
+
+        row.append(&lbl);
+        row.append(&spacer);
+        row.append(&dropdown);
+        rows.append(&row);
+    }
+}
+
+/// Appearance dropdown entries, in order
+const APPEARANCES: [Appearance; 3] = [Appearance::System, Appearance::Light, Appearance::Dark];
+
+pub fn appearance_index(appearance: Appearance) -> u32 {
+    APPEARANCES.iter().position(|&a| a == appearance).unwrap_or(0) as u32
+}
+
+/// Canvas layout dropdown entries, in order
+const CANVAS_LAYOUTS: [CanvasLayout; 4] = [CanvasLayout::Free, CanvasLayout::Grid, CanvasLayout::Ring, CanvasLayout::Physics];
+
+pub fn canvas_layout_index(layout: CanvasLayout) -> u32 {
+    CANVAS_LAYOUTS.iter().position(|&l| l == layout).unwrap_or(0) as u32
+}
+
+fn confirm_reset(parent: Option<&gtk4::Window>, cmd_tx: Sender<UiCommand>) {
+    let dialog = gtk4::Window::builder()
+        .title("Reset Audio Routing")
+        .modal(true)
+        .resizable(false)
+        .default_width(360)
+        .build();
+    dialog.set_transient_for(parent);
+
+    let content = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    content.set_margin_start(24);
+    content.set_margin_end(24);
+    content.set_margin_top(24);
+    content.set_margin_bottom(24);
+
+    let message = gtk4::Label::builder()
+        .label("All clusters will be separated and every device shown on its own again. Names and zones are kept.")
+        .wrap(true)
+        .halign(gtk4::Align::Start)
+        .build();
+    content.append(&message);
+
+    let button_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 8);
+    button_box.set_halign(gtk4::Align::End);
+    button_box.set_margin_top(12);
+
+    let cancel_btn = gtk4::Button::with_label("Cancel");
+    let reset_btn = gtk4::Button::with_label("Reset");
+    reset_btn.add_css_class("btn-destructive");
+
+    button_box.append(&cancel_btn);
+    button_box.append(&reset_btn);
+    content.append(&button_box);
+
+    dialog.set_child(Some(&content));
+
+    let dialog_cancel = dialog.clone();
+    cancel_btn.connect_clicked(move |_| dialog_cancel.close());
+
+    let dialog_reset = dialog.clone();
+    reset_btn.connect_clicked(move |_| {
+        let _ = cmd_tx.send(UiCommand::ResetAll);
+        dialog_reset.close();
+    });
+
+    dialog.present();
 }
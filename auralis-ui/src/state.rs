@@ -1,13 +1,76 @@
 use std::collections::HashMap;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Instant;
 use uuid::Uuid;
-use auralis_core::Orb;
+use auralis_core::{media_roles, AudioGraph, Orb, OrbKind, Settings, UiCommand};
+use crate::theme::AuralisTheme;
 
 #[derive(Clone)]
 pub struct AppState {
     pub orbs: HashMap<Uuid, Orb>,
     pub dragged_orb_id: Option<Uuid>,
+    pub backend_connected: bool,
+    /// The core has reported every device that existed at connect time
+    pub discovery_complete: bool,
+    pub settings: Settings,
+    /// Canvas colors, derived from `settings.accent_color`
+    pub theme: AuralisTheme,
+    /// App orb -> device/cluster orb it currently plays to
+    pub routes: Vec<(Uuid, Uuid)>,
+    /// Orb a device dragged from the list is hovering over, and whether dropping it
+    /// there would connect the two
+    pub drop_preview: Option<(Uuid, bool)>,
+    /// Something dragged is over the Beam zone, and whether dropping it there would beam it
+    pub beam_preview: Option<bool>,
+    /// Device shown in the Properties panel
+    pub selected: Option<Uuid>,
+    /// Volume (percent) of devices and clusters as the core last read it (`OrbEvent::Volume`)
+    pub volumes: HashMap<Uuid, u32>,
+    /// Node name of the server's default sink, as the core last reported it
+    pub default_sink: Option<String>,
+    /// Device -> device also playing its audio, see `UiCommand::Duplicate`
+    pub duplicates: Vec<(Uuid, Uuid)>,
+    /// How far the canvas is zoomed and panned; kept until Auralis quits
+    pub view: CanvasView,
+    /// Cluster that just appeared on the canvas and since when, for its flash
+    pub formed: Option<(Uuid, Instant)>,
+    /// Devices and clusters dropped onto each other while `settings.auto_cluster_on_drop` is
+    /// off, waiting for "Cluster These"
+    pub gathered: Vec<Uuid>,
+}
+
+/// Zoom and pan of the canvas. Orb positions are canvas coordinates, pointer events come in
+/// widget coordinates: widget = canvas * zoom + pan.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CanvasView {
+    pub zoom: f64,
+    pub pan: (f64, f64),
+}
+
+impl Default for CanvasView {
+    fn default() -> Self {
+        Self { zoom: 1.0, pan: (0.0, 0.0) }
+    }
+}
+
+impl CanvasView {
+    /// The canvas point drawn at widget point (x, y)
+    pub fn to_canvas(&self, x: f64, y: f64) -> (f64, f64) {
+        ((x - self.pan.0) / self.zoom, (y - self.pan.1) / self.zoom)
+    }
+
+    /// Where canvas point (x, y) is drawn on the widget
+    pub fn to_widget(&self, x: f64, y: f64) -> (f64, f64) {
+        (x * self.zoom + self.pan.0, y * self.zoom + self.pan.1)
+    }
+
+    /// Zoom to `zoom`, keeping the canvas point under widget point `anchor` where it is
+    pub fn zoom_at(&mut self, zoom: f64, anchor: (f64, f64)) {
+        let (cx, cy) = self.to_canvas(anchor.0, anchor.1);
+        self.zoom = zoom;
+        self.pan = (anchor.0 - cx * zoom, anchor.1 - cy * zoom);
+    }
 }
 
 impl AppState {
@@ -15,6 +78,114 @@ impl AppState {
         Self {
             orbs: HashMap::new(),
             dragged_orb_id: None,
+            backend_connected: true,
+            discovery_complete: false,
+            settings: Settings::default(),
+            theme: AuralisTheme::default(),
+            routes: Vec::new(),
+            drop_preview: None,
+            beam_preview: None,
+            selected: None,
+            default_sink: None,
+            duplicates: Vec::new(),
+            view: CanvasView::default(),
+            formed: None,
+            volumes: HashMap::new(),
+            gathered: Vec::new(),
+        }
+    }
+
+    /// Whether the device or cluster is the default output
+    pub fn is_default(&self, orb: &Orb) -> bool {
+        self.default_sink.as_deref() == Some(orb.node_name.as_str())
+    }
+
+    /// Whether the user suspended this device or cluster (`settings.suspended_outputs`)
+    pub fn is_suspended(&self, orb: &Orb) -> bool {
+        media_roles::target_key(&self.orbs, orb)
+            .is_some_and(|key| self.settings.suspended_outputs.contains(&key))
+    }
+
+    /// Put orbs that aren't on the canvas where an orb of the same name was last left
+    /// (`settings.canvas_positions`). Cluster members stay with their cluster.
+    pub fn restore_positions(&mut self) {
+        for orb in self.orbs.values_mut() {
+            if orb.position != (0.0, 0.0) || orb.membership().is_some() {
+                continue;
+            }
+            if let Some(&position) = self.settings.canvas_positions.get(&orb.name) {
+                orb.position = position;
+            }
+        }
+    }
+
+    /// Remember where an orb was left on the canvas, or that it was taken off it. Written to
+    /// the local settings right away so the orb isn't put back before the core confirms.
+    pub fn save_position(&mut self, id: Uuid) -> Option<UiCommand> {
+        let orb = self.orbs.get(&id)?;
+        let position = Some((orb.position.0.round(), orb.position.1.round()))
+            .filter(|_| orb.position != (0.0, 0.0));
+        match position {
+            Some(position) => self.settings.canvas_positions.insert(orb.name.clone(), position),
+            None => self.settings.canvas_positions.remove(&orb.name),
+        };
+        Some(UiCommand::SetCanvasPosition { name: orb.name.clone(), position })
+    }
+
+    /// Gather `source` and the orb it was dropped on, to be clustered together later
+    pub fn gather(&mut self, source: Uuid, target: Uuid) {
+        for id in [target, source] {
+            if !self.gathered.contains(&id) {
+                self.gathered.push(id);
+            }
+        }
+    }
+
+    /// The devices "Cluster These" would cluster: gathered devices and the members of
+    /// gathered clusters, each once
+    pub fn gathered_devices(&self) -> Vec<Uuid> {
+        let mut devices: Vec<Uuid> = Vec::new();
+        for orb in self.gathered.iter().filter_map(|id| self.orbs.get(id)) {
+            let ids = match &orb.kind {
+                OrbKind::Cluster { members, .. } => members.clone(),
+                OrbKind::PhysicalSink { .. } => vec![orb.id],
+                _ => Vec::new(),
+            };
+            for id in ids {
+                if !devices.contains(&id) {
+                    devices.push(id);
+                }
+            }
+        }
+        devices
+    }
+
+    /// Take the orbs and routes from the core's graph (`auralis_core::GraphView`), for when
+    /// the mirrored state may have gone out of step. Canvas positions are kept.
+    pub fn resync(&mut self, graph: &AudioGraph) {
+        let mut orbs = graph.orbs.clone();
+        for orb in orbs.values_mut() {
+            if let Some(existing) = self.orbs.get(&orb.id) {
+                orb.position = existing.position;
+                orb.velocity = existing.velocity;
+            }
+        }
+        self.orbs = orbs;
+        self.routes = graph.routes.clone();
+        self.restore_positions();
+    }
+
+    /// Message for an empty device list/canvas: still scanning, or really nothing there.
+    /// `None` once there is at least one output to show.
+    pub fn empty_message(&self) -> Option<&'static str> {
+        let has_outputs = self.orbs.values()
+            .any(|o| matches!(o.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. }));
+        if has_outputs {
+            None
+        } else if !self.discovery_complete {
+            Some("Scanning for devices…")
+        } else {
+            Some("No audio devices found — is PipeWire running?")
         }
     }
 }
@@ -0,0 +1,94 @@
+use auralis_core::Appearance;
+
+/// Colors the canvas draws with. The CSS has its own copy of the default primary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AuralisTheme {
+    /// Orb fill and the tint of clusters without a zone
+    pub primary: (f64, f64, f64),
+    /// Orb glow, a lighter shade of the primary
+    pub accent: (f64, f64, f64),
+    /// Whether the active color scheme is dark (see `StyleManager::is_dark`)
+    pub dark: bool,
+}
+
+impl Default for AuralisTheme {
+    fn default() -> Self {
+        Self {
+            primary: (0.17, 0.42, 0.93),
+            accent: (0.4, 0.6, 1.0),
+            dark: true,
+        }
+    }
+}
+
+impl AuralisTheme {
+    /// Theme for a user-picked accent (`#rrggbb`), the default if it's missing or invalid
+    pub fn from_accent(hex: Option<&str>) -> Self {
+        let Some(primary) = hex.and_then(parse_hex) else {
+            return Self::default();
+        };
+        // Glow is the accent mixed 40% towards white
+        let lighten = |c: f64| c + (1.0 - c) * 0.4;
+        Self {
+            primary,
+            accent: (lighten(primary.0), lighten(primary.1), lighten(primary.2)),
+            ..Self::default()
+        }
+    }
+
+    /// Color for text and outlines drawn straight onto the canvas background
+    pub fn ink(&self) -> (f64, f64, f64) {
+        if self.dark {
+            (1.0, 1.0, 1.0)
+        } else {
+            (0.06, 0.09, 0.16) // slate-900
+        }
+    }
+}
+
+pub fn color_scheme(appearance: Appearance) -> libadwaita::ColorScheme {
+    match appearance {
+        Appearance::System => libadwaita::ColorScheme::Default,
+        Appearance::Light => libadwaita::ColorScheme::ForceLight,
+        Appearance::Dark => libadwaita::ColorScheme::ForceDark,
+    }
+}
+
+thread_local! {
+    static LIGHT_CSS: gtk4::CssProvider = {
+        let provider = gtk4::CssProvider::new();
+        provider.load_from_data(include_str!("style-light.css"));
+        provider
+    };
+}
+
+/// style.css is written for dark backgrounds; layer the light overrides on top when needed.
+/// Call on every scheme change, not repeatedly for the same one.
+pub fn set_light_css(light: bool) {
+    let Some(display) = gtk4::gdk::Display::default() else {
+        return;
+    };
+    LIGHT_CSS.with(|provider| {
+        if light {
+            // Above style.css, below the user's own overrides
+            gtk4::style_context_add_provider_for_display(&display, provider, gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION + 1);
+        } else {
+            gtk4::style_context_remove_provider_for_display(&display, provider);
+        }
+    });
+}
+
+fn parse_hex(hex: &str) -> Option<(f64, f64, f64)> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok().map(|v| v as f64 / 255.0);
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// `#rrggbb` for a color from a GTK color picker
+pub fn to_hex(rgba: &gtk4::gdk::RGBA) -> String {
+    let byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", byte(rgba.red()), byte(rgba.green()), byte(rgba.blue()))
+}
@@ -1,17 +1,32 @@
 use gtk4::prelude::*;
 use libadwaita::Application;
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
 use std::rc::Rc;
 use std::cell::RefCell;
-use auralis_core::{UiCommand, OrbEvent};
-
-pub fn build(app: &Application, rx: async_channel::Receiver<OrbEvent>, cmd_tx: Sender<UiCommand>) {
-    // Force Dark Mode
+use auralis_core::event_queue::QueueStats;
+use auralis_core::{Appearance, GraphView, OrbKind, UiCommand, OrbEvent};
+use uuid::Uuid;
+
+/// How much one key press changes the selected output's volume, in percent
+const VOLUME_STEP: i32 = 5;
+
+pub fn build(
+    app: &Application,
+    rx: async_channel::Receiver<OrbEvent>,
+    cmd_tx: Sender<UiCommand>,
+    graph_view: GraphView,
+    bridge_stats: Arc<QueueStats>,
+    profiles: Vec<String>,
+) {
+    // Dark until the core sends the persisted appearance
     let style_manager = libadwaita::StyleManager::default();
-    style_manager.set_color_scheme(libadwaita::ColorScheme::ForceDark);
+    style_manager.set_color_scheme(theme::color_scheme(Appearance::default()));
 
     // Shared State
     let state = Rc::new(RefCell::new(state::AppState::new()));
+    state.borrow_mut().theme.dark = style_manager.is_dark();
+    theme::set_light_css(!style_manager.is_dark());
 
     // Main Content Box (Horizontal Split: Sidebar | Content)
     let main_box = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
@@ -66,10 +81,14 @@ pub fn build(app: &Application, rx: async_channel::Receiver<OrbEvent>, cmd_tx: S
 
     let row_dev = create_nav_row("computer-symbolic", "Devices", "devices");
     let row_clus = create_nav_row("view-grid-symbolic", "Clusters", "clusters");
+    let row_act = create_nav_row("media-playback-start-symbolic", "Activity", "activity");
+    let row_beam = create_nav_row("network-wireless-symbolic", "Beam", "beam");
     let row_set = create_nav_row("emblem-system-symbolic", "Settings", "settings");
     
     nav_list.append(&row_dev);
     nav_list.append(&row_clus);
+    nav_list.append(&row_act);
+    nav_list.append(&row_beam);
     nav_list.append(&row_set);
     
     sidebar.append(&nav_list);
@@ -85,23 +104,7 @@ pub fn build(app: &Application, rx: async_channel::Receiver<OrbEvent>, cmd_tx: S
     
     let props_label = gtk4::Label::builder().label("Properties").halign(gtk4::Align::Start).css_classes(vec!["heading"]).build();
     
-    let props_card = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
-    props_card.add_css_class("properties-card");
-    props_card.set_height_request(120);
-    
-    let props_icon = gtk4::Image::from_icon_name("touch-symbolic");
-    props_icon.set_pixel_size(32);
-    props_icon.set_opacity(0.3);
-    
-    let props_hint = gtk4::Label::new(Some("Select a device or cluster\nto see its properties."));
-    props_hint.set_justify(gtk4::Justification::Center);
-    props_hint.add_css_class("caption");
-    props_hint.set_opacity(0.5);
-
-    props_card.append(&props_icon);
-    props_card.append(&props_hint);
-    props_card.set_valign(gtk4::Align::Center);
-    props_card.set_halign(gtk4::Align::Center);
+    let props_card = properties_view::build(&state);
 
     props_box.append(&props_label);
     props_box.append(&props_card);
@@ -133,6 +136,31 @@ pub fn build(app: &Application, rx: async_channel::Receiver<OrbEvent>, cmd_tx: S
     let spacer_header = gtk4::Box::new(gtk4::Orientation::Horizontal, 0);
     spacer_header.set_hexpand(true);
 
+    // Spins while the core is still working through commands (slow pactl calls)
+    let busy_spinner = gtk4::Spinner::new();
+    busy_spinner.set_visible(false);
+
+    // Pause: clusters stay, but the system default output is handed back (troubleshooting)
+    let pause_toggle = gtk4::ToggleButton::new();
+    pause_toggle.set_icon_name("media-playback-pause-symbolic");
+    pause_toggle.add_css_class("btn-icon");
+    pause_toggle.set_tooltip_text(Some("Pause Auralis routing"));
+    let cmd_tx_pause = cmd_tx.clone();
+    pause_toggle.connect_toggled(move |toggle| {
+        let cmd = if toggle.is_active() { UiCommand::SuspendRouting } else { UiCommand::ResumeRouting };
+        let _ = cmd_tx_pause.send(cmd);
+    });
+
+    // Profiles (from profiles.toml)
+    let profiles_btn = gtk4::MenuButton::new();
+    profiles_btn.set_label("Profiles");
+
+    let profiles_menu = gtk4::Box::new(gtk4::Orientation::Vertical, 4);
+    fill_profiles(&profiles_btn, &profiles_menu, profiles, &cmd_tx);
+    let profiles_popover = gtk4::Popover::new();
+    profiles_popover.set_child(Some(&profiles_menu));
+    profiles_btn.set_popover(Some(&profiles_popover));
+
     let create_cluster_btn = gtk4::Button::with_label("Create New Cluster");
     create_cluster_btn.add_css_class("btn-primary");
     create_cluster_btn.set_icon_name("list-add-symbolic");
@@ -143,6 +171,9 @@ pub fn build(app: &Application, rx: async_channel::Receiver<OrbEvent>, cmd_tx: S
     header_box.append(&refresh_btn);
     header_box.append(&play_btn);
     header_box.append(&spacer_header);
+    header_box.append(&busy_spinner);
+    header_box.append(&pause_toggle);
+    header_box.append(&profiles_btn);
     header_box.append(&create_cluster_btn);
     header_box.append(&window_controls);
 
@@ -153,18 +184,32 @@ pub fn build(app: &Application, rx: async_channel::Receiver<OrbEvent>, cmd_tx: S
     devices_page.append(&header_handle);
 
     // Available Devices List
-    let device_list_widget = device_list::build(state.clone(), cmd_tx.clone());
+    let device_list = device_list::build(state.clone(), cmd_tx.clone());
     
+    // Selecting a device shows it in the Properties panel
+    let (props_card_list, state_list, cmd_tx_list) = (props_card.downgrade(), state.clone(), cmd_tx.clone());
+    device_list.list_box.connect_row_selected(move |_, row| {
+        let device = row.and_then(|row| Uuid::parse_str(&row.widget_name()).ok());
+        if let (Some(device), Some(card)) = (device, props_card_list.upgrade()) {
+            properties_view::select(&card, &state_list, &cmd_tx_list, device);
+        }
+    });
+    let (props_card_flow, state_flow, cmd_tx_flow) = (props_card.downgrade(), state.clone(), cmd_tx.clone());
+    device_list.flow_box.connect_selected_children_changed(move |flow_box| {
+        let device = flow_box.selected_children().first().and_then(|child| Uuid::parse_str(&child.widget_name()).ok());
+        if let (Some(device), Some(card)) = (device, props_card_flow.upgrade()) {
+            properties_view::select(&card, &state_flow, &cmd_tx_flow, device);
+        }
+    });
+
     // Callback for Canvas to update Device List
-    let device_list_weak = device_list_widget.downgrade();
+    let device_list_weak = device_list.list_box.downgrade();
+    let device_flow_weak = device_list.flow_box.downgrade();
     let state_cb = state.clone();
+    let cmd_tx_cb = cmd_tx.clone();
     let on_drop = move || {
-        if let Some(w) = device_list_weak.upgrade() {
-            if let Some(box_widget) = w.downcast_ref::<gtk4::Box>() {
-                if let Some(list_box) = box_widget.last_child().and_then(|w| w.downcast::<gtk4::ListBox>().ok()) {
-                    device_list::update_list(&list_box, &state_cb);
-                }
-            }
+        if let (Some(list_box), Some(flow_box)) = (device_list_weak.upgrade(), device_flow_weak.upgrade()) {
+            device_list::update_list(&list_box, &flow_box, &state_cb, &cmd_tx_cb);
         }
     };
 
@@ -176,69 +221,386 @@ pub fn build(app: &Application, rx: async_channel::Receiver<OrbEvent>, cmd_tx: S
     
     let canvas_widget = canvas::build(state.clone(), cmd_tx.clone(), on_drop);
     canvas_widget.add_css_class("clustering-zone");
-    zone_box.append(&canvas_widget);
+    let canvas_overlay = gtk4::Overlay::new();
+    canvas_overlay.set_child(Some(&canvas_widget));
+    canvas_overlay.add_overlay(&canvas::build_gathered_bar(state.clone(), cmd_tx.clone(), &canvas_widget));
+    zone_box.append(&canvas_overlay);
     
     devices_page.append(&zone_box);
-    devices_page.append(&device_list_widget);
+    devices_page.append(&device_list.container);
     
-    stack.add_named(&devices_page, Some("devices"));
+    // First-run tour on top of the devices page, shown while `settings.first_run` is set
+    let devices_overlay = gtk4::Overlay::new();
+    devices_overlay.set_child(Some(&devices_page));
+    let tour = build_tour(cmd_tx.clone());
+    tour.set_visible(false);
+    devices_overlay.add_overlay(&tour);
 
-use crate::{canvas, device_list, clusters_view, settings_view, state};
+    stack.add_named(&devices_overlay, Some("devices"));
+
+use crate::{activity_view, beam_view, canvas, device_list, clusters_view, properties_view, settings_view, state};
+use crate::theme::{self, AuralisTheme};
 
 // ... (inside build function)
 
     // PAGE 2: CLUSTERS
-    let clusters_page = clusters_view::build(state.clone(), cmd_tx.clone());
-    stack.add_named(&clusters_page, Some("clusters"));
-
-    // PAGE 3: SETTINGS
-    let settings_page = settings_view::build();
-    stack.add_named(&settings_page, Some("settings"));
-
-    main_box.append(&stack);
+    let clusters_view = clusters_view::build(state.clone(), cmd_tx.clone());
+    stack.add_named(&clusters_view.container, Some("clusters"));
+
+    // PAGE 3: ACTIVITY (what's playing where)
+    let activity_view = activity_view::build(state.clone());
+    stack.add_named(&activity_view.container, Some("activity"));
+
+    // PAGE 4: BEAM (remote sessions)
+    let beam_view = beam_view::build(state.clone(), cmd_tx.clone());
+    stack.add_named(&beam_view.container, Some("beam"));
+
+    // PAGE 5: SETTINGS
+    let settings_view = settings_view::build(state.clone(), cmd_tx.clone());
+    stack.add_named(&settings_view.container, Some("settings"));
+
+    // Connection banner above the pages, shown while the core reconnects to PipeWire
+    let content_box = gtk4::Box::new(gtk4::Orientation::Vertical, 0);
+    content_box.set_hexpand(true);
+
+    let banner_label = gtk4::Label::new(Some("Lost connection to PipeWire. Reconnecting…"));
+    banner_label.add_css_class("backend-banner");
+    let banner = gtk4::Revealer::new();
+    banner.set_transition_type(gtk4::RevealerTransitionType::SlideDown);
+    banner.set_child(Some(&banner_label));
+    banner.set_reveal_child(false);
+
+    content_box.append(&banner);
+    content_box.append(&stack);
+    main_box.append(&content_box);
+
+    // Errors from the core (e.g. a profile with missing devices) show up as toasts
+    let toast_overlay = libadwaita::ToastOverlay::new();
+    toast_overlay.set_child(Some(&main_box));
     
-    // ... (navigation connection remains same)
+    // Rows are named after their page
+    let stack_nav = stack.clone();
+    nav_list.connect_row_selected(move |_, row| {
+        if let Some(row) = row {
+            stack_nav.set_visible_child_name(&row.widget_name());
+        }
+    });
+    nav_list.select_row(Some(&row_dev));
+
+    let shortcuts = volume_shortcuts(&state, &cmd_tx);
 
     // --- EVENT LOOP ---
     let state_evt = state.clone();
-    let device_list_weak = device_list_widget.downgrade(); 
-    let clusters_view_weak = clusters_page.downgrade(); // To update clusters
+    let device_list_weak = device_list.list_box.downgrade();
+    let device_flow_weak = device_list.flow_box.downgrade();
+    let compact_toggle_weak = device_list.compact_toggle.downgrade();
+    let clusters_view_weak = clusters_view.groups.downgrade(); // To update clusters
+    let activity_view_weak = activity_view.outputs.downgrade();
+    let beam_view_weak = beam_view.sessions.downgrade();
     let canvas_weak = canvas_widget.downgrade(); 
+    let banner_weak = banner.downgrade();
+    let busy_spinner_weak = busy_spinner.downgrade();
+    let pause_toggle_weak = pause_toggle.downgrade();
+    let tour_weak = tour.downgrade();
+    let nav_list_weak = nav_list.downgrade();
+    let row_dev_weak = row_dev.downgrade();
+    let toast_weak = toast_overlay.downgrade();
+    let profiles_btn_weak = profiles_btn.downgrade();
+    let profiles_menu_weak = profiles_menu.downgrade();
+    let cmd_tx_profiles = cmd_tx.clone();
+    let props_card_weak = props_card.downgrade();
+    let auto_switch_weak = settings_view.auto_switch.downgrade();
+    let show_members_weak = settings_view.show_members.downgrade();
+    let confirm_merges_weak = settings_view.confirm_merges.downgrade();
+    let auto_cluster_weak = settings_view.auto_cluster.downgrade();
+    let balance_new_clusters_weak = settings_view.balance_new_clusters.downgrade();
+    let rebuild_clusters_weak = settings_view.rebuild_clusters.downgrade();
+    let notifications_off_clusters_weak = settings_view.notifications_off_clusters.downgrade();
+    let dropout_grace_weak = settings_view.dropout_grace.downgrade();
+    let bluetooth_offset_weak = settings_view.bluetooth_offset.downgrade();
+    let accent_weak = settings_view.accent.downgrade();
+    let appearance_weak = settings_view.appearance.downgrade();
+    let canvas_layout_weak = settings_view.canvas_layout.downgrade();
+    let reduce_motion_weak = settings_view.reduce_motion.downgrade();
+    let show_notifications_weak = settings_view.show_notifications.downgrade();
+    let turn_url_weak = settings_view.turn_url.downgrade();
+    let role_targets_weak = settings_view.role_targets.downgrade();
+    let role_targets_shown = settings_view.role_targets_shown.clone();
+    let turn_username_weak = settings_view.turn_username.downgrade();
+    let turn_credential_weak = settings_view.turn_credential.downgrade();
+
+    // Scheme changes (setting or desktop preference) restyle both the CSS and the Cairo drawing
+    let state_scheme = state.clone();
+    let canvas_scheme = canvas_widget.downgrade();
+    style_manager.connect_dark_notify(move |style_manager| {
+        let dark = style_manager.is_dark();
+        state_scheme.borrow_mut().theme.dark = dark;
+        theme::set_light_css(!dark);
+        if let Some(canvas) = canvas_scheme.upgrade() {
+            canvas.queue_draw();
+        }
+    });
+    let mut check_toast: Option<libadwaita::Toast> = None; // Channel check progress
 
     glib::MainContext::default().spawn_local(async move {
         while let Ok(event) = rx.recv().await {
             let mut state = state_evt.borrow_mut();
+            let mut scheme = None;
             match event {
-                OrbEvent::Add(orb) => {
+                OrbEvent::Add(mut orb) => {
+                    // Already there if an error resync picked it up before this event
+                    if let Some(existing) = state.orbs.get(&orb.id) {
+                        orb.position = existing.position;
+                        orb.velocity = existing.velocity;
+                    }
+                    canvas::cluster_formed(&mut state, &orb);
                     state.orbs.insert(orb.id, orb);
+                    state.restore_positions();
+                    canvas::place_beams(&mut state);
                 }
                 OrbEvent::Remove(id) => {
                     state.orbs.remove(&id);
+                    state.gathered.retain(|gathered| *gathered != id);
+                }
+                OrbEvent::Update(mut orb) => {
+                    // Keep where the user placed it on the canvas
+                    if let Some(existing) = state.orbs.get(&orb.id) {
+                        orb.position = existing.position;
+                        orb.velocity = existing.velocity;
+                    }
+                    state.orbs.insert(orb.id, orb);
+                }
+                OrbEvent::Clear => {
+                    state.orbs.clear();
+                }
+                OrbEvent::BackendState { connected } => {
+                    state.backend_connected = connected;
+                    // A new connection starts a new scan
+                    state.discovery_complete = false;
+                    if let Some(banner) = banner_weak.upgrade() {
+                        banner.set_reveal_child(!connected);
+                    }
+                }
+                OrbEvent::DiscoveryComplete => {
+                    state.discovery_complete = true;
+                }
+                OrbEvent::Settings(settings) => {
+                    if let Some(switch) = auto_switch_weak.upgrade() {
+                        switch.set_active(settings.auto_switch_new_devices);
+                    }
+                    if let Some(switch) = show_members_weak.upgrade() {
+                        switch.set_active(settings.show_cluster_members);
+                    }
+                    if let Some(switch) = confirm_merges_weak.upgrade() {
+                        switch.set_active(settings.confirm_merges);
+                    }
+                    if let Some(switch) = auto_cluster_weak.upgrade() {
+                        switch.set_active(settings.auto_cluster_on_drop);
+                    }
+                    // Drops cluster right away again; what was gathered is let go
+                    if settings.auto_cluster_on_drop {
+                        state.gathered.clear();
+                    }
+                    if let Some(switch) = balance_new_clusters_weak.upgrade() {
+                        switch.set_active(settings.balance_new_clusters);
+                    }
+                    if let Some(switch) = rebuild_clusters_weak.upgrade() {
+                        switch.set_active(settings.rebuild_clusters);
+                    }
+                    if let Some(switch) = notifications_off_clusters_weak.upgrade() {
+                        switch.set_active(settings.notifications_off_clusters);
+                    }
+                    if let Some(toggle) = compact_toggle_weak.upgrade() {
+                        toggle.set_active(settings.compact_device_list);
+                    }
+                    if let Some(toggle) = pause_toggle_weak.upgrade() {
+                        toggle.set_active(settings.routing_suspended);
+                        toggle.set_tooltip_text(Some(if settings.routing_suspended {
+                            "Auralis routing is paused. Click to resume."
+                        } else {
+                            "Pause Auralis routing"
+                        }));
+                    }
+                    if let Some(spin) = dropout_grace_weak.upgrade() {
+                        spin.set_value(f64::from(settings.dropout_grace_ms) / 1000.0);
+                    }
+                    if let Some(spin) = bluetooth_offset_weak.upgrade() {
+                        spin.set_value(f64::from(settings.bluetooth_offset_ms));
+                    }
+                    // Recolors the canvas on the redraw below
+                    state.theme = AuralisTheme {
+                        dark: state.theme.dark,
+                        ..AuralisTheme::from_accent(settings.accent_color.as_deref())
+                    };
+                    if let Some(button) = accent_weak.upgrade() {
+                        let (r, g, b) = state.theme.primary;
+                        button.set_rgba(&gtk4::gdk::RGBA::new(r as f32, g as f32, b as f32, 1.0));
+                    }
+                    if let Some(dropdown) = appearance_weak.upgrade() {
+                        dropdown.set_selected(settings_view::appearance_index(settings.appearance));
+                    }
+                    if let Some(dropdown) = canvas_layout_weak.upgrade() {
+                        dropdown.set_selected(settings_view::canvas_layout_index(settings.canvas_layout));
+                    }
+                    if let Some(switch) = reduce_motion_weak.upgrade() {
+                        switch.set_active(settings.reduce_motion);
+                    }
+                    if let Some(switch) = show_notifications_weak.upgrade() {
+                        switch.set_active(settings.show_notifications);
+                    }
+                    let turn = settings.beam_turn.clone().unwrap_or_default();
+                    if let Some(entry) = turn_url_weak.upgrade() {
+                        entry.set_text(&turn.url);
+                    }
+                    if let Some(entry) = turn_username_weak.upgrade() {
+                        entry.set_text(&turn.username);
+                    }
+                    if let Some(entry) = turn_credential_weak.upgrade() {
+                        entry.set_text(&turn.credential);
+                    }
+                    if let Some(tour) = tour_weak.upgrade() {
+                        tour.set_visible(settings.first_run);
+                    }
+                    // "Show tour again" is clicked on the settings page; take the user to the tour
+                    if settings.first_run && !state.settings.first_run {
+                        if let (Some(nav), Some(row)) = (nav_list_weak.upgrade(), row_dev_weak.upgrade()) {
+                            nav.select_row(Some(&row));
+                        }
+                    }
+                    scheme = Some(theme::color_scheme(settings.appearance));
+                    state.settings = settings;
+                    state.restore_positions();
+                }
+                OrbEvent::Error { message } => {
+                    // A failed command may have left the mirrored orbs out of step with the core
+                    state.resync(&graph_view.read());
+                    if let Some(overlay) = toast_weak.upgrade() {
+                        overlay.add_toast(libadwaita::Toast::new(&message));
+                    }
+                }
+                OrbEvent::ClusterScript { script } => {
+                    if let Some(display) = gtk4::gdk::Display::default() {
+                        display.clipboard().set_text(&script);
+                    }
+                    if let Some(overlay) = toast_weak.upgrade() {
+                        overlay.add_toast(libadwaita::Toast::new("Shell commands copied to clipboard"));
+                    }
+                }
+                OrbEvent::GraphExport { contents } => {
+                    if let Some(display) = gtk4::gdk::Display::default() {
+                        display.clipboard().set_text(&contents);
+                    }
+                    // The core's state can't tell how far behind the UI fell; the bridge can
+                    if let Some(overlay) = toast_weak.upgrade() {
+                        let message = match (bridge_stats.coalesced(), bridge_stats.dropped()) {
+                            (0, 0) => "Debug state copied to clipboard".to_string(),
+                            (coalesced, dropped) => format!(
+                                "Debug state copied to clipboard · UI events: {} coalesced, {} dropped", coalesced, dropped),
+                        };
+                        overlay.add_toast(libadwaita::Toast::new(&message));
+                    }
+                }
+                OrbEvent::LayoutExported { path } => {
+                    if let Some(overlay) = toast_weak.upgrade() {
+                        overlay.add_toast(libadwaita::Toast::new(&format!("Layout exported to {}", path.display())));
+                    }
+                }
+                OrbEvent::LayoutImported { clusters, profiles } => {
+                    if let Some(overlay) = toast_weak.upgrade() {
+                        let message = match profiles {
+                            0 => format!("Imported {} cluster setup(s)", clusters),
+                            _ => format!("Imported {} cluster setup(s) and {} profile(s)", clusters, profiles),
+                        };
+                        overlay.add_toast(libadwaita::Toast::new(&message));
+                    }
+                }
+                OrbEvent::Profiles { names } => {
+                    if let (Some(button), Some(menu)) = (profiles_btn_weak.upgrade(), profiles_menu_weak.upgrade()) {
+                        fill_profiles(&button, &menu, names, &cmd_tx_profiles);
+                    }
+                }
+                OrbEvent::Routes { routes } => {
+                    state.routes = routes;
+                }
+                OrbEvent::DefaultSink { sink_name } => {
+                    state.default_sink = sink_name;
+                }
+                OrbEvent::Duplicates { pairs } => {
+                    state.duplicates = pairs;
+                }
+                OrbEvent::Volume { device, percent } => {
+                    state.volumes.insert(device, percent);
+                }
+                OrbEvent::OrphansCleaned { count } => {
+                    if let Some(overlay) = toast_weak.upgrade() {
+                        let message = match count {
+                            0 => "No orphaned sinks found".to_string(),
+                            _ => format!("Removed {} orphaned Auralis module(s)", count),
+                        };
+                        overlay.add_toast(libadwaita::Toast::new(&message));
+                    }
+                }
+                OrbEvent::CommandStatus { in_flight, queued } => {
+                    if let Some(spinner) = busy_spinner_weak.upgrade() {
+                        let pending = in_flight + queued;
+                        spinner.set_visible(pending > 0);
+                        spinner.set_spinning(pending > 0);
+                        let tooltip = match queued {
+                            0 => format!("Working on {} action(s)…", in_flight),
+                            _ => format!("Working on {} action(s), {} more waiting…", in_flight, queued),
+                        };
+                        spinner.set_tooltip_text(Some(&tooltip));
+                    }
+                }
+                OrbEvent::ChannelCheck { playing, .. } => {
+                    if let Some(toast) = check_toast.take() {
+                        toast.dismiss();
+                    }
+                    if let (Some(playing), Some(overlay)) = (playing, toast_weak.upgrade()) {
+                        let toast = libadwaita::Toast::builder()
+                            .title(format!("Playing: {}…", playing))
+                            .priority(libadwaita::ToastPriority::High) // Replace the previous step right away
+                            .timeout(0)
+                            .build();
+                        overlay.add_toast(toast.clone());
+                        check_toast = Some(toast);
+                    }
                 }
             }
             drop(state); // Release lock
 
+            // Can fire connect_dark_notify, which borrows the state itself
+            if let Some(scheme) = scheme {
+                libadwaita::StyleManager::default().set_color_scheme(scheme);
+            }
+
             // Update Device List
-            if let Some(w) = device_list_weak.upgrade() {
-                // The device_list::build returns a Box containing the ListBox
-                // Structure: Box -> [Label, ListBox]
-                if let Some(box_widget) = w.downcast_ref::<gtk4::Box>() {
-                    if let Some(list_box) = box_widget.last_child().and_then(|w| w.downcast::<gtk4::ListBox>().ok()) {
-                        device_list::update_list(&list_box, &state_evt);
-                    }
-                }
+            if let (Some(list_box), Some(flow_box)) = (device_list_weak.upgrade(), device_flow_weak.upgrade()) {
+                device_list::update_list(&list_box, &flow_box, &state_evt, &cmd_tx);
+            }
+
+            if let Some(card) = props_card_weak.upgrade() {
+                properties_view::update(&card, &state_evt);
             }
 
             // Update Clusters View
-            if let Some(w) = clusters_view_weak.upgrade() {
-                if let Some(box_widget) = w.downcast_ref::<gtk4::Box>() {
-                    // We need to find the FlowBox inside the container
-                    // The structure is Box -> [Label, FlowBox]
-                    // So we get the last child
-                    if let Some(flow_box) = box_widget.last_child().and_then(|w| w.downcast::<gtk4::FlowBox>().ok()) {
-                        clusters_view::update_list(&flow_box, &state_evt, &cmd_tx);
-                    }
-                }
+            if let Some(groups) = clusters_view_weak.upgrade() {
+                clusters_view::update_list(&groups, &state_evt, &cmd_tx);
+            }
+            
+            // Update Activity View
+            if let Some(outputs) = activity_view_weak.upgrade() {
+                activity_view::update_list(&outputs, &state_evt);
+            }
+
+            // Update Beam View
+            if let Some(sessions) = beam_view_weak.upgrade() {
+                beam_view::update_list(&sessions, &state_evt, &cmd_tx);
+            }
+
+            // Media role dropdowns follow the outputs
+            if let Some(rows) = role_targets_weak.upgrade() {
+                settings_view::update_role_targets(&rows, &role_targets_shown, &state_evt, &cmd_tx);
             }
             
             // Update Canvas
@@ -251,10 +613,170 @@ use crate::{canvas, device_list, clusters_view, settings_view, state};
     let window = libadwaita::ApplicationWindow::builder()
         .application(app)
         .title("PipeWire Cluster Manager")
-        .content(&main_box)
+        .content(&toast_overlay)
         .default_width(1280)
         .default_height(800)
         .build();
+    window.add_controller(shortcuts);
 
     window.present();
 }
+
+/// Arrow keys and +/- change the selected output's volume by `VOLUME_STEP`, M mutes it.
+/// Only keys nothing else took get here, and none while a text field has the focus.
+/// One item per profile in the header's Profiles menu, hidden when there are none
+fn fill_profiles(button: &gtk4::MenuButton, menu: &gtk4::Box, names: Vec<String>, cmd_tx: &Sender<UiCommand>) {
+    while let Some(child) = menu.first_child() {
+        menu.remove(&child);
+    }
+    button.set_visible(!names.is_empty());
+    for name in names {
+        let item = gtk4::Button::with_label(&name);
+        item.add_css_class("flat");
+        let cmd_tx_profile = cmd_tx.clone();
+        let button_item = button.clone();
+        item.connect_clicked(move |_| {
+            let _ = cmd_tx_profile.send(UiCommand::ApplyProfile { name: name.clone() });
+            button_item.popdown();
+        });
+        menu.append(&item);
+    }
+}
+
+fn volume_shortcuts(state: &state::SharedState, cmd_tx: &Sender<UiCommand>) -> gtk4::ShortcutController {
+    let controller = gtk4::ShortcutController::new();
+    let keys = [
+        ("Up|Right|plus|equal|KP_Add", Some(VOLUME_STEP)),
+        ("Down|Left|minus|KP_Subtract", Some(-VOLUME_STEP)),
+        ("m|M", None),
+    ];
+    for (trigger, step) in keys {
+        let (state, cmd_tx) = (state.clone(), cmd_tx.clone());
+        let action = gtk4::CallbackAction::new(move |widget, _| {
+            let typing = widget.root().and_then(|root| root.focus()).is_some_and(|focus| focus.is::<gtk4::Editable>());
+            let mut state = state.borrow_mut();
+            let selected = state.selected
+                .and_then(|id| state.orbs.get(&id))
+                .filter(|orb| matches!(orb.kind, OrbKind::PhysicalSink { .. } | OrbKind::Cluster { .. }) && !orb.is_staged())
+                .map(|orb| orb.id);
+            let (Some(device), false) = (selected, typing) else {
+                return glib::Propagation::Proceed;
+            };
+            let command = match (step, state.volumes.get(&device).copied()) {
+                (None, _) => UiCommand::ToggleMute { device },
+                (Some(step), Some(current)) => {
+                    // Never past 100% from the keyboard, nor down to it from louder
+                    let percent = (current as i32 + step).clamp(0, current.max(100) as i32) as u32;
+                    state.volumes.insert(device, percent);
+                    UiCommand::SetVolume { device, percent }
+                }
+                (Some(_), None) => UiCommand::ReadVolume { device },
+            };
+            let _ = cmd_tx.send(command);
+            glib::Propagation::Stop
+        });
+        controller.add_shortcut(gtk4::Shortcut::new(gtk4::ShortcutTrigger::parse_string(trigger), Some(action)));
+    }
+    controller
+}
+
+/// Where a tour callout's subject is, relative to the callout
+#[derive(Clone, Copy)]
+enum Pointing {
+    Up,
+    Down,
+    /// The callout sits on top of its subject
+    Here,
+}
+
+/// A callout of the tour: short title and text, with an arrow pointing at what it explains
+fn tour_callout(pointing: Pointing, title: &str, text: &str) -> gtk4::Box {
+    let callout = gtk4::Box::new(gtk4::Orientation::Vertical, 4);
+
+    let bubble = gtk4::Box::new(gtk4::Orientation::Vertical, 4);
+    bubble.add_css_class("tour-callout");
+    let title_lbl = gtk4::Label::builder().label(title).halign(gtk4::Align::Start).css_classes(vec!["device-name"]).build();
+    let text_lbl = gtk4::Label::builder()
+        .label(text)
+        .halign(gtk4::Align::Start)
+        .wrap(true)
+        .max_width_chars(36)
+        .css_classes(vec!["caption"])
+        .build();
+    bubble.append(&title_lbl);
+    bubble.append(&text_lbl);
+
+    let arrow = |glyph: &str| {
+        let arrow_lbl = gtk4::Label::new(Some(glyph));
+        arrow_lbl.add_css_class("tour-arrow");
+        arrow_lbl
+    };
+    match pointing {
+        Pointing::Up => {
+            callout.append(&arrow("↑"));
+            callout.append(&bubble);
+        }
+        Pointing::Down => {
+            callout.append(&bubble);
+            callout.append(&arrow("↓"));
+        }
+        Pointing::Here => callout.append(&bubble),
+    }
+    callout
+}
+
+/// One-time tour over the devices page. The callouts follow the page layout: header
+/// (create button) on top, clustering zone in the middle, device list at the bottom.
+fn build_tour(cmd_tx: Sender<UiCommand>) -> gtk4::Box {
+    let tour = gtk4::Box::new(gtk4::Orientation::Vertical, 0);
+    tour.add_css_class("tour-backdrop");
+    tour.set_hexpand(true);
+    tour.set_vexpand(true);
+
+    let header_callout = tour_callout(
+        Pointing::Up,
+        "Clusters and profiles",
+        "Create New Cluster and your saved profiles live up here.",
+    );
+    header_callout.set_halign(gtk4::Align::End);
+    header_callout.set_margin_top(48);
+    header_callout.set_margin_end(120);
+    tour.append(&header_callout);
+
+    let zone_callout = tour_callout(
+        Pointing::Here,
+        "Drop devices on each other",
+        "Drag a device into the clustering zone, then drag another one on top of it. \
+         They become a cluster and play the same audio together.",
+    );
+    zone_callout.set_halign(gtk4::Align::Center);
+    zone_callout.set_valign(gtk4::Align::Center);
+    zone_callout.set_vexpand(true);
+
+    let dismiss_btn = gtk4::Button::with_label("Got it");
+    dismiss_btn.add_css_class("btn-primary");
+    dismiss_btn.set_halign(gtk4::Align::End);
+    dismiss_btn.set_margin_top(8);
+    let tour_dismiss = tour.downgrade();
+    dismiss_btn.connect_clicked(move |_| {
+        // Hide right away, the core's echo of the setting would do the same a moment later
+        if let Some(tour) = tour_dismiss.upgrade() {
+            tour.set_visible(false);
+        }
+        let _ = cmd_tx.send(UiCommand::SetFirstRun { first_run: false });
+    });
+    zone_callout.append(&dismiss_btn);
+    tour.append(&zone_callout);
+
+    let list_callout = tour_callout(
+        Pointing::Down,
+        "Your devices",
+        "Every output Auralis found is listed here. Drag one up to start.",
+    );
+    list_callout.set_halign(gtk4::Align::Start);
+    list_callout.set_margin_start(48);
+    list_callout.set_margin_bottom(24);
+    tour.append(&list_callout);
+
+    tour
+}